@@ -171,7 +171,8 @@ create table if not exists public.purple_order
     refund_amount             integer,
     balance_amount            integer,
     surplus_order_ids         text,
-    status                    boolean default false not null,
+    transfer_amount           bigint,
+    status                    smallint default 0    not null,
     commission_status         boolean default false not null,
     commission_balance        integer default 0     not null,
     actual_commission_balance integer,
@@ -180,7 +181,7 @@ create table if not exists public.purple_order
     updated_at                integer               not null
 );
 
-comment on column public.purple_order.type is '1新购2续费3升级';
+comment on column public.purple_order.type is '1新购2续费3升级4流量重置5流量包';
 
 comment on column public.purple_order.surplus_amount is '剩余价值';
 
@@ -190,6 +191,8 @@ comment on column public.purple_order.balance_amount is '使用余额';
 
 comment on column public.purple_order.surplus_order_ids is '折抵订单';
 
+comment on column public.purple_order.transfer_amount is '流量包流量（GB）';
+
 comment on column public.purple_order.status is '0待支付1开通中2已取消3已完成4已折抵';
 
 comment on column public.purple_order.commission_status is '0待确认1发放中2有效3无效';
@@ -240,7 +243,7 @@ create table if not exists public.purple_plan
     three_year_price     integer,
     onetime_price        integer,
     reset_price          integer,
-    reset_traffic_method boolean,
+    reset_traffic_method smallint,
     capacity_limit       integer,
     daily_unit_price     integer,
     transfer_unit_price  integer,
@@ -248,6 +251,8 @@ create table if not exists public.purple_plan
     updated_at           integer               not null
 );
 
+comment on column public.purple_plan.reset_traffic_method is '流量重置方式 null跟随全局 0每月1日 1每月购买日 2不重置 3每年1月1日 4每年购买日';
+
 alter table public.purple_plan
     owner to purple;
 
@@ -569,7 +574,7 @@ create table if not exists public.purple_user
     telegram_id        bigint,
     email              varchar(64)           not null
         unique,
-    password           varchar(64)           not null,
+    password           varchar(255)          not null,
     password_algo      char(10),
     password_salt      char(10),
     balance            integer default 0     not null,
@@ -604,3 +609,403 @@ comment on column public.purple_user.commission_type is '0: system 1: period 2:
 alter table public.purple_user
     owner to purple;
 
+-- argon2 哈希超过 64 个字符，已有数据库需要加宽密码列
+alter table public.purple_user
+    alter column password type varchar(255);
+
+comment on column public.purple_user.password_algo is '从 V2Board 导入的密码加密方式（md5、sha256、md5salt、bcrypt），登录成功后升级为 argon2 并清空';
+
+-- 用户名可用于登录，唯一且不区分大小写，从 V2Board 导入的用户没有用户名
+alter table public.purple_user
+    add column if not exists username varchar(32);
+
+create unique index if not exists purple_user_username_lower_unique
+    on public.purple_user (lower(username));
+
+-- 当前套餐的开通时间，开通前的重置日不再重置流量
+alter table public.purple_user
+    add column if not exists plan_activated_at integer;
+
+comment on column public.purple_user.plan_activated_at is '当前套餐的开通时间，为空时按已开通处理';
+
+-- 已有套餐的用户以首次迁移的时间作为开通时间，避免部署后立即重置本周期已用的流量
+update public.purple_user
+set plan_activated_at = extract(epoch from now())::integer
+where plan_id is not null
+  and plan_activated_at is null;
+
+
+create table if not exists public.purple_server_status
+(
+    server_id     integer           not null,
+    server_type   varchar(11)       not null,
+    last_check_at integer,
+    last_push_at  integer,
+    online_user   integer default 0 not null,
+    cpu           double precision,
+    mem           double precision,
+    disk          double precision,
+    uptime        bigint,
+    updated_at    integer           not null,
+    primary key (server_type, server_id)
+);
+
+comment on table public.purple_server_status is '节点运行状态';
+
+comment on column public.purple_server_status.last_check_at is '最后检查时间';
+
+comment on column public.purple_server_status.last_push_at is '最后推送时间';
+
+comment on column public.purple_server_status.online_user is '在线用户数';
+
+alter table public.purple_server_status
+    owner to purple;
+
+create table if not exists public.purple_subscribe_template
+(
+    name       varchar(32) not null
+        primary key,
+    content    text        not null,
+    created_at integer     not null,
+    updated_at integer     not null
+);
+
+comment on table public.purple_subscribe_template is '订阅模板';
+
+comment on column public.purple_subscribe_template.name is '模板类型';
+
+comment on column public.purple_subscribe_template.content is '模板内容';
+
+alter table public.purple_subscribe_template
+    owner to purple;
+
+create table if not exists public.purple_subscribe_log
+(
+    id         serial
+        primary key,
+    user_id    integer      not null,
+    ip         varchar(128),
+    user_agent varchar(255),
+    created_at integer      not null
+);
+
+comment on table public.purple_subscribe_log is '订阅拉取记录';
+
+comment on column public.purple_subscribe_log.ip is '拉取订阅的客户端IP';
+
+comment on column public.purple_subscribe_log.user_agent is '拉取订阅的客户端 User-Agent';
+
+create index if not exists purple_subscribe_log_user_id_created_at_index
+    on public.purple_subscribe_log (user_id, created_at);
+
+alter table public.purple_subscribe_log
+    owner to purple;
+
+create table if not exists public.purple_server_rate_schedule
+(
+    server_type varchar(11) not null,
+    server_id   integer     not null,
+    windows     text        not null,
+    updated_at  integer     not null,
+    primary key (server_type, server_id)
+);
+
+comment on table public.purple_server_rate_schedule is '节点时间段倍率';
+
+comment on column public.purple_server_rate_schedule.windows is '时间段倍率列表（JSON），未命中时使用节点的基础倍率';
+
+alter table public.purple_server_rate_schedule
+    owner to purple;
+
+-- 早期版本的 reset_traffic_method 为 boolean，转换为 smallint，null（跟随全局）保持不变
+do
+$$
+    begin
+        if exists (select 1
+                   from information_schema.columns
+                   where table_schema = 'public'
+                     and table_name = 'purple_plan'
+                     and column_name = 'reset_traffic_method'
+                     and data_type = 'boolean') then
+            alter table public.purple_plan
+                alter column reset_traffic_method type smallint
+                    using (case
+                           when reset_traffic_method is null then null
+                           when reset_traffic_method then 1
+                           else 0 end);
+        end if;
+    end
+$$;
+
+create table if not exists public.purple_traffic_reset_log
+(
+    id                   serial
+        primary key,
+    user_id              integer     not null,
+    plan_id              integer,
+    reset_traffic_method smallint    not null,
+    period               varchar(7)  not null,
+    u                    bigint      not null,
+    d                    bigint      not null,
+    created_at           integer     not null,
+    constraint purple_traffic_reset_log_user_id_period_unique
+        unique (user_id, period)
+);
+
+comment on table public.purple_traffic_reset_log is '流量重置记录';
+
+comment on column public.purple_traffic_reset_log.period is '重置周期，月度为 YYYY-MM，年度为 YYYY，同一周期只重置一次';
+
+comment on column public.purple_traffic_reset_log.u is '重置前的上行流量';
+
+comment on column public.purple_traffic_reset_log.d is '重置前的下行流量';
+
+alter table public.purple_traffic_reset_log
+    owner to purple;
+
+-- 早期版本的订单状态为 boolean，无法表示多种状态，转换为 smallint
+do
+$$
+    begin
+        if exists (select 1
+                   from information_schema.columns
+                   where table_schema = 'public'
+                     and table_name = 'purple_order'
+                     and column_name = 'status'
+                     and data_type = 'boolean') then
+            alter table public.purple_order
+                alter column status drop default;
+            alter table public.purple_order
+                alter column status type smallint
+                    using (case when status then 3 else 0 end);
+            alter table public.purple_order
+                alter column status set default 0;
+        end if;
+    end
+$$;
+
+alter table public.purple_order
+    add column if not exists transfer_amount bigint;
+
+create table if not exists public.purple_balance_log
+(
+    id         serial
+        primary key,
+    user_id    integer  not null,
+    type       smallint not null,
+    amount     integer  not null,
+    balance    integer  not null,
+    order_id   integer,
+    period     varchar(10),
+    transfer   bigint,
+    remark     varchar(255),
+    created_at integer  not null
+);
+
+comment on table public.purple_balance_log is '余额变动记录';
+
+comment on column public.purple_balance_log.type is '1订单支付 2按日计费 3按流量计费';
+
+comment on column public.purple_balance_log.amount is '变动金额，扣费为负数';
+
+comment on column public.purple_balance_log.balance is '变动后的余额';
+
+comment on column public.purple_balance_log.period is '按日计费的日期 YYYY-MM-DD，同一用户同一天只扣费一次';
+
+comment on column public.purple_balance_log.transfer is '按流量计费的流量（字节）';
+
+alter table public.purple_balance_log
+    owner to purple;
+
+create index if not exists purple_balance_log_user_id_index
+    on public.purple_balance_log (user_id);
+
+create unique index if not exists purple_balance_log_user_id_type_period_unique
+    on public.purple_balance_log (user_id, type, period)
+    where period is not null;
+
+create table if not exists public.purple_metered_billing
+(
+    user_id         integer
+        primary key,
+    billed_transfer bigint  default 0     not null,
+    suspended       boolean default false not null,
+    updated_at      integer               not null
+);
+
+comment on table public.purple_metered_billing is '按量计费状态';
+
+comment on column public.purple_metered_billing.billed_transfer is '已计费的流量（字节），不足 1GB 的部分留待下次计费';
+
+comment on column public.purple_metered_billing.suspended is '是否因余额耗尽被停用，停用期间节点不下发该用户，余额充足后自动恢复';
+
+alter table public.purple_metered_billing
+    owner to purple;
+
+create table if not exists public.purple_staff_permission
+(
+    user_id    integer     not null,
+    permission varchar(64) not null,
+    created_at integer     not null,
+    primary key (user_id, permission)
+);
+
+comment on table public.purple_staff_permission is '客服账户的权限，管理员默认拥有全部权限';
+
+comment on column public.purple_staff_permission.permission is '权限名称，如 users.read、tickets.reply';
+
+alter table public.purple_staff_permission
+    owner to purple;
+
+create table if not exists public.purple_user_session
+(
+    id                  serial
+        primary key,
+    user_id             integer      not null,
+    refresh_token_hash  varchar(64)  not null,
+    previous_token_hash varchar(64),
+    device              varchar(255),
+    ip                  varchar(128),
+    created_at          integer      not null,
+    last_used_at        integer      not null,
+    expired_at          integer      not null,
+    revoked_at          integer
+);
+
+comment on table public.purple_user_session is '用户登录会话，访问令牌绑定会话，会话撤销后令牌立即失效';
+
+comment on column public.purple_user_session.refresh_token_hash is '当前刷新令牌的 SHA-256 哈希';
+
+comment on column public.purple_user_session.previous_token_hash is '上一个刷新令牌的哈希，再次使用视为令牌泄露并撤销会话';
+
+comment on column public.purple_user_session.device is '登录设备（User-Agent）';
+
+comment on column public.purple_user_session.ip is '最近一次使用的IP地址';
+
+comment on column public.purple_user_session.revoked_at is '撤销时间，为空表示会话有效';
+
+create unique index if not exists purple_user_session_refresh_token_hash_unique
+    on public.purple_user_session (refresh_token_hash);
+
+create index if not exists purple_user_session_previous_token_hash_index
+    on public.purple_user_session (previous_token_hash);
+
+create index if not exists purple_user_session_user_id_index
+    on public.purple_user_session (user_id);
+
+alter table public.purple_user_session
+    owner to purple;
+
+create table if not exists public.purple_email_code
+(
+    id         serial
+        primary key,
+    email      varchar(64)       not null,
+    purpose    smallint          not null,
+    code_hash  varchar(64)       not null,
+    ip         varchar(128),
+    attempts   integer default 0 not null,
+    created_at integer           not null,
+    expired_at integer           not null,
+    used_at    integer
+);
+
+comment on table public.purple_email_code is '邮箱验证码，只保存验证码哈希';
+
+comment on column public.purple_email_code.purpose is '用途：0注册 1重置密码';
+
+comment on column public.purple_email_code.code_hash is '验证码的 SHA-256 哈希';
+
+comment on column public.purple_email_code.ip is '请求发送验证码的IP地址';
+
+comment on column public.purple_email_code.attempts is '验证失败次数，达到上限后验证码失效';
+
+comment on column public.purple_email_code.used_at is '使用时间，为空表示未使用';
+
+create index if not exists purple_email_code_email_purpose_index
+    on public.purple_email_code (email, purpose);
+
+create index if not exists purple_email_code_ip_created_at_index
+    on public.purple_email_code (ip, created_at);
+
+alter table public.purple_email_code
+    owner to purple;
+
+create table if not exists public.purple_user_two_factor
+(
+    user_id        integer not null
+        primary key,
+    secret         varchar(64) not null,
+    enabled_at     integer,
+    last_used_step bigint,
+    created_at     integer not null,
+    updated_at     integer not null
+);
+
+comment on table public.purple_user_two_factor is '用户 TOTP 双重验证';
+
+comment on column public.purple_user_two_factor.secret is 'Base32 编码的 TOTP 密钥';
+
+comment on column public.purple_user_two_factor.enabled_at is '启用时间，为空表示已生成密钥但尚未确认';
+
+comment on column public.purple_user_two_factor.last_used_step is '最近一次验证通过的时间步，同一验证码不能重复使用';
+
+alter table public.purple_user_two_factor
+    owner to purple;
+
+create table if not exists public.purple_user_recovery_code
+(
+    id         serial
+        primary key,
+    user_id    integer     not null,
+    code_hash  varchar(64) not null,
+    used_at    integer,
+    created_at integer     not null
+);
+
+comment on table public.purple_user_recovery_code is '双重验证恢复码，每个恢复码只能使用一次';
+
+comment on column public.purple_user_recovery_code.code_hash is '恢复码的 SHA-256 哈希';
+
+create index if not exists purple_user_recovery_code_user_id_index
+    on public.purple_user_recovery_code (user_id);
+
+alter table public.purple_user_recovery_code
+    owner to purple;
+
+create table if not exists public.purple_user_api_key
+(
+    id           serial
+        primary key,
+    user_id      integer                      not null,
+    name         varchar(64)                  not null,
+    prefix       varchar(16)                  not null,
+    secret_hash  varchar(64)                  not null,
+    scopes       varchar(64)[] default '{}'   not null,
+    expired_at   integer,
+    last_used_at integer,
+    last_used_ip varchar(128),
+    created_at   integer                      not null,
+    revoked_at   integer
+);
+
+comment on table public.purple_user_api_key is '个人 API 密钥，用于脚本等自动化场景代替登录';
+
+comment on column public.purple_user_api_key.prefix is '密钥前缀，用于查找密钥，可公开展示';
+
+comment on column public.purple_user_api_key.secret_hash is '密钥的 SHA-256 哈希';
+
+comment on column public.purple_user_api_key.scopes is '授予的后台权限，如 users.read，实际生效的权限不超过所属用户的权限';
+
+comment on column public.purple_user_api_key.expired_at is '过期时间，为空表示永不过期';
+
+comment on column public.purple_user_api_key.revoked_at is '撤销时间，为空表示密钥有效';
+
+create unique index if not exists purple_user_api_key_prefix_unique
+    on public.purple_user_api_key (prefix);
+
+create index if not exists purple_user_api_key_user_id_index
+    on public.purple_user_api_key (user_id);
+
+alter table public.purple_user_api_key
+    owner to purple;
@@ -0,0 +1,125 @@
+use actix_web::{get, post, web, HttpResponse};
+
+use crate::{
+    common::{ApiResult, ResponseBuilder},
+    models::server::{
+        NodeAlivePush, NodeQuery, NodeStatusPush, NodeTrafficPush, NodeUserListResponse,
+    },
+    services::ServerService,
+};
+
+/// 节点后端拉取节点配置（包含路由规则）
+#[utoipa::path(
+    get,
+    path = "/api/agent/config",
+    tag = "agent",
+    params(NodeQuery),
+    responses(
+        (status = 200, description = "获取节点配置成功", body = crate::common::ApiResponse<crate::models::server::NodeConfigResponse>),
+        (status = 401, description = "节点通讯密钥无效", body = crate::common::ApiResponse<()>)
+    )
+)]
+#[get("/config")]
+pub async fn agent_config(
+    query: web::Query<NodeQuery>,
+    service: web::Data<ServerService>,
+) -> ApiResult<HttpResponse> {
+    let server = service.authenticate(&query).await?;
+    let config = service.node_config(&server).await?;
+
+    Ok(ResponseBuilder::success(config))
+}
+
+/// 节点后端拉取用户列表
+#[utoipa::path(
+    get,
+    path = "/api/agent/users",
+    tag = "agent",
+    params(NodeQuery),
+    responses(
+        (status = 200, description = "获取用户列表成功", body = crate::common::ApiResponse<NodeUserListResponse>),
+        (status = 401, description = "节点通讯密钥无效", body = crate::common::ApiResponse<()>)
+    )
+)]
+#[get("/users")]
+pub async fn agent_users(
+    query: web::Query<NodeQuery>,
+    service: web::Data<ServerService>,
+) -> ApiResult<HttpResponse> {
+    let server = service.authenticate(&query).await?;
+    let users = service.fetch_users(&server).await?;
+
+    Ok(ResponseBuilder::success(NodeUserListResponse { users }))
+}
+
+/// 节点后端上报用户流量
+#[utoipa::path(
+    post,
+    path = "/api/agent/push",
+    tag = "agent",
+    params(NodeQuery),
+    request_body(content = Object, description = "用户ID -> [上行流量, 下行流量]"),
+    responses(
+        (status = 200, description = "上报成功", body = crate::common::ApiResponse<bool>),
+        (status = 401, description = "节点通讯密钥无效", body = crate::common::ApiResponse<()>)
+    )
+)]
+#[post("/push")]
+pub async fn agent_push(
+    query: web::Query<NodeQuery>,
+    traffic: web::Json<NodeTrafficPush>,
+    service: web::Data<ServerService>,
+) -> ApiResult<HttpResponse> {
+    let server = service.authenticate(&query).await?;
+    service.push_traffic(&server, traffic.into_inner()).await?;
+
+    Ok(ResponseBuilder::success(true))
+}
+
+/// 节点后端上报在线用户
+#[utoipa::path(
+    post,
+    path = "/api/agent/alive",
+    tag = "agent",
+    params(NodeQuery),
+    request_body(content = Object, description = "用户ID -> 在线IP列表"),
+    responses(
+        (status = 200, description = "上报成功", body = crate::common::ApiResponse<bool>),
+        (status = 401, description = "节点通讯密钥无效", body = crate::common::ApiResponse<()>)
+    )
+)]
+#[post("/alive")]
+pub async fn agent_alive(
+    query: web::Query<NodeQuery>,
+    alive: web::Json<NodeAlivePush>,
+    service: web::Data<ServerService>,
+) -> ApiResult<HttpResponse> {
+    let server = service.authenticate(&query).await?;
+    service.push_alive(&server, alive.into_inner()).await?;
+
+    Ok(ResponseBuilder::success(true))
+}
+
+/// 节点后端上报负载信息
+#[utoipa::path(
+    post,
+    path = "/api/agent/status",
+    tag = "agent",
+    params(NodeQuery),
+    request_body = NodeStatusPush,
+    responses(
+        (status = 200, description = "上报成功", body = crate::common::ApiResponse<bool>),
+        (status = 401, description = "节点通讯密钥无效", body = crate::common::ApiResponse<()>)
+    )
+)]
+#[post("/status")]
+pub async fn agent_status(
+    query: web::Query<NodeQuery>,
+    load: web::Json<NodeStatusPush>,
+    service: web::Data<ServerService>,
+) -> ApiResult<HttpResponse> {
+    let server = service.authenticate(&query).await?;
+    service.push_status(&server, load.into_inner()).await?;
+
+    Ok(ResponseBuilder::success(true))
+}
@@ -0,0 +1,184 @@
+use actix_web::{delete, get, post, web, HttpResponse};
+use validator::Validate;
+
+use crate::{
+    api::user::forbid_unmanageable,
+    common::{ApiError, ApiResult, ErrorCode, ResponseBuilder},
+    middleware::{RequirePermission, RequireSession},
+    models::{
+        api_key::CreateApiKeyRequest,
+        permission::{Permission, PermissionSet},
+        user::User,
+    },
+    repositories::UserRepository,
+    services::ApiKeyService,
+};
+
+/// 获取我的 API 密钥
+///
+/// 只返回密钥前缀，不包含完整密钥
+#[utoipa::path(
+    get,
+    path = "/api/user/api-keys",
+    tag = "user",
+    responses(
+        (status = 200, description = "获取 API 密钥成功", body = crate::common::ApiResponse<Vec<crate::models::api_key::ApiKeyResponse>>),
+        (status = 401, description = "未登录", body = crate::common::ApiResponse<()>),
+        (status = 403, description = "不支持使用 API 密钥访问", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[get("/api-keys", wrap = "RequireSession")]
+pub async fn list_api_keys(
+    user: web::ReqData<User>,
+    service: web::Data<ApiKeyService>,
+) -> ApiResult<HttpResponse> {
+    let api_keys = service.list(user.id).await?;
+    Ok(ResponseBuilder::success(api_keys))
+}
+
+/// 创建 API 密钥
+///
+/// 完整密钥只在创建时返回一次，请求时放在 `Authorization: Bearer` 中
+#[utoipa::path(
+    post,
+    path = "/api/user/api-keys",
+    tag = "user",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 200, description = "创建 API 密钥成功", body = crate::common::ApiResponse<crate::models::api_key::CreateApiKeyResponse>),
+        (status = 400, description = "请求参数无效或密钥数量已达上限", body = crate::common::ApiResponse<()>),
+        (status = 401, description = "未登录", body = crate::common::ApiResponse<()>),
+        (status = 403, description = "授予的权限超过当前账户的权限", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[post("/api-keys", wrap = "RequireSession")]
+pub async fn create_api_key(
+    user: web::ReqData<User>,
+    permissions: web::ReqData<PermissionSet>,
+    request: web::Json<CreateApiKeyRequest>,
+    service: web::Data<ApiKeyService>,
+) -> ApiResult<HttpResponse> {
+    request.validate()?;
+    let api_key = service
+        .create(user.id, &permissions, request.into_inner())
+        .await?;
+    Ok(ResponseBuilder::success_with_message(
+        api_key,
+        "API 密钥已创建，请妥善保存".to_string(),
+    ))
+}
+
+/// 撤销 API 密钥
+#[utoipa::path(
+    delete,
+    path = "/api/user/api-keys/{id}",
+    tag = "user",
+    params(
+        ("id" = i32, Path, description = "API 密钥ID")
+    ),
+    responses(
+        (status = 200, description = "撤销 API 密钥成功", body = crate::common::ApiResponse<()>),
+        (status = 400, description = "密钥不存在或已撤销", body = crate::common::ApiResponse<()>),
+        (status = 401, description = "未登录", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[delete("/api-keys/{id}", wrap = "RequireSession")]
+pub async fn revoke_api_key(
+    id: web::Path<i32>,
+    user: web::ReqData<User>,
+    service: web::Data<ApiKeyService>,
+) -> ApiResult<HttpResponse> {
+    service.revoke(user.id, *id).await?;
+    Ok(ResponseBuilder::success_with_message(
+        (),
+        "撤销 API 密钥成功".to_string(),
+    ))
+}
+
+/// 获取用户的 API 密钥
+#[utoipa::path(
+    get,
+    path = "/api/admin/users/{id}/api-keys",
+    tag = "users",
+    params(
+        ("id" = i32, Path, description = "用户ID")
+    ),
+    responses(
+        (status = 200, description = "获取 API 密钥成功", body = crate::common::ApiResponse<Vec<crate::models::api_key::ApiKeyResponse>>),
+        (status = 403, description = "权限不足或不能管理角色不低于自己的账户", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = ["users.read"])
+    )
+)]
+#[get("/{id}/api-keys", wrap = "RequirePermission(Permission::UsersRead)")]
+pub async fn list_user_api_keys(
+    caller: web::ReqData<User>,
+    id: web::Path<i32>,
+    user_repo: web::Data<UserRepository>,
+    service: web::Data<ApiKeyService>,
+) -> ApiResult<HttpResponse> {
+    let target = find_target(&user_repo, *id).await?;
+    if let Some(response) = forbid_unmanageable(&caller, &target) {
+        return Ok(response);
+    }
+    let api_keys = service.list(*id).await?;
+    Ok(ResponseBuilder::success(api_keys))
+}
+
+/// 撤销用户的 API 密钥
+#[utoipa::path(
+    delete,
+    path = "/api/admin/users/{id}/api-keys/{key_id}",
+    tag = "users",
+    params(
+        ("id" = i32, Path, description = "用户ID"),
+        ("key_id" = i32, Path, description = "API 密钥ID")
+    ),
+    responses(
+        (status = 200, description = "撤销 API 密钥成功", body = crate::common::ApiResponse<()>),
+        (status = 400, description = "密钥不存在或已撤销", body = crate::common::ApiResponse<()>),
+        (status = 403, description = "权限不足或不能管理角色不低于自己的账户", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = ["users.write"])
+    )
+)]
+#[delete(
+    "/{id}/api-keys/{key_id}",
+    wrap = "RequirePermission(Permission::UsersWrite)"
+)]
+pub async fn revoke_user_api_key(
+    caller: web::ReqData<User>,
+    path: web::Path<(i32, i32)>,
+    user_repo: web::Data<UserRepository>,
+    service: web::Data<ApiKeyService>,
+) -> ApiResult<HttpResponse> {
+    let (user_id, key_id) = path.into_inner();
+    let target = find_target(&user_repo, user_id).await?;
+    if let Some(response) = forbid_unmanageable(&caller, &target) {
+        return Ok(response);
+    }
+    service.revoke(user_id, key_id).await?;
+    Ok(ResponseBuilder::success_with_message(
+        (),
+        "撤销 API 密钥成功".to_string(),
+    ))
+}
+
+/// 查找被管理的用户，不存在时返回 `UserNotFound`
+async fn find_target(user_repo: &UserRepository, id: i32) -> ApiResult<User> {
+    user_repo
+        .find_by_id(id)
+        .await?
+        .ok_or_else(|| ApiError::new(ErrorCode::UserNotFound))
+}
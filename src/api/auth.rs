@@ -1,12 +1,20 @@
-use actix_web::{post, web, HttpResponse};
+use actix_web::{http::header, post, web, HttpRequest, HttpResponse};
 use serde::Serialize;
 use utoipa::ToSchema;
 use validator::Validate;
 
 use crate::{
-    common::{ErrorCode, ResponseBuilder},
-    models::auth::{LoginRequest, RegisterRequest, TokenResponse},
+    common::{ApiError, ApiResult, ErrorCode, ResponseBuilder},
+    middleware::{Auth, RequireSession},
+    models::{
+        auth::{ChangePasswordRequest, LoginRequest, RegisterRequest, TokenResponse},
+        email_code::{ForgotPasswordRequest, ResetPasswordRequest, SendEmailCodeRequest},
+        session::{ClientInfo, RefreshTokenRequest, Session},
+        two_factor::{LoginResponse, TwoFactorLoginRequest},
+        user::User,
+    },
     services::AuthService,
+    utils::client_ip,
 };
 
 /// 用户注册
@@ -17,7 +25,7 @@ use crate::{
     request_body = RegisterRequest,
     responses(
         (status = 200, description = "用户注册成功", body = crate::common::ApiResponse<i32>),
-        (status = 400, description = "请求参数无效", body = crate::common::ApiResponse<()>),
+        (status = 400, description = "请求参数无效或邮箱验证码错误", body = crate::common::ApiResponse<()>),
         (status = 409, description = "用户已存在", body = crate::common::ApiResponse<()>),
         (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
     )
@@ -53,6 +61,17 @@ pub async fn register(
     match service.register(request.into_inner()).await {
         Ok(user_id) => ResponseBuilder::success_with_message(user_id, "用户注册成功".to_string()),
         Err(e) => {
+            // 验证码错误等业务错误直接返回
+            let e = match e.downcast::<ApiError>() {
+                Ok(api_error) => {
+                    tracing::warn!("用户注册失败: {}", api_error);
+                    return ResponseBuilder::error_with_message(
+                        api_error.error_code,
+                        api_error.message(),
+                    );
+                }
+                Err(e) => e,
+            };
             let error_msg = e.to_string();
             tracing::error!("用户注册失败: {}", error_msg);
 
@@ -82,21 +101,25 @@ pub async fn register(
 }
 
 /// 用户登录
+///
+/// 启用双重验证的账户返回挑战令牌，需再调用 `/api/auth/login/2fa` 提交验证码
 #[utoipa::path(
     post,
     path = "/api/auth/login",
     tag = "auth",
     request_body = LoginRequest,
     responses(
-        (status = 200, description = "用户登录成功", body = crate::common::ApiResponse<TokenResponse>),
+        (status = 200, description = "用户登录成功或需要双重验证", body = crate::common::ApiResponse<LoginResponse>),
         (status = 400, description = "请求参数无效", body = crate::common::ApiResponse<()>),
         (status = 401, description = "用户名或密码错误", body = crate::common::ApiResponse<()>),
-        (status = 403, description = "账户已被禁用", body = crate::common::ApiResponse<()>),
+        (status = 403, description = "账户已被禁用或因多次登录失败被临时锁定", body = crate::common::ApiResponse<()>),
+        (status = 429, description = "登录尝试过于频繁", body = crate::common::ApiResponse<()>),
         (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
     )
 )]
 #[post("/api/auth/login")]
 pub async fn login(
+    req: HttpRequest,
     request: web::Json<LoginRequest>,
     service: web::Data<AuthService>,
 ) -> HttpResponse {
@@ -123,9 +146,23 @@ pub async fn login(
         return ResponseBuilder::error_with_message(ErrorCode::ValidationError, error_msg);
     }
 
-    match service.login(request.into_inner()).await {
-        Ok(token) => ResponseBuilder::success_with_message(token, "登录成功".to_string()),
+    match service.login(request.into_inner(), client_info(&req)).await {
+        Ok(response @ LoginResponse::TwoFactor(_)) => {
+            ResponseBuilder::success_with_message(response, "请输入双重验证码".to_string())
+        }
+        Ok(response) => ResponseBuilder::success_with_message(response, "登录成功".to_string()),
         Err(e) => {
+            // 登录限制等业务错误直接返回
+            let e = match e.downcast::<ApiError>() {
+                Ok(api_error) => {
+                    tracing::warn!("用户登录失败: {}", api_error);
+                    return ResponseBuilder::error_with_message(
+                        api_error.error_code,
+                        api_error.message(),
+                    );
+                }
+                Err(e) => e,
+            };
             let error_msg = e.to_string();
             tracing::error!("用户登录失败: {}", error_msg);
 
@@ -161,3 +198,293 @@ pub async fn login(
         }
     }
 }
+
+/// 双重验证登录
+///
+/// 提交登录返回的挑战令牌和身份验证器中的验证码（或恢复码），验证通过后签发令牌
+#[utoipa::path(
+    post,
+    path = "/api/auth/login/2fa",
+    tag = "auth",
+    request_body = TwoFactorLoginRequest,
+    responses(
+        (status = 200, description = "登录成功", body = crate::common::ApiResponse<TokenResponse>),
+        (status = 400, description = "验证码错误", body = crate::common::ApiResponse<()>),
+        (status = 401, description = "挑战令牌无效或已过期", body = crate::common::ApiResponse<()>),
+        (status = 403, description = "账户已被禁用或因多次验证失败被临时锁定", body = crate::common::ApiResponse<()>),
+        (status = 429, description = "尝试过于频繁", body = crate::common::ApiResponse<()>)
+    )
+)]
+#[post("/api/auth/login/2fa")]
+pub async fn login_two_factor(
+    req: HttpRequest,
+    request: web::Json<TwoFactorLoginRequest>,
+    service: web::Data<AuthService>,
+) -> ApiResult<HttpResponse> {
+    request.validate()?;
+    let token = service
+        .login_two_factor(request.into_inner(), client_info(&req))
+        .await?;
+    Ok(ResponseBuilder::success_with_message(
+        token,
+        "登录成功".to_string(),
+    ))
+}
+
+/// 刷新访问令牌
+///
+/// 使用刷新令牌换取新的访问令牌和刷新令牌，旧的刷新令牌随即失效
+#[utoipa::path(
+    post,
+    path = "/api/auth/refresh",
+    tag = "auth",
+    request_body = RefreshTokenRequest,
+    responses(
+        (status = 200, description = "刷新令牌成功", body = crate::common::ApiResponse<TokenResponse>),
+        (status = 401, description = "刷新令牌无效或会话已过期", body = crate::common::ApiResponse<()>),
+        (status = 403, description = "账户已被禁用", body = crate::common::ApiResponse<()>)
+    )
+)]
+#[post("/api/auth/refresh")]
+pub async fn refresh_token(
+    req: HttpRequest,
+    request: web::Json<RefreshTokenRequest>,
+    service: web::Data<AuthService>,
+) -> ApiResult<HttpResponse> {
+    let token = service
+        .refresh(&request.refresh_token, client_info(&req))
+        .await?;
+    Ok(ResponseBuilder::success(token))
+}
+
+/// 退出登录
+///
+/// 撤销当前会话，访问令牌和刷新令牌同时失效
+#[utoipa::path(
+    post,
+    path = "/api/auth/logout",
+    tag = "auth",
+    responses(
+        (status = 200, description = "退出登录成功", body = crate::common::ApiResponse<()>),
+        (status = 401, description = "未登录", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[post("/api/auth/logout", wrap = "RequireSession", wrap = "Auth::new()")]
+pub async fn logout(
+    session: web::ReqData<Session>,
+    service: web::Data<AuthService>,
+) -> ApiResult<HttpResponse> {
+    service.revoke_session(session.user_id, session.id).await?;
+    Ok(ResponseBuilder::success_with_message(
+        (),
+        "退出登录成功".to_string(),
+    ))
+}
+
+/// 发送注册验证码
+///
+/// 开启注册邮箱验证时，注册前需先获取验证码
+#[utoipa::path(
+    post,
+    path = "/api/auth/email-code",
+    tag = "auth",
+    request_body = SendEmailCodeRequest,
+    responses(
+        (status = 200, description = "验证码已发送", body = crate::common::ApiResponse<()>),
+        (status = 400, description = "请求参数无效", body = crate::common::ApiResponse<()>),
+        (status = 409, description = "邮箱已注册", body = crate::common::ApiResponse<()>),
+        (status = 429, description = "验证码发送过于频繁", body = crate::common::ApiResponse<()>)
+    )
+)]
+#[post("/api/auth/email-code")]
+pub async fn send_email_code(
+    req: HttpRequest,
+    request: web::Json<SendEmailCodeRequest>,
+    service: web::Data<AuthService>,
+) -> ApiResult<HttpResponse> {
+    request.validate()?;
+    service
+        .send_register_code(&request.email, client_ip(&req).as_deref())
+        .await?;
+    Ok(ResponseBuilder::success_with_message(
+        (),
+        "验证码已发送".to_string(),
+    ))
+}
+
+/// 忘记密码
+///
+/// 向注册邮箱发送重置密码验证码，邮箱未注册时同样返回成功并同样限制发送频率
+#[utoipa::path(
+    post,
+    path = "/api/auth/forgot-password",
+    tag = "auth",
+    request_body = ForgotPasswordRequest,
+    responses(
+        (status = 200, description = "验证码已发送", body = crate::common::ApiResponse<()>),
+        (status = 400, description = "请求参数无效", body = crate::common::ApiResponse<()>),
+        (status = 429, description = "验证码发送过于频繁", body = crate::common::ApiResponse<()>)
+    )
+)]
+#[post("/api/auth/forgot-password")]
+pub async fn forgot_password(
+    req: HttpRequest,
+    request: web::Json<ForgotPasswordRequest>,
+    service: web::Data<AuthService>,
+) -> ApiResult<HttpResponse> {
+    request.validate()?;
+    service
+        .forgot_password(&request.email, client_ip(&req).as_deref())
+        .await?;
+    Ok(ResponseBuilder::success_with_message(
+        (),
+        "如果该邮箱已注册，验证码将发送至该邮箱".to_string(),
+    ))
+}
+
+/// 重置密码
+///
+/// 使用邮箱验证码设置新密码，成功后撤销全部会话，所有设备需要重新登录
+#[utoipa::path(
+    post,
+    path = "/api/auth/reset-password",
+    tag = "auth",
+    request_body = ResetPasswordRequest,
+    responses(
+        (status = 200, description = "重置密码成功", body = crate::common::ApiResponse<()>),
+        (status = 400, description = "请求参数无效或验证码错误", body = crate::common::ApiResponse<()>)
+    )
+)]
+#[post("/api/auth/reset-password")]
+pub async fn reset_password(
+    request: web::Json<ResetPasswordRequest>,
+    service: web::Data<AuthService>,
+) -> ApiResult<HttpResponse> {
+    request.validate()?;
+    service.reset_password(request.into_inner()).await?;
+    Ok(ResponseBuilder::success_with_message(
+        (),
+        "重置密码成功，请重新登录".to_string(),
+    ))
+}
+
+/// 修改密码
+///
+/// 修改成功后撤销全部会话，所有设备需要重新登录
+#[utoipa::path(
+    post,
+    path = "/api/user/password",
+    tag = "user",
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 200, description = "修改密码成功", body = crate::common::ApiResponse<()>),
+        (status = 400, description = "请求参数无效", body = crate::common::ApiResponse<()>),
+        (status = 401, description = "原密码错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[post("/password", wrap = "RequireSession")]
+pub async fn change_password(
+    user: web::ReqData<User>,
+    request: web::Json<ChangePasswordRequest>,
+    service: web::Data<AuthService>,
+) -> ApiResult<HttpResponse> {
+    request.validate()?;
+    service
+        .change_password(&user, &request.old_password, &request.new_password)
+        .await?;
+    Ok(ResponseBuilder::success_with_message(
+        (),
+        "修改密码成功，请重新登录".to_string(),
+    ))
+}
+
+/// 从请求中提取登录设备信息，IP 只采信可信反向代理转发的地址
+fn client_info(req: &HttpRequest) -> ClientInfo {
+    ClientInfo {
+        device: req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.chars().take(255).collect()),
+        ip: client_ip(req),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::ProxyConfig, test_support};
+    use actix_web::{test, App};
+    use sqlx::PgPool;
+
+    #[sqlx::test(migrations = false)]
+    async fn test_forged_forwarded_for_does_not_bypass_ip_limit(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let ip_max_failures = test_support::auth_config().login_guard.ip_max_failures;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_support::auth_service(&pool)))
+                .app_data(web::Data::new(ProxyConfig {
+                    trusted_proxies: vec!["127.0.0.1".parse().unwrap()],
+                }))
+                .service(login),
+        )
+        .await;
+
+        // 不可信的直连地址每次伪造不同的 X-Forwarded-For，失败仍计入同一个IP
+        let mut codes = Vec::new();
+        for i in 0..=ip_max_failures {
+            let req = test::TestRequest::post()
+                .uri("/api/auth/login")
+                .peer_addr("203.0.113.7:50000".parse().unwrap())
+                .insert_header(("x-forwarded-for", format!("10.0.{}.{}", i / 256, i % 256)))
+                .set_json(serde_json::json!({
+                    "username": format!("nobody{}", i),
+                    "password": "wrong-password",
+                }))
+                .to_request();
+            let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            codes.push(body["code"].as_i64().unwrap() as i32);
+        }
+
+        assert!(codes[..ip_max_failures as usize]
+            .iter()
+            .all(|code| *code != ErrorCode::TooManyRequests as i32));
+        assert_eq!(codes.last(), Some(&(ErrorCode::TooManyRequests as i32)));
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_forged_forwarded_for_does_not_bypass_email_code_limit(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let ip_hourly_limit = test_support::auth_config().email_code.ip_hourly_limit;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_support::auth_service(&pool)))
+                .service(send_email_code),
+        )
+        .await;
+
+        let mut codes = Vec::new();
+        for i in 0..=ip_hourly_limit {
+            let req = test::TestRequest::post()
+                .uri("/api/auth/email-code")
+                .peer_addr("203.0.113.7:50000".parse().unwrap())
+                .insert_header(("x-forwarded-for", format!("10.0.0.{}", i)))
+                .set_json(serde_json::json!({ "email": format!("user{}@example.com", i) }))
+                .to_request();
+            let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            codes.push(body["code"].as_i64().unwrap() as i32);
+        }
+
+        assert!(codes[..ip_hourly_limit as usize]
+            .iter()
+            .all(|code| *code == ErrorCode::Success as i32));
+        assert_eq!(codes.last(), Some(&(ErrorCode::TooManyRequests as i32)));
+    }
+}
@@ -0,0 +1,104 @@
+use actix_web::{get, post, web, HttpResponse};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    common::{ApiError, ApiResult, ErrorCode, ResponseBuilder},
+    middleware::RequirePermission,
+    models::{balance::BalanceLogQuery, permission::Permission, user::User},
+    services::BillingService,
+};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct RunBillingQuery {
+    /// 按指定日期（YYYY-MM-DD）执行按日扣费，用于补扣错过的日期，默认为当天
+    pub date: Option<String>,
+}
+
+/// 获取余额变动记录
+#[utoipa::path(
+    get,
+    path = "/api/admin/billing/balance-logs",
+    tag = "billing",
+    params(BalanceLogQuery),
+    responses(
+        (status = 200, description = "获取余额变动记录成功", body = crate::common::ApiResponse<crate::common::PageResponse<crate::models::balance::BalanceLog>>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = ["billing.read"])
+    )
+)]
+#[get("/balance-logs", wrap = "RequirePermission(Permission::BillingRead)")]
+pub async fn list_balance_logs(
+    query: web::Query<BalanceLogQuery>,
+    service: web::Data<BillingService>,
+) -> ApiResult<HttpResponse> {
+    let page = query.page.max(1);
+    let page_size = query.page_size.clamp(1, 100);
+    let (logs, total) = service.logs(query.user_id, page, page_size).await?;
+
+    Ok(ResponseBuilder::page(logs, total as u64, page, page_size))
+}
+
+/// 立即执行按量扣费
+///
+/// 当天已按日扣费的用户不会重复扣费，可重复执行
+#[utoipa::path(
+    post,
+    path = "/api/admin/billing/run",
+    tag = "billing",
+    params(RunBillingQuery),
+    responses(
+        (status = 200, description = "执行按量扣费成功", body = crate::common::ApiResponse<crate::models::balance::BillingSummary>),
+        (status = 400, description = "日期格式无效", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = ["billing.write"])
+    )
+)]
+#[post("/run", wrap = "RequirePermission(Permission::BillingWrite)")]
+pub async fn run_billing(
+    query: web::Query<RunBillingQuery>,
+    service: web::Data<BillingService>,
+) -> ApiResult<HttpResponse> {
+    let date = match &query.date {
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| {
+            ApiError::with_message(ErrorCode::InvalidParams, format!("日期格式无效: {}", date))
+        })?,
+        None => service.today(),
+    };
+
+    let summary = service.run(date).await?;
+    Ok(ResponseBuilder::success(summary))
+}
+
+/// 获取我的余额变动记录
+#[utoipa::path(
+    get,
+    path = "/api/user/balance-logs",
+    tag = "user",
+    params(BalanceLogQuery),
+    responses(
+        (status = 200, description = "获取余额变动记录成功", body = crate::common::ApiResponse<crate::common::PageResponse<crate::models::balance::BalanceLog>>),
+        (status = 401, description = "未授权", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[get("/balance-logs")]
+pub async fn list_my_balance_logs(
+    user: web::ReqData<User>,
+    query: web::Query<BalanceLogQuery>,
+    service: web::Data<BillingService>,
+) -> ApiResult<HttpResponse> {
+    let page = query.page.max(1);
+    let page_size = query.page_size.clamp(1, 100);
+    let (logs, total) = service.logs(Some(user.id), page, page_size).await?;
+
+    Ok(ResponseBuilder::page(logs, total as u64, page, page_size))
+}
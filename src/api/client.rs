@@ -0,0 +1,78 @@
+use actix_web::{get, http::header, web, HttpRequest, HttpResponse};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    common::ApiResult,
+    services::{SubscriptionService, SubscriptionUserInfo},
+    utils::{client_ip, percent_encode},
+};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct SubscribeQuery {
+    /// 用户订阅令牌
+    pub token: String,
+    /// 手动指定订阅格式（clash/meta/sing-box/shadowrocket/quantumultx/surge/v2rayn），
+    /// 不指定时根据 User-Agent 自动识别
+    pub flag: Option<String>,
+}
+
+/// 获取订阅
+#[utoipa::path(
+    get,
+    path = "/api/client/subscribe",
+    tag = "client",
+    params(SubscribeQuery),
+    responses(
+        (status = 200, description = "订阅内容，格式取决于客户端", body = String),
+        (status = 401, description = "订阅令牌无效", body = crate::common::ApiResponse<()>),
+        (status = 403, description = "用户已禁用、订阅已过期或流量已用尽", body = crate::common::ApiResponse<()>),
+        (status = 429, description = "订阅拉取过于频繁", body = crate::common::ApiResponse<()>)
+    )
+)]
+#[get("/subscribe")]
+pub async fn subscribe(
+    req: HttpRequest,
+    query: web::Query<SubscribeQuery>,
+    service: web::Data<SubscriptionService>,
+) -> ApiResult<HttpResponse> {
+    let user = service.authenticate(&query.token).await?;
+    service.check_rate_limit(&user).await?;
+
+    let user_agent = req
+        .headers()
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok());
+    let ip = client_ip(&req);
+    if let Err(e) = service
+        .record_access(&user, ip.as_deref(), user_agent)
+        .await
+    {
+        // 记录失败不影响订阅拉取
+        tracing::warn!("记录用户 {} 订阅拉取失败: {:#}", user.id, e);
+    }
+
+    let servers = service.available_servers(&user).await?;
+    let format = service.detect_format(query.flag.as_deref(), user_agent.unwrap_or_default());
+    let output = service.render(&user, &servers, format).await?;
+
+    let config = service.config();
+    Ok(HttpResponse::Ok()
+        .content_type(output.content_type)
+        .insert_header((
+            "subscription-userinfo",
+            SubscriptionUserInfo::from_user(&user).header_value(),
+        ))
+        .insert_header((
+            "profile-update-interval",
+            config.update_interval.to_string(),
+        ))
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename*=UTF-8''{}",
+                percent_encode(&config.app_name)
+            ),
+        ))
+        .body(output.body))
+}
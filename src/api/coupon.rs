@@ -5,8 +5,12 @@ use validator::Validate;
 
 use crate::{
     api::response::{ApiError, ApiResponse, Response},
-    models::coupon::{
-        Coupon, CouponListResponse, CouponResponse, CreateCouponRequest, UpdateCouponRequest,
+    middleware::RequirePermission,
+    models::{
+        coupon::{
+            Coupon, CouponListResponse, CouponResponse, CreateCouponRequest, UpdateCouponRequest,
+        },
+        permission::Permission,
     },
     repositories::CouponRepository,
 };
@@ -40,7 +44,7 @@ pub struct CouponsListResponse {
 /// 创建优惠券
 #[utoipa::path(
     post,
-    path = "/api/coupons",
+    path = "/api/admin/coupons",
     tag = "coupons",
     request_body = CreateCouponRequest,
     responses(
@@ -49,10 +53,10 @@ pub struct CouponsListResponse {
         (status = 500, description = "Internal server error", body = ResponseError)
     ),
     security(
-        ("jwt_token" = [])
+        ("jwt_token" = ["coupons.write"])
     )
 )]
-#[post("/api/coupons")]
+#[post("", wrap = "RequirePermission(Permission::CouponsWrite)")]
 pub async fn create_coupon(
     coupon: web::Json<CreateCouponRequest>,
     repo: web::Data<CouponRepository>,
@@ -69,7 +73,7 @@ pub async fn create_coupon(
 /// 获取优惠券列表
 #[utoipa::path(
     get,
-    path = "/api/coupons",
+    path = "/api/admin/coupons",
     tag = "coupons",
     responses(
         (status = 200, description = "Coupons retrieved successfully", body = CouponListResponse),
@@ -82,10 +86,10 @@ pub async fn create_coupon(
         ("only_valid" = bool, Query, description = "Only show valid coupons (default: false)")
     ),
     security(
-        ("jwt_token" = [])
+        ("jwt_token" = ["coupons.read"])
     )
 )]
-#[get("/api/coupons")]
+#[get("", wrap = "RequirePermission(Permission::CouponsRead)")]
 pub async fn list_coupons(
     query: web::Query<GetCouponsQuery>,
     repo: web::Data<CouponRepository>,
@@ -105,7 +109,7 @@ pub async fn list_coupons(
 /// 获取优惠券信息
 #[utoipa::path(
     get,
-    path = "/api/coupons/{id}",
+    path = "/api/admin/coupons/{id}",
     tag = "coupons",
     responses(
         (status = 200, description = "Coupon found", body = CouponResponse),
@@ -116,10 +120,10 @@ pub async fn list_coupons(
         ("id" = i32, Path, description = "Coupon id")
     ),
     security(
-        ("jwt_token" = [])
+        ("jwt_token" = ["coupons.read"])
     )
 )]
-#[get("/api/coupons/{id}")]
+#[get("/{id}", wrap = "RequirePermission(Permission::CouponsRead)")]
 pub async fn get_coupon(
     id: web::Path<i32>,
     repo: web::Data<CouponRepository>,
@@ -143,7 +147,7 @@ pub async fn get_coupon(
 /// 更新优惠券信息
 #[utoipa::path(
     put,
-    path = "/api/coupons/{id}",
+    path = "/api/admin/coupons/{id}",
     tag = "coupons",
     request_body = UpdateCouponRequest,
     responses(
@@ -156,10 +160,10 @@ pub async fn get_coupon(
         ("id" = i32, Path, description = "Coupon id")
     ),
     security(
-        ("jwt_token" = [])
+        ("jwt_token" = ["coupons.write"])
     )
 )]
-#[put("/api/coupons/{id}")]
+#[put("/{id}", wrap = "RequirePermission(Permission::CouponsWrite)")]
 pub async fn update_coupon(
     id: web::Path<i32>,
     coupon: web::Json<UpdateCouponRequest>,
@@ -177,7 +181,7 @@ pub async fn update_coupon(
 /// 删除优惠券
 #[utoipa::path(
     delete,
-    path = "/api/coupons/{id}",
+    path = "/api/admin/coupons/{id}",
     tag = "coupons",
     responses(
         (status = 200, description = "Coupon deleted successfully"),
@@ -188,10 +192,10 @@ pub async fn update_coupon(
         ("id" = i32, Path, description = "Coupon id")
     ),
     security(
-        ("jwt_token" = [])
+        ("jwt_token" = ["coupons.write"])
     )
 )]
-#[delete("/api/coupons/{id}")]
+#[delete("/{id}", wrap = "RequirePermission(Permission::CouponsWrite)")]
 pub async fn delete_coupon(
     id: web::Path<i32>,
     repo: web::Data<CouponRepository>,
@@ -203,7 +207,7 @@ pub async fn delete_coupon(
 /// 验证优惠码
 #[utoipa::path(
     get,
-    path = "/coupons/verify/{code}",
+    path = "/api/user/coupons/verify/{code}",
     tag = "coupons",
     params(
         ("code" = String, Path, description = "优惠码"),
@@ -212,6 +216,9 @@ pub async fn delete_coupon(
         (status = 200, description = "验证优惠码成功", body = CouponResponse),
         (status = 404, description = "优惠码不存在或已失效", body = Response),
         (status = 500, description = "服务器内部错误", body = Response),
+    ),
+    security(
+        ("jwt_token" = [])
     )
 )]
 #[get("/coupons/verify/{code}")]
@@ -1,17 +1,58 @@
+mod agent;
+mod api_key;
 mod auth;
+mod billing;
+mod client;
 mod coupon;
 mod health;
 pub mod openapi;
+mod order;
+mod permission;
 mod plan;
 pub mod response;
+mod route;
+mod server;
+mod session;
+mod subscribe_log;
+mod subscribe_template;
+mod traffic_reset;
+mod two_factor;
 pub mod user;
 
-pub use auth::{login, register};
+pub use agent::{agent_alive, agent_config, agent_push, agent_status, agent_users};
+pub use api_key::{
+    create_api_key, list_api_keys, list_user_api_keys, revoke_api_key, revoke_user_api_key,
+};
+pub use auth::{
+    change_password, forgot_password, login, login_two_factor, logout, refresh_token, register,
+    reset_password, send_email_code,
+};
+pub use billing::{list_balance_logs, list_my_balance_logs, run_billing};
+pub use client::subscribe;
 pub use coupon::{
     create_coupon, delete_coupon, get_coupon, list_coupons, update_coupon, verify_coupon,
 };
 pub use health::health_check;
 pub use openapi::*;
+pub use order::{
+    cancel_order, create_plan_order, create_traffic_addon_order, list_orders, pay_order,
+};
+pub use permission::{get_user_permissions, list_permissions, update_user_permissions};
 pub use plan::{create_plan, delete_plan, get_enabled_plans, get_plan, list_plans, update_plan};
 pub use response::*;
+pub use route::{create_route, delete_route, get_route, list_routes, update_route};
+pub use server::{
+    get_server_rate_schedule, get_servers_health, list_servers, update_server_parent,
+    update_server_rate_schedule, update_server_routes,
+};
+pub use session::{list_sessions, revoke_session};
+pub use subscribe_log::{list_subscribe_logs, reset_subscribe_token};
+pub use subscribe_template::{
+    get_subscribe_template, reset_subscribe_template, update_subscribe_template,
+};
+pub use traffic_reset::{list_traffic_resets, run_traffic_reset};
+pub use two_factor::{
+    disable_two_factor, enable_two_factor, get_two_factor_status, regenerate_recovery_codes,
+    setup_two_factor,
+};
 pub use user::*;
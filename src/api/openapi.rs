@@ -11,13 +11,44 @@ use crate::api::{
 };
 use crate::common::{ApiError, ApiResponse, ErrorCode, PageResponse};
 use crate::models::{
-    auth::{Claims, LoginRequest, RegisterRequest, TokenResponse},
+    api_key::{ApiKeyResponse, CreateApiKeyRequest, CreateApiKeyResponse},
+    auth::{ChangePasswordRequest, Claims, LoginRequest, RegisterRequest, TokenResponse},
+    balance::{BalanceLog, BalanceLogType, BillingSummary},
     coupon::{
         Coupon, CouponListResponse, CouponResponse, CreateCouponRequest, UpdateCouponRequest,
         ValidateCouponResponse,
     },
-    plan::{CreatePlanRequest, Plan, PlanListResponse, PlanResponse, UpdatePlanRequest},
-    user::{User, UserResponse as UserModel},
+    email_code::{ForgotPasswordRequest, ResetPasswordRequest, SendEmailCodeRequest},
+    order::{
+        CreatePlanOrderRequest, CreateTrafficAddonRequest, OrderResponse, OrderStatus, OrderType,
+    },
+    permission::{Permission, UpdatePermissionsRequest, UserPermissionsResponse},
+    plan::{
+        CreatePlanRequest, Plan, PlanAvailability, PlanListResponse, PlanPeriod, PlanPrice,
+        PlanResponse, ResetTrafficMethod, UpdatePlanRequest,
+    },
+    rate_schedule::{RateScheduleResponse, RateWindow, UpdateRateScheduleRequest},
+    route::{
+        CreateRouteRequest, RouteAction, RouteConfig, RouteListResponse, RouteResponse,
+        UpdateRouteRequest, UpdateServerRoutesRequest,
+    },
+    server::{
+        NodeBaseConfig, NodeConfigResponse, NodeStatusPush, NodeUser, NodeUserListResponse,
+        ServerAvailableStatus, ServerHealthResponse, ServerListResponse, ServerLoad,
+        ServerNodeResponse, ServerType, UpdateServerParentRequest,
+    },
+    session::{RefreshTokenRequest, SessionResponse},
+    subscribe_log::{ResetSubscribeTokenRequest, ResetSubscribeTokenResponse, SubscribeLog},
+    subscribe_template::{
+        SubscribeTemplateKind, SubscribeTemplateResponse, UpdateSubscribeTemplateRequest,
+    },
+    traffic_reset::{TrafficResetLog, TrafficResetSummary},
+    two_factor::{
+        DisableTwoFactorRequest, LoginResponse, RecoveryCodesResponse, TwoFactorChallenge,
+        TwoFactorCodeRequest, TwoFactorLoginRequest, TwoFactorSetupResponse,
+        TwoFactorStatusResponse,
+    },
+    user::{Role, User, UserResponse as UserModel},
 };
 
 #[derive(OpenApi)]
@@ -26,12 +57,35 @@ use crate::models::{
         crate::api::health::health_check,
         crate::api::auth::register,
         crate::api::auth::login,
+        crate::api::auth::refresh_token,
+        crate::api::auth::logout,
+        crate::api::auth::send_email_code,
+        crate::api::auth::forgot_password,
+        crate::api::auth::reset_password,
+        crate::api::auth::login_two_factor,
+        crate::api::auth::change_password,
+        crate::api::session::list_sessions,
+        crate::api::session::revoke_session,
+        crate::api::two_factor::get_two_factor_status,
+        crate::api::two_factor::setup_two_factor,
+        crate::api::two_factor::enable_two_factor,
+        crate::api::two_factor::disable_two_factor,
+        crate::api::two_factor::regenerate_recovery_codes,
+        crate::api::api_key::list_api_keys,
+        crate::api::api_key::create_api_key,
+        crate::api::api_key::revoke_api_key,
         crate::api::user::create_user,
         crate::api::user::get_users,
         crate::api::user::get_user,
         crate::api::user::update_user,
         crate::api::user::delete_user,
         crate::api::user::update_user_status,
+        crate::api::user::unlock_user,
+        crate::api::api_key::list_user_api_keys,
+        crate::api::api_key::revoke_user_api_key,
+        crate::api::permission::list_permissions,
+        crate::api::permission::get_user_permissions,
+        crate::api::permission::update_user_permissions,
         crate::api::plan::create_plan,
         crate::api::plan::list_plans,
         crate::api::plan::get_plan,
@@ -44,6 +98,38 @@ use crate::models::{
         crate::api::coupon::update_coupon,
         crate::api::coupon::delete_coupon,
         crate::api::coupon::verify_coupon,
+        crate::api::server::list_servers,
+        crate::api::server::get_servers_health,
+        crate::api::server::update_server_routes,
+        crate::api::server::update_server_parent,
+        crate::api::server::get_server_rate_schedule,
+        crate::api::server::update_server_rate_schedule,
+        crate::api::route::create_route,
+        crate::api::route::list_routes,
+        crate::api::route::get_route,
+        crate::api::route::update_route,
+        crate::api::route::delete_route,
+        crate::api::agent::agent_config,
+        crate::api::agent::agent_users,
+        crate::api::agent::agent_push,
+        crate::api::agent::agent_alive,
+        crate::api::agent::agent_status,
+        crate::api::client::subscribe,
+        crate::api::subscribe_log::reset_subscribe_token,
+        crate::api::subscribe_log::list_subscribe_logs,
+        crate::api::order::create_plan_order,
+        crate::api::order::create_traffic_addon_order,
+        crate::api::order::pay_order,
+        crate::api::order::cancel_order,
+        crate::api::order::list_orders,
+        crate::api::billing::list_balance_logs,
+        crate::api::billing::run_billing,
+        crate::api::billing::list_my_balance_logs,
+        crate::api::traffic_reset::list_traffic_resets,
+        crate::api::traffic_reset::run_traffic_reset,
+        crate::api::subscribe_template::get_subscribe_template,
+        crate::api::subscribe_template::update_subscribe_template,
+        crate::api::subscribe_template::reset_subscribe_template,
     ),
     components(
         schemas(
@@ -54,20 +140,83 @@ use crate::models::{
             GetUsersQuery,
             UserResponse,
             UserModel,
+            Role,
+            Permission,
+            UpdatePermissionsRequest,
+            UserPermissionsResponse,
             Plan,
             CreatePlanRequest,
             UpdatePlanRequest,
+            ResetTrafficMethod,
             PlanResponse,
             PlanListResponse,
+            PlanAvailability,
+            PlanPeriod,
+            PlanPrice,
             Coupon,
             CreateCouponRequest,
             UpdateCouponRequest,
             CouponResponse,
             CouponListResponse,
             ValidateCouponResponse,
+            ServerType,
+            ServerAvailableStatus,
+            ServerLoad,
+            ServerNodeResponse,
+            ServerListResponse,
+            ServerHealthResponse,
+            NodeUser,
+            NodeUserListResponse,
+            NodeStatusPush,
+            NodeBaseConfig,
+            NodeConfigResponse,
+            RouteAction,
+            RouteConfig,
+            CreateRouteRequest,
+            UpdateRouteRequest,
+            RouteResponse,
+            RouteListResponse,
+            UpdateServerRoutesRequest,
+            UpdateServerParentRequest,
+            RateWindow,
+            UpdateRateScheduleRequest,
+            RateScheduleResponse,
+            SubscribeLog,
+            ResetSubscribeTokenRequest,
+            ResetSubscribeTokenResponse,
+            SubscribeTemplateKind,
+            SubscribeTemplateResponse,
+            UpdateSubscribeTemplateRequest,
+            TrafficResetLog,
+            TrafficResetSummary,
+            OrderResponse,
+            OrderType,
+            OrderStatus,
+            CreatePlanOrderRequest,
+            CreateTrafficAddonRequest,
+            BalanceLog,
+            BalanceLogType,
+            BillingSummary,
             RegisterRequest,
             LoginRequest,
             TokenResponse,
+            RefreshTokenRequest,
+            ChangePasswordRequest,
+            SendEmailCodeRequest,
+            ForgotPasswordRequest,
+            ResetPasswordRequest,
+            LoginResponse,
+            TwoFactorChallenge,
+            TwoFactorLoginRequest,
+            TwoFactorStatusResponse,
+            TwoFactorSetupResponse,
+            TwoFactorCodeRequest,
+            DisableTwoFactorRequest,
+            RecoveryCodesResponse,
+            SessionResponse,
+            CreateApiKeyRequest,
+            ApiKeyResponse,
+            CreateApiKeyResponse,
             Claims,
             HealthResponse,
             UserApiResponse,
@@ -80,9 +229,19 @@ use crate::models::{
     tags(
         (name = "health", description = "Health check endpoints"),
         (name = "users", description = "User management endpoints"),
+        (name = "permissions", description = "Staff permission endpoints"),
         (name = "plans", description = "Plan management endpoints"),
         (name = "coupons", description = "Coupon management endpoints"),
         (name = "auth", description = "Authentication endpoints"),
+        (name = "servers", description = "Server node management endpoints"),
+        (name = "server-routes", description = "Server routing rule endpoints"),
+        (name = "agent", description = "Node backend communication endpoints"),
+        (name = "client", description = "Client subscription endpoints"),
+        (name = "traffic-resets", description = "Traffic reset endpoints"),
+        (name = "billing", description = "Metered billing and balance ledger endpoints"),
+        (name = "orders", description = "Order endpoints"),
+        (name = "user", description = "User self-service endpoints"),
+        (name = "subscribe-templates", description = "Subscription template endpoints"),
     )
 )]
 pub struct ApiDoc;
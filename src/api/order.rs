@@ -0,0 +1,181 @@
+use actix_web::{get, post, web, HttpResponse};
+use validator::Validate;
+
+use crate::{
+    common::{ApiError, ApiResult, ResponseBuilder},
+    middleware::RequireSession,
+    models::{
+        order::{CreatePlanOrderRequest, CreateTrafficAddonRequest, OrderQuery, OrderResponse},
+        user::User,
+    },
+    services::OrderService,
+};
+
+/// 购买套餐
+///
+/// 创建待支付订单，订单在保留时长内占用套餐名额
+#[utoipa::path(
+    post,
+    path = "/api/user/orders",
+    tag = "orders",
+    request_body = CreatePlanOrderRequest,
+    responses(
+        (status = 200, description = "创建套餐订单成功", body = crate::common::ApiResponse<OrderResponse>),
+        (status = 400, description = "请求参数错误、套餐不可购买或名额已满", body = crate::common::ApiResponse<()>),
+        (status = 401, description = "未授权", body = crate::common::ApiResponse<()>),
+        (status = 404, description = "套餐不存在", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[post("/orders")]
+pub async fn create_plan_order(
+    user: web::ReqData<User>,
+    request: web::Json<CreatePlanOrderRequest>,
+    service: web::Data<OrderService>,
+) -> ApiResult<HttpResponse> {
+    request.validate().map_err(ApiError::from)?;
+
+    let order = service
+        .checkout(&user, request.plan_id, request.period)
+        .await?;
+
+    Ok(ResponseBuilder::success_with_message(
+        OrderResponse::from(order),
+        "订单创建成功".to_string(),
+    ))
+}
+
+/// 购买流量包
+///
+/// 流量包在下次流量重置或套餐到期时失效，不重置流量的套餐上购买的流量包在到期前一直有效
+#[utoipa::path(
+    post,
+    path = "/api/user/orders/traffic-addon",
+    tag = "orders",
+    request_body = CreateTrafficAddonRequest,
+    responses(
+        (status = 200, description = "创建流量包订单成功", body = crate::common::ApiResponse<OrderResponse>),
+        (status = 400, description = "请求参数错误或当前套餐不支持购买流量包", body = crate::common::ApiResponse<()>),
+        (status = 401, description = "未授权", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[post("/orders/traffic-addon")]
+pub async fn create_traffic_addon_order(
+    user: web::ReqData<User>,
+    request: web::Json<CreateTrafficAddonRequest>,
+    service: web::Data<OrderService>,
+) -> ApiResult<HttpResponse> {
+    request.validate().map_err(ApiError::from)?;
+
+    let order = service
+        .create_traffic_addon(&user, request.transfer_gb)
+        .await?;
+
+    Ok(ResponseBuilder::success_with_message(
+        OrderResponse::from(order),
+        "订单创建成功".to_string(),
+    ))
+}
+
+/// 使用余额支付订单，目前仅支持流量包订单
+#[utoipa::path(
+    post,
+    path = "/api/user/orders/{trade_no}/pay",
+    tag = "orders",
+    params(
+        ("trade_no" = String, Path, description = "订单号")
+    ),
+    responses(
+        (status = 200, description = "支付成功", body = crate::common::ApiResponse<OrderResponse>),
+        (status = 400, description = "订单状态错误或余额不足", body = crate::common::ApiResponse<()>),
+        (status = 401, description = "未授权", body = crate::common::ApiResponse<()>),
+        (status = 403, description = "不支持使用 API 密钥访问", body = crate::common::ApiResponse<()>),
+        (status = 404, description = "订单不存在", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[post("/orders/{trade_no}/pay", wrap = "RequireSession")]
+pub async fn pay_order(
+    user: web::ReqData<User>,
+    path: web::Path<String>,
+    service: web::Data<OrderService>,
+) -> ApiResult<HttpResponse> {
+    let order = service.pay_with_balance(&user, &path.into_inner()).await?;
+
+    Ok(ResponseBuilder::success_with_message(
+        OrderResponse::from(order),
+        "支付成功".to_string(),
+    ))
+}
+
+/// 取消订单
+#[utoipa::path(
+    post,
+    path = "/api/user/orders/{trade_no}/cancel",
+    tag = "orders",
+    params(
+        ("trade_no" = String, Path, description = "订单号")
+    ),
+    responses(
+        (status = 200, description = "订单已取消", body = crate::common::ApiResponse<OrderResponse>),
+        (status = 400, description = "订单不是待支付状态", body = crate::common::ApiResponse<()>),
+        (status = 401, description = "未授权", body = crate::common::ApiResponse<()>),
+        (status = 404, description = "订单不存在", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[post("/orders/{trade_no}/cancel")]
+pub async fn cancel_order(
+    user: web::ReqData<User>,
+    path: web::Path<String>,
+    service: web::Data<OrderService>,
+) -> ApiResult<HttpResponse> {
+    let order = service.cancel(&user, &path.into_inner()).await?;
+
+    Ok(ResponseBuilder::success_with_message(
+        OrderResponse::from(order),
+        "订单已取消".to_string(),
+    ))
+}
+
+/// 获取我的订单
+#[utoipa::path(
+    get,
+    path = "/api/user/orders",
+    tag = "orders",
+    params(OrderQuery),
+    responses(
+        (status = 200, description = "获取订单列表成功", body = crate::common::ApiResponse<crate::common::PageResponse<OrderResponse>>),
+        (status = 401, description = "未授权", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[get("/orders")]
+pub async fn list_orders(
+    user: web::ReqData<User>,
+    query: web::Query<OrderQuery>,
+    service: web::Data<OrderService>,
+) -> ApiResult<HttpResponse> {
+    let page = query.page.max(1);
+    let page_size = query.page_size.clamp(1, 100);
+    let (orders, total) = service.list(user.id, page, page_size).await?;
+    let orders: Vec<OrderResponse> = orders.into_iter().map(OrderResponse::from).collect();
+
+    Ok(ResponseBuilder::page(orders, total as u64, page, page_size))
+}
@@ -0,0 +1,138 @@
+use actix_web::{get, put, web, HttpResponse};
+
+use crate::{
+    common::{ApiError, ApiResult, ErrorCode, ResponseBuilder},
+    middleware::{RequireRole, RequireSession},
+    models::{
+        permission::{Permission, UpdatePermissionsRequest, UserPermissionsResponse},
+        user::{Role, User},
+    },
+    repositories::{PermissionRepository, UserRepository},
+};
+
+/// 获取全部可分配的权限
+#[utoipa::path(
+    get,
+    path = "/api/admin/permissions",
+    tag = "permissions",
+    responses(
+        (status = 200, description = "获取权限列表成功", body = crate::common::ApiResponse<Vec<Permission>>),
+        (status = 403, description = "权限不足", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[get("", wrap = "RequireRole(Role::Admin)", wrap = "RequireSession")]
+pub async fn list_permissions() -> ApiResult<HttpResponse> {
+    Ok(ResponseBuilder::success(Permission::ALL.to_vec()))
+}
+
+/// 获取用户实际生效的权限
+#[utoipa::path(
+    get,
+    path = "/api/admin/users/{id}/permissions",
+    tag = "permissions",
+    params(
+        ("id" = i32, Path, description = "用户ID")
+    ),
+    responses(
+        (status = 200, description = "获取用户权限成功", body = crate::common::ApiResponse<UserPermissionsResponse>),
+        (status = 404, description = "用户不存在", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[get(
+    "/{id}/permissions",
+    wrap = "RequireRole(Role::Admin)",
+    wrap = "RequireSession"
+)]
+pub async fn get_user_permissions(
+    id: web::Path<i32>,
+    user_repo: web::Data<UserRepository>,
+    permission_repo: web::Data<PermissionRepository>,
+) -> ApiResult<HttpResponse> {
+    let user = find_user(&user_repo, *id).await?;
+    let response = effective_permissions(&user, &permission_repo).await?;
+
+    Ok(ResponseBuilder::success(response))
+}
+
+/// 设置客服账户的权限
+///
+/// 传入的列表会整体替换原有权限，仅能为客服账户设置
+#[utoipa::path(
+    put,
+    path = "/api/admin/users/{id}/permissions",
+    tag = "permissions",
+    params(
+        ("id" = i32, Path, description = "用户ID")
+    ),
+    request_body = UpdatePermissionsRequest,
+    responses(
+        (status = 200, description = "设置用户权限成功", body = crate::common::ApiResponse<UserPermissionsResponse>),
+        (status = 400, description = "用户不是客服账户", body = crate::common::ApiResponse<()>),
+        (status = 404, description = "用户不存在", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[put(
+    "/{id}/permissions",
+    wrap = "RequireRole(Role::Admin)",
+    wrap = "RequireSession"
+)]
+pub async fn update_user_permissions(
+    id: web::Path<i32>,
+    request: web::Json<UpdatePermissionsRequest>,
+    user_repo: web::Data<UserRepository>,
+    permission_repo: web::Data<PermissionRepository>,
+) -> ApiResult<HttpResponse> {
+    let user = find_user(&user_repo, *id).await?;
+    if user.role() != Role::Staff {
+        return Err(ApiError::with_message(
+            ErrorCode::InvalidParams,
+            "只能为客服账户设置权限".to_string(),
+        ));
+    }
+
+    permission_repo
+        .replace(user.id, &request.permissions)
+        .await?;
+    let response = effective_permissions(&user, &permission_repo).await?;
+
+    Ok(ResponseBuilder::success_with_message(
+        response,
+        "设置用户权限成功".to_string(),
+    ))
+}
+
+async fn find_user(user_repo: &UserRepository, id: i32) -> ApiResult<User> {
+    user_repo
+        .find_by_id(id)
+        .await?
+        .ok_or_else(|| ApiError::with_message(ErrorCode::UserNotFound, "用户不存在".to_string()))
+}
+
+async fn effective_permissions(
+    user: &User,
+    permission_repo: &PermissionRepository,
+) -> ApiResult<UserPermissionsResponse> {
+    let role = user.role();
+    let permissions = match role {
+        Role::Admin => Permission::ALL.to_vec(),
+        Role::Staff => permission_repo.find_by_user(user.id).await?,
+        Role::User => Vec::new(),
+    };
+
+    Ok(UserPermissionsResponse {
+        user_id: user.id,
+        role,
+        permissions,
+    })
+}
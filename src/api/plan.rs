@@ -5,8 +5,14 @@ use validator::Validate;
 
 use crate::{
     api::response::{ApiError, ApiResponse, Response},
-    models::plan::{CreatePlanRequest, Plan, PlanListResponse, PlanResponse, UpdatePlanRequest},
+    middleware::RequirePermission,
+    models::{
+        permission::Permission,
+        plan::{CreatePlanRequest, Plan, PlanListResponse, PlanResponse, UpdatePlanRequest},
+        user::User,
+    },
     repositories::PlanRepository,
+    services::OrderService,
 };
 
 #[derive(Debug, Deserialize, ToSchema, IntoParams)]
@@ -35,7 +41,7 @@ pub struct ListPlansQuery {
 /// 创建套餐
 #[utoipa::path(
     post,
-    path = "/api/plans",
+    path = "/api/admin/plans",
     tag = "plans",
     request_body = CreatePlanRequest,
     responses(
@@ -44,10 +50,10 @@ pub struct ListPlansQuery {
         (status = 500, description = "Internal server error", body = ResponseError)
     ),
     security(
-        ("jwt_token" = [])
+        ("jwt_token" = ["plans.write"])
     )
 )]
-#[post("/api/plans")]
+#[post("", wrap = "RequirePermission(Permission::PlansWrite)")]
 pub async fn create_plan(
     plan: web::Json<CreatePlanRequest>,
     repo: web::Data<PlanRepository>,
@@ -65,7 +71,7 @@ pub async fn create_plan(
 /// 获取套餐列表
 #[utoipa::path(
     get,
-    path = "/api/plans",
+    path = "/api/admin/plans",
     tag = "plans",
     responses(
         (status = 200, description = "Plans retrieved successfully", body = PlanListResponse),
@@ -77,10 +83,10 @@ pub async fn create_plan(
         ("only_enabled" = bool, Query, description = "Only show enabled plans (default: false)")
     ),
     security(
-        ("jwt_token" = [])
+        ("jwt_token" = ["plans.read"])
     )
 )]
-#[get("/api/plans")]
+#[get("", wrap = "RequirePermission(Permission::PlansRead)")]
 pub async fn list_plans(
     query: web::Query<ListPlansQuery>,
     repo: web::Data<PlanRepository>,
@@ -111,7 +117,7 @@ pub async fn list_plans(
 /// 获取套餐信息
 #[utoipa::path(
     get,
-    path = "/api/plans/{id}",
+    path = "/api/admin/plans/{id}",
     tag = "plans",
     responses(
         (status = 200, description = "Plan found", body = PlanResponse),
@@ -122,10 +128,10 @@ pub async fn list_plans(
         ("id" = i32, Path, description = "Plan id")
     ),
     security(
-        ("jwt_token" = [])
+        ("jwt_token" = ["plans.read"])
     )
 )]
-#[get("/api/plans/{id}")]
+#[get("/{id}", wrap = "RequirePermission(Permission::PlansRead)")]
 pub async fn get_plan(
     id: web::Path<i32>,
     repo: web::Data<PlanRepository>,
@@ -146,7 +152,7 @@ pub async fn get_plan(
 /// 更新套餐信息
 #[utoipa::path(
     put,
-    path = "/api/plans/{id}",
+    path = "/api/admin/plans/{id}",
     tag = "plans",
     request_body = UpdatePlanRequest,
     responses(
@@ -159,10 +165,10 @@ pub async fn get_plan(
         ("id" = i32, Path, description = "Plan id")
     ),
     security(
-        ("jwt_token" = [])
+        ("jwt_token" = ["plans.write"])
     )
 )]
-#[put("/api/plans/{id}")]
+#[put("/{id}", wrap = "RequirePermission(Permission::PlansWrite)")]
 pub async fn update_plan(
     id: web::Path<i32>,
     plan: web::Json<UpdatePlanRequest>,
@@ -181,7 +187,7 @@ pub async fn update_plan(
 /// 删除套餐
 #[utoipa::path(
     delete,
-    path = "/api/plans/{id}",
+    path = "/api/admin/plans/{id}",
     tag = "plans",
     responses(
         (status = 200, description = "Plan deleted successfully"),
@@ -192,10 +198,10 @@ pub async fn update_plan(
         ("id" = i32, Path, description = "Plan id")
     ),
     security(
-        ("jwt_token" = [])
+        ("jwt_token" = ["plans.write"])
     )
 )]
-#[delete("/api/plans/{id}")]
+#[delete("/{id}", wrap = "RequirePermission(Permission::PlansWrite)")]
 pub async fn delete_plan(
     id: web::Path<i32>,
     repo: web::Data<PlanRepository>,
@@ -208,6 +214,9 @@ pub async fn delete_plan(
 }
 
 /// 获取已启用的套餐列表
+///
+/// 无需登录，限制了名额的套餐会返回剩余可售名额；已登录时返回每个套餐对当前用户的购买信息，
+/// 当前订阅的套餐即使已隐藏也会包含在列表中
 #[utoipa::path(
     get,
     path = "/api/plans/enabled",
@@ -217,18 +226,47 @@ pub async fn delete_plan(
         (status = 500, description = "Internal server error", body = ResponseError)
     ),
     security(
+        (),
         ("jwt_token" = [])
     )
 )]
-#[get("/api/plans/enabled")]
-pub async fn get_enabled_plans(repo: web::Data<PlanRepository>) -> Response<HttpResponse> {
-    let plans = repo
+#[get("/enabled")]
+pub async fn get_enabled_plans(
+    user: Option<web::ReqData<User>>,
+    repo: web::Data<PlanRepository>,
+    order_service: web::Data<OrderService>,
+) -> Response<HttpResponse> {
+    let mut plans = repo
         .get_ref()
         .find_enabled()
         .await
         .map_err(ApiError::from)?;
+    if let Some(plan_id) = user.as_ref().and_then(|user| user.plan_id) {
+        if !plans.iter().any(|plan| plan.id == plan_id) {
+            if let Some(plan) = repo.find_by_id(plan_id).await.map_err(ApiError::from)? {
+                plans.push(plan);
+            }
+        }
+    }
+    let seats = order_service
+        .remaining_seats(&plans)
+        .await
+        .map_err(ApiError::from)?;
     let total = plans.len() as i64;
-    let plans = plans.into_iter().map(PlanResponse::from).collect();
+    let plans = plans
+        .into_iter()
+        .map(|plan| {
+            let remaining_seats = seats.get(&plan.id).copied();
+            let availability = user
+                .as_ref()
+                .map(|user| OrderService::availability(user, &plan, remaining_seats));
+            PlanResponse {
+                remaining_seats,
+                availability,
+                ..PlanResponse::from(plan)
+            }
+        })
+        .collect();
 
     Ok(HttpResponse::Ok().json(ApiResponse::success(PlanListResponse { plans, total })))
 }
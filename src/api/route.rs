@@ -0,0 +1,177 @@
+use actix_web::{delete, get, post, put, web, HttpResponse};
+use validator::Validate;
+
+use crate::{
+    common::{ApiError, ApiResult, ErrorCode, ResponseBuilder},
+    middleware::RequirePermission,
+    models::{
+        permission::Permission,
+        route::{
+            CreateRouteRequest, RouteAction, RouteListResponse, RouteResponse, UpdateRouteRequest,
+        },
+    },
+    repositories::RouteRepository,
+};
+
+/// 创建路由规则
+#[utoipa::path(
+    post,
+    path = "/api/admin/server-routes",
+    tag = "server-routes",
+    request_body = CreateRouteRequest,
+    responses(
+        (status = 200, description = "创建路由规则成功", body = crate::common::ApiResponse<RouteResponse>),
+        (status = 400, description = "请求参数无效", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = ["servers.write"])
+    )
+)]
+#[post("", wrap = "RequirePermission(Permission::ServersWrite)")]
+pub async fn create_route(
+    route: web::Json<CreateRouteRequest>,
+    repo: web::Data<RouteRepository>,
+) -> ApiResult<HttpResponse> {
+    route.validate()?;
+
+    let route = repo.create(&route.into_inner()).await?;
+    Ok(ResponseBuilder::success(RouteResponse::from(route)))
+}
+
+/// 获取路由规则列表
+#[utoipa::path(
+    get,
+    path = "/api/admin/server-routes",
+    tag = "server-routes",
+    responses(
+        (status = 200, description = "获取路由规则列表成功", body = crate::common::ApiResponse<RouteListResponse>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = ["servers.read"])
+    )
+)]
+#[get("", wrap = "RequirePermission(Permission::ServersRead)")]
+pub async fn list_routes(repo: web::Data<RouteRepository>) -> ApiResult<HttpResponse> {
+    let routes = repo.find_all().await?;
+    let total = routes.len() as i64;
+    let routes = routes.into_iter().map(RouteResponse::from).collect();
+
+    Ok(ResponseBuilder::success(RouteListResponse {
+        routes,
+        total,
+    }))
+}
+
+/// 获取路由规则
+#[utoipa::path(
+    get,
+    path = "/api/admin/server-routes/{id}",
+    tag = "server-routes",
+    params(
+        ("id" = i32, Path, description = "路由规则ID")
+    ),
+    responses(
+        (status = 200, description = "获取路由规则成功", body = crate::common::ApiResponse<RouteResponse>),
+        (status = 400, description = "路由规则不存在", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = ["servers.read"])
+    )
+)]
+#[get("/{id}", wrap = "RequirePermission(Permission::ServersRead)")]
+pub async fn get_route(
+    id: web::Path<i32>,
+    repo: web::Data<RouteRepository>,
+) -> ApiResult<HttpResponse> {
+    match repo.find_by_id(id.into_inner()).await? {
+        Some(route) => Ok(ResponseBuilder::success(RouteResponse::from(route))),
+        None => Err(route_not_found()),
+    }
+}
+
+/// 更新路由规则
+#[utoipa::path(
+    put,
+    path = "/api/admin/server-routes/{id}",
+    tag = "server-routes",
+    params(
+        ("id" = i32, Path, description = "路由规则ID")
+    ),
+    request_body = UpdateRouteRequest,
+    responses(
+        (status = 200, description = "更新路由规则成功", body = crate::common::ApiResponse<RouteResponse>),
+        (status = 400, description = "请求参数无效", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = ["servers.write"])
+    )
+)]
+#[put("/{id}", wrap = "RequirePermission(Permission::ServersWrite)")]
+pub async fn update_route(
+    id: web::Path<i32>,
+    route: web::Json<UpdateRouteRequest>,
+    repo: web::Data<RouteRepository>,
+) -> ApiResult<HttpResponse> {
+    route.validate()?;
+    let id = id.into_inner();
+
+    let existing = repo.find_by_id(id).await?.ok_or_else(route_not_found)?;
+
+    // 合并后的动作仍需满足 action_value 要求
+    let action = route.action.as_deref().unwrap_or(&existing.action);
+    let action_value = match &route.action_value {
+        Some(action_value) => action_value.as_deref(),
+        None => existing.action_value.as_deref(),
+    }
+    .unwrap_or_default();
+    let requires_value = action
+        .parse::<RouteAction>()
+        .map(|action| action.requires_value())
+        .unwrap_or(false);
+    if requires_value && action_value.trim().is_empty() {
+        return Err(ApiError::with_message(
+            ErrorCode::ValidationError,
+            format!("动作 {} 需要设置 action_value", action),
+        ));
+    }
+
+    let route = repo.update(id, &route.into_inner()).await?;
+    Ok(ResponseBuilder::success(RouteResponse::from(route)))
+}
+
+/// 删除路由规则
+#[utoipa::path(
+    delete,
+    path = "/api/admin/server-routes/{id}",
+    tag = "server-routes",
+    params(
+        ("id" = i32, Path, description = "路由规则ID")
+    ),
+    responses(
+        (status = 200, description = "删除路由规则成功", body = crate::common::ApiResponse<()>),
+        (status = 400, description = "路由规则不存在", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = ["servers.write"])
+    )
+)]
+#[delete("/{id}", wrap = "RequirePermission(Permission::ServersWrite)")]
+pub async fn delete_route(
+    id: web::Path<i32>,
+    repo: web::Data<RouteRepository>,
+) -> ApiResult<HttpResponse> {
+    if !repo.delete(id.into_inner()).await? {
+        return Err(route_not_found());
+    }
+
+    Ok(ResponseBuilder::success(()))
+}
+
+fn route_not_found() -> ApiError {
+    ApiError::with_message(ErrorCode::InvalidParams, "路由规则不存在".to_string())
+}
@@ -0,0 +1,238 @@
+use actix_web::{get, put, web, HttpResponse};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use validator::Validate;
+
+use crate::{
+    common::{ApiError, ApiResult, ErrorCode, ResponseBuilder},
+    middleware::RequirePermission,
+    models::{
+        permission::Permission,
+        rate_schedule::UpdateRateScheduleRequest,
+        route::UpdateServerRoutesRequest,
+        server::{ServerListResponse, ServerType, UpdateServerParentRequest},
+    },
+    services::ServerService,
+};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ListServersQuery {
+    /// 节点类型（vmess/vless/trojan/shadowsocks/hysteria）
+    #[serde(rename = "type")]
+    pub r#type: Option<String>,
+}
+
+/// 获取节点列表（包含在线状态）
+#[utoipa::path(
+    get,
+    path = "/api/admin/servers",
+    tag = "servers",
+    params(ListServersQuery),
+    responses(
+        (status = 200, description = "获取节点列表成功", body = crate::common::ApiResponse<ServerListResponse>),
+        (status = 400, description = "请求参数无效", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = ["servers.read"])
+    )
+)]
+#[get("", wrap = "RequirePermission(Permission::ServersRead)")]
+pub async fn list_servers(
+    query: web::Query<ListServersQuery>,
+    service: web::Data<ServerService>,
+) -> ApiResult<HttpResponse> {
+    let server_type = match &query.r#type {
+        Some(server_type) => Some(
+            server_type
+                .parse::<ServerType>()
+                .map_err(|e| ApiError::with_message(ErrorCode::InvalidParams, e.to_string()))?,
+        ),
+        None => None,
+    };
+
+    let servers = service.list_nodes(server_type).await?;
+    let total = servers.len() as i64;
+
+    Ok(ResponseBuilder::success(ServerListResponse {
+        servers,
+        total,
+    }))
+}
+
+/// 获取节点健康概况
+#[utoipa::path(
+    get,
+    path = "/api/admin/servers/health",
+    tag = "servers",
+    responses(
+        (status = 200, description = "获取节点健康概况成功", body = crate::common::ApiResponse<crate::models::server::ServerHealthResponse>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = ["servers.read"])
+    )
+)]
+#[get("/health", wrap = "RequirePermission(Permission::ServersRead)")]
+pub async fn get_servers_health(service: web::Data<ServerService>) -> ApiResult<HttpResponse> {
+    let health = service.health().await?;
+    Ok(ResponseBuilder::success(health))
+}
+
+/// 为节点分配路由规则
+#[utoipa::path(
+    put,
+    path = "/api/admin/servers/{type}/{id}/routes",
+    tag = "servers",
+    params(
+        ("type" = String, Path, description = "节点类型"),
+        ("id" = i32, Path, description = "节点ID")
+    ),
+    request_body = UpdateServerRoutesRequest,
+    responses(
+        (status = 200, description = "分配路由规则成功", body = crate::common::ApiResponse<()>),
+        (status = 400, description = "节点或路由规则不存在", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = ["servers.write"])
+    )
+)]
+#[put(
+    "/{type}/{id}/routes",
+    wrap = "RequirePermission(Permission::ServersWrite)"
+)]
+pub async fn update_server_routes(
+    path: web::Path<(String, i32)>,
+    request: web::Json<UpdateServerRoutesRequest>,
+    service: web::Data<ServerService>,
+) -> ApiResult<HttpResponse> {
+    let (server_type, id) = path.into_inner();
+    let server_type = server_type
+        .parse::<ServerType>()
+        .map_err(|e| ApiError::with_message(ErrorCode::InvalidParams, e.to_string()))?;
+
+    service
+        .update_routes(server_type, id, &request.route_ids)
+        .await?;
+
+    Ok(ResponseBuilder::success(()))
+}
+
+/// 设置节点的父节点（中转节点）
+#[utoipa::path(
+    put,
+    path = "/api/admin/servers/{type}/{id}/parent",
+    tag = "servers",
+    params(
+        ("type" = String, Path, description = "节点类型"),
+        ("id" = i32, Path, description = "节点ID")
+    ),
+    request_body = UpdateServerParentRequest,
+    responses(
+        (status = 200, description = "设置父节点成功", body = crate::common::ApiResponse<()>),
+        (status = 400, description = "父节点无效或形成循环引用", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = ["servers.write"])
+    )
+)]
+#[put(
+    "/{type}/{id}/parent",
+    wrap = "RequirePermission(Permission::ServersWrite)"
+)]
+pub async fn update_server_parent(
+    path: web::Path<(String, i32)>,
+    request: web::Json<UpdateServerParentRequest>,
+    service: web::Data<ServerService>,
+) -> ApiResult<HttpResponse> {
+    let (server_type, id) = path.into_inner();
+    let server_type = server_type
+        .parse::<ServerType>()
+        .map_err(|e| ApiError::with_message(ErrorCode::InvalidParams, e.to_string()))?;
+
+    service
+        .update_parent(server_type, id, request.parent_id, request.parent_type)
+        .await?;
+
+    Ok(ResponseBuilder::success(()))
+}
+
+/// 获取节点的时间段倍率
+#[utoipa::path(
+    get,
+    path = "/api/admin/servers/{type}/{id}/rate-schedule",
+    tag = "servers",
+    params(
+        ("type" = String, Path, description = "节点类型"),
+        ("id" = i32, Path, description = "节点ID")
+    ),
+    responses(
+        (status = 200, description = "获取时间段倍率成功", body = crate::common::ApiResponse<crate::models::rate_schedule::RateScheduleResponse>),
+        (status = 400, description = "节点不存在", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = ["servers.read"])
+    )
+)]
+#[get(
+    "/{type}/{id}/rate-schedule",
+    wrap = "RequirePermission(Permission::ServersRead)"
+)]
+pub async fn get_server_rate_schedule(
+    path: web::Path<(String, i32)>,
+    service: web::Data<ServerService>,
+) -> ApiResult<HttpResponse> {
+    let (server_type, id) = path.into_inner();
+    let server_type = server_type
+        .parse::<ServerType>()
+        .map_err(|e| ApiError::with_message(ErrorCode::InvalidParams, e.to_string()))?;
+
+    let schedule = service.rate_schedule(server_type, id).await?;
+    Ok(ResponseBuilder::success(schedule))
+}
+
+/// 设置节点的时间段倍率
+///
+/// 未命中任何时间段时使用节点的基础倍率，传入空列表即恢复为固定倍率
+#[utoipa::path(
+    put,
+    path = "/api/admin/servers/{type}/{id}/rate-schedule",
+    tag = "servers",
+    params(
+        ("type" = String, Path, description = "节点类型"),
+        ("id" = i32, Path, description = "节点ID")
+    ),
+    request_body = UpdateRateScheduleRequest,
+    responses(
+        (status = 200, description = "设置时间段倍率成功", body = crate::common::ApiResponse<crate::models::rate_schedule::RateScheduleResponse>),
+        (status = 400, description = "节点不存在或时间段无效", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = ["servers.write"])
+    )
+)]
+#[put(
+    "/{type}/{id}/rate-schedule",
+    wrap = "RequirePermission(Permission::ServersWrite)"
+)]
+pub async fn update_server_rate_schedule(
+    path: web::Path<(String, i32)>,
+    request: web::Json<UpdateRateScheduleRequest>,
+    service: web::Data<ServerService>,
+) -> ApiResult<HttpResponse> {
+    request.validate().map_err(ApiError::from)?;
+
+    let (server_type, id) = path.into_inner();
+    let server_type = server_type
+        .parse::<ServerType>()
+        .map_err(|e| ApiError::with_message(ErrorCode::InvalidParams, e.to_string()))?;
+
+    let schedule = service
+        .update_rate_schedule(server_type, id, request.into_inner().windows)
+        .await?;
+    Ok(ResponseBuilder::success(schedule))
+}
@@ -0,0 +1,62 @@
+use actix_web::{delete, get, web, HttpResponse};
+
+use crate::{
+    common::{ApiResult, ResponseBuilder},
+    middleware::RequireSession,
+    models::session::Session,
+    services::AuthService,
+};
+
+/// 获取我的登录会话
+#[utoipa::path(
+    get,
+    path = "/api/user/sessions",
+    tag = "user",
+    responses(
+        (status = 200, description = "获取登录会话成功", body = crate::common::ApiResponse<Vec<crate::models::session::SessionResponse>>),
+        (status = 401, description = "未登录", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[get("/sessions", wrap = "RequireSession")]
+pub async fn list_sessions(
+    session: web::ReqData<Session>,
+    service: web::Data<AuthService>,
+) -> ApiResult<HttpResponse> {
+    let sessions = service.sessions(session.user_id, session.id).await?;
+    Ok(ResponseBuilder::success(sessions))
+}
+
+/// 撤销我的登录会话
+///
+/// 被撤销会话的访问令牌和刷新令牌立即失效
+#[utoipa::path(
+    delete,
+    path = "/api/user/sessions/{id}",
+    tag = "user",
+    params(
+        ("id" = i32, Path, description = "会话ID")
+    ),
+    responses(
+        (status = 200, description = "撤销会话成功", body = crate::common::ApiResponse<()>),
+        (status = 400, description = "会话不存在或已失效", body = crate::common::ApiResponse<()>),
+        (status = 401, description = "未登录", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[delete("/sessions/{id}", wrap = "RequireSession")]
+pub async fn revoke_session(
+    id: web::Path<i32>,
+    session: web::ReqData<Session>,
+    service: web::Data<AuthService>,
+) -> ApiResult<HttpResponse> {
+    service.revoke_session(session.user_id, *id).await?;
+    Ok(ResponseBuilder::success_with_message(
+        (),
+        "撤销会话成功".to_string(),
+    ))
+}
@@ -0,0 +1,70 @@
+use actix_web::{get, post, web, HttpResponse};
+
+use crate::{
+    common::{ApiResult, ResponseBuilder},
+    middleware::RequireSession,
+    models::{
+        subscribe_log::{ResetSubscribeTokenRequest, SubscribeLogQuery},
+        user::User,
+    },
+    services::SubscriptionService,
+};
+
+/// 重置订阅令牌
+#[utoipa::path(
+    post,
+    path = "/api/user/subscribe/reset-token",
+    tag = "user",
+    request_body = ResetSubscribeTokenRequest,
+    responses(
+        (status = 200, description = "重置订阅令牌成功，旧订阅链接立即失效", body = crate::common::ApiResponse<crate::models::subscribe_log::ResetSubscribeTokenResponse>),
+        (status = 401, description = "未授权", body = crate::common::ApiResponse<()>),
+        (status = 403, description = "不支持使用 API 密钥访问", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[post("/subscribe/reset-token", wrap = "RequireSession")]
+pub async fn reset_subscribe_token(
+    user: web::ReqData<User>,
+    request: Option<web::Json<ResetSubscribeTokenRequest>>,
+    service: web::Data<SubscriptionService>,
+) -> ApiResult<HttpResponse> {
+    let request = request.map(|r| r.into_inner()).unwrap_or_default();
+    let response = service.reset_token(user.id, request.reset_uuid).await?;
+
+    Ok(ResponseBuilder::success_with_message(
+        response,
+        "订阅令牌已重置".to_string(),
+    ))
+}
+
+/// 获取订阅拉取记录
+#[utoipa::path(
+    get,
+    path = "/api/user/subscribe/logs",
+    tag = "user",
+    params(SubscribeLogQuery),
+    responses(
+        (status = 200, description = "获取订阅拉取记录成功", body = crate::common::ApiResponse<crate::common::PageResponse<crate::models::subscribe_log::SubscribeLog>>),
+        (status = 401, description = "未授权", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[get("/subscribe/logs")]
+pub async fn list_subscribe_logs(
+    user: web::ReqData<User>,
+    query: web::Query<SubscribeLogQuery>,
+    service: web::Data<SubscriptionService>,
+) -> ApiResult<HttpResponse> {
+    let page = query.page.max(1);
+    let page_size = query.page_size.clamp(1, 100);
+    let (logs, total) = service.access_logs(user.id, page, page_size).await?;
+
+    Ok(ResponseBuilder::page(logs, total as u64, page, page_size))
+}
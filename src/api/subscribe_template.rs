@@ -0,0 +1,104 @@
+use actix_web::{delete, get, put, web, HttpResponse};
+use validator::Validate;
+
+use crate::{
+    common::{ApiError, ApiResult, ErrorCode, ResponseBuilder},
+    middleware::RequirePermission,
+    models::{
+        permission::Permission,
+        subscribe_template::{SubscribeTemplateKind, UpdateSubscribeTemplateRequest},
+    },
+    services::SubscriptionService,
+};
+
+/// 获取订阅模板（未自定义时返回内置默认模板）
+#[utoipa::path(
+    get,
+    path = "/api/admin/subscribe-templates/{name}",
+    tag = "subscribe-templates",
+    params(
+        ("name" = String, Path, description = "模板类型（clash/sing-box）")
+    ),
+    responses(
+        (status = 200, description = "获取订阅模板成功", body = crate::common::ApiResponse<crate::models::subscribe_template::SubscribeTemplateResponse>),
+        (status = 400, description = "模板类型无效", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = ["settings.read"])
+    )
+)]
+#[get("/{name}", wrap = "RequirePermission(Permission::SettingsRead)")]
+pub async fn get_subscribe_template(
+    name: web::Path<String>,
+    service: web::Data<SubscriptionService>,
+) -> ApiResult<HttpResponse> {
+    let kind = parse_kind(&name)?;
+    let template = service.get_template(kind).await?;
+
+    Ok(ResponseBuilder::success(template))
+}
+
+/// 更新订阅模板
+#[utoipa::path(
+    put,
+    path = "/api/admin/subscribe-templates/{name}",
+    tag = "subscribe-templates",
+    params(
+        ("name" = String, Path, description = "模板类型（clash/sing-box）")
+    ),
+    request_body = UpdateSubscribeTemplateRequest,
+    responses(
+        (status = 200, description = "更新订阅模板成功", body = crate::common::ApiResponse<crate::models::subscribe_template::SubscribeTemplateResponse>),
+        (status = 400, description = "模板类型或内容无效", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = ["settings.write"])
+    )
+)]
+#[put("/{name}", wrap = "RequirePermission(Permission::SettingsWrite)")]
+pub async fn update_subscribe_template(
+    name: web::Path<String>,
+    template: web::Json<UpdateSubscribeTemplateRequest>,
+    service: web::Data<SubscriptionService>,
+) -> ApiResult<HttpResponse> {
+    template.validate()?;
+    let kind = parse_kind(&name)?;
+    let template = service.update_template(kind, &template.content).await?;
+
+    Ok(ResponseBuilder::success(template))
+}
+
+/// 重置订阅模板为内置默认模板
+#[utoipa::path(
+    delete,
+    path = "/api/admin/subscribe-templates/{name}",
+    tag = "subscribe-templates",
+    params(
+        ("name" = String, Path, description = "模板类型（clash/sing-box）")
+    ),
+    responses(
+        (status = 200, description = "重置订阅模板成功", body = crate::common::ApiResponse<crate::models::subscribe_template::SubscribeTemplateResponse>),
+        (status = 400, description = "模板类型无效", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = ["settings.write"])
+    )
+)]
+#[delete("/{name}", wrap = "RequirePermission(Permission::SettingsWrite)")]
+pub async fn reset_subscribe_template(
+    name: web::Path<String>,
+    service: web::Data<SubscriptionService>,
+) -> ApiResult<HttpResponse> {
+    let kind = parse_kind(&name)?;
+    let template = service.reset_template(kind).await?;
+
+    Ok(ResponseBuilder::success(template))
+}
+
+fn parse_kind(name: &str) -> Result<SubscribeTemplateKind, ApiError> {
+    name.parse::<SubscribeTemplateKind>()
+        .map_err(|e| ApiError::with_message(ErrorCode::InvalidParams, e.to_string()))
+}
@@ -0,0 +1,76 @@
+use actix_web::{get, post, web, HttpResponse};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    common::{ApiError, ApiResult, ErrorCode, ResponseBuilder},
+    middleware::RequirePermission,
+    models::{permission::Permission, traffic_reset::TrafficResetLogQuery},
+    services::TrafficResetService,
+};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct RunTrafficResetQuery {
+    /// 按指定日期（YYYY-MM-DD）执行，用于补跑错过的重置日，默认为当天
+    pub date: Option<String>,
+}
+
+/// 获取流量重置记录
+#[utoipa::path(
+    get,
+    path = "/api/admin/traffic-resets",
+    tag = "traffic-resets",
+    params(TrafficResetLogQuery),
+    responses(
+        (status = 200, description = "获取流量重置记录成功", body = crate::common::ApiResponse<crate::common::PageResponse<crate::models::traffic_reset::TrafficResetLog>>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = ["users.read"])
+    )
+)]
+#[get("", wrap = "RequirePermission(Permission::UsersRead)")]
+pub async fn list_traffic_resets(
+    query: web::Query<TrafficResetLogQuery>,
+    service: web::Data<TrafficResetService>,
+) -> ApiResult<HttpResponse> {
+    let page = query.page.max(1);
+    let page_size = query.page_size.clamp(1, 100);
+    let (logs, total) = service.logs(query.user_id, page, page_size).await?;
+
+    Ok(ResponseBuilder::page(logs, total as u64, page, page_size))
+}
+
+/// 立即执行流量重置
+///
+/// 已在本周期重置过的用户会被跳过，可重复执行
+#[utoipa::path(
+    post,
+    path = "/api/admin/traffic-resets/run",
+    tag = "traffic-resets",
+    params(RunTrafficResetQuery),
+    responses(
+        (status = 200, description = "执行流量重置成功", body = crate::common::ApiResponse<crate::models::traffic_reset::TrafficResetSummary>),
+        (status = 400, description = "日期格式无效", body = crate::common::ApiResponse<()>),
+        (status = 500, description = "内部服务器错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = ["users.write"])
+    )
+)]
+#[post("/run", wrap = "RequirePermission(Permission::UsersWrite)")]
+pub async fn run_traffic_reset(
+    query: web::Query<RunTrafficResetQuery>,
+    service: web::Data<TrafficResetService>,
+) -> ApiResult<HttpResponse> {
+    let date = match &query.date {
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| {
+            ApiError::with_message(ErrorCode::InvalidParams, format!("日期格式无效: {}", date))
+        })?,
+        None => service.today(),
+    };
+
+    let summary = service.run(date).await?;
+    Ok(ResponseBuilder::success(summary))
+}
@@ -0,0 +1,153 @@
+use actix_web::{get, post, web, HttpResponse};
+use validator::Validate;
+
+use crate::{
+    common::{ApiResult, ResponseBuilder},
+    middleware::RequireSession,
+    models::{
+        two_factor::{DisableTwoFactorRequest, TwoFactorCodeRequest},
+        user::User,
+    },
+    services::{AuthService, TwoFactorService},
+};
+
+/// 获取双重验证状态
+#[utoipa::path(
+    get,
+    path = "/api/user/2fa",
+    tag = "user",
+    responses(
+        (status = 200, description = "获取双重验证状态成功", body = crate::common::ApiResponse<crate::models::two_factor::TwoFactorStatusResponse>),
+        (status = 401, description = "未登录", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[get("/2fa", wrap = "RequireSession")]
+pub async fn get_two_factor_status(
+    user: web::ReqData<User>,
+    service: web::Data<TwoFactorService>,
+) -> ApiResult<HttpResponse> {
+    let status = service.status(&user).await?;
+    Ok(ResponseBuilder::success(status))
+}
+
+/// 生成双重验证密钥
+///
+/// 返回密钥和 `otpauth://` 链接，需调用启用接口提交第一个验证码后才会生效
+#[utoipa::path(
+    post,
+    path = "/api/user/2fa/setup",
+    tag = "user",
+    responses(
+        (status = 200, description = "生成密钥成功", body = crate::common::ApiResponse<crate::models::two_factor::TwoFactorSetupResponse>),
+        (status = 400, description = "双重验证已启用", body = crate::common::ApiResponse<()>),
+        (status = 401, description = "未登录", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[post("/2fa/setup", wrap = "RequireSession")]
+pub async fn setup_two_factor(
+    user: web::ReqData<User>,
+    service: web::Data<TwoFactorService>,
+) -> ApiResult<HttpResponse> {
+    let setup = service.setup(&user).await?;
+    Ok(ResponseBuilder::success(setup))
+}
+
+/// 启用双重验证
+///
+/// 提交身份验证器中的第一个验证码确认密钥，返回的恢复码只显示这一次
+#[utoipa::path(
+    post,
+    path = "/api/user/2fa/enable",
+    tag = "user",
+    request_body = TwoFactorCodeRequest,
+    responses(
+        (status = 200, description = "启用成功", body = crate::common::ApiResponse<crate::models::two_factor::RecoveryCodesResponse>),
+        (status = 400, description = "验证码错误或未生成密钥", body = crate::common::ApiResponse<()>),
+        (status = 401, description = "未登录", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[post("/2fa/enable", wrap = "RequireSession")]
+pub async fn enable_two_factor(
+    user: web::ReqData<User>,
+    request: web::Json<TwoFactorCodeRequest>,
+    service: web::Data<TwoFactorService>,
+) -> ApiResult<HttpResponse> {
+    request.validate()?;
+    let recovery_codes = service.enable(&user, &request.code).await?;
+    Ok(ResponseBuilder::success_with_message(
+        recovery_codes,
+        "双重验证已启用，请妥善保存恢复码".to_string(),
+    ))
+}
+
+/// 关闭双重验证
+///
+/// 需要同时提供密码和验证码（或恢复码）
+#[utoipa::path(
+    post,
+    path = "/api/user/2fa/disable",
+    tag = "user",
+    request_body = DisableTwoFactorRequest,
+    responses(
+        (status = 200, description = "关闭成功", body = crate::common::ApiResponse<()>),
+        (status = 400, description = "验证码错误", body = crate::common::ApiResponse<()>),
+        (status = 401, description = "未登录或密码错误", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[post("/2fa/disable", wrap = "RequireSession")]
+pub async fn disable_two_factor(
+    user: web::ReqData<User>,
+    request: web::Json<DisableTwoFactorRequest>,
+    service: web::Data<AuthService>,
+) -> ApiResult<HttpResponse> {
+    request.validate()?;
+    service
+        .disable_two_factor(&user, &request.password, &request.code)
+        .await?;
+    Ok(ResponseBuilder::success_with_message(
+        (),
+        "双重验证已关闭".to_string(),
+    ))
+}
+
+/// 重新生成恢复码
+///
+/// 提交身份验证器中的验证码，旧恢复码全部失效
+#[utoipa::path(
+    post,
+    path = "/api/user/2fa/recovery-codes",
+    tag = "user",
+    request_body = TwoFactorCodeRequest,
+    responses(
+        (status = 200, description = "生成成功", body = crate::common::ApiResponse<crate::models::two_factor::RecoveryCodesResponse>),
+        (status = 400, description = "验证码错误", body = crate::common::ApiResponse<()>),
+        (status = 401, description = "未登录", body = crate::common::ApiResponse<()>)
+    ),
+    security(
+        ("jwt_token" = [])
+    )
+)]
+#[post("/2fa/recovery-codes", wrap = "RequireSession")]
+pub async fn regenerate_recovery_codes(
+    user: web::ReqData<User>,
+    request: web::Json<TwoFactorCodeRequest>,
+    service: web::Data<TwoFactorService>,
+) -> ApiResult<HttpResponse> {
+    request.validate()?;
+    let recovery_codes = service
+        .regenerate_recovery_codes(&user, &request.code)
+        .await?;
+    Ok(ResponseBuilder::success(recovery_codes))
+}
@@ -5,8 +5,14 @@ use uuid::Uuid;
 
 use crate::{
     common::{ErrorCode, PageResponse, ResponseBuilder},
-    models::user::{CreateUser, User},
-    repositories::UserRepository,
+    middleware::RequirePermission,
+    models::{
+        auth::validate_username,
+        permission::Permission,
+        user::{CreateUser, User},
+    },
+    repositories::{PlanRepository, UserRepository},
+    services::AuthService,
 };
 
 // 为OpenAPI定义具体的响应结构体
@@ -46,6 +52,8 @@ pub struct UserPageApiResponse {
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateUserRequest {
     pub email: String,
+    /// 用户名，3-20 位字母、数字或下划线，以字母开头
+    pub username: Option<String>,
     pub password: String,
     pub invite_user_id: Option<i32>,
 }
@@ -53,9 +61,12 @@ pub struct CreateUserRequest {
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdateUserRequest {
     pub email: Option<String>,
+    /// 用户名，3-20 位字母、数字或下划线，以字母开头
+    pub username: Option<String>,
     pub password: Option<String>,
     pub remarks: Option<String>,
     pub group_id: Option<i32>,
+    /// 开通的套餐，可用流量恢复为套餐流量，已购买的流量包随之失效
     pub plan_id: Option<i32>,
     pub speed_limit: Option<i32>,
 }
@@ -81,28 +92,61 @@ fn default_page_size() -> u64 {
     10
 }
 
+/// 当前账户不能管理目标账户时返回 `PermissionDenied` 响应，见 [`Role::can_manage`](crate::models::user::Role::can_manage)
+pub(crate) fn forbid_unmanageable(caller: &User, target: &User) -> Option<HttpResponse> {
+    if caller.role().can_manage(target.role()) {
+        return None;
+    }
+    tracing::warn!(
+        "用户 {} 尝试管理角色不低于自己的账户 {}",
+        caller.id,
+        target.id
+    );
+    Some(ResponseBuilder::error_with_message(
+        ErrorCode::PermissionDenied,
+        "不能管理角色不低于自己的账户".to_string(),
+    ))
+}
+
 /// 创建用户
 #[utoipa::path(
     post,
-    path = "/users",
+    path = "/api/admin/users",
     tag = "users",
     request_body = CreateUserRequest,
     responses(
         (status = 200, description = "创建用户成功", body = UserApiResponse),
         (status = 400, description = "创建用户失败", body = EmptyApiResponse),
+    ),
+    security(
+        ("jwt_token" = ["users.write"])
     )
 )]
-#[post("/users")]
+#[post("", wrap = "RequirePermission(Permission::UsersWrite)")]
 pub async fn create_user(
     user_repo: web::Data<UserRepository>,
     user: web::Json<CreateUserRequest>,
 ) -> HttpResponse {
+    if let Some(Err(e)) = user.username.as_deref().map(validate_username) {
+        return ResponseBuilder::error_with_message(ErrorCode::ValidationError, e.to_string());
+    }
+    let password = match AuthService::hash_password(&user.password) {
+        Ok(password) => password,
+        Err(e) => {
+            tracing::error!("创建用户失败: {}", e);
+            return ResponseBuilder::error_with_message(
+                ErrorCode::InternalError,
+                "密码加密失败".to_string(),
+            );
+        }
+    };
     let user = CreateUser {
         email: user.email.clone(),
-        password: user.password.clone(),
+        username: user.username.clone(),
+        password,
         invite_user_id: user.invite_user_id,
         uuid: Uuid::new_v4().to_string(),
-        token: Uuid::new_v4().to_string(),
+        token: Uuid::new_v4().simple().to_string(),
     };
 
     match user_repo.create(user).await {
@@ -128,7 +172,7 @@ pub async fn create_user(
 /// 获取用户列表
 #[utoipa::path(
     get,
-    path = "/users",
+    path = "/api/admin/users",
     tag = "users",
     params(
         GetUsersQuery
@@ -136,9 +180,12 @@ pub async fn create_user(
     responses(
         (status = 200, description = "获取用户列表成功", body = UserPageApiResponse),
         (status = 500, description = "服务器内部错误", body = EmptyApiResponse),
+    ),
+    security(
+        ("jwt_token" = ["users.read"])
     )
 )]
-#[get("/users")]
+#[get("", wrap = "RequirePermission(Permission::UsersRead)")]
 pub async fn get_users(
     user_repo: web::Data<UserRepository>,
     query: web::Query<GetUsersQuery>,
@@ -164,7 +211,7 @@ pub async fn get_users(
 /// 获取用户信息
 #[utoipa::path(
     get,
-    path = "/users/{id}",
+    path = "/api/admin/users/{id}",
     tag = "users",
     params(
         ("id" = i32, Path, description = "用户ID"),
@@ -173,9 +220,12 @@ pub async fn get_users(
         (status = 200, description = "获取用户成功", body = UserApiResponse),
         (status = 404, description = "用户不存在", body = EmptyApiResponse),
         (status = 500, description = "服务器内部错误", body = EmptyApiResponse),
+    ),
+    security(
+        ("jwt_token" = ["users.read"])
     )
 )]
-#[get("/users/{id}")]
+#[get("/{id}", wrap = "RequirePermission(Permission::UsersRead)")]
 pub async fn get_user(user_repo: web::Data<UserRepository>, id: web::Path<i32>) -> HttpResponse {
     match user_repo.find_by_id(*id).await {
         Ok(Some(user)) => ResponseBuilder::success_with_message(user, "获取用户成功".to_string()),
@@ -195,7 +245,7 @@ pub async fn get_user(user_repo: web::Data<UserRepository>, id: web::Path<i32>)
 /// 更新用户信息
 #[utoipa::path(
     put,
-    path = "/users/{id}",
+    path = "/api/admin/users/{id}",
     tag = "users",
     params(
         ("id" = i32, Path, description = "用户ID"),
@@ -203,23 +253,53 @@ pub async fn get_user(user_repo: web::Data<UserRepository>, id: web::Path<i32>)
     request_body = UpdateUserRequest,
     responses(
         (status = 200, description = "更新用户成功", body = UserApiResponse),
-        (status = 404, description = "用户不存在", body = EmptyApiResponse),
+        (status = 403, description = "不能管理角色不低于自己的账户", body = EmptyApiResponse),
+        (status = 404, description = "用户或套餐不存在", body = EmptyApiResponse),
         (status = 500, description = "服务器内部错误", body = EmptyApiResponse),
+    ),
+    security(
+        ("jwt_token" = ["users.write"])
     )
 )]
-#[put("/users/{id}")]
+#[put("/{id}", wrap = "RequirePermission(Permission::UsersWrite)")]
 pub async fn update_user(
+    caller: web::ReqData<User>,
     user_repo: web::Data<UserRepository>,
+    plan_repo: web::Data<PlanRepository>,
+    auth_service: web::Data<AuthService>,
     id: web::Path<i32>,
     update: web::Json<UpdateUserRequest>,
 ) -> HttpResponse {
     match user_repo.find_by_id(*id).await {
         Ok(Some(mut user)) => {
+            if let Some(response) = forbid_unmanageable(&caller, &user) {
+                return response;
+            }
             if let Some(email) = update.email.clone() {
                 user.email = email;
             }
-            if let Some(password) = update.password.clone() {
-                user.password = password;
+            if let Some(username) = update.username.clone() {
+                if let Err(e) = validate_username(&username) {
+                    return ResponseBuilder::error_with_message(
+                        ErrorCode::ValidationError,
+                        e.to_string(),
+                    );
+                }
+                user.username = Some(username);
+            }
+            if let Some(password) = update.password.as_deref() {
+                user.password = match AuthService::hash_password(password) {
+                    Ok(password) => password,
+                    Err(e) => {
+                        tracing::error!("更新用户失败: {}", e);
+                        return ResponseBuilder::error_with_message(
+                            ErrorCode::InternalError,
+                            "密码加密失败".to_string(),
+                        );
+                    }
+                };
+                user.password_algo = None;
+                user.password_salt = None;
             }
             if let Some(remarks) = update.remarks.clone() {
                 user.remarks = Some(remarks);
@@ -228,20 +308,54 @@ pub async fn update_user(
                 user.group_id = Some(group_id);
             }
             if let Some(plan_id) = update.plan_id {
-                user.plan_id = Some(plan_id);
+                // 重新开通套餐，可用流量恢复为套餐流量，之前购买的流量包随之失效；
+                // 开通前的重置日不再重置流量
+                let plan = match plan_repo.find_by_id(plan_id).await {
+                    Ok(Some(plan)) => plan,
+                    Ok(None) => return ResponseBuilder::error(ErrorCode::PlanNotFound),
+                    Err(e) => {
+                        tracing::error!("查询套餐失败: {}", e);
+                        return ResponseBuilder::error_with_message(
+                            ErrorCode::DatabaseError,
+                            "查询套餐失败".to_string(),
+                        );
+                    }
+                };
+                user.plan_id = Some(plan.id);
+                user.plan_activated_at = Some(chrono::Utc::now().timestamp() as i32);
+                user.transfer_enable = plan.transfer_enable_bytes();
             }
             if let Some(speed_limit) = update.speed_limit {
                 user.speed_limit = Some(speed_limit);
             }
 
             match user_repo.update(&user).await {
-                Ok(user) => ResponseBuilder::success_with_message(user, "更新用户成功".to_string()),
+                Ok(user) => {
+                    // 修改密码后所有设备需要重新登录
+                    if update.password.is_some() {
+                        if let Err(e) = auth_service.revoke_all_sessions(user.id).await {
+                            tracing::error!("撤销用户 {} 的会话失败: {}", user.id, e);
+                            return ResponseBuilder::error_with_message(
+                                ErrorCode::DatabaseError,
+                                "用户已更新，但撤销登录会话失败".to_string(),
+                            );
+                        }
+                    }
+                    ResponseBuilder::success_with_message(user, "更新用户成功".to_string())
+                }
                 Err(e) => {
                     tracing::error!("更新用户失败: {}", e);
-                    ResponseBuilder::error_with_message(
-                        ErrorCode::DatabaseError,
-                        "更新用户失败".to_string(),
-                    )
+                    if e.to_string().contains("duplicate") {
+                        ResponseBuilder::error_with_message(
+                            ErrorCode::UserAlreadyExists,
+                            "邮箱或用户名已被使用".to_string(),
+                        )
+                    } else {
+                        ResponseBuilder::error_with_message(
+                            ErrorCode::DatabaseError,
+                            "更新用户失败".to_string(),
+                        )
+                    }
                 }
             }
         }
@@ -261,30 +375,43 @@ pub async fn update_user(
 /// 删除用户
 #[utoipa::path(
     delete,
-    path = "/users/{id}",
+    path = "/api/admin/users/{id}",
     tag = "users",
     params(
         ("id" = i32, Path, description = "用户ID"),
     ),
     responses(
         (status = 200, description = "删除用户成功", body = EmptyApiResponse),
+        (status = 403, description = "不能管理角色不低于自己的账户", body = EmptyApiResponse),
         (status = 404, description = "用户不存在", body = EmptyApiResponse),
         (status = 500, description = "服务器内部错误", body = EmptyApiResponse),
+    ),
+    security(
+        ("jwt_token" = ["users.write"])
     )
 )]
-#[delete("/users/{id}")]
-pub async fn delete_user(user_repo: web::Data<UserRepository>, id: web::Path<i32>) -> HttpResponse {
+#[delete("/{id}", wrap = "RequirePermission(Permission::UsersWrite)")]
+pub async fn delete_user(
+    caller: web::ReqData<User>,
+    user_repo: web::Data<UserRepository>,
+    id: web::Path<i32>,
+) -> HttpResponse {
     match user_repo.find_by_id(*id).await {
-        Ok(Some(_)) => match user_repo.delete(*id).await {
-            Ok(_) => ResponseBuilder::success_with_message((), "删除用户成功".to_string()),
-            Err(e) => {
-                tracing::error!("删除用户失败: {}", e);
-                ResponseBuilder::error_with_message(
-                    ErrorCode::DatabaseError,
-                    "删除用户失败".to_string(),
-                )
+        Ok(Some(user)) => {
+            if let Some(response) = forbid_unmanageable(&caller, &user) {
+                return response;
             }
-        },
+            match user_repo.delete(*id).await {
+                Ok(_) => ResponseBuilder::success_with_message((), "删除用户成功".to_string()),
+                Err(e) => {
+                    tracing::error!("删除用户失败: {}", e);
+                    ResponseBuilder::error_with_message(
+                        ErrorCode::DatabaseError,
+                        "删除用户失败".to_string(),
+                    )
+                }
+            }
+        }
         Ok(None) => {
             ResponseBuilder::error_with_message(ErrorCode::UserNotFound, "用户不存在".to_string())
         }
@@ -301,7 +428,7 @@ pub async fn delete_user(user_repo: web::Data<UserRepository>, id: web::Path<i32
 /// 更新用户状态
 #[utoipa::path(
     patch,
-    path = "/users/{id}/status",
+    path = "/api/admin/users/{id}/status",
     tag = "users",
     params(
         ("id" = i32, Path, description = "用户ID"),
@@ -309,22 +436,41 @@ pub async fn delete_user(user_repo: web::Data<UserRepository>, id: web::Path<i32
     request_body = UpdateUserStatusRequest,
     responses(
         (status = 200, description = "更新用户状态成功", body = UserApiResponse),
+        (status = 403, description = "不能管理角色不低于自己的账户", body = EmptyApiResponse),
         (status = 404, description = "用户不存在", body = EmptyApiResponse),
         (status = 500, description = "服务器内部错误", body = EmptyApiResponse),
+    ),
+    security(
+        ("jwt_token" = ["users.write"])
     )
 )]
-#[patch("/users/{id}/status")]
+#[patch("/{id}/status", wrap = "RequirePermission(Permission::UsersWrite)")]
 pub async fn update_user_status(
+    caller: web::ReqData<User>,
     user_repo: web::Data<UserRepository>,
+    auth_service: web::Data<AuthService>,
     id: web::Path<i32>,
     status: web::Json<UpdateUserStatusRequest>,
 ) -> HttpResponse {
     match user_repo.find_by_id(*id).await {
         Ok(Some(mut user)) => {
+            if let Some(response) = forbid_unmanageable(&caller, &user) {
+                return response;
+            }
             user.banned = Some(status.banned);
 
             match user_repo.update(&user).await {
                 Ok(user) => {
+                    // 封禁后立即踢下线
+                    if status.banned {
+                        if let Err(e) = auth_service.revoke_all_sessions(user.id).await {
+                            tracing::error!("撤销用户 {} 的会话失败: {}", user.id, e);
+                            return ResponseBuilder::error_with_message(
+                                ErrorCode::DatabaseError,
+                                "用户已封禁，但撤销登录会话失败".to_string(),
+                            );
+                        }
+                    }
                     ResponseBuilder::success_with_message(user, "更新用户状态成功".to_string())
                 }
                 Err(e) => {
@@ -348,3 +494,228 @@ pub async fn update_user_status(
         }
     }
 }
+
+/// 解除登录锁定
+///
+/// 清除账户的登录失败记录，账户可立即重新登录
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{id}/unlock",
+    tag = "users",
+    params(
+        ("id" = i32, Path, description = "用户ID"),
+    ),
+    responses(
+        (status = 200, description = "解除锁定成功", body = EmptyApiResponse),
+        (status = 403, description = "不能管理角色不低于自己的账户", body = EmptyApiResponse),
+        (status = 404, description = "用户不存在", body = EmptyApiResponse),
+        (status = 500, description = "服务器内部错误", body = EmptyApiResponse),
+    ),
+    security(
+        ("jwt_token" = ["users.write"])
+    )
+)]
+#[post("/{id}/unlock", wrap = "RequirePermission(Permission::UsersWrite)")]
+pub async fn unlock_user(
+    caller: web::ReqData<User>,
+    user_repo: web::Data<UserRepository>,
+    auth_service: web::Data<AuthService>,
+    id: web::Path<i32>,
+) -> HttpResponse {
+    match user_repo.find_by_id(*id).await {
+        Ok(Some(user)) => {
+            if let Some(response) = forbid_unmanageable(&caller, &user) {
+                return response;
+            }
+            match auth_service.unlock(user.id).await {
+                Ok(()) => ResponseBuilder::success_with_message((), "解除锁定成功".to_string()),
+                Err(e) => {
+                    tracing::error!("解除用户 {} 的登录锁定失败: {}", user.id, e);
+                    ResponseBuilder::error_with_message(
+                        ErrorCode::InternalError,
+                        "解除锁定失败".to_string(),
+                    )
+                }
+            }
+        }
+        Ok(None) => {
+            ResponseBuilder::error_with_message(ErrorCode::UserNotFound, "用户不存在".to_string())
+        }
+        Err(e) => {
+            tracing::error!("查询用户失败: {}", e);
+            ResponseBuilder::error_with_message(
+                ErrorCode::DatabaseError,
+                "查询用户失败".to_string(),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        models::permission::PermissionSet, repositories::ApiKeyRepository, services::ApiKeyService,
+        test_support,
+    };
+    use actix_web::{dev::Service, test, App, HttpMessage};
+    use sqlx::PgPool;
+
+    async fn set_role(pool: &PgPool, id: i32, is_admin: bool, is_staff: bool) {
+        sqlx::query("UPDATE purple_user SET is_admin = $1, is_staff = $2 WHERE id = $3")
+            .bind(is_admin)
+            .bind(is_staff)
+            .bind(id)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    /// 以指定账户的身份调用用户管理接口，返回 (业务错误码, 目标账户)
+    async fn call_as(
+        pool: &PgPool,
+        caller_id: i32,
+        req: test::TestRequest,
+        target_id: i32,
+    ) -> (i32, Option<User>) {
+        let user_repo = UserRepository::new(pool.clone());
+        let caller = user_repo.find_by_id(caller_id).await.unwrap().unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(user_repo.clone()))
+                .app_data(web::Data::new(PlanRepository::new(pool.clone())))
+                .app_data(web::Data::new(test_support::auth_service(pool)))
+                .app_data(web::Data::new(ApiKeyService::new(ApiKeyRepository::new(
+                    pool.clone(),
+                ))))
+                .wrap_fn(move |req, srv| {
+                    req.extensions_mut().insert(caller.clone());
+                    req.extensions_mut().insert(PermissionSet::all());
+                    srv.call(req)
+                })
+                .service(
+                    web::scope("/users")
+                        .service(update_user)
+                        .service(delete_user)
+                        .service(unlock_user)
+                        .service(crate::api::list_user_api_keys)
+                        .service(crate::api::revoke_user_api_key),
+                ),
+        )
+        .await;
+
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req.to_request()).await;
+        let code = body["code"].as_i64().unwrap() as i32;
+        (code, user_repo.find_by_id(target_id).await.unwrap())
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_staff_cannot_manage_admin(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let staff = test_support::insert_user(&pool, "staff@example.com", None).await;
+        let admin = test_support::insert_user(&pool, "admin@example.com", None).await;
+        set_role(&pool, staff, false, true).await;
+        set_role(&pool, admin, true, false).await;
+
+        let req = test::TestRequest::delete().uri(&format!("/users/{}", admin));
+        let (code, target) = call_as(&pool, staff, req, admin).await;
+        assert_eq!(code, ErrorCode::PermissionDenied as i32);
+        assert!(target.is_some());
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_staff_cannot_manage_staff(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let staff = test_support::insert_user(&pool, "staff@example.com", None).await;
+        let other = test_support::insert_user(&pool, "other@example.com", None).await;
+        set_role(&pool, staff, false, true).await;
+        set_role(&pool, other, false, true).await;
+
+        let req = test::TestRequest::delete().uri(&format!("/users/{}", other));
+        let (code, target) = call_as(&pool, staff, req, other).await;
+        assert_eq!(code, ErrorCode::PermissionDenied as i32);
+        assert!(target.is_some());
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_staff_manages_user_and_admin_manages_admin(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let staff = test_support::insert_user(&pool, "staff@example.com", None).await;
+        let admin = test_support::insert_user(&pool, "admin@example.com", None).await;
+        let other_admin = test_support::insert_user(&pool, "root@example.com", None).await;
+        let user = test_support::insert_user(&pool, "user@example.com", None).await;
+        set_role(&pool, staff, false, true).await;
+        set_role(&pool, admin, true, false).await;
+        set_role(&pool, other_admin, true, false).await;
+
+        let req = test::TestRequest::delete().uri(&format!("/users/{}", user));
+        let (code, target) = call_as(&pool, staff, req, user).await;
+        assert_eq!(code, ErrorCode::Success as i32);
+        assert!(target.is_none());
+
+        let req = test::TestRequest::delete().uri(&format!("/users/{}", other_admin));
+        let (code, target) = call_as(&pool, admin, req, other_admin).await;
+        assert_eq!(code, ErrorCode::Success as i32);
+        assert!(target.is_none());
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_staff_cannot_unlock_or_manage_keys_of_admin(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let staff = test_support::insert_user(&pool, "staff@example.com", None).await;
+        let admin = test_support::insert_user(&pool, "admin@example.com", None).await;
+        let user = test_support::insert_user(&pool, "user@example.com", None).await;
+        set_role(&pool, staff, false, true).await;
+        set_role(&pool, admin, true, false).await;
+
+        for req in [
+            test::TestRequest::post().uri(&format!("/users/{}/unlock", admin)),
+            test::TestRequest::get().uri(&format!("/users/{}/api-keys", admin)),
+            test::TestRequest::delete().uri(&format!("/users/{}/api-keys/1", admin)),
+        ] {
+            let (code, _) = call_as(&pool, staff, req, admin).await;
+            assert_eq!(code, ErrorCode::PermissionDenied as i32);
+        }
+
+        // 客服仍可管理普通用户
+        for req in [
+            test::TestRequest::post().uri(&format!("/users/{}/unlock", user)),
+            test::TestRequest::get().uri(&format!("/users/{}/api-keys", user)),
+        ] {
+            let (code, _) = call_as(&pool, staff, req, user).await;
+            assert_eq!(code, ErrorCode::Success as i32);
+        }
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_assigning_plan_drops_traffic_addon(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let admin = test_support::insert_user(&pool, "admin@example.com", None).await;
+        let user = test_support::insert_user(&pool, "user@example.com", None).await;
+        set_role(&pool, admin, true, false).await;
+        let plan_id = test_support::insert_plan(&pool, 1).await;
+        // 已有 15GB 的可用流量（含流量包）
+        sqlx::query("UPDATE purple_user SET transfer_enable = $1 WHERE id = $2")
+            .bind(15 * crate::utils::GB)
+            .bind(user)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let req = test::TestRequest::put()
+            .uri(&format!("/users/{}", user))
+            .set_json(serde_json::json!({ "plan_id": plan_id }));
+        let (code, target) = call_as(&pool, admin, req, user).await;
+        assert_eq!(code, ErrorCode::Success as i32);
+        let target = target.unwrap();
+        assert_eq!(target.plan_id, Some(plan_id));
+        assert_eq!(target.transfer_enable, 100 * crate::utils::GB);
+        assert!(target.plan_activated_at.is_some());
+
+        let req = test::TestRequest::put()
+            .uri(&format!("/users/{}", user))
+            .set_json(serde_json::json!({ "plan_id": plan_id + 1 }));
+        let (code, _) = call_as(&pool, admin, req, user).await;
+        assert_eq!(code, ErrorCode::PlanNotFound as i32);
+    }
+}
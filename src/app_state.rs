@@ -3,9 +3,17 @@ use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 
 use crate::{
-    config::DatabaseConfig,
-    repositories::{CouponRepository, PlanRepository, UserRepository},
-    services::AuthService,
+    config::{Config, DatabaseConfig},
+    repositories::{
+        ApiKeyRepository, BalanceRepository, CouponRepository, EmailCodeRepository,
+        OrderRepository, PermissionRepository, PlanRepository, RouteRepository, ServerRepository,
+        SessionRepository, SubscribeLogRepository, SubscribeTemplateRepository,
+        TrafficResetRepository, TwoFactorRepository, UserRepository,
+    },
+    services::{
+        mail_sender_from_config, ApiKeyService, AuthService, BillingService, EmailCodeService,
+        OrderService, ServerService, SubscriptionService, TrafficResetService, TwoFactorService,
+    },
 };
 
 /// 应用共享状态
@@ -17,14 +25,25 @@ pub struct AppState {
     pub user_repository: UserRepository,
     pub plan_repository: PlanRepository,
     pub coupon_repository: CouponRepository,
+    pub server_repository: ServerRepository,
+    pub route_repository: RouteRepository,
+    pub permission_repository: PermissionRepository,
+    pub session_repository: SessionRepository,
     pub auth_service: AuthService,
+    pub two_factor_service: TwoFactorService,
+    pub api_key_service: ApiKeyService,
+    pub server_service: ServerService,
+    pub subscription_service: SubscriptionService,
+    pub traffic_reset_service: TrafficResetService,
+    pub order_service: OrderService,
+    pub billing_service: BillingService,
 }
 
 impl AppState {
     /// 创建新的应用状态实例
     ///
     /// 初始化数据库连接池、仓库实例和服务实例
-    pub async fn new(database_config: &DatabaseConfig) -> Result<Self> {
+    pub async fn new(config: &Config, database_config: &DatabaseConfig) -> Result<Self> {
         // 创建数据库连接池
         let pool = create_db_pool(database_config).await?;
 
@@ -32,17 +51,83 @@ impl AppState {
         let user_repository = UserRepository::new(pool.clone());
         let plan_repository = PlanRepository::new(pool.clone());
         let coupon_repository = CouponRepository::new(pool.clone());
+        let server_repository = ServerRepository::new(pool.clone());
+        let route_repository = RouteRepository::new(pool.clone());
+        let subscribe_template_repository = SubscribeTemplateRepository::new(pool.clone());
+        let subscribe_log_repository = SubscribeLogRepository::new(pool.clone());
+        let traffic_reset_repository = TrafficResetRepository::new(pool.clone());
+        let order_repository = OrderRepository::new(pool.clone());
+        let balance_repository = BalanceRepository::new(pool.clone());
+        let permission_repository = PermissionRepository::new(pool.clone());
+        let session_repository = SessionRepository::new(pool.clone());
 
         // 创建服务实例
-        let jwt_secret = std::env::var("JWT_SECRET")
-            .map_err(|_| anyhow::anyhow!("JWT_SECRET environment variable must be set"))?;
-        let auth_service = AuthService::new(user_repository.clone(), jwt_secret);
+        let email_code_service = EmailCodeService::new(
+            EmailCodeRepository::new(pool.clone()),
+            mail_sender_from_config(&config.mail)?,
+            config.auth.email_code.clone(),
+            config.mail.app_name.clone(),
+        );
+        let two_factor_service = TwoFactorService::new(
+            TwoFactorRepository::new(pool.clone()),
+            config.auth.two_factor.clone(),
+        );
+        let auth_service = AuthService::new(
+            user_repository.clone(),
+            session_repository.clone(),
+            email_code_service,
+            two_factor_service.clone(),
+            config.auth.clone(),
+        )?;
+        let api_key_service = ApiKeyService::new(ApiKeyRepository::new(pool.clone()));
+        let server_service = ServerService::new(
+            server_repository.clone(),
+            user_repository.clone(),
+            route_repository.clone(),
+            config.node.clone(),
+        );
+        let subscription_service = SubscriptionService::new(
+            user_repository.clone(),
+            server_repository.clone(),
+            subscribe_template_repository,
+            subscribe_log_repository,
+            server_service.clone(),
+            config.subscribe.clone(),
+        );
+        let traffic_reset_service = TrafficResetService::new(
+            user_repository.clone(),
+            plan_repository.clone(),
+            traffic_reset_repository,
+            config.traffic_reset.clone(),
+        );
+        let order_service = OrderService::new(
+            order_repository,
+            plan_repository.clone(),
+            config.order.clone(),
+        );
+        let billing_service = BillingService::new(
+            user_repository.clone(),
+            plan_repository.clone(),
+            balance_repository,
+            config.billing.clone(),
+        );
 
         Ok(Self {
             user_repository,
             plan_repository,
             coupon_repository,
+            server_repository,
+            route_repository,
+            permission_repository,
+            session_repository,
             auth_service,
+            two_factor_service,
+            api_key_service,
+            server_service,
+            subscription_service,
+            traffic_reset_service,
+            order_service,
+            billing_service,
         })
     }
 }
@@ -18,6 +18,8 @@ pub enum ErrorCode {
     DatabaseError = 1004,
     #[serde(rename = "NETWORK_ERROR")]
     NetworkError = 1005,
+    #[serde(rename = "TOO_MANY_REQUESTS")]
+    TooManyRequests = 1006,
 
     // 认证相关错误 (2000-2999)
     #[serde(rename = "UNAUTHORIZED")]
@@ -32,6 +34,10 @@ pub enum ErrorCode {
     AccountLocked = 2004,
     #[serde(rename = "PERMISSION_DENIED")]
     PermissionDenied = 2005,
+    #[serde(rename = "INVALID_VERIFICATION_CODE")]
+    InvalidVerificationCode = 2006,
+    #[serde(rename = "TWO_FACTOR_REQUIRED")]
+    TwoFactorRequired = 2007,
 
     // 用户相关错误 (3000-3999)
     #[serde(rename = "USER_NOT_FOUND")]
@@ -44,6 +50,10 @@ pub enum ErrorCode {
     InvalidPassword = 3003,
     #[serde(rename = "USER_DISABLED")]
     UserDisabled = 3004,
+    #[serde(rename = "SUBSCRIPTION_EXPIRED")]
+    SubscriptionExpired = 3005,
+    #[serde(rename = "TRAFFIC_EXHAUSTED")]
+    TrafficExhausted = 3006,
 
     // 套餐相关错误 (4000-4999)
     #[serde(rename = "PLAN_NOT_FOUND")]
@@ -92,6 +102,7 @@ impl ErrorCode {
             ErrorCode::ValidationError => "数据验证失败",
             ErrorCode::DatabaseError => "数据库操作失败",
             ErrorCode::NetworkError => "网络连接错误",
+            ErrorCode::TooManyRequests => "请求过于频繁",
 
             // 认证相关错误
             ErrorCode::Unauthorized => "未授权访问",
@@ -100,6 +111,8 @@ impl ErrorCode {
             ErrorCode::InvalidCredentials => "用户名或密码错误",
             ErrorCode::AccountLocked => "账户已被锁定",
             ErrorCode::PermissionDenied => "权限不足",
+            ErrorCode::InvalidVerificationCode => "验证码错误或已过期",
+            ErrorCode::TwoFactorRequired => "需要启用双重验证",
 
             // 用户相关错误
             ErrorCode::UserNotFound => "用户不存在",
@@ -107,6 +120,8 @@ impl ErrorCode {
             ErrorCode::InvalidEmail => "邮箱格式无效",
             ErrorCode::InvalidPassword => "密码格式无效",
             ErrorCode::UserDisabled => "用户已被禁用",
+            ErrorCode::SubscriptionExpired => "订阅已过期",
+            ErrorCode::TrafficExhausted => "流量已用尽",
 
             // 套餐相关错误
             ErrorCode::PlanNotFound => "套餐不存在",
@@ -138,6 +153,7 @@ impl ErrorCode {
             ErrorCode::ValidationError => "Data validation failed",
             ErrorCode::DatabaseError => "Database operation failed",
             ErrorCode::NetworkError => "Network connection error",
+            ErrorCode::TooManyRequests => "Too many requests",
 
             // 认证相关错误
             ErrorCode::Unauthorized => "Unauthorized access",
@@ -146,6 +162,8 @@ impl ErrorCode {
             ErrorCode::InvalidCredentials => "Invalid username or password",
             ErrorCode::AccountLocked => "Account locked",
             ErrorCode::PermissionDenied => "Permission denied",
+            ErrorCode::InvalidVerificationCode => "Invalid or expired verification code",
+            ErrorCode::TwoFactorRequired => "Two-factor authentication required",
 
             // 用户相关错误
             ErrorCode::UserNotFound => "User not found",
@@ -153,6 +171,8 @@ impl ErrorCode {
             ErrorCode::InvalidEmail => "Invalid email format",
             ErrorCode::InvalidPassword => "Invalid password format",
             ErrorCode::UserDisabled => "User disabled",
+            ErrorCode::SubscriptionExpired => "Subscription expired",
+            ErrorCode::TrafficExhausted => "Traffic exhausted",
 
             // 套餐相关错误
             ErrorCode::PlanNotFound => "Plan not found",
@@ -213,11 +233,15 @@ impl From<i32> for ErrorCode {
             2003 => ErrorCode::InvalidCredentials,
             2004 => ErrorCode::AccountLocked,
             2005 => ErrorCode::PermissionDenied,
+            2006 => ErrorCode::InvalidVerificationCode,
+            2007 => ErrorCode::TwoFactorRequired,
             3000 => ErrorCode::UserNotFound,
             3001 => ErrorCode::UserAlreadyExists,
             3002 => ErrorCode::InvalidEmail,
             3003 => ErrorCode::InvalidPassword,
             3004 => ErrorCode::UserDisabled,
+            3005 => ErrorCode::SubscriptionExpired,
+            3006 => ErrorCode::TrafficExhausted,
             4000 => ErrorCode::PlanNotFound,
             4001 => ErrorCode::PlanUnavailable,
             4002 => ErrorCode::PlanQuotaExceeded,
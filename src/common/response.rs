@@ -184,8 +184,14 @@ impl From<ErrorCode> for ApiError {
 
 impl From<anyhow::Error> for ApiError {
     fn from(err: anyhow::Error) -> Self {
-        tracing::error!("Anyhow error: {}", err);
-        Self::with_message(ErrorCode::InternalError, err.to_string())
+        // 服务层可以通过 anyhow 直接抛出业务错误
+        match err.downcast::<ApiError>() {
+            Ok(api_error) => api_error,
+            Err(err) => {
+                tracing::error!("Anyhow error: {}", err);
+                Self::with_message(ErrorCode::InternalError, err.to_string())
+            }
+        }
     }
 }
 
@@ -13,16 +13,20 @@ impl ErrorCode {
             ErrorCode::InvalidParams
             | ErrorCode::ValidationError
             | ErrorCode::InvalidEmail
-            | ErrorCode::InvalidPassword => StatusCode::BAD_REQUEST,
+            | ErrorCode::InvalidPassword
+            | ErrorCode::InvalidVerificationCode => StatusCode::BAD_REQUEST,
 
             ErrorCode::Unauthorized
             | ErrorCode::InvalidToken
             | ErrorCode::TokenExpired
             | ErrorCode::InvalidCredentials => StatusCode::UNAUTHORIZED,
 
-            ErrorCode::PermissionDenied | ErrorCode::AccountLocked | ErrorCode::UserDisabled => {
-                StatusCode::FORBIDDEN
-            }
+            ErrorCode::PermissionDenied
+            | ErrorCode::AccountLocked
+            | ErrorCode::TwoFactorRequired
+            | ErrorCode::UserDisabled
+            | ErrorCode::SubscriptionExpired
+            | ErrorCode::TrafficExhausted => StatusCode::FORBIDDEN,
 
             ErrorCode::UserNotFound
             | ErrorCode::PlanNotFound
@@ -33,6 +37,8 @@ impl ErrorCode {
                 StatusCode::CONFLICT
             }
 
+            ErrorCode::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+
             ErrorCode::CouponExpired
             | ErrorCode::CouponInvalid
             | ErrorCode::OrderExpired
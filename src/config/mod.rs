@@ -1,9 +1,13 @@
 use anyhow::Result;
+use chrono::FixedOffset;
 use config;
+use std::net::IpAddr;
 
 pub mod database;
 pub use database::DatabaseConfig;
 
+use crate::models::plan::ResetTrafficMethod;
+
 #[derive(Debug)]
 pub struct LogConfig {
     pub level: String,
@@ -14,12 +18,256 @@ pub struct LogConfig {
     pub file_path: String,
 }
 
+/// 节点后端通讯配置
+#[derive(Debug, Clone)]
+pub struct NodeConfig {
+    /// 节点后端通讯密钥
+    pub token: String,
+    /// 节点超过该时长（秒）未上报即判定为离线
+    pub offline_threshold: i32,
+    /// 节点后端流量上报间隔（秒）
+    pub push_interval: i32,
+    /// 节点后端用户拉取间隔（秒）
+    pub pull_interval: i32,
+    /// 计算节点时间段倍率使用的时区
+    pub rate_timezone: FixedOffset,
+}
+
+/// 反向代理配置
+#[derive(Debug, Clone, Default)]
+pub struct ProxyConfig {
+    /// 可信反向代理的地址或网段，只有来自这些地址的请求才采信 X-Forwarded-For 和 X-Real-IP
+    pub trusted_proxies: Vec<TrustedProxy>,
+}
+
+impl ProxyConfig {
+    /// 判断地址是否为可信反向代理
+    pub fn is_trusted(&self, ip: &IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|proxy| proxy.contains(ip))
+    }
+}
+
+/// 可信反向代理，格式为 IP 或 CIDR 网段，如 `127.0.0.1`、`172.16.0.0/12`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustedProxy {
+    pub network: IpAddr,
+    pub prefix: u8,
+}
+
+impl TrustedProxy {
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(*ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(*ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::str::FromStr for TrustedProxy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (network, prefix) = match s.split_once('/') {
+            Some((network, prefix)) => (network, Some(prefix)),
+            None => (s, None),
+        };
+        let network: IpAddr = network
+            .parse()
+            .map_err(|_| anyhow::anyhow!("无效的可信代理地址: {}", s))?;
+        let max_prefix = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max_prefix)
+                .ok_or_else(|| anyhow::anyhow!("无效的可信代理网段: {}", s))?,
+            None => max_prefix,
+        };
+
+        Ok(Self { network, prefix })
+    }
+}
+
+/// 订阅配置
+#[derive(Debug, Clone)]
+pub struct SubscribeConfig {
+    /// 站点名称，用于订阅文件名和策略组名称
+    pub app_name: String,
+    /// 建议客户端自动更新订阅的间隔（小时）
+    pub update_interval: i32,
+    /// 每个订阅令牌每小时允许拉取的次数，0 表示不限制
+    pub rate_limit: i32,
+}
+
+/// 流量重置配置
+#[derive(Debug, Clone)]
+pub struct TrafficResetConfig {
+    /// 套餐未设置重置方式时使用的默认方式
+    pub default_method: ResetTrafficMethod,
+    /// 检查是否需要重置的间隔（秒）
+    pub check_interval: u64,
+    /// 判断重置日使用的时区
+    pub timezone: FixedOffset,
+}
+
+/// 订单配置
+#[derive(Debug, Clone)]
+pub struct OrderConfig {
+    /// 待支付订单的保留时长（秒），期间占用套餐名额，超时后无法支付
+    pub reservation_window: i32,
+}
+
+/// 按量计费配置
+#[derive(Debug, Clone)]
+pub struct BillingConfig {
+    /// 扣费检查间隔（秒），按流量扣费随检查进行
+    pub check_interval: u64,
+    /// 判断按日扣费日期使用的时区
+    pub timezone: FixedOffset,
+}
+
+/// 登录认证配置
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    /// 访问令牌有效期（秒）
+    pub access_token_ttl: i64,
+    /// 刷新令牌有效期（秒），每次刷新后重新计算
+    pub refresh_token_ttl: i64,
+    /// HS256 签名密钥，对应 kid 为 `default`，同时用于验证不带 kid 的旧令牌
+    pub jwt_secret: Option<String>,
+    /// PEM 格式的非对称密钥，轮换后旧密钥保留用于验证未过期的令牌
+    pub jwt_keys: Vec<JwtKeyConfig>,
+    /// 签发新令牌使用的 kid，未设置时使用最后一个带私钥的密钥，没有则使用 `JWT_SECRET`
+    pub jwt_active_kid: Option<String>,
+    pub login_guard: LoginGuardConfig,
+    /// 注册时是否需要邮箱验证码
+    pub register_email_verify: bool,
+    pub email_code: EmailCodeConfig,
+    pub two_factor: TwoFactorConfig,
+}
+
+/// 登录防暴力破解配置
+#[derive(Debug, Clone)]
+pub struct LoginGuardConfig {
+    /// 账户连续失败达到该次数后临时锁定
+    pub max_failures: u32,
+    /// 同一IP连续失败达到该次数后临时拒绝登录
+    pub ip_max_failures: u32,
+    /// 锁定时长（秒）
+    pub lockout_duration: i64,
+    /// 退避基准时长（秒），第二次失败起按指数增长，0 表示不退避
+    pub backoff_base: i64,
+    /// 失败次数统计窗口（秒），超过该时长没有失败则重新计数
+    pub failure_window: i64,
+}
+
+/// 邮箱验证码配置
+#[derive(Debug, Clone)]
+pub struct EmailCodeConfig {
+    /// 验证码有效期（秒）
+    pub ttl: i64,
+    /// 同一邮箱两次发送的最小间隔（秒）
+    pub resend_interval: i64,
+    /// 同一邮箱每小时最多发送次数
+    pub email_hourly_limit: i64,
+    /// 同一IP每小时最多请求发送次数
+    pub ip_hourly_limit: i64,
+    /// 验证失败达到该次数后验证码失效
+    pub max_attempts: i32,
+}
+
+/// 双重验证配置
+#[derive(Debug, Clone)]
+pub struct TwoFactorConfig {
+    /// 是否要求管理员和客服启用双重验证，未启用时无法访问管理后台
+    pub required_for_staff: bool,
+    /// 登录挑战令牌有效期（秒）
+    pub challenge_ttl: i64,
+    /// 身份验证器中显示的发行方名称
+    pub issuer: String,
+}
+
+/// 邮件发送配置
+#[derive(Debug, Clone)]
+pub struct MailConfig {
+    /// SMTP 服务器地址，未设置时只将邮件内容写入日志
+    pub host: Option<String>,
+    pub port: u16,
+    /// 加密方式：`ssl`、`tls`（STARTTLS）或 `none`
+    pub encryption: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// 发件人，如 `Purple <noreply@example.com>`
+    pub from_address: String,
+    /// 站点名称，用于邮件标题
+    pub app_name: String,
+}
+
+/// JWT 非对称密钥配置
+///
+/// 格式为 `kid:算法:公钥路径[:私钥路径]`，如 `2025-01:RS256:keys/2025-01.pub.pem:keys/2025-01.pem`，
+/// 只提供公钥的密钥仅用于验证
+#[derive(Debug, Clone)]
+pub struct JwtKeyConfig {
+    pub kid: String,
+    pub algorithm: jsonwebtoken::Algorithm,
+    pub public_key_path: String,
+    pub private_key_path: Option<String>,
+}
+
+impl std::str::FromStr for JwtKeyConfig {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.trim().split(':').map(str::trim).collect();
+        let (kid, algorithm, public_key_path, private_key_path) = match parts.as_slice() {
+            [kid, algorithm, public] => (*kid, *algorithm, *public, None),
+            [kid, algorithm, public, private] => (*kid, *algorithm, *public, Some(*private)),
+            _ => anyhow::bail!("JWT 密钥格式应为 kid:算法:公钥路径[:私钥路径]: {}", s),
+        };
+        if kid.is_empty() || public_key_path.is_empty() {
+            anyhow::bail!("JWT 密钥的 kid 和公钥路径不能为空: {}", s);
+        }
+
+        Ok(Self {
+            kid: kid.to_string(),
+            algorithm: algorithm
+                .parse()
+                .map_err(|_| anyhow::anyhow!("未知的 JWT 签名算法: {}", algorithm))?,
+            public_key_path: public_key_path.to_string(),
+            private_key_path: private_key_path
+                .filter(|path| !path.is_empty())
+                .map(str::to_string),
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct Config {
     pub server_addr: String,
     pub server_port: u16,
     pub database: DatabaseConfig,
     pub log: LogConfig,
+    pub proxy: ProxyConfig,
+    pub node: NodeConfig,
+    pub subscribe: SubscribeConfig,
+    pub traffic_reset: TrafficResetConfig,
+    pub billing: BillingConfig,
+    pub order: OrderConfig,
+    pub auth: AuthConfig,
+    pub mail: MailConfig,
 }
 
 impl Config {
@@ -46,6 +294,188 @@ impl Config {
                     .get_string("log_file_path")
                     .unwrap_or_else(|_| "logs/app.log".to_string()),
             },
+            proxy: ProxyConfig {
+                trusted_proxies: config
+                    .get_string("trusted_proxies")
+                    .unwrap_or_default()
+                    .split(',')
+                    .filter(|proxy| !proxy.trim().is_empty())
+                    .map(str::parse)
+                    .collect::<Result<_>>()?,
+            },
+            node: NodeConfig {
+                token: config.get_string("server_token").unwrap_or_default(),
+                offline_threshold: config.get_int("server_offline_threshold").unwrap_or(300) as i32,
+                push_interval: config.get_int("server_push_interval").unwrap_or(60) as i32,
+                pull_interval: config.get_int("server_pull_interval").unwrap_or(60) as i32,
+                rate_timezone: config
+                    .get_string("server_rate_timezone")
+                    .unwrap_or_else(|_| "+00:00".to_string())
+                    .parse()
+                    .map_err(|e| anyhow::anyhow!("SERVER_RATE_TIMEZONE 格式无效: {}", e))?,
+            },
+            subscribe: SubscribeConfig {
+                app_name: config
+                    .get_string("app_name")
+                    .unwrap_or_else(|_| "Purple".to_string()),
+                update_interval: config.get_int("subscribe_update_interval").unwrap_or(24) as i32,
+                rate_limit: config.get_int("subscribe_rate_limit").unwrap_or(0) as i32,
+            },
+            traffic_reset: TrafficResetConfig {
+                default_method: config
+                    .get_string("traffic_reset_method")
+                    .unwrap_or_else(|_| "0".to_string())
+                    .parse()?,
+                check_interval: config
+                    .get_int("traffic_reset_check_interval")
+                    .unwrap_or(3600) as u64,
+                timezone: config
+                    .get_string("traffic_reset_timezone")
+                    .unwrap_or_else(|_| "+00:00".to_string())
+                    .parse()
+                    .map_err(|e| anyhow::anyhow!("TRAFFIC_RESET_TIMEZONE 格式无效: {}", e))?,
+            },
+            billing: BillingConfig {
+                check_interval: config.get_int("billing_check_interval").unwrap_or(3600) as u64,
+                timezone: config
+                    .get_string("billing_timezone")
+                    .unwrap_or_else(|_| "+00:00".to_string())
+                    .parse()
+                    .map_err(|e| anyhow::anyhow!("BILLING_TIMEZONE 格式无效: {}", e))?,
+            },
+            order: OrderConfig {
+                reservation_window: config.get_int("order_reservation_window").unwrap_or(1800)
+                    as i32,
+            },
+            auth: AuthConfig {
+                access_token_ttl: config.get_int("auth_access_token_ttl").unwrap_or(900),
+                refresh_token_ttl: config
+                    .get_int("auth_refresh_token_ttl")
+                    .unwrap_or(30 * 24 * 3600),
+                jwt_secret: config
+                    .get_string("jwt_secret")
+                    .ok()
+                    .filter(|secret| !secret.is_empty()),
+                jwt_keys: config
+                    .get_string("jwt_keys")
+                    .unwrap_or_default()
+                    .split(',')
+                    .filter(|key| !key.trim().is_empty())
+                    .map(str::parse)
+                    .collect::<Result<_>>()?,
+                jwt_active_kid: config
+                    .get_string("jwt_active_kid")
+                    .ok()
+                    .filter(|kid| !kid.is_empty()),
+                login_guard: LoginGuardConfig {
+                    max_failures: config.get_int("login_max_failures").unwrap_or(5) as u32,
+                    ip_max_failures: config.get_int("login_ip_max_failures").unwrap_or(20) as u32,
+                    lockout_duration: config.get_int("login_lockout_duration").unwrap_or(900),
+                    backoff_base: config.get_int("login_backoff_base").unwrap_or(1),
+                    failure_window: config.get_int("login_failure_window").unwrap_or(3600),
+                },
+                register_email_verify: config.get_bool("register_email_verify").unwrap_or(false),
+                email_code: EmailCodeConfig {
+                    ttl: config.get_int("email_code_ttl").unwrap_or(600),
+                    resend_interval: config.get_int("email_code_resend_interval").unwrap_or(60),
+                    email_hourly_limit: config
+                        .get_int("email_code_email_hourly_limit")
+                        .unwrap_or(5),
+                    ip_hourly_limit: config.get_int("email_code_ip_hourly_limit").unwrap_or(20),
+                    max_attempts: config.get_int("email_code_max_attempts").unwrap_or(5) as i32,
+                },
+                two_factor: TwoFactorConfig {
+                    required_for_staff: config
+                        .get_bool("two_factor_required_for_staff")
+                        .unwrap_or(false),
+                    challenge_ttl: config.get_int("two_factor_challenge_ttl").unwrap_or(300),
+                    issuer: config
+                        .get_string("app_name")
+                        .unwrap_or_else(|_| "Purple".to_string()),
+                },
+            },
+            mail: MailConfig {
+                host: config
+                    .get_string("mail_host")
+                    .ok()
+                    .filter(|host| !host.is_empty()),
+                port: config.get_int("mail_port").unwrap_or(465) as u16,
+                encryption: config
+                    .get_string("mail_encryption")
+                    .unwrap_or_else(|_| "ssl".to_string())
+                    .to_lowercase(),
+                username: config
+                    .get_string("mail_username")
+                    .ok()
+                    .filter(|username| !username.is_empty()),
+                password: config
+                    .get_string("mail_password")
+                    .ok()
+                    .filter(|password| !password.is_empty()),
+                from_address: config
+                    .get_string("mail_from_address")
+                    .unwrap_or_else(|_| "noreply@localhost".to_string()),
+                app_name: config
+                    .get_string("app_name")
+                    .unwrap_or_else(|_| "Purple".to_string()),
+            },
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trusted_proxy() {
+        let proxy: TrustedProxy = "172.16.0.0/12".parse().unwrap();
+        assert!(proxy.contains(&"172.16.0.1".parse().unwrap()));
+        assert!(proxy.contains(&"172.31.255.255".parse().unwrap()));
+        assert!(!proxy.contains(&"172.32.0.1".parse().unwrap()));
+        assert!(!proxy.contains(&"::ffff:172.16.0.1".parse().unwrap()));
+
+        let proxy: TrustedProxy = "127.0.0.1".parse().unwrap();
+        assert_eq!(proxy.prefix, 32);
+        assert!(proxy.contains(&"127.0.0.1".parse().unwrap()));
+        assert!(!proxy.contains(&"127.0.0.2".parse().unwrap()));
+
+        let proxy: TrustedProxy = "fd00::/8".parse().unwrap();
+        assert!(proxy.contains(&"fd12:3456::1".parse().unwrap()));
+        assert!(!proxy.contains(&"fe80::1".parse().unwrap()));
+
+        let proxy: TrustedProxy = "0.0.0.0/0".parse().unwrap();
+        assert!(proxy.contains(&"8.8.8.8".parse().unwrap()));
+
+        for invalid in ["", "localhost", "10.0.0.0/33", "::/129", "10.0.0.0/x"] {
+            assert!(invalid.parse::<TrustedProxy>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_jwt_key_config() {
+        let key: JwtKeyConfig = "2025-01:RS256:keys/2025-01.pub.pem:keys/2025-01.pem"
+            .parse()
+            .unwrap();
+        assert_eq!(key.kid, "2025-01");
+        assert_eq!(key.algorithm, jsonwebtoken::Algorithm::RS256);
+        assert_eq!(key.public_key_path, "keys/2025-01.pub.pem");
+        assert_eq!(key.private_key_path.as_deref(), Some("keys/2025-01.pem"));
+
+        // 只有公钥的旧密钥仅用于验证
+        let key: JwtKeyConfig = " old : EdDSA : keys/old.pub.pem ".parse().unwrap();
+        assert_eq!(key.kid, "old");
+        assert_eq!(key.algorithm, jsonwebtoken::Algorithm::EdDSA);
+        assert_eq!(key.private_key_path, None);
+
+        for invalid in [
+            "",
+            "kid:RS256",
+            ":RS256:a.pem",
+            "kid:RS999:a.pem",
+            "a:RS256:b:c:d",
+        ] {
+            assert!(invalid.parse::<JwtKeyConfig>().is_err(), "{invalid}");
+        }
+    }
+}
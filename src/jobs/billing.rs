@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use crate::services::BillingService;
+
+/// 按配置的间隔对按量计费用户扣费
+///
+/// 按日扣费以日期去重，按流量扣费只结算整 GB，重复执行不会多扣
+pub fn spawn(service: BillingService) {
+    let interval = Duration::from_secs(service.config().check_interval.max(60));
+
+    actix_web::rt::spawn(async move {
+        let mut ticker = actix_web::rt::time::interval(interval);
+        loop {
+            ticker.tick().await;
+
+            let date = service.today();
+            match service.run(date).await {
+                Ok(summary)
+                    if summary.daily_charged > 0
+                        || summary.transfer_charged > 0
+                        || summary.suspended > 0
+                        || summary.resumed > 0
+                        || summary.failed > 0 =>
+                {
+                    tracing::info!(
+                        "按量扣费完成: 日期 {}，按日扣费 {} 个用户，按流量扣费 {} 个用户，停用 {} 个，恢复 {} 个，失败 {} 个",
+                        summary.date,
+                        summary.daily_charged,
+                        summary.transfer_charged,
+                        summary.suspended,
+                        summary.resumed,
+                        summary.failed
+                    )
+                }
+                Ok(_) => {}
+                Err(e) => tracing::error!("按量扣费任务执行失败: {:#}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,12 @@
+//! 后台定时任务
+
+mod billing;
+mod traffic_reset;
+
+use crate::app_state::AppState;
+
+/// 启动所有后台任务
+pub fn spawn_all(app_state: &AppState) {
+    traffic_reset::spawn(app_state.traffic_reset_service.clone());
+    billing::spawn(app_state.billing_service.clone());
+}
@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+use crate::services::TrafficResetService;
+
+/// 按配置的间隔检查并重置用户流量
+///
+/// 重置以周期去重，间隔小于一天时同一天会多次执行，
+/// 服务在重置日中途重启也能补上未完成的用户
+pub fn spawn(service: TrafficResetService) {
+    let interval = Duration::from_secs(service.config().check_interval.max(60));
+
+    actix_web::rt::spawn(async move {
+        let mut ticker = actix_web::rt::time::interval(interval);
+        loop {
+            ticker.tick().await;
+
+            let date = service.today();
+            match service.run(date).await {
+                Ok(summary) if summary.reset > 0 || summary.failed > 0 => tracing::info!(
+                    "流量重置完成: 日期 {}，重置 {} 个用户，失败 {} 个",
+                    summary.date,
+                    summary.reset,
+                    summary.failed
+                ),
+                Ok(_) => {}
+                Err(e) => tracing::error!("流量重置任务执行失败: {:#}", e),
+            }
+        }
+    });
+}
@@ -2,6 +2,7 @@ mod api;
 mod app_state;
 mod common;
 mod config;
+mod jobs;
 mod logging;
 mod middleware;
 mod models;
@@ -9,6 +10,8 @@ mod repositories;
 mod routes;
 mod services;
 mod startup;
+#[cfg(test)]
+mod test_support;
 mod utils;
 
 use startup::Application;
@@ -1,4 +1,7 @@
-use std::future::{ready, Ready};
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
 
 use actix_web::{
     body::{BoxBody, EitherBody},
@@ -6,20 +9,33 @@ use actix_web::{
     web, Error, HttpMessage,
 };
 use futures_util::future::LocalBoxFuture;
+use jsonwebtoken::errors::ErrorKind;
 
 use crate::{
     common::{ErrorCode, ResponseBuilder},
-    models::auth::Claims,
-    repositories::UserRepository,
+    models::{api_key::ApiKey, permission::PermissionSet, user::Role},
+    repositories::{PermissionRepository, SessionRepository, UserRepository},
+    services::{ApiKeyService, AuthService},
+    utils::client_ip,
 };
 
 /// 认证中间件
+///
+/// 接受 `Authorization: Bearer` 携带的访问令牌或个人 API 密钥
 #[derive(Clone)]
-pub struct Auth;
+pub struct Auth {
+    optional: bool,
+}
 
 impl Auth {
     pub fn new() -> Self {
-        Auth
+        Auth { optional: false }
+    }
+
+    /// 可选认证：未携带授权令牌时直接放行，处理器中没有当前用户；
+    /// 携带了令牌时按正常流程校验，令牌无效仍返回错误
+    pub fn optional() -> Self {
+        Auth { optional: true }
     }
 }
 
@@ -31,7 +47,7 @@ impl Default for Auth {
 
 impl<S, B> Transform<S, ServiceRequest> for Auth
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static + Clone,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
@@ -42,17 +58,21 @@ where
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
-        ready(Ok(AuthMiddleware { service }))
+        ready(Ok(AuthMiddleware {
+            service: Rc::new(service),
+            optional: self.optional,
+        }))
     }
 }
 
 pub struct AuthMiddleware<S> {
-    service: S,
+    service: Rc<S>,
+    optional: bool,
 }
 
 impl<S, B> Service<ServiceRequest> for AuthMiddleware<S>
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static + Clone,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
@@ -63,7 +83,8 @@ where
     forward_ready!(service);
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
-        let service = self.service.clone();
+        let service = Rc::clone(&self.service);
+        let optional = self.optional;
 
         Box::pin(async move {
             // 获取 Authorization header
@@ -75,6 +96,10 @@ where
 
             let token = match auth_header {
                 Some(token) => token,
+                None if optional => {
+                    let res = service.call(req).await?;
+                    return Ok(res.map_body(|_, body| EitherBody::left(body)));
+                }
                 None => {
                     tracing::warn!("Missing authorization header");
                     let response = ResponseBuilder::error_with_message(
@@ -87,30 +112,125 @@ where
             };
 
             // 验证 token
-            let claims = match Claims::decode(token) {
-                Ok(claims) => claims,
-                Err(e) => {
-                    tracing::warn!("Invalid token: {}", e);
+            let auth_service = match req.app_data::<web::Data<AuthService>>() {
+                Some(auth_service) => auth_service,
+                None => {
+                    tracing::error!("AuthService not found in app data");
                     let response = ResponseBuilder::error_with_message(
-                        ErrorCode::InvalidToken,
-                        "无效的授权令牌".to_string(),
+                        ErrorCode::InternalError,
+                        "服务器配置错误".to_string(),
                     );
                     return Ok(ServiceResponse::new(req.into_parts().0, response)
                         .map_body(|_, body| EitherBody::right(body)));
                 }
             };
 
-            // 检查token是否过期
-            let now = chrono::Utc::now().timestamp();
-            if claims.exp < now {
-                tracing::warn!("Token expired for user: {}", claims.sub);
-                let response = ResponseBuilder::error_with_message(
-                    ErrorCode::TokenExpired,
-                    "授权令牌已过期".to_string(),
-                );
-                return Ok(ServiceResponse::new(req.into_parts().0, response)
-                    .map_body(|_, body| EitherBody::right(body)));
-            }
+            // pk_ 开头的为个人 API 密钥，否则按访问令牌处理
+            let (user_id, session, api_key) = if token.starts_with(ApiKey::TOKEN_PREFIX) {
+                let api_key_service = req
+                    .app_data::<web::Data<ApiKeyService>>()
+                    .map(|service| service.get_ref().clone());
+                let ip = client_ip(req.request());
+                let api_key = match api_key_service {
+                    Some(service) => service.authenticate(token, ip.as_deref()).await,
+                    None => Err(anyhow::anyhow!("ApiKeyService not found in app data")),
+                };
+                match api_key {
+                    Ok(Some(api_key)) => (api_key.user_id, None, Some(api_key)),
+                    Ok(None) => {
+                        tracing::warn!("Invalid or expired API key");
+                        let response = ResponseBuilder::error_with_message(
+                            ErrorCode::InvalidToken,
+                            "API 密钥无效或已过期".to_string(),
+                        );
+                        return Ok(ServiceResponse::new(req.into_parts().0, response)
+                            .map_body(|_, body| EitherBody::right(body)));
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to verify API key: {}", e);
+                        let response = ResponseBuilder::error_with_message(
+                            ErrorCode::DatabaseError,
+                            "验证 API 密钥失败".to_string(),
+                        );
+                        return Ok(ServiceResponse::new(req.into_parts().0, response)
+                            .map_body(|_, body| EitherBody::right(body)));
+                    }
+                }
+            } else {
+                let claims = match auth_service.decode_access_token(token) {
+                    Ok(claims) => claims,
+                    Err(e) if matches!(e.kind(), ErrorKind::ExpiredSignature) => {
+                        tracing::warn!("Token expired: {}", e);
+                        let response = ResponseBuilder::error_with_message(
+                            ErrorCode::TokenExpired,
+                            "授权令牌已过期".to_string(),
+                        );
+                        return Ok(ServiceResponse::new(req.into_parts().0, response)
+                            .map_body(|_, body| EitherBody::right(body)));
+                    }
+                    Err(e) => {
+                        tracing::warn!("Invalid token: {}", e);
+                        let response = ResponseBuilder::error_with_message(
+                            ErrorCode::InvalidToken,
+                            "无效的授权令牌".to_string(),
+                        );
+                        return Ok(ServiceResponse::new(req.into_parts().0, response)
+                            .map_body(|_, body| EitherBody::right(body)));
+                    }
+                };
+
+                // 检查token是否过期
+                let now = chrono::Utc::now().timestamp();
+                if claims.exp < now {
+                    tracing::warn!("Token expired for user: {}", claims.sub);
+                    let response = ResponseBuilder::error_with_message(
+                        ErrorCode::TokenExpired,
+                        "授权令牌已过期".to_string(),
+                    );
+                    return Ok(ServiceResponse::new(req.into_parts().0, response)
+                        .map_body(|_, body| EitherBody::right(body)));
+                }
+
+                // 检查会话是否仍然有效，会话被撤销后访问令牌立即失效
+                let session_repository = req
+                    .app_data::<web::Data<SessionRepository>>()
+                    .map(|repo| repo.get_ref().clone());
+                let session = match session_repository {
+                    Some(repo) => repo.find_by_id(claims.sid).await,
+                    None => Err(anyhow::anyhow!("SessionRepository not found in app data")),
+                };
+                let session = match session {
+                    Ok(Some(session))
+                        if session.user_id == claims.sub && session.is_active(now) =>
+                    {
+                        session
+                    }
+                    Ok(_) => {
+                        tracing::warn!(
+                            "Session {} of user {} is no longer active",
+                            claims.sid,
+                            claims.sub
+                        );
+                        let response = ResponseBuilder::error_with_message(
+                            ErrorCode::InvalidToken,
+                            "会话已失效，请重新登录".to_string(),
+                        );
+                        return Ok(ServiceResponse::new(req.into_parts().0, response)
+                            .map_body(|_, body| EitherBody::right(body)));
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to verify session {}: {}", claims.sid, e);
+                        let response = ResponseBuilder::error_with_message(
+                            ErrorCode::DatabaseError,
+                            "验证会话失败".to_string(),
+                        );
+                        return Ok(ServiceResponse::new(req.into_parts().0, response)
+                            .map_body(|_, body| EitherBody::right(body)));
+                    }
+                };
+
+                (claims.sub, Some(session), None)
+            };
 
             // 检查用户是否存在
             let user_repository = match req.app_data::<web::Data<UserRepository>>() {
@@ -126,11 +246,11 @@ where
                 }
             };
 
-            match user_repository.get_ref().find_by_id(claims.sub).await {
+            match user_repository.get_ref().find_by_id(user_id).await {
                 Ok(Some(user)) => {
                     // 检查用户是否被禁用
                     if user.banned.unwrap_or(false) {
-                        tracing::warn!("Banned user {} attempted to access", claims.sub);
+                        tracing::warn!("Banned user {} attempted to access", user_id);
                         let response = ResponseBuilder::error_with_message(
                             ErrorCode::UserDisabled,
                             "账户已被禁用".to_string(),
@@ -139,17 +259,64 @@ where
                             .map_body(|_, body| EitherBody::right(body)));
                     }
 
+                    // 加载后台权限，供权限校验中间件使用
+                    let permissions = match user.role() {
+                        Role::Admin => PermissionSet::all(),
+                        Role::User => PermissionSet::default(),
+                        Role::Staff => {
+                            let permission_repository = req
+                                .app_data::<web::Data<PermissionRepository>>()
+                                .map(|repo| repo.get_ref().clone());
+                            let permissions = match permission_repository {
+                                Some(repo) => repo.find_by_user(user.id).await,
+                                None => Err(anyhow::anyhow!(
+                                    "PermissionRepository not found in app data"
+                                )),
+                            };
+                            match permissions {
+                                Ok(permissions) => permissions.into_iter().collect(),
+                                Err(e) => {
+                                    tracing::error!(
+                                        "Failed to load permissions for user {}: {}",
+                                        user.id,
+                                        e
+                                    );
+                                    let response = ResponseBuilder::error_with_message(
+                                        ErrorCode::DatabaseError,
+                                        "加载用户权限失败".to_string(),
+                                    );
+                                    return Ok(ServiceResponse::new(req.into_parts().0, response)
+                                        .map_body(|_, body| EitherBody::right(body)));
+                                }
+                            }
+                        }
+                    };
+                    // API 密钥只保留授予的权限
+                    let permissions = match &api_key {
+                        Some(api_key) => permissions.restrict(&api_key.permissions()),
+                        None => permissions,
+                    };
+                    req.extensions_mut().insert(permissions);
+
                     // 将用户ID添加到请求扩展中，供后续处理器使用
-                    req.extensions_mut().insert(claims.sub);
+                    req.extensions_mut().insert(user_id);
 
                     // 可选：将完整的用户信息也添加到扩展中
                     req.extensions_mut().insert(user);
 
+                    // 当前会话，供退出登录和会话管理使用，使用 API 密钥时没有会话
+                    if let Some(session) = session {
+                        req.extensions_mut().insert(session);
+                    }
+                    if let Some(api_key) = api_key {
+                        req.extensions_mut().insert(api_key);
+                    }
+
                     let res = service.call(req).await?;
                     Ok(res.map_body(|_, body| EitherBody::left(body)))
                 }
                 Ok(None) => {
-                    tracing::warn!("User {} not found", claims.sub);
+                    tracing::warn!("User {} not found", user_id);
                     let response = ResponseBuilder::error_with_message(
                         ErrorCode::UserNotFound,
                         "用户不存在".to_string(),
@@ -158,7 +325,7 @@ where
                         .map_body(|_, body| EitherBody::right(body)))
                 }
                 Err(e) => {
-                    tracing::error!("Failed to verify user {}: {}", claims.sub, e);
+                    tracing::error!("Failed to verify user {}: {}", user_id, e);
                     let response = ResponseBuilder::error_with_message(
                         ErrorCode::DatabaseError,
                         "验证用户失败".to_string(),
@@ -180,3 +347,78 @@ pub fn get_current_user_id(req: &ServiceRequest) -> Option<i32> {
 pub fn get_current_user(req: &ServiceRequest) -> Option<crate::models::user::User> {
     req.extensions().get::<crate::models::user::User>().cloned()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{models::user::User, test_support};
+    use actix_web::{test, App, HttpResponse};
+
+    async fn whoami(user: Option<web::ReqData<User>>) -> HttpResponse {
+        match user {
+            Some(user) => HttpResponse::Ok().body(user.id.to_string()),
+            None => HttpResponse::Ok().body("guest"),
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_optional_without_token() {
+        let app = test::init_service(
+            App::new().service(
+                web::scope("/plans")
+                    .wrap(Auth::optional())
+                    .route("", web::get().to(whoami)),
+            ),
+        )
+        .await;
+
+        let body =
+            test::call_and_read_body(&app, test::TestRequest::get().uri("/plans").to_request())
+                .await;
+        assert_eq!(body, "guest");
+    }
+
+    #[actix_web::test]
+    async fn test_required_without_token() {
+        let app = test::init_service(
+            App::new().service(
+                web::scope("/user")
+                    .wrap(Auth::new())
+                    .route("", web::get().to(whoami)),
+            ),
+        )
+        .await;
+
+        let body =
+            test::call_and_read_body(&app, test::TestRequest::get().uri("/user").to_request())
+                .await;
+        assert_ne!(body, "guest");
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], ErrorCode::Unauthorized as i32);
+    }
+
+    #[actix_web::test]
+    async fn test_optional_rejects_invalid_token() {
+        // 携带了令牌时不会降级为未登录，校验失败照常返回错误
+        let pool = sqlx::PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_support::auth_service(&pool)))
+                .service(
+                    web::scope("/plans")
+                        .wrap(Auth::optional())
+                        .route("", web::get().to(whoami)),
+                ),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/plans")
+            .insert_header(("Authorization", "Bearer invalid"))
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        assert_ne!(body, "guest");
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], ErrorCode::InvalidToken as i32);
+    }
+}
@@ -1,7 +1,15 @@
 pub mod auth;
 pub mod cors;
 pub mod logging;
+pub mod permission;
+pub mod role;
+pub mod session;
+pub mod two_factor;
 
 pub use auth::Auth;
 pub use cors::Cors;
 pub use logging::RequestLogging;
+pub use permission::RequirePermission;
+pub use role::RequireRole;
+pub use session::RequireSession;
+pub use two_factor::RequireTwoFactor;
@@ -0,0 +1,98 @@
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error, HttpMessage,
+};
+use futures_util::future::LocalBoxFuture;
+
+use crate::{
+    common::{ErrorCode, ResponseBuilder},
+    models::permission::{Permission, PermissionSet},
+};
+
+/// 权限校验中间件，要求当前用户拥有指定权限
+///
+/// 依赖 [`Auth`](super::Auth) 写入的权限信息，通常通过路由宏的 `wrap` 参数
+/// 作用于单个接口，如 `#[get("", wrap = "RequirePermission(Permission::UsersRead)")]`
+#[derive(Debug, Clone, Copy)]
+pub struct RequirePermission(pub Permission);
+
+impl<S, B> Transform<S, ServiceRequest> for RequirePermission
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequirePermissionMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequirePermissionMiddleware {
+            service: Rc::new(service),
+            permission: self.0,
+        }))
+    }
+}
+
+pub struct RequirePermissionMiddleware<S> {
+    service: Rc<S>,
+    permission: Permission,
+}
+
+impl<S, B> Service<ServiceRequest> for RequirePermissionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let required = self.permission;
+
+        Box::pin(async move {
+            let granted = req
+                .extensions()
+                .get::<PermissionSet>()
+                .map(|permissions| permissions.contains(required));
+            let response = match granted {
+                Some(true) => {
+                    let res = service.call(req).await?;
+                    return Ok(res.map_body(|_, body| EitherBody::left(body)));
+                }
+                Some(false) => {
+                    tracing::warn!(
+                        "Permission denied for {} {}: missing {}",
+                        req.method(),
+                        req.path(),
+                        required.as_str()
+                    );
+                    ResponseBuilder::error_with_message(
+                        ErrorCode::PermissionDenied,
+                        format!("缺少权限: {}", required.as_str()),
+                    )
+                }
+                None => ResponseBuilder::error_with_message(
+                    ErrorCode::Unauthorized,
+                    "缺少授权令牌".to_string(),
+                ),
+            };
+
+            Ok(ServiceResponse::new(req.into_parts().0, response)
+                .map_body(|_, body| EitherBody::right(body)))
+        })
+    }
+}
@@ -0,0 +1,96 @@
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error, HttpMessage,
+};
+use futures_util::future::LocalBoxFuture;
+
+use crate::{
+    common::{ErrorCode, ResponseBuilder},
+    models::user::{Role, User},
+};
+
+/// 角色校验中间件，要求当前用户的角色不低于指定角色
+///
+/// 依赖 [`Auth`](super::Auth) 写入的用户信息，需先于 `Auth` 调用 `wrap`，
+/// 使 `Auth` 在外层先执行
+#[derive(Debug, Clone, Copy)]
+pub struct RequireRole(pub Role);
+
+impl<S, B> Transform<S, ServiceRequest> for RequireRole
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequireRoleMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequireRoleMiddleware {
+            service: Rc::new(service),
+            role: self.0,
+        }))
+    }
+}
+
+pub struct RequireRoleMiddleware<S> {
+    service: Rc<S>,
+    role: Role,
+}
+
+impl<S, B> Service<ServiceRequest> for RequireRoleMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let required = self.role;
+
+        Box::pin(async move {
+            let role = req.extensions().get::<User>().map(User::role);
+            let response = match role {
+                Some(role) if role >= required => {
+                    let res = service.call(req).await?;
+                    return Ok(res.map_body(|_, body| EitherBody::left(body)));
+                }
+                Some(role) => {
+                    tracing::warn!(
+                        "Permission denied for {} {}: role {:?}, required {:?}",
+                        req.method(),
+                        req.path(),
+                        role,
+                        required
+                    );
+                    ResponseBuilder::error_with_message(
+                        ErrorCode::PermissionDenied,
+                        "权限不足".to_string(),
+                    )
+                }
+                None => ResponseBuilder::error_with_message(
+                    ErrorCode::Unauthorized,
+                    "缺少授权令牌".to_string(),
+                ),
+            };
+
+            Ok(ServiceResponse::new(req.into_parts().0, response)
+                .map_body(|_, body| EitherBody::right(body)))
+        })
+    }
+}
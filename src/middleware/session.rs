@@ -0,0 +1,83 @@
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    Error, HttpMessage,
+};
+use futures_util::future::LocalBoxFuture;
+
+use crate::{
+    common::{ErrorCode, ResponseBuilder},
+    models::session::Session,
+};
+
+/// 登录会话校验中间件，拒绝使用 API 密钥访问
+///
+/// 用于修改密码、管理会话和密钥等账户安全相关的接口，避免密钥泄露后被用来接管账户。
+/// 依赖 [`Auth`](super::Auth) 写入的会话信息，如 `#[post("/password", wrap = "RequireSession")]`
+#[derive(Debug, Clone, Copy)]
+pub struct RequireSession;
+
+impl<S, B> Transform<S, ServiceRequest> for RequireSession
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequireSessionMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequireSessionMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct RequireSessionMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequireSessionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            if req.extensions().contains::<Session>() {
+                let res = service.call(req).await?;
+                return Ok(res.map_body(|_, body| EitherBody::left(body)));
+            }
+
+            tracing::warn!(
+                "Session required for {} {}, rejecting API key",
+                req.method(),
+                req.path()
+            );
+            let response = ResponseBuilder::error_with_message(
+                ErrorCode::PermissionDenied,
+                "该操作需要登录，不支持使用 API 密钥".to_string(),
+            );
+
+            Ok(ServiceResponse::new(req.into_parts().0, response)
+                .map_body(|_, body| EitherBody::right(body)))
+        })
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    web, Error, HttpMessage,
+};
+use futures_util::future::LocalBoxFuture;
+
+use crate::{
+    common::{ErrorCode, ResponseBuilder},
+    models::user::User,
+    services::TwoFactorService,
+};
+
+/// 双重验证校验中间件，开启 `TWO_FACTOR_REQUIRED_FOR_STAFF` 后，
+/// 未启用双重验证的管理员和客服无法访问被保护的接口
+///
+/// 依赖 [`Auth`](super::Auth) 写入的用户信息，需先于 `Auth` 调用 `wrap`
+#[derive(Debug, Clone, Copy)]
+pub struct RequireTwoFactor;
+
+impl<S, B> Transform<S, ServiceRequest> for RequireTwoFactor
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequireTwoFactorMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequireTwoFactorMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct RequireTwoFactorMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequireTwoFactorMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            let user = req.extensions().get::<User>().cloned();
+            let two_factor_service = req.app_data::<web::Data<TwoFactorService>>().cloned();
+
+            let response = match (user, two_factor_service) {
+                (Some(user), Some(two_factor_service)) => {
+                    if !two_factor_service.is_required(&user) {
+                        let res = service.call(req).await?;
+                        return Ok(res.map_body(|_, body| EitherBody::left(body)));
+                    }
+                    match two_factor_service.is_enabled(user.id).await {
+                        Ok(true) => {
+                            let res = service.call(req).await?;
+                            return Ok(res.map_body(|_, body| EitherBody::left(body)));
+                        }
+                        Ok(false) => {
+                            tracing::warn!(
+                                "Two-factor required for {} {}: user {} has not enabled it",
+                                req.method(),
+                                req.path(),
+                                user.id
+                            );
+                            ResponseBuilder::error_with_message(
+                                ErrorCode::TwoFactorRequired,
+                                "请先启用双重验证".to_string(),
+                            )
+                        }
+                        Err(e) => {
+                            tracing::error!(
+                                "Failed to load two-factor status for user {}: {}",
+                                user.id,
+                                e
+                            );
+                            ResponseBuilder::error_with_message(
+                                ErrorCode::DatabaseError,
+                                "加载双重验证状态失败".to_string(),
+                            )
+                        }
+                    }
+                }
+                (None, _) => ResponseBuilder::error_with_message(
+                    ErrorCode::Unauthorized,
+                    "缺少授权令牌".to_string(),
+                ),
+                (Some(_), None) => {
+                    tracing::error!("TwoFactorService not found in app data");
+                    ResponseBuilder::error_with_message(
+                        ErrorCode::InternalError,
+                        "服务器配置错误".to_string(),
+                    )
+                }
+            };
+
+            Ok(ServiceResponse::new(req.into_parts().0, response)
+                .map_body(|_, body| EitherBody::right(body)))
+        })
+    }
+}
@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::models::permission::Permission;
+
+/// 个人 API 密钥
+///
+/// 完整密钥格式为 `pk_<前缀>_<密钥>`，只在创建时返回一次，数据库只保存前缀和密钥的哈希
+#[derive(Debug, Clone, FromRow)]
+pub struct ApiKey {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub prefix: String,
+    pub secret_hash: String,
+    /// 授予的后台权限名称
+    pub scopes: Vec<String>,
+    pub expired_at: Option<i32>,
+    pub last_used_at: Option<i32>,
+    pub last_used_ip: Option<String>,
+    pub created_at: i32,
+    pub revoked_at: Option<i32>,
+}
+
+impl ApiKey {
+    /// 完整密钥的固定前缀，认证时据此与访问令牌区分
+    pub const TOKEN_PREFIX: &'static str = "pk_";
+
+    /// 密钥未撤销且未过期
+    pub fn is_active(&self, now: i64) -> bool {
+        self.revoked_at.is_none()
+            && self
+                .expired_at
+                .is_none_or(|expired_at| i64::from(expired_at) > now)
+    }
+
+    /// 授予的后台权限，忽略已不存在的权限名称
+    pub fn permissions(&self) -> Vec<Permission> {
+        self.scopes
+            .iter()
+            .filter_map(|scope| scope.parse().ok())
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct CreateApiKeyRequest {
+    /// 密钥名称，便于区分用途
+    #[validate(length(min = 1, max = 64))]
+    pub name: String,
+    /// 授予的后台权限，不能超过当前账户的权限，普通用户只能为空
+    #[serde(default)]
+    pub scopes: Vec<Permission>,
+    /// 过期时间，为空表示永不过期
+    pub expired_at: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ApiKeyResponse {
+    pub id: i32,
+    pub name: String,
+    /// 密钥前缀，用于识别密钥
+    pub prefix: String,
+    pub scopes: Vec<Permission>,
+    pub expired_at: Option<i32>,
+    pub last_used_at: Option<i32>,
+    pub last_used_ip: Option<String>,
+    pub created_at: i32,
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(api_key: ApiKey) -> Self {
+        Self {
+            scopes: api_key.permissions(),
+            id: api_key.id,
+            name: api_key.name,
+            prefix: api_key.prefix,
+            expired_at: api_key.expired_at,
+            last_used_at: api_key.last_used_at,
+            last_used_ip: api_key.last_used_ip,
+            created_at: api_key.created_at,
+        }
+    }
+}
+
+/// 创建密钥的结果，完整密钥只在此时返回一次
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateApiKeyResponse {
+    /// 完整密钥，请求时放在 `Authorization: Bearer` 中
+    pub key: String,
+    pub api_key: ApiKeyResponse,
+}
@@ -1,65 +1,107 @@
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use utoipa::ToSchema;
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
 #[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct RegisterRequest {
-    #[validate(length(min = 3, max = 20))]
+    /// 3-20 位字母、数字或下划线，以字母开头，不区分大小写
+    #[validate(custom = "validate_username")]
     pub username: String,
-    #[validate(email)]
+    #[validate(email, length(max = 64))]
     pub email: String,
     #[validate(length(min = 6, max = 32))]
     pub password: String,
+    /// 邮箱验证码，开启注册邮箱验证时必填
+    pub email_code: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct LoginRequest {
-    #[validate(length(min = 3, max = 20))]
+    /// 用户名或邮箱
+    #[validate(length(min = 3, max = 64))]
     pub username: String,
     #[validate(length(min = 6, max = 32))]
     pub password: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct ChangePasswordRequest {
+    pub old_password: String,
+    #[validate(length(min = 6, max = 32))]
+    pub new_password: String,
+}
+
+/// 校验用户名：3-20 位字母、数字或下划线，以字母开头
+///
+/// 用户名不能包含 `@`，登录时据此区分用户名和邮箱
+pub fn validate_username(username: &str) -> Result<(), ValidationError> {
+    let valid = (3..=20).contains(&username.len())
+        && username.starts_with(|c: char| c.is_ascii_alphabetic())
+        && username
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        return Ok(());
+    }
+
+    let mut error = ValidationError::new("username");
+    error.message = Some(Cow::Borrowed(
+        "用户名须为 3-20 位字母、数字或下划线，并以字母开头",
+    ));
+    Err(error)
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct TokenResponse {
     pub access_token: String,
     pub token_type: String,
     pub expires_in: i64,
+    /// 用于换取新访问令牌，每次使用后轮换
+    pub refresh_token: String,
+    pub refresh_expires_in: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct Claims {
     pub sub: i32, // user_id
+    pub sid: i32, // session id
     pub exp: i64, // expiration time
     pub iat: i64, // issued at
 }
 
 impl Claims {
-    pub fn new(user_id: i32) -> Self {
-        let exp = chrono::Utc::now()
-            .checked_add_signed(chrono::Duration::days(7))
-            .expect("valid timestamp")
-            .timestamp();
+    pub fn new(user_id: i32, session_id: i32, ttl: i64) -> Self {
+        let now = chrono::Utc::now().timestamp();
 
         Claims {
             sub: user_id,
-            exp,
-            iat: chrono::Utc::now().timestamp(),
+            sid: session_id,
+            exp: now + ttl,
+            iat: now,
         }
     }
+}
 
-    pub fn decode(token: &str) -> Result<Self, jsonwebtoken::errors::Error> {
-        let jwt_secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set");
-        let key = DecodingKey::from_secret(jwt_secret.as_bytes());
-        let validation = Validation::default();
-        let token_data = decode::<Claims>(token, &key, &validation)?;
-        Ok(token_data.claims)
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    pub fn encode(&self) -> Result<String, jsonwebtoken::errors::Error> {
-        let jwt_secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set");
-        let key = EncodingKey::from_secret(jwt_secret.as_bytes());
-        encode(&Header::default(), self, &key)
+    #[test]
+    fn test_validate_username() {
+        for valid in ["abc", "Alice_2024", "a_b_c", "abcdefghijklmnopqrst"] {
+            assert!(validate_username(valid).is_ok(), "{valid}");
+        }
+        for invalid in [
+            "ab",
+            "abcdefghijklmnopqrstu",
+            "1abc",
+            "_abc",
+            "user@example.com",
+            "ab cd",
+            "用户名",
+        ] {
+            assert!(validate_username(invalid).is_err(), "{invalid}");
+        }
     }
 }
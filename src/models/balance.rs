@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+/// 余额变动类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceLogType {
+    /// 余额支付订单
+    OrderPayment = 1,
+    /// 按量计费套餐按日扣费
+    DailyCharge = 2,
+    /// 按量计费套餐按流量扣费
+    TransferCharge = 3,
+}
+
+impl BalanceLogType {
+    pub fn as_i16(&self) -> i16 {
+        *self as i16
+    }
+}
+
+/// 余额变动记录
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct BalanceLog {
+    pub id: i32,
+    pub user_id: i32,
+    /// 变动类型，取值见 [`BalanceLogType`]
+    pub r#type: i16,
+    /// 变动金额，扣费为负数
+    pub amount: i32,
+    /// 变动后的余额
+    pub balance: i32,
+    pub order_id: Option<i32>,
+    /// 按日计费的日期 `YYYY-MM-DD`
+    pub period: Option<String>,
+    /// 按流量计费的流量（字节）
+    pub transfer: Option<i64>,
+    pub remark: Option<String>,
+    pub created_at: i32,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct BalanceLogQuery {
+    /// 按用户筛选，用户自助接口忽略该参数
+    pub user_id: Option<i32>,
+    #[serde(default = "default_page")]
+    pub page: u64,
+    #[serde(default = "default_page_size")]
+    pub page_size: u64,
+}
+
+fn default_page() -> u64 {
+    1
+}
+
+fn default_page_size() -> u64 {
+    10
+}
+
+/// 按量计费状态
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MeteredBilling {
+    pub user_id: i32,
+    /// 已计费的流量（字节）
+    pub billed_transfer: i64,
+    /// 是否因余额耗尽被停用，停用期间节点不再下发该用户
+    pub suspended: bool,
+    pub updated_at: i32,
+}
+
+/// 一次按量扣费
+#[derive(Debug, Clone)]
+pub struct MeteredCharge {
+    pub user_id: i32,
+    pub r#type: BalanceLogType,
+    /// 应扣金额，余额不足时只扣除剩余余额
+    pub amount: i32,
+    /// 按日计费的日期，用于去重
+    pub period: Option<String>,
+    /// 本次计费的流量（字节）
+    pub transfer: Option<i64>,
+    /// 扣费前读取到的已计费流量（字节），用于防止重复计费
+    pub expected_billed_transfer: Option<i64>,
+    /// 扣费后的已计费流量（字节）
+    pub billed_transfer: Option<i64>,
+    pub remark: Option<String>,
+}
+
+/// 扣费结果
+#[derive(Debug, Clone, Copy)]
+pub struct ChargeOutcome {
+    /// 实际扣除的金额
+    pub amount: i32,
+    /// 本次扣费后余额耗尽，用户已被停用
+    pub suspended: bool,
+}
+
+/// 一次按量计费任务的执行结果
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct BillingSummary {
+    /// 执行所用的日期
+    pub date: String,
+    /// 检查的按量计费用户数
+    pub checked: u64,
+    /// 本次按日扣费的用户数
+    pub daily_charged: u64,
+    /// 本次按流量扣费的用户数
+    pub transfer_charged: u64,
+    /// 本次扣除的总金额
+    pub amount: i64,
+    /// 因余额耗尽被停用的用户数
+    pub suspended: u64,
+    /// 余额充足后恢复的用户数
+    pub resumed: u64,
+    /// 扣费失败的用户数
+    pub failed: u64,
+}
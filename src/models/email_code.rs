@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// 邮箱验证码用途
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailCodePurpose {
+    /// 注册
+    Register = 0,
+    /// 重置密码
+    ResetPassword = 1,
+}
+
+impl EmailCodePurpose {
+    pub fn as_i16(&self) -> i16 {
+        *self as i16
+    }
+
+    /// 邮件标题中的用途说明
+    pub fn subject(&self) -> &'static str {
+        match self {
+            EmailCodePurpose::Register => "注册验证码",
+            EmailCodePurpose::ResetPassword => "重置密码验证码",
+        }
+    }
+}
+
+/// 邮箱验证码，只保存哈希
+#[derive(Debug, Clone, FromRow)]
+pub struct EmailCode {
+    pub id: i32,
+    pub code_hash: String,
+    pub attempts: i32,
+    pub created_at: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct SendEmailCodeRequest {
+    #[validate(email)]
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct ForgotPasswordRequest {
+    #[validate(email)]
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct ResetPasswordRequest {
+    #[validate(email)]
+    pub email: String,
+    #[validate(length(min = 1, max = 16))]
+    pub email_code: String,
+    #[validate(length(min = 6, max = 32))]
+    pub password: String,
+}
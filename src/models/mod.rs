@@ -1,7 +1,19 @@
 // 模型定义将在这里添加
 
+pub mod api_key;
 pub mod auth;
+pub mod balance;
 pub mod coupon;
+pub mod email_code;
 pub mod order;
+pub mod permission;
 pub mod plan;
+pub mod rate_schedule;
+pub mod route;
+pub mod server;
+pub mod session;
+pub mod subscribe_log;
+pub mod subscribe_template;
+pub mod traffic_reset;
+pub mod two_factor;
 pub mod user;
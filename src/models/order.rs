@@ -1,8 +1,54 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
+use crate::models::plan::PlanPeriod;
+
+/// 订单类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderType {
+    /// 新购
+    New = 1,
+    /// 续费
+    Renew = 2,
+    /// 升级
+    Upgrade = 3,
+    /// 流量重置
+    ResetTraffic = 4,
+    /// 流量包
+    TrafficAddon = 5,
+}
+
+impl OrderType {
+    pub fn as_i32(&self) -> i32 {
+        *self as i32
+    }
+}
+
+/// 订单状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    /// 待支付
+    Pending = 0,
+    /// 开通中
+    Processing = 1,
+    /// 已取消
+    Cancelled = 2,
+    /// 已完成
+    Completed = 3,
+    /// 已折抵
+    Discounted = 4,
+}
+
+impl OrderStatus {
+    pub fn as_i16(&self) -> i16 {
+        *self as i16
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Order {
     pub id: i32,
@@ -22,7 +68,9 @@ pub struct Order {
     pub refund_amount: Option<i32>,
     pub balance_amount: Option<i32>,
     pub surplus_order_ids: Option<String>,
-    pub status: bool,
+    /// 流量包流量（GB）
+    pub transfer_amount: Option<i64>,
+    pub status: i16,
     pub commission_status: bool,
     pub commission_balance: i32,
     pub actual_commission_balance: Option<i32>,
@@ -61,7 +109,8 @@ pub struct CreateOrderRequest {
     #[validate(range(min = 0))]
     pub balance_amount: Option<i32>,
     pub surplus_order_ids: Option<String>,
-    pub status: Option<bool>,
+    #[validate(range(min = 0, max = 4))]
+    pub status: Option<i16>,
     pub commission_status: Option<bool>,
     #[validate(range(min = 0))]
     pub commission_balance: Option<i32>,
@@ -100,7 +149,8 @@ pub struct UpdateOrderRequest {
     #[validate(range(min = 0))]
     pub balance_amount: Option<i32>,
     pub surplus_order_ids: Option<String>,
-    pub status: Option<bool>,
+    #[validate(range(min = 0, max = 4))]
+    pub status: Option<i16>,
     pub commission_status: Option<bool>,
     #[validate(range(min = 0))]
     pub commission_balance: Option<i32>,
@@ -109,7 +159,7 @@ pub struct UpdateOrderRequest {
     pub paid_at: Option<i32>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct OrderResponse {
     pub id: i32,
     pub invite_user_id: Option<i32>,
@@ -128,7 +178,9 @@ pub struct OrderResponse {
     pub refund_amount: Option<i32>,
     pub balance_amount: Option<i32>,
     pub surplus_order_ids: Option<String>,
-    pub status: bool,
+    /// 流量包流量（GB）
+    pub transfer_amount: Option<i64>,
+    pub status: i16,
     pub commission_status: bool,
     pub commission_balance: i32,
     pub actual_commission_balance: Option<i32>,
@@ -157,6 +209,7 @@ impl From<Order> for OrderResponse {
             refund_amount: order.refund_amount,
             balance_amount: order.balance_amount,
             surplus_order_ids: order.surplus_order_ids,
+            transfer_amount: order.transfer_amount,
             status: order.status,
             commission_status: order.commission_status,
             commission_balance: order.commission_balance,
@@ -168,8 +221,64 @@ impl From<Order> for OrderResponse {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct OrderListResponse {
     pub orders: Vec<OrderResponse>,
     pub total: i64,
 }
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct OrderQuery {
+    #[serde(default = "default_page")]
+    pub page: u64,
+    #[serde(default = "default_page_size")]
+    pub page_size: u64,
+}
+
+fn default_page() -> u64 {
+    1
+}
+
+fn default_page_size() -> u64 {
+    10
+}
+
+/// 购买套餐
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct CreatePlanOrderRequest {
+    #[validate(range(min = 1))]
+    pub plan_id: i32,
+    /// 购买周期
+    pub period: PlanPeriod,
+}
+
+/// 购买流量包
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct CreateTrafficAddonRequest {
+    /// 购买的流量（GB）
+    #[validate(range(min = 1, max = 10240))]
+    pub transfer_gb: i32,
+}
+
+/// 创建订单所需的字段
+#[derive(Debug, Clone)]
+pub struct NewOrder {
+    pub user_id: i32,
+    pub plan_id: i32,
+    pub r#type: OrderType,
+    pub period: String,
+    pub trade_no: String,
+    pub total_amount: i32,
+    pub transfer_amount: Option<i64>,
+}
+
+/// 余额支付结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalancePayment {
+    /// 支付成功并已开通
+    Paid,
+    /// 订单已不是待支付状态
+    NotPending,
+    /// 余额不足
+    InsufficientBalance,
+}
@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use utoipa::ToSchema;
+
+use crate::models::user::Role;
+
+/// 后台操作权限
+///
+/// 管理员拥有全部权限，客服账户按需分配，普通用户没有后台权限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub enum Permission {
+    /// 查看用户及其流量重置记录
+    #[serde(rename = "users.read")]
+    UsersRead,
+    /// 创建、修改、封禁用户，执行流量重置
+    #[serde(rename = "users.write")]
+    UsersWrite,
+    /// 查看套餐
+    #[serde(rename = "plans.read")]
+    PlansRead,
+    /// 创建、修改、删除套餐
+    #[serde(rename = "plans.write")]
+    PlansWrite,
+    /// 查看优惠券
+    #[serde(rename = "coupons.read")]
+    CouponsRead,
+    /// 创建、修改、删除优惠券
+    #[serde(rename = "coupons.write")]
+    CouponsWrite,
+    /// 查看节点、节点路由规则和倍率
+    #[serde(rename = "servers.read")]
+    ServersRead,
+    /// 修改节点、节点路由规则和倍率
+    #[serde(rename = "servers.write")]
+    ServersWrite,
+    /// 查看订阅模板等系统设置
+    #[serde(rename = "settings.read")]
+    SettingsRead,
+    /// 修改订阅模板等系统设置
+    #[serde(rename = "settings.write")]
+    SettingsWrite,
+    /// 查看余额变动记录
+    #[serde(rename = "billing.read")]
+    BillingRead,
+    /// 执行按量扣费
+    #[serde(rename = "billing.write")]
+    BillingWrite,
+    /// 回复工单
+    #[serde(rename = "tickets.reply")]
+    TicketsReply,
+    /// 订单退款
+    #[serde(rename = "orders.refund")]
+    OrdersRefund,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 14] = [
+        Permission::UsersRead,
+        Permission::UsersWrite,
+        Permission::PlansRead,
+        Permission::PlansWrite,
+        Permission::CouponsRead,
+        Permission::CouponsWrite,
+        Permission::ServersRead,
+        Permission::ServersWrite,
+        Permission::SettingsRead,
+        Permission::SettingsWrite,
+        Permission::BillingRead,
+        Permission::BillingWrite,
+        Permission::TicketsReply,
+        Permission::OrdersRefund,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::UsersRead => "users.read",
+            Permission::UsersWrite => "users.write",
+            Permission::PlansRead => "plans.read",
+            Permission::PlansWrite => "plans.write",
+            Permission::CouponsRead => "coupons.read",
+            Permission::CouponsWrite => "coupons.write",
+            Permission::ServersRead => "servers.read",
+            Permission::ServersWrite => "servers.write",
+            Permission::SettingsRead => "settings.read",
+            Permission::SettingsWrite => "settings.write",
+            Permission::BillingRead => "billing.read",
+            Permission::BillingWrite => "billing.write",
+            Permission::TicketsReply => "tickets.reply",
+            Permission::OrdersRefund => "orders.refund",
+        }
+    }
+}
+
+impl std::str::FromStr for Permission {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Permission::ALL
+            .into_iter()
+            .find(|permission| permission.as_str() == s.trim())
+            .ok_or_else(|| anyhow::anyhow!("未知的权限: {}", s))
+    }
+}
+
+/// 当前用户拥有的权限，由认证中间件写入请求扩展
+#[derive(Debug, Clone, Default)]
+pub struct PermissionSet {
+    all: bool,
+    permissions: HashSet<Permission>,
+}
+
+impl PermissionSet {
+    /// 拥有全部权限
+    pub fn all() -> Self {
+        Self {
+            all: true,
+            permissions: HashSet::new(),
+        }
+    }
+
+    pub fn contains(&self, permission: Permission) -> bool {
+        self.all || self.permissions.contains(&permission)
+    }
+
+    /// 只保留给定范围内的权限，用于 API 密钥
+    pub fn restrict(&self, scopes: &[Permission]) -> Self {
+        scopes
+            .iter()
+            .copied()
+            .filter(|permission| self.contains(*permission))
+            .collect()
+    }
+}
+
+impl FromIterator<Permission> for PermissionSet {
+    fn from_iter<I: IntoIterator<Item = Permission>>(iter: I) -> Self {
+        Self {
+            all: false,
+            permissions: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// 设置客服账户的权限，传入的列表会整体替换原有权限
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdatePermissionsRequest {
+    pub permissions: Vec<Permission>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UserPermissionsResponse {
+    pub user_id: i32,
+    pub role: Role,
+    /// 实际生效的权限，管理员为全部权限
+    pub permissions: Vec<Permission>,
+}
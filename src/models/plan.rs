@@ -1,9 +1,11 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Months, NaiveDate};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use utoipa::ToSchema;
 use validator::Validate;
 
+use crate::utils::GB;
+
 #[derive(Debug, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "plan_status", rename_all = "snake_case")]
 pub enum PlanStatus {
@@ -12,6 +14,238 @@ pub enum PlanStatus {
     Deprecated,
 }
 
+/// 流量重置方式，取值与 V2Board 保持一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ResetTrafficMethod {
+    /// 每月 1 日
+    MonthFirstDay = 0,
+    /// 每月购买日（以到期时间的日期为准）
+    MonthAnniversary = 1,
+    /// 不重置
+    Never = 2,
+    /// 每年 1 月 1 日
+    YearFirstDay = 3,
+    /// 每年购买日（以到期时间的月日为准）
+    YearAnniversary = 4,
+}
+
+impl ResetTrafficMethod {
+    pub fn from_i16(value: i16) -> Option<Self> {
+        match value {
+            0 => Some(ResetTrafficMethod::MonthFirstDay),
+            1 => Some(ResetTrafficMethod::MonthAnniversary),
+            2 => Some(ResetTrafficMethod::Never),
+            3 => Some(ResetTrafficMethod::YearFirstDay),
+            4 => Some(ResetTrafficMethod::YearAnniversary),
+            _ => None,
+        }
+    }
+
+    pub fn as_i16(&self) -> i16 {
+        *self as i16
+    }
+
+    /// 不晚于指定日期的最近一次重置日所在的周期标识（月度 `YYYY-MM`，年度 `YYYY`）
+    ///
+    /// 任务错过重置日（如停机）后再运行时仍会返回该周期，
+    /// 由重置记录的 (user_id, period) 唯一约束保证每个周期只重置一次
+    pub fn reset_period(&self, today: NaiveDate, anchor: Option<NaiveDate>) -> Option<String> {
+        let reset_date = self.last_reset_date(today, anchor)?;
+        let format = match self {
+            ResetTrafficMethod::YearFirstDay | ResetTrafficMethod::YearAnniversary => "%Y",
+            _ => "%Y-%m",
+        };
+        Some(reset_date.format(format).to_string())
+    }
+
+    /// 不晚于指定日期的最近一次重置日
+    ///
+    /// `anchor` 为购买日，缺失时按每月 1 日或每年 1 月 1 日处理；
+    /// 购买日大于当月天数时在当月最后一天重置
+    pub fn last_reset_date(
+        &self,
+        today: NaiveDate,
+        anchor: Option<NaiveDate>,
+    ) -> Option<NaiveDate> {
+        match (self, anchor) {
+            (ResetTrafficMethod::Never, _) => None,
+            (ResetTrafficMethod::MonthFirstDay, _)
+            | (ResetTrafficMethod::MonthAnniversary, None) => today.with_day(1),
+            (ResetTrafficMethod::MonthAnniversary, Some(anchor)) => {
+                let this_month = clamped_date(today.year(), today.month(), anchor.day())?;
+                if this_month <= today {
+                    return Some(this_month);
+                }
+                let last_month = today.with_day(1)?.pred_opt()?;
+                clamped_date(last_month.year(), last_month.month(), anchor.day())
+            }
+            (ResetTrafficMethod::YearFirstDay, _) | (ResetTrafficMethod::YearAnniversary, None) => {
+                NaiveDate::from_ymd_opt(today.year(), 1, 1)
+            }
+            (ResetTrafficMethod::YearAnniversary, Some(anchor)) => {
+                let this_year = clamped_date(today.year(), anchor.month(), anchor.day())?;
+                if this_year <= today {
+                    return Some(this_year);
+                }
+                clamped_date(today.year() - 1, anchor.month(), anchor.day())
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for ResetTrafficMethod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim()
+            .parse::<i16>()
+            .ok()
+            .and_then(ResetTrafficMethod::from_i16)
+            .ok_or_else(|| anyhow::anyhow!("未知的流量重置方式: {}", s))
+    }
+}
+
+/// 套餐购买周期，序列化值与 V2Board 的价格字段名一致，如 `month_price`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub enum PlanPeriod {
+    /// 月付
+    #[serde(rename = "month_price")]
+    Month,
+    /// 季付
+    #[serde(rename = "quarter_price")]
+    Quarter,
+    /// 半年付
+    #[serde(rename = "half_year_price")]
+    HalfYear,
+    /// 年付
+    #[serde(rename = "year_price")]
+    Year,
+    /// 两年付
+    #[serde(rename = "two_year_price")]
+    TwoYear,
+    /// 三年付
+    #[serde(rename = "three_year_price")]
+    ThreeYear,
+    /// 一次性，永不过期
+    #[serde(rename = "onetime_price")]
+    Onetime,
+    /// 重置流量，不改变到期时间
+    #[serde(rename = "reset_price")]
+    Reset,
+}
+
+impl PlanPeriod {
+    pub const ALL: [PlanPeriod; 8] = [
+        PlanPeriod::Month,
+        PlanPeriod::Quarter,
+        PlanPeriod::HalfYear,
+        PlanPeriod::Year,
+        PlanPeriod::TwoYear,
+        PlanPeriod::ThreeYear,
+        PlanPeriod::Onetime,
+        PlanPeriod::Reset,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlanPeriod::Month => "month_price",
+            PlanPeriod::Quarter => "quarter_price",
+            PlanPeriod::HalfYear => "half_year_price",
+            PlanPeriod::Year => "year_price",
+            PlanPeriod::TwoYear => "two_year_price",
+            PlanPeriod::ThreeYear => "three_year_price",
+            PlanPeriod::Onetime => "onetime_price",
+            PlanPeriod::Reset => "reset_price",
+        }
+    }
+
+    /// 周期包含的月数，一次性和重置流量没有时长
+    pub fn months(&self) -> Option<u32> {
+        match self {
+            PlanPeriod::Month => Some(1),
+            PlanPeriod::Quarter => Some(3),
+            PlanPeriod::HalfYear => Some(6),
+            PlanPeriod::Year => Some(12),
+            PlanPeriod::TwoYear => Some(24),
+            PlanPeriod::ThreeYear => Some(36),
+            PlanPeriod::Onetime | PlanPeriod::Reset => None,
+        }
+    }
+
+    /// 是否为购买套餐时长的周期（不含重置流量）
+    pub fn is_subscription(&self) -> bool {
+        *self != PlanPeriod::Reset
+    }
+
+    /// 购买该周期后的到期时间，返回 None 表示永不过期
+    ///
+    /// 未过期时从原到期时间顺延，已过期时从当前时间起算；
+    /// 月末按目标月份的最后一天处理，如 1 月 31 日加一个月为 2 月 28 日（或 29 日）。
+    /// 原本永不过期（如一次性套餐）时没有可顺延的到期时间，同样从当前时间起算，
+    /// 购买后变为按周期到期
+    pub fn extend_expiry(&self, expired_at: Option<i64>, now: i64) -> Option<i64> {
+        let months = match self {
+            PlanPeriod::Onetime => return None,
+            PlanPeriod::Reset => return expired_at,
+            _ => self.months()?,
+        };
+
+        let base = expired_at.filter(|at| *at > now).unwrap_or(now);
+        DateTime::from_timestamp(base, 0)
+            .and_then(|at| at.checked_add_months(Months::new(months)))
+            .map(|at| at.timestamp())
+    }
+
+    /// 按月折算的价格，一次性和重置流量没有月均价
+    pub fn monthly_equivalent(&self, amount: i32) -> Option<i32> {
+        let months = self.months()? as i64;
+        Some(((amount as i64 + months / 2) / months) as i32)
+    }
+}
+
+impl std::str::FromStr for PlanPeriod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        PlanPeriod::ALL
+            .into_iter()
+            .find(|period| {
+                period.as_str() == s || period.as_str().strip_suffix("_price") == Some(s)
+            })
+            .ok_or_else(|| anyhow::anyhow!("未知的购买周期: {}", s))
+    }
+}
+
+/// 套餐某一周期的价格
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PlanPrice {
+    pub period: PlanPeriod,
+    pub amount: i32,
+    /// 按月折算的价格，一次性和重置流量为空
+    pub monthly_equivalent: Option<i32>,
+}
+
+/// 指定年月中的某一天，超过当月天数时取当月最后一天
+fn clamped_date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+    let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+    first.with_day(day.min(days_in_month(first)))
+}
+
+/// 指定日期所在月份的天数
+fn days_in_month(date: NaiveDate) -> u32 {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|first| first.pred_opt())
+        .map(|last| last.day())
+        .unwrap_or(31)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Plan {
     pub id: i32,
@@ -31,7 +265,7 @@ pub struct Plan {
     pub three_year_price: Option<i32>,
     pub onetime_price: Option<i32>,
     pub reset_price: Option<i32>,
-    pub reset_traffic_method: Option<bool>,
+    pub reset_traffic_method: Option<i16>,
     pub capacity_limit: Option<i32>,
     pub daily_unit_price: Option<i32>,
     pub transfer_unit_price: Option<i32>,
@@ -39,6 +273,64 @@ pub struct Plan {
     pub updated_at: i32,
 }
 
+impl Plan {
+    /// 套餐流量（字节），套餐中以 GB 为单位存储
+    pub fn transfer_enable_bytes(&self) -> i64 {
+        self.transfer_enable as i64 * GB
+    }
+
+    /// 指定周期的价格，未设置价格的周期不可购买
+    pub fn price(&self, period: PlanPeriod) -> Option<i32> {
+        match period {
+            PlanPeriod::Month => self.month_price,
+            PlanPeriod::Quarter => self.quarter_price,
+            PlanPeriod::HalfYear => self.half_year_price,
+            PlanPeriod::Year => self.year_price,
+            PlanPeriod::TwoYear => self.two_year_price,
+            PlanPeriod::ThreeYear => self.three_year_price,
+            PlanPeriod::Onetime => self.onetime_price,
+            PlanPeriod::Reset => self.reset_price,
+        }
+    }
+
+    /// 已设置价格的周期列表
+    pub fn prices(&self) -> Vec<PlanPrice> {
+        PlanPeriod::ALL
+            .into_iter()
+            .filter_map(|period| {
+                self.price(period).map(|amount| PlanPrice {
+                    period,
+                    amount,
+                    monthly_equivalent: period.monthly_equivalent(amount),
+                })
+            })
+            .collect()
+    }
+
+    /// 是否设置了可购买的周期价格（不含流量重置）
+    pub fn has_price(&self) -> bool {
+        PlanPeriod::ALL
+            .into_iter()
+            .any(|period| period.is_subscription() && self.price(period).is_some())
+    }
+
+    /// 剩余可售名额，未限制名额时为空
+    pub fn remaining_seats(&self, holders: i64) -> Option<i32> {
+        self.capacity_limit
+            .map(|limit| (limit as i64 - holders).max(0) as i32)
+    }
+
+    /// 是否为按量计费套餐：设置了按日单价的套餐按日和按流量从余额扣费
+    pub fn is_metered(&self) -> bool {
+        self.daily_unit_price.is_some_and(|price| price > 0)
+    }
+
+    /// 是否提供按 GB 计价的流量包，按量计费套餐的流量单价用于扣费而非出售流量包
+    pub fn sells_traffic_addon(&self) -> bool {
+        !self.is_metered() && self.transfer_unit_price.is_some_and(|price| price > 0)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreatePlanRequest {
     #[validate(range(min = 1))]
@@ -70,7 +362,9 @@ pub struct CreatePlanRequest {
     pub onetime_price: Option<i32>,
     #[validate(range(min = 0))]
     pub reset_price: Option<i32>,
-    pub reset_traffic_method: Option<bool>,
+    /// 流量重置方式，为空时使用全局默认值，取值见 [`ResetTrafficMethod`]
+    #[validate(range(min = 0, max = 4))]
+    pub reset_traffic_method: Option<i16>,
     #[validate(range(min = 0))]
     pub capacity_limit: Option<i32>,
     #[validate(range(min = 0))]
@@ -110,7 +404,9 @@ pub struct UpdatePlanRequest {
     pub onetime_price: Option<i32>,
     #[validate(range(min = 0))]
     pub reset_price: Option<i32>,
-    pub reset_traffic_method: Option<bool>,
+    /// 流量重置方式，为空时使用全局默认值，取值见 [`ResetTrafficMethod`]
+    #[validate(range(min = 0, max = 4))]
+    pub reset_traffic_method: Option<i16>,
     #[validate(range(min = 0))]
     pub capacity_limit: Option<i32>,
     #[validate(range(min = 0))]
@@ -138,16 +434,23 @@ pub struct PlanResponse {
     pub three_year_price: Option<i32>,
     pub onetime_price: Option<i32>,
     pub reset_price: Option<i32>,
-    pub reset_traffic_method: Option<bool>,
+    pub reset_traffic_method: Option<i16>,
     pub capacity_limit: Option<i32>,
     pub daily_unit_price: Option<i32>,
     pub transfer_unit_price: Option<i32>,
+    /// 已设置价格的周期列表
+    pub prices: Vec<PlanPrice>,
+    /// 剩余可售名额，未限制名额时为空
+    pub remaining_seats: Option<i32>,
+    /// 当前用户的购买信息，仅在已登录时获取已启用套餐列表返回
+    pub availability: Option<PlanAvailability>,
     pub created_at: i32,
     pub updated_at: i32,
 }
 
 impl From<Plan> for PlanResponse {
     fn from(plan: Plan) -> Self {
+        let prices = plan.prices();
         Self {
             id: plan.id,
             group_id: plan.group_id,
@@ -170,14 +473,229 @@ impl From<Plan> for PlanResponse {
             capacity_limit: plan.capacity_limit,
             daily_unit_price: plan.daily_unit_price,
             transfer_unit_price: plan.transfer_unit_price,
+            prices,
+            remaining_seats: None,
+            availability: None,
             created_at: plan.created_at,
             updated_at: plan.updated_at,
         }
     }
 }
 
+/// 套餐对当前用户的购买信息
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct PlanAvailability {
+    /// 当前订阅的即为该套餐（含已过期）
+    pub owned: bool,
+    /// 可以下单，持有该套餐时即可以续费
+    pub purchasable: bool,
+    /// 持有该套餐且套餐允许续费
+    pub renewable: bool,
+    /// 购买该套餐将替换当前生效中的其他套餐
+    pub upgrade: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PlanListResponse {
     pub plans: Vec<PlanResponse>,
     pub total: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn last_reset(method: ResetTrafficMethod, today: &str, anchor: Option<&str>) -> Option<String> {
+        method
+            .last_reset_date(date(today), anchor.map(date))
+            .map(|date| date.to_string())
+    }
+
+    fn timestamp(value: &str) -> i64 {
+        DateTime::parse_from_rfc3339(value).unwrap().timestamp()
+    }
+
+    #[test]
+    fn test_extend_expiry_month_end() {
+        let now = timestamp("2025-01-01T00:00:00Z");
+        let jan31 = Some(timestamp("2025-01-31T12:00:00Z"));
+        assert_eq!(
+            PlanPeriod::Month.extend_expiry(jan31, now),
+            Some(timestamp("2025-02-28T12:00:00Z"))
+        );
+        assert_eq!(
+            PlanPeriod::Month
+                .extend_expiry(Some(timestamp("2024-01-31T12:00:00Z")), now - 366 * 86400),
+            Some(timestamp("2024-02-29T12:00:00Z"))
+        );
+        assert_eq!(
+            PlanPeriod::Quarter.extend_expiry(jan31, now),
+            Some(timestamp("2025-04-30T12:00:00Z"))
+        );
+        assert_eq!(
+            PlanPeriod::Year
+                .extend_expiry(Some(timestamp("2024-02-29T00:00:00Z")), now - 366 * 86400),
+            Some(timestamp("2025-02-28T00:00:00Z"))
+        );
+    }
+
+    #[test]
+    fn test_extend_expiry_base() {
+        let now = timestamp("2025-03-10T08:00:00Z");
+        let next_month = Some(timestamp("2025-04-10T08:00:00Z"));
+        // 已过期、永不过期（含一次性套餐）时从当前时间起算
+        for expired_at in [Some(timestamp("2025-03-01T00:00:00Z")), None, Some(0)] {
+            assert_eq!(PlanPeriod::Month.extend_expiry(expired_at, now), next_month);
+        }
+        // 未过期时顺延
+        assert_eq!(
+            PlanPeriod::Month.extend_expiry(next_month, now),
+            Some(timestamp("2025-05-10T08:00:00Z"))
+        );
+        // 一次性永不过期，重置流量不改变到期时间
+        assert_eq!(PlanPeriod::Onetime.extend_expiry(next_month, now), None);
+        assert_eq!(PlanPeriod::Reset.extend_expiry(next_month, now), next_month);
+        assert_eq!(PlanPeriod::Reset.extend_expiry(None, now), None);
+    }
+
+    #[test]
+    fn test_period_parse_and_monthly_equivalent() {
+        assert_eq!(
+            "month_price".parse::<PlanPeriod>().unwrap(),
+            PlanPeriod::Month
+        );
+        assert_eq!(" year ".parse::<PlanPeriod>().unwrap(), PlanPeriod::Year);
+        assert!("week_price".parse::<PlanPeriod>().is_err());
+        assert_eq!(PlanPeriod::Quarter.monthly_equivalent(2500), Some(833));
+        assert_eq!(PlanPeriod::Year.monthly_equivalent(12000), Some(1000));
+        assert_eq!(PlanPeriod::Onetime.monthly_equivalent(5000), None);
+        assert_eq!(PlanPeriod::Reset.monthly_equivalent(500), None);
+    }
+
+    #[test]
+    fn test_days_in_month() {
+        assert_eq!(days_in_month(date("2024-02-10")), 29);
+        assert_eq!(days_in_month(date("2025-02-10")), 28);
+        assert_eq!(days_in_month(date("1900-02-01")), 28);
+        assert_eq!(days_in_month(date("2000-02-01")), 29);
+        assert_eq!(days_in_month(date("2025-04-30")), 30);
+        assert_eq!(days_in_month(date("2025-12-31")), 31);
+    }
+
+    #[test]
+    fn test_clamped_date() {
+        assert_eq!(clamped_date(2025, 2, 31), Some(date("2025-02-28")));
+        assert_eq!(clamped_date(2024, 2, 31), Some(date("2024-02-29")));
+        assert_eq!(clamped_date(2025, 4, 31), Some(date("2025-04-30")));
+        assert_eq!(clamped_date(2025, 5, 31), Some(date("2025-05-31")));
+    }
+
+    #[test]
+    fn test_month_first_day() {
+        let method = ResetTrafficMethod::MonthFirstDay;
+        assert_eq!(
+            last_reset(method, "2025-03-01", None).as_deref(),
+            Some("2025-03-01")
+        );
+        assert_eq!(
+            last_reset(method, "2025-03-17", None).as_deref(),
+            Some("2025-03-01")
+        );
+        assert_eq!(
+            method.reset_period(date("2025-03-17"), None).as_deref(),
+            Some("2025-03")
+        );
+    }
+
+    #[test]
+    fn test_month_anniversary_clamps_to_month_end() {
+        let method = ResetTrafficMethod::MonthAnniversary;
+        let anchor = Some("2025-01-31");
+        // 2 月没有 31 日，在最后一天重置
+        assert_eq!(
+            last_reset(method, "2025-02-28", anchor).as_deref(),
+            Some("2025-02-28")
+        );
+        assert_eq!(
+            last_reset(method, "2024-02-29", anchor).as_deref(),
+            Some("2024-02-29")
+        );
+        // 闰年 2 月 28 日还未到重置日，仍属于 1 月的周期
+        assert_eq!(
+            last_reset(method, "2024-02-28", anchor).as_deref(),
+            Some("2024-01-31")
+        );
+        assert_eq!(
+            last_reset(method, "2025-04-30", anchor).as_deref(),
+            Some("2025-04-30")
+        );
+        assert_eq!(
+            last_reset(method, "2025-05-30", anchor).as_deref(),
+            Some("2025-04-30")
+        );
+    }
+
+    #[test]
+    fn test_month_anniversary_catches_up_missed_day() {
+        let method = ResetTrafficMethod::MonthAnniversary;
+        let anchor = Some(date("2024-12-15"));
+        // 错过 15 日后的任意一天都归属当月周期，跨年时回到上一年 12 月
+        assert_eq!(
+            method.reset_period(date("2025-03-16"), anchor).as_deref(),
+            Some("2025-03")
+        );
+        assert_eq!(
+            method.reset_period(date("2025-03-14"), anchor).as_deref(),
+            Some("2025-02")
+        );
+        assert_eq!(
+            method.reset_period(date("2025-01-10"), anchor).as_deref(),
+            Some("2024-12")
+        );
+    }
+
+    #[test]
+    fn test_year_anniversary() {
+        let method = ResetTrafficMethod::YearAnniversary;
+        let anchor = Some("2024-02-29");
+        // 非闰年在 2 月 28 日重置
+        assert_eq!(
+            last_reset(method, "2025-02-28", anchor).as_deref(),
+            Some("2025-02-28")
+        );
+        assert_eq!(
+            last_reset(method, "2025-02-27", anchor).as_deref(),
+            Some("2024-02-29")
+        );
+        assert_eq!(
+            last_reset(method, "2028-03-01", anchor).as_deref(),
+            Some("2028-02-29")
+        );
+        assert_eq!(
+            method
+                .reset_period(date("2025-01-10"), anchor.map(date))
+                .as_deref(),
+            Some("2024")
+        );
+    }
+
+    #[test]
+    fn test_year_first_day_and_never() {
+        assert_eq!(
+            last_reset(ResetTrafficMethod::YearFirstDay, "2025-06-01", None).as_deref(),
+            Some("2025-01-01")
+        );
+        assert_eq!(
+            last_reset(ResetTrafficMethod::YearAnniversary, "2025-06-01", None).as_deref(),
+            Some("2025-01-01")
+        );
+        assert_eq!(
+            ResetTrafficMethod::Never.reset_period(date("2025-06-01"), Some(date("2025-01-01"))),
+            None
+        );
+    }
+}
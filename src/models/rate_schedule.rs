@@ -0,0 +1,227 @@
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::borrow::Cow;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+/// 时间段倍率，时间格式为 `HH:MM`
+///
+/// 结束时间早于开始时间表示跨越零点，如 `23:00`-`02:00`；
+/// 区间包含开始时间、不包含结束时间
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RateWindow {
+    #[schema(example = "01:00")]
+    pub start: String,
+    #[schema(example = "07:00")]
+    pub end: String,
+    #[schema(example = 0.5)]
+    pub rate: f64,
+}
+
+impl RateWindow {
+    /// 判断指定时间是否落在该时间段内
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        let (start, end) = match (parse_time(&self.start), parse_time(&self.end)) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return false,
+        };
+
+        if start < end {
+            start <= time && time < end
+        } else {
+            // 跨越零点
+            time >= start || time < end
+        }
+    }
+}
+
+/// 节点倍率计划：基础倍率加若干时间段倍率
+#[derive(Debug, Clone)]
+pub struct RateSchedule {
+    pub base: f64,
+    pub windows: Vec<RateWindow>,
+}
+
+impl RateSchedule {
+    /// 指定时间的生效倍率，时间段重叠时以先配置的为准
+    pub fn rate_at(&self, time: NaiveTime) -> f64 {
+        self.windows
+            .iter()
+            .find(|window| window.contains(time))
+            .map(|window| window.rate)
+            .unwrap_or(self.base)
+    }
+}
+
+/// 节点倍率计划存储记录
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ServerRateSchedule {
+    pub server_type: String,
+    pub server_id: i32,
+    /// JSON 数组形式存储的时间段倍率
+    pub windows: String,
+    pub updated_at: i32,
+}
+
+impl ServerRateSchedule {
+    /// 解析时间段倍率列表，数据损坏时忽略并回落到基础倍率
+    pub fn window_list(&self) -> Vec<RateWindow> {
+        serde_json::from_str(&self.windows).unwrap_or_else(|e| {
+            tracing::warn!(
+                "忽略无效的节点倍率计划 {}#{}: {}",
+                self.server_type,
+                self.server_id,
+                e
+            );
+            Vec::new()
+        })
+    }
+}
+
+/// 设置节点的时间段倍率，传入空列表即恢复为固定倍率
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct UpdateRateScheduleRequest {
+    #[validate(custom = "validate_rate_windows")]
+    pub windows: Vec<RateWindow>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RateScheduleResponse {
+    /// 基础倍率，即节点的 rate 字段
+    pub base_rate: String,
+    pub windows: Vec<RateWindow>,
+    /// 当前生效的倍率
+    pub current_rate: f64,
+}
+
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+fn validation_error(code: &'static str, message: String) -> ValidationError {
+    let mut error = ValidationError::new(code);
+    error.message = Some(Cow::Owned(message));
+    error
+}
+
+/// 校验时间段倍率：时间格式为 `HH:MM`，开始和结束时间不能相同，倍率不能为负数
+pub fn validate_rate_windows(windows: &Vec<RateWindow>) -> Result<(), ValidationError> {
+    for window in windows {
+        let (start, end) = match (parse_time(&window.start), parse_time(&window.end)) {
+            (Some(start), Some(end)) => (start, end),
+            _ => {
+                return Err(validation_error(
+                    "rate_window",
+                    format!("时间格式必须为 HH:MM: {}-{}", window.start, window.end),
+                ))
+            }
+        };
+        if start == end {
+            return Err(validation_error(
+                "rate_window",
+                format!("开始时间和结束时间不能相同: {}", window.start),
+            ));
+        }
+        if !window.rate.is_finite() || window.rate < 0.0 {
+            return Err(validation_error(
+                "rate_window",
+                format!("倍率无效: {}", window.rate),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(start: &str, end: &str, rate: f64) -> RateWindow {
+        RateWindow {
+            start: start.to_string(),
+            end: end.to_string(),
+            rate,
+        }
+    }
+
+    fn time(value: &str) -> NaiveTime {
+        parse_time(value).unwrap()
+    }
+
+    #[test]
+    fn test_window_contains() {
+        let night = window("01:00", "07:00", 0.5);
+        assert!(night.contains(time("01:00")));
+        assert!(night.contains(time("06:59")));
+        assert!(!night.contains(time("07:00")));
+        assert!(!night.contains(time("00:59")));
+
+        // 跨越零点
+        let late = window("23:00", "02:00", 0.5);
+        assert!(late.contains(time("23:00")));
+        assert!(late.contains(time("00:00")));
+        assert!(late.contains(time("01:59")));
+        assert!(!late.contains(time("02:00")));
+        assert!(!late.contains(time("22:59")));
+
+        assert!(!window("25:00", "02:00", 0.5).contains(time("01:00")));
+    }
+
+    #[test]
+    fn test_rate_at() {
+        let schedule = RateSchedule {
+            base: 1.5,
+            windows: vec![
+                window("01:00", "07:00", 0.5),
+                window("06:00", "09:00", 0.8),
+                window("20:00", "23:00", 2.0),
+            ],
+        };
+        assert_eq!(schedule.rate_at(time("03:00")), 0.5);
+        // 重叠时以先配置的为准
+        assert_eq!(schedule.rate_at(time("06:30")), 0.5);
+        assert_eq!(schedule.rate_at(time("08:00")), 0.8);
+        assert_eq!(schedule.rate_at(time("21:00")), 2.0);
+        assert_eq!(schedule.rate_at(time("12:00")), 1.5);
+    }
+
+    #[test]
+    fn test_validate_rate_windows() {
+        assert!(validate_rate_windows(&vec![
+            window("01:00", "07:00", 0.5),
+            window("23:00", "02:00", 0.0),
+        ])
+        .is_ok());
+        assert!(validate_rate_windows(&Vec::new()).is_ok());
+
+        for invalid in [
+            window("0100", "07:00", 0.5),
+            window("01:00", "24:00", 0.5),
+            window("01:00", "01:00", 0.5),
+            window("01:00", "07:00", -1.0),
+            window("01:00", "07:00", f64::NAN),
+        ] {
+            assert!(
+                validate_rate_windows(&vec![invalid.clone()]).is_err(),
+                "{:?}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_window_list_ignores_invalid_data() {
+        let mut schedule = ServerRateSchedule {
+            server_type: "trojan".to_string(),
+            server_id: 1,
+            windows: r#"[{"start":"01:00","end":"07:00","rate":0.5}]"#.to_string(),
+            updated_at: 0,
+        };
+        assert_eq!(schedule.window_list().len(), 1);
+
+        schedule.windows = "not json".to_string();
+        assert!(schedule.window_list().is_empty());
+    }
+}
@@ -0,0 +1,361 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::borrow::Cow;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+use crate::utils::double_option;
+
+/// 路由规则动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteAction {
+    /// 阻断
+    Block,
+    /// 直连
+    Direct,
+    /// 使用指定DNS解析
+    Dns,
+    /// 转发到指定出站
+    Proxy,
+}
+
+impl RouteAction {
+    /// 该动作是否需要 action_value（DNS服务器地址或出站标签）
+    pub fn requires_value(&self) -> bool {
+        matches!(self, RouteAction::Dns | RouteAction::Proxy)
+    }
+}
+
+impl std::str::FromStr for RouteAction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "block" => Ok(RouteAction::Block),
+            "direct" => Ok(RouteAction::Direct),
+            "dns" => Ok(RouteAction::Dns),
+            "proxy" => Ok(RouteAction::Proxy),
+            _ => anyhow::bail!("未知的路由动作: {}", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ServerRoute {
+    pub id: i32,
+    pub remarks: String,
+    /// JSON 数组形式存储的匹配规则
+    #[serde(rename = "match")]
+    #[sqlx(rename = "match")]
+    pub r#match: String,
+    pub action: String,
+    pub action_value: Option<String>,
+    pub created_at: i32,
+    pub updated_at: i32,
+}
+
+impl ServerRoute {
+    /// 解析匹配规则列表
+    pub fn match_list(&self) -> Vec<String> {
+        serde_json::from_str::<Vec<String>>(&self.r#match).unwrap_or_else(|_| {
+            // 兼容以逗号或换行分隔的旧数据
+            self.r#match
+                .split([',', '\n'])
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+#[validate(schema(function = "validate_create_route_value"))]
+pub struct CreateRouteRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub remarks: String,
+    #[serde(rename = "match")]
+    #[validate(custom = "validate_route_match")]
+    pub r#match: Vec<String>,
+    #[validate(custom = "validate_route_action")]
+    pub action: String,
+    #[validate(length(max = 255))]
+    pub action_value: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct UpdateRouteRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub remarks: Option<String>,
+    #[serde(rename = "match")]
+    #[validate(custom = "validate_route_match")]
+    pub r#match: Option<Vec<String>>,
+    #[validate(custom = "validate_route_action")]
+    pub action: Option<String>,
+    /// 传 null 清空
+    #[serde(default, deserialize_with = "double_option")]
+    #[schema(value_type = Option<String>, nullable)]
+    #[validate(length(max = 255))]
+    pub action_value: Option<Option<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RouteResponse {
+    pub id: i32,
+    pub remarks: String,
+    #[serde(rename = "match")]
+    pub r#match: Vec<String>,
+    pub action: String,
+    pub action_value: Option<String>,
+    pub created_at: i32,
+    pub updated_at: i32,
+}
+
+impl From<ServerRoute> for RouteResponse {
+    fn from(route: ServerRoute) -> Self {
+        Self {
+            id: route.id,
+            r#match: route.match_list(),
+            remarks: route.remarks,
+            action: route.action,
+            action_value: route.action_value,
+            created_at: route.created_at,
+            updated_at: route.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RouteListResponse {
+    pub routes: Vec<RouteResponse>,
+    pub total: i64,
+}
+
+/// 下发给节点后端的路由规则
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RouteConfig {
+    pub id: i32,
+    #[serde(rename = "match")]
+    pub r#match: Vec<String>,
+    pub action: RouteAction,
+    pub action_value: Option<String>,
+}
+
+impl TryFrom<&ServerRoute> for RouteConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(route: &ServerRoute) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: route.id,
+            r#match: route.match_list(),
+            action: route.action.parse()?,
+            action_value: route.action_value.clone(),
+        })
+    }
+}
+
+/// 为节点分配路由规则
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateServerRoutesRequest {
+    pub route_ids: Vec<i32>,
+}
+
+fn validation_error(code: &'static str, message: String) -> ValidationError {
+    let mut error = ValidationError::new(code);
+    error.message = Some(Cow::Owned(message));
+    error
+}
+
+/// 校验路由动作
+pub fn validate_route_action(action: &str) -> Result<(), ValidationError> {
+    action.parse::<RouteAction>().map(|_| ()).map_err(|_| {
+        validation_error(
+            "route_action",
+            "路由动作必须为 block、direct、dns 或 proxy".to_string(),
+        )
+    })
+}
+
+/// 校验匹配规则：支持域名、IP/CIDR 以及 geosite:、geoip:、domain:、full:、keyword:、regexp: 前缀
+pub fn validate_route_match(rules: &Vec<String>) -> Result<(), ValidationError> {
+    if rules.is_empty() {
+        return Err(validation_error(
+            "route_match",
+            "匹配规则不能为空".to_string(),
+        ));
+    }
+
+    for rule in rules {
+        if !is_valid_match_rule(rule) {
+            return Err(validation_error(
+                "route_match",
+                format!("无效的匹配规则: {}", rule),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_create_route_value(request: &CreateRouteRequest) -> Result<(), ValidationError> {
+    let requires_value = request
+        .action
+        .parse::<RouteAction>()
+        .map(|action| action.requires_value())
+        .unwrap_or(false);
+    let has_value = request
+        .action_value
+        .as_deref()
+        .map(|v| !v.trim().is_empty())
+        .unwrap_or(false);
+
+    if requires_value && !has_value {
+        return Err(validation_error(
+            "action_value",
+            format!("动作 {} 需要设置 action_value", request.action),
+        ));
+    }
+
+    Ok(())
+}
+
+fn is_valid_match_rule(rule: &str) -> bool {
+    let rule = rule.trim();
+    if rule.is_empty() {
+        return false;
+    }
+
+    if let Some((prefix, value)) = rule.split_once(':') {
+        match prefix {
+            "geosite" | "geoip" => {
+                return !value.is_empty()
+                    && value
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '!' | '@'))
+            }
+            "domain" | "full" | "keyword" => return is_valid_domain(value),
+            "regexp" => return !value.is_empty(),
+            // 不带前缀的IPv6地址同样包含冒号
+            _ => {}
+        }
+    }
+
+    is_valid_ip_or_cidr(rule) || is_valid_domain(rule)
+}
+
+fn is_valid_ip_or_cidr(value: &str) -> bool {
+    match value.split_once('/') {
+        Some((ip, prefix)) => match ip.parse::<std::net::IpAddr>() {
+            Ok(std::net::IpAddr::V4(_)) => prefix.parse::<u8>().is_ok_and(|p| p <= 32),
+            Ok(std::net::IpAddr::V6(_)) => prefix.parse::<u8>().is_ok_and(|p| p <= 128),
+            Err(_) => false,
+        },
+        None => value.parse::<std::net::IpAddr>().is_ok(),
+    }
+}
+
+fn is_valid_domain(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= 253
+        && value.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && label
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(rules: &[&str]) -> Vec<String> {
+        rules.iter().map(|rule| rule.to_string()).collect()
+    }
+
+    #[test]
+    fn test_validate_route_match() {
+        assert!(validate_route_match(&rules(&[
+            "geosite:category-ads-all",
+            "geoip:cn",
+            "geoip:!cn",
+            "domain:example.com",
+            "full:www.example.com",
+            "keyword:google",
+            "regexp:^ad\\..*$",
+            "example.com",
+            "10.0.0.0/8",
+            "2001:db8::/32",
+            "::1",
+        ]))
+        .is_ok());
+
+        for rule in [
+            "",
+            "geoip:",
+            "domain:",
+            "10.0.0.0/33",
+            "2001:db8::/129",
+            "exa mple.com",
+        ] {
+            assert!(validate_route_match(&rules(&[rule])).is_err(), "{rule}");
+        }
+        assert!(validate_route_match(&Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_match_list_accepts_legacy_format() {
+        let mut route = ServerRoute {
+            id: 1,
+            remarks: "广告".to_string(),
+            r#match: r#"["geosite:category-ads-all","geoip:cn"]"#.to_string(),
+            action: "block".to_string(),
+            action_value: None,
+            created_at: 0,
+            updated_at: 0,
+        };
+        assert_eq!(
+            route.match_list(),
+            rules(&["geosite:category-ads-all", "geoip:cn"])
+        );
+
+        route.r#match = "geosite:category-ads-all, geoip:cn\nexample.com,".to_string();
+        assert_eq!(
+            route.match_list(),
+            rules(&["geosite:category-ads-all", "geoip:cn", "example.com"])
+        );
+    }
+
+    #[test]
+    fn test_create_route_requires_action_value() {
+        let mut request = CreateRouteRequest {
+            remarks: "走代理".to_string(),
+            r#match: rules(&["geosite:netflix"]),
+            action: "proxy".to_string(),
+            action_value: Some(" ".to_string()),
+        };
+        assert!(request.validate().is_err());
+
+        request.action_value = Some("hk-out".to_string());
+        assert!(request.validate().is_ok());
+
+        request.action = "block".to_string();
+        request.action_value = None;
+        assert!(request.validate().is_ok());
+    }
+
+    #[test]
+    fn test_update_route_action_value_can_be_cleared() {
+        let request: UpdateRouteRequest = serde_json::from_str(r#"{"remarks": "直连"}"#).unwrap();
+        assert_eq!(request.action_value, None);
+
+        let request: UpdateRouteRequest =
+            serde_json::from_str(r#"{"action": "direct", "action_value": null}"#).unwrap();
+        assert_eq!(request.action_value, Some(None));
+
+        let request: UpdateRouteRequest =
+            serde_json::from_str(r#"{"action_value": "8.8.8.8"}"#).unwrap();
+        assert_eq!(request.action_value, Some(Some("8.8.8.8".to_string())));
+    }
+}
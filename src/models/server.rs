@@ -0,0 +1,644 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::collections::HashMap;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::models::{
+    rate_schedule::{RateSchedule, RateWindow},
+    route::RouteConfig,
+};
+
+/// 节点协议类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ServerType {
+    Vmess,
+    Vless,
+    Trojan,
+    Shadowsocks,
+    Hysteria,
+}
+
+impl ServerType {
+    /// 获取节点类型的字符串表示（与数据库中 server_type 字段一致）
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ServerType::Vmess => "vmess",
+            ServerType::Vless => "vless",
+            ServerType::Trojan => "trojan",
+            ServerType::Shadowsocks => "shadowsocks",
+            ServerType::Hysteria => "hysteria",
+        }
+    }
+}
+
+impl std::fmt::Display for ServerType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ServerType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "vmess" | "v2ray" => Ok(ServerType::Vmess),
+            "vless" => Ok(ServerType::Vless),
+            "trojan" => Ok(ServerType::Trojan),
+            "shadowsocks" | "ss" => Ok(ServerType::Shadowsocks),
+            "hysteria" | "hysteria2" => Ok(ServerType::Hysteria),
+            _ => anyhow::bail!("未知的节点类型: {}", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ServerVmess {
+    pub id: i32,
+    pub group_id: String,
+    pub route_id: Option<String>,
+    pub name: String,
+    pub parent_id: Option<i32>,
+    pub host: String,
+    pub port: String,
+    pub server_port: i32,
+    pub tls: bool,
+    pub tags: Option<String>,
+    pub rate: String,
+    pub network: String,
+    pub rules: Option<String>,
+    pub networksettings: Option<String>,
+    pub tlssettings: Option<String>,
+    pub rulesettings: Option<String>,
+    pub dnssettings: Option<String>,
+    pub show: bool,
+    pub sort: Option<i32>,
+    pub created_at: i32,
+    pub updated_at: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ServerVless {
+    pub id: i32,
+    pub group_id: String,
+    pub route_id: Option<String>,
+    pub name: String,
+    pub parent_id: Option<i32>,
+    pub host: String,
+    pub port: i32,
+    pub server_port: i32,
+    pub tls: bool,
+    pub tls_settings: Option<String>,
+    pub flow: Option<String>,
+    pub network: String,
+    pub network_settings: Option<String>,
+    pub tags: Option<String>,
+    pub rate: String,
+    pub show: bool,
+    pub sort: Option<i32>,
+    pub created_at: i32,
+    pub updated_at: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ServerTrojan {
+    pub id: i32,
+    pub group_id: String,
+    pub route_id: Option<String>,
+    pub parent_id: Option<i32>,
+    pub tags: Option<String>,
+    pub name: String,
+    pub rate: String,
+    pub host: String,
+    pub port: String,
+    pub server_port: i32,
+    pub allow_insecure: bool,
+    pub server_name: Option<String>,
+    pub show: bool,
+    pub sort: Option<i32>,
+    pub created_at: i32,
+    pub updated_at: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ServerShadowsocks {
+    pub id: i32,
+    pub group_id: String,
+    pub route_id: Option<String>,
+    pub parent_id: Option<i32>,
+    pub tags: Option<String>,
+    pub name: String,
+    pub rate: String,
+    pub host: String,
+    pub port: String,
+    pub server_port: i32,
+    pub cipher: String,
+    pub obfs: Option<String>,
+    pub obfs_settings: Option<String>,
+    pub show: bool,
+    pub sort: Option<i32>,
+    pub created_at: i32,
+    pub updated_at: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ServerHysteria {
+    pub id: i32,
+    pub group_id: String,
+    pub route_id: Option<String>,
+    pub name: String,
+    pub parent_id: Option<i32>,
+    pub host: String,
+    pub port: String,
+    pub server_port: i32,
+    pub tags: Option<String>,
+    pub rate: String,
+    pub show: bool,
+    pub sort: Option<i32>,
+    pub up_mbps: i32,
+    pub down_mbps: i32,
+    pub server_name: Option<String>,
+    pub insecure: bool,
+    pub ignore_client_bandwidth: bool,
+    pub obfs_type: Option<String>,
+    pub created_at: i32,
+    pub updated_at: i32,
+}
+
+/// 任意协议的节点
+#[derive(Debug, Clone)]
+pub enum Server {
+    Vmess(ServerVmess),
+    Vless(ServerVless),
+    Trojan(ServerTrojan),
+    Shadowsocks(ServerShadowsocks),
+    Hysteria(ServerHysteria),
+}
+
+impl Server {
+    pub fn server_type(&self) -> ServerType {
+        match self {
+            Server::Vmess(_) => ServerType::Vmess,
+            Server::Vless(_) => ServerType::Vless,
+            Server::Trojan(_) => ServerType::Trojan,
+            Server::Shadowsocks(_) => ServerType::Shadowsocks,
+            Server::Hysteria(_) => ServerType::Hysteria,
+        }
+    }
+
+    pub fn id(&self) -> i32 {
+        match self {
+            Server::Vmess(s) => s.id,
+            Server::Vless(s) => s.id,
+            Server::Trojan(s) => s.id,
+            Server::Shadowsocks(s) => s.id,
+            Server::Hysteria(s) => s.id,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Server::Vmess(s) => &s.name,
+            Server::Vless(s) => &s.name,
+            Server::Trojan(s) => &s.name,
+            Server::Shadowsocks(s) => &s.name,
+            Server::Hysteria(s) => &s.name,
+        }
+    }
+
+    /// 修改节点名称，用于在订阅中附加倍率等信息
+    pub fn set_name(&mut self, name: String) {
+        match self {
+            Server::Vmess(s) => s.name = name,
+            Server::Vless(s) => s.name = name,
+            Server::Trojan(s) => s.name = name,
+            Server::Shadowsocks(s) => s.name = name,
+            Server::Hysteria(s) => s.name = name,
+        }
+    }
+
+    pub fn host(&self) -> &str {
+        match self {
+            Server::Vmess(s) => &s.host,
+            Server::Vless(s) => &s.host,
+            Server::Trojan(s) => &s.host,
+            Server::Shadowsocks(s) => &s.host,
+            Server::Hysteria(s) => &s.host,
+        }
+    }
+
+    /// 面向用户的连接端口（vless 为整数，其余协议可能是端口范围）
+    pub fn port(&self) -> String {
+        match self {
+            Server::Vmess(s) => s.port.clone(),
+            Server::Vless(s) => s.port.to_string(),
+            Server::Trojan(s) => s.port.clone(),
+            Server::Shadowsocks(s) => s.port.clone(),
+            Server::Hysteria(s) => s.port.clone(),
+        }
+    }
+
+    pub fn server_port(&self) -> i32 {
+        match self {
+            Server::Vmess(s) => s.server_port,
+            Server::Vless(s) => s.server_port,
+            Server::Trojan(s) => s.server_port,
+            Server::Shadowsocks(s) => s.server_port,
+            Server::Hysteria(s) => s.server_port,
+        }
+    }
+
+    pub fn group_id(&self) -> &str {
+        match self {
+            Server::Vmess(s) => &s.group_id,
+            Server::Vless(s) => &s.group_id,
+            Server::Trojan(s) => &s.group_id,
+            Server::Shadowsocks(s) => &s.group_id,
+            Server::Hysteria(s) => &s.group_id,
+        }
+    }
+
+    pub fn route_id(&self) -> Option<&str> {
+        match self {
+            Server::Vmess(s) => s.route_id.as_deref(),
+            Server::Vless(s) => s.route_id.as_deref(),
+            Server::Trojan(s) => s.route_id.as_deref(),
+            Server::Shadowsocks(s) => s.route_id.as_deref(),
+            Server::Hysteria(s) => s.route_id.as_deref(),
+        }
+    }
+
+    pub fn parent_id(&self) -> Option<i32> {
+        match self {
+            Server::Vmess(s) => s.parent_id,
+            Server::Vless(s) => s.parent_id,
+            Server::Trojan(s) => s.parent_id,
+            Server::Shadowsocks(s) => s.parent_id,
+            Server::Hysteria(s) => s.parent_id,
+        }
+    }
+
+    pub fn rate(&self) -> &str {
+        match self {
+            Server::Vmess(s) => &s.rate,
+            Server::Vless(s) => &s.rate,
+            Server::Trojan(s) => &s.rate,
+            Server::Shadowsocks(s) => &s.rate,
+            Server::Hysteria(s) => &s.rate,
+        }
+    }
+
+    pub fn show(&self) -> bool {
+        match self {
+            Server::Vmess(s) => s.show,
+            Server::Vless(s) => s.show,
+            Server::Trojan(s) => s.show,
+            Server::Shadowsocks(s) => s.show,
+            Server::Hysteria(s) => s.show,
+        }
+    }
+
+    pub fn sort(&self) -> Option<i32> {
+        match self {
+            Server::Vmess(s) => s.sort,
+            Server::Vless(s) => s.sort,
+            Server::Trojan(s) => s.sort,
+            Server::Shadowsocks(s) => s.sort,
+            Server::Hysteria(s) => s.sort,
+        }
+    }
+
+    /// 节点所属的权限组ID列表
+    pub fn group_ids(&self) -> Vec<i32> {
+        parse_id_list(self.group_id())
+    }
+
+    /// 节点关联的路由规则ID列表
+    pub fn route_ids(&self) -> Vec<i32> {
+        self.route_id().map(parse_id_list).unwrap_or_default()
+    }
+
+    /// 下发给节点后端的协议配置
+    pub fn protocol_settings(&self) -> serde_json::Value {
+        match self {
+            Server::Vmess(s) => serde_json::json!({
+                "network": s.network,
+                "network_settings": parse_json_setting(s.networksettings.as_deref()),
+                "tls": s.tls,
+                "tls_settings": parse_json_setting(s.tlssettings.as_deref()),
+            }),
+            Server::Vless(s) => serde_json::json!({
+                "network": s.network,
+                "network_settings": parse_json_setting(s.network_settings.as_deref()),
+                "tls": s.tls,
+                "tls_settings": parse_json_setting(s.tls_settings.as_deref()),
+                "flow": s.flow,
+            }),
+            Server::Trojan(s) => serde_json::json!({
+                "server_name": s.server_name,
+            }),
+            Server::Shadowsocks(s) => serde_json::json!({
+                "cipher": s.cipher,
+                "obfs": s.obfs.as_deref().map(str::trim),
+                "obfs_settings": parse_json_setting(s.obfs_settings.as_deref()),
+                "server_key": s.server_key(),
+            }),
+            Server::Hysteria(s) => serde_json::json!({
+                "up_mbps": s.up_mbps,
+                "down_mbps": s.down_mbps,
+                "server_name": s.server_name,
+                "obfs_type": s.obfs_type,
+                "obfs_password": s.obfs_password(),
+                "ignore_client_bandwidth": s.ignore_client_bandwidth,
+            }),
+        }
+    }
+
+    /// 节点流量倍率（无法解析时按 1 倍计算）
+    pub fn rate_value(&self) -> f64 {
+        self.rate().trim().parse::<f64>().unwrap_or(1.0)
+    }
+
+    /// 以节点倍率为基础倍率的倍率计划
+    pub fn rate_schedule(&self, windows: Vec<RateWindow>) -> RateSchedule {
+        RateSchedule {
+            base: self.rate_value(),
+            windows,
+        }
+    }
+}
+
+impl ServerShadowsocks {
+    /// 是否为 Shadowsocks 2022 加密方式
+    pub fn is_2022_cipher(&self) -> bool {
+        self.cipher.starts_with("2022-blake3-")
+    }
+
+    /// Shadowsocks 2022 加密方式要求的密钥长度
+    fn key_length(&self) -> usize {
+        if self.cipher.contains("aes-128") {
+            16
+        } else {
+            32
+        }
+    }
+
+    /// 节点密钥（仅 Shadowsocks 2022 使用）
+    pub fn server_key(&self) -> Option<String> {
+        self.is_2022_cipher()
+            .then(|| server_key(self.created_at, self.key_length()))
+    }
+
+    /// 用户连接密码，Shadowsocks 2022 为 `节点密钥:用户密钥` 形式
+    pub fn user_password(&self, uuid: &str) -> String {
+        match self.server_key() {
+            Some(server_key) => {
+                let length = self.key_length().min(uuid.len());
+                format!(
+                    "{}:{}",
+                    server_key,
+                    BASE64.encode(&uuid.as_bytes()[..length])
+                )
+            }
+            None => uuid.to_string(),
+        }
+    }
+}
+
+impl ServerHysteria {
+    /// salamander 混淆密码
+    pub fn obfs_password(&self) -> Option<String> {
+        self.obfs_type
+            .as_deref()
+            .filter(|obfs| !obfs.trim().is_empty())
+            .map(|_| server_key(self.created_at, 16))
+    }
+}
+
+/// 根据节点创建时间派生节点密钥，与节点后端保持一致
+pub fn server_key(created_at: i32, length: usize) -> String {
+    let digest = hex_encode(&Md5::digest(created_at.to_string().as_bytes()));
+    BASE64.encode(&digest.as_bytes()[..length.min(digest.len())])
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 解析以 JSON 数组（如 `["1","2"]`、`[1,2]`）或逗号分隔形式存储的ID列表
+pub fn parse_id_list(raw: &str) -> Vec<i32> {
+    raw.trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .filter_map(|s| s.trim().trim_matches('"').parse::<i32>().ok())
+        .collect()
+}
+
+/// 解析以文本形式存储的 JSON 配置，无法解析时返回 null
+pub fn parse_json_setting(raw: Option<&str>) -> serde_json::Value {
+    raw.and_then(|raw| serde_json::from_str(raw).ok())
+        .unwrap_or(serde_json::Value::Null)
+}
+
+/// 节点运行状态（由节点后端上报）
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct ServerStatus {
+    pub server_id: i32,
+    pub server_type: String,
+    pub last_check_at: Option<i32>,
+    pub last_push_at: Option<i32>,
+    pub online_user: i32,
+    pub cpu: Option<f64>,
+    pub mem: Option<f64>,
+    pub disk: Option<f64>,
+    pub uptime: Option<i64>,
+    pub updated_at: i32,
+}
+
+impl ServerStatus {
+    /// 最近一次心跳（拉取用户或上报流量）的时间
+    pub fn last_seen_at(&self) -> Option<i32> {
+        match (self.last_check_at, self.last_push_at) {
+            (Some(check), Some(push)) => Some(check.max(push)),
+            (check, push) => check.or(push),
+        }
+    }
+
+    /// 判断节点是否已超过阈值未上报
+    pub fn is_silent(&self, now: i32, threshold: i32) -> bool {
+        match self.last_seen_at() {
+            Some(last_seen) => now - last_seen > threshold,
+            None => true,
+        }
+    }
+}
+
+/// 节点负载信息
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ServerLoad {
+    pub cpu: Option<f64>,
+    pub mem: Option<f64>,
+    pub disk: Option<f64>,
+    pub uptime: Option<i64>,
+}
+
+/// 节点在线状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerAvailableStatus {
+    /// 从未上报
+    Unknown,
+    /// 正常运行
+    Online,
+    /// 超过阈值未上报
+    Offline,
+}
+
+/// 节点列表项（包含运行状态）
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ServerNodeResponse {
+    pub id: i32,
+    #[serde(rename = "type")]
+    pub r#type: ServerType,
+    pub name: String,
+    pub host: String,
+    pub port: String,
+    pub server_port: i32,
+    pub group_ids: Vec<i32>,
+    pub parent_id: Option<i32>,
+    pub rate: String,
+    pub show: bool,
+    pub sort: Option<i32>,
+    pub available_status: ServerAvailableStatus,
+    pub last_check_at: Option<i32>,
+    pub last_push_at: Option<i32>,
+    pub online_user: i32,
+    pub load: Option<ServerLoad>,
+}
+
+impl ServerNodeResponse {
+    pub fn new(server: &Server, status: Option<&ServerStatus>, now: i32, threshold: i32) -> Self {
+        let available_status = match status {
+            Some(status) if status.last_seen_at().is_some() => {
+                if status.is_silent(now, threshold) {
+                    ServerAvailableStatus::Offline
+                } else {
+                    ServerAvailableStatus::Online
+                }
+            }
+            _ => ServerAvailableStatus::Unknown,
+        };
+
+        Self {
+            id: server.id(),
+            r#type: server.server_type(),
+            name: server.name().to_string(),
+            host: server.host().to_string(),
+            port: server.port(),
+            server_port: server.server_port(),
+            group_ids: server.group_ids(),
+            parent_id: server.parent_id(),
+            rate: server.rate().to_string(),
+            show: server.show(),
+            sort: server.sort(),
+            available_status,
+            last_check_at: status.and_then(|s| s.last_check_at),
+            last_push_at: status.and_then(|s| s.last_push_at),
+            online_user: status.map(|s| s.online_user).unwrap_or(0),
+            load: status.map(|s| ServerLoad {
+                cpu: s.cpu,
+                mem: s.mem,
+                disk: s.disk,
+                uptime: s.uptime,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ServerListResponse {
+    pub servers: Vec<ServerNodeResponse>,
+    pub total: i64,
+}
+
+/// 节点健康概况
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ServerHealthResponse {
+    pub total: i64,
+    pub online: i64,
+    /// 判定离线的阈值（秒）
+    pub threshold: i32,
+    /// 超过阈值未上报的节点
+    pub silent: Vec<ServerNodeResponse>,
+}
+
+/// 设置节点的父节点（置空即取消中转）
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateServerParentRequest {
+    pub parent_id: Option<i32>,
+    /// 父节点类型，必须与当前节点一致
+    pub parent_type: Option<ServerType>,
+}
+
+/// 节点后端请求的公共参数
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct NodeQuery {
+    /// 节点通讯密钥
+    pub token: String,
+    /// 节点ID
+    pub node_id: i32,
+    /// 节点类型
+    pub node_type: String,
+}
+
+/// 下发给节点后端的用户信息
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct NodeUser {
+    pub id: i32,
+    pub uuid: String,
+    pub speed_limit: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NodeUserListResponse {
+    pub users: Vec<NodeUser>,
+}
+
+/// 流量上报：用户ID -> [上行, 下行]
+pub type NodeTrafficPush = HashMap<i32, [i64; 2]>;
+
+/// 在线用户上报：用户ID -> 在线IP列表
+pub type NodeAlivePush = HashMap<i32, Vec<String>>;
+
+/// 节点负载上报
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NodeStatusPush {
+    pub cpu: f64,
+    pub mem: f64,
+    pub disk: f64,
+    pub uptime: i64,
+}
+
+/// 节点后端轮询间隔配置
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NodeBaseConfig {
+    /// 流量上报间隔（秒）
+    pub push_interval: i32,
+    /// 用户拉取间隔（秒）
+    pub pull_interval: i32,
+}
+
+/// 下发给节点后端的节点配置
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NodeConfigResponse {
+    pub protocol: ServerType,
+    pub server_port: i32,
+    #[schema(value_type = Object)]
+    pub settings: serde_json::Value,
+    pub routes: Vec<RouteConfig>,
+    pub base_config: NodeBaseConfig,
+}
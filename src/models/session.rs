@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+
+/// 用户登录会话
+///
+/// 每次登录创建一个会话，访问令牌携带会话ID，刷新令牌只保存哈希；
+/// 上一个刷新令牌的哈希只在数据库中用于识别令牌重放，不加载到结构体中
+#[derive(Debug, Clone, FromRow)]
+pub struct Session {
+    pub id: i32,
+    pub user_id: i32,
+    pub refresh_token_hash: String,
+    pub device: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: i32,
+    pub last_used_at: i32,
+    pub expired_at: i32,
+    pub revoked_at: Option<i32>,
+}
+
+impl Session {
+    /// 会话未撤销且未过期
+    pub fn is_active(&self, now: i64) -> bool {
+        self.revoked_at.is_none() && i64::from(self.expired_at) > now
+    }
+}
+
+/// 登录设备信息，记录到会话中
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    /// 客户端 User-Agent
+    pub device: Option<String>,
+    pub ip: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SessionResponse {
+    pub id: i32,
+    pub device: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: i32,
+    pub last_used_at: i32,
+    pub expired_at: i32,
+    /// 是否为发起请求的会话
+    pub current: bool,
+}
+
+impl SessionResponse {
+    pub fn from_session(session: Session, current_id: i32) -> Self {
+        Self {
+            current: session.id == current_id,
+            id: session.id,
+            device: session.device,
+            ip: session.ip,
+            created_at: session.created_at,
+            last_used_at: session.last_used_at,
+            expired_at: session.expired_at,
+        }
+    }
+}
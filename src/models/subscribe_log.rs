@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+/// 订阅拉取记录
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SubscribeLog {
+    pub id: i32,
+    pub user_id: i32,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: i32,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct SubscribeLogQuery {
+    #[serde(default = "default_page")]
+    pub page: u64,
+    #[serde(default = "default_page_size")]
+    pub page_size: u64,
+}
+
+fn default_page() -> u64 {
+    1
+}
+
+fn default_page_size() -> u64 {
+    10
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct ResetSubscribeTokenRequest {
+    /// 是否同时重置 UUID，重置后需要重新导入订阅才能连接节点
+    #[serde(default)]
+    pub reset_uuid: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ResetSubscribeTokenResponse {
+    pub token: String,
+    pub uuid: String,
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// 订阅模板类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum SubscribeTemplateKind {
+    /// Clash / Clash.Meta 配置模板（YAML）
+    Clash,
+    /// sing-box 配置模板（JSON），节点出站之外的部分均可自定义
+    SingBox,
+}
+
+impl SubscribeTemplateKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubscribeTemplateKind::Clash => "clash",
+            SubscribeTemplateKind::SingBox => "sing-box",
+        }
+    }
+}
+
+impl std::str::FromStr for SubscribeTemplateKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clash" => Ok(SubscribeTemplateKind::Clash),
+            "sing-box" => Ok(SubscribeTemplateKind::SingBox),
+            _ => anyhow::bail!("未知的订阅模板类型: {}", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct SubscribeTemplate {
+    pub name: String,
+    pub content: String,
+    pub created_at: i32,
+    pub updated_at: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct UpdateSubscribeTemplateRequest {
+    #[validate(length(min = 1))]
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SubscribeTemplateResponse {
+    pub name: String,
+    pub content: String,
+    /// 是否为内置默认模板（未自定义）
+    pub is_default: bool,
+    pub updated_at: Option<i32>,
+}
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+/// 流量重置记录
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TrafficResetLog {
+    pub id: i32,
+    pub user_id: i32,
+    pub plan_id: Option<i32>,
+    pub reset_traffic_method: i16,
+    /// 重置周期，月度为 `YYYY-MM`，年度为 `YYYY`
+    pub period: String,
+    /// 重置前的上行流量
+    pub u: i64,
+    /// 重置前的下行流量
+    pub d: i64,
+    pub created_at: i32,
+}
+
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+pub struct TrafficResetLogQuery {
+    pub user_id: Option<i32>,
+    #[serde(default = "default_page")]
+    pub page: u64,
+    #[serde(default = "default_page_size")]
+    pub page_size: u64,
+}
+
+fn default_page() -> u64 {
+    1
+}
+
+fn default_page_size() -> u64 {
+    10
+}
+
+/// 一次流量重置任务的执行结果
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct TrafficResetSummary {
+    /// 执行所用的日期
+    pub date: String,
+    /// 检查的用户数
+    pub checked: u64,
+    /// 本次重置的用户数
+    pub reset: u64,
+    /// 本周期已重置过或在重置日之后才开通套餐而跳过的用户数
+    pub skipped: u64,
+    /// 重置失败的用户数
+    pub failed: u64,
+    /// 套餐已到期而收回流量包的用户数
+    pub expired_addons: u64,
+}
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::models::auth::TokenResponse;
+
+/// 用户 TOTP 双重验证
+#[derive(Debug, Clone, FromRow)]
+pub struct TwoFactor {
+    /// Base32 编码的 TOTP 密钥
+    pub secret: String,
+    /// 启用时间，为空表示尚未确认
+    pub enabled_at: Option<i32>,
+    /// 最近一次验证通过的时间步
+    pub last_used_step: Option<i64>,
+}
+
+impl TwoFactor {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled_at.is_some()
+    }
+}
+
+/// 双重验证登录挑战令牌的载荷
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TwoFactorClaims {
+    pub sub: i32,
+    /// 固定为 `2fa`，与访问令牌区分
+    pub typ: String,
+    pub exp: i64,
+    pub iat: i64,
+}
+
+impl TwoFactorClaims {
+    pub const TYPE: &'static str = "2fa";
+
+    pub fn new(user_id: i32, ttl: i64) -> Self {
+        let now = chrono::Utc::now().timestamp();
+
+        Self {
+            sub: user_id,
+            typ: Self::TYPE.to_string(),
+            exp: now + ttl,
+            iat: now,
+        }
+    }
+}
+
+/// 启用双重验证的账户登录时，密码验证通过后返回的挑战
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TwoFactorChallenge {
+    /// 固定为 true
+    pub two_factor_required: bool,
+    /// 提交验证码时携带的挑战令牌
+    pub challenge_token: String,
+    pub expires_in: i64,
+}
+
+/// 登录结果，未启用双重验证时直接返回令牌，否则返回挑战
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Token(TokenResponse),
+    TwoFactor(TwoFactorChallenge),
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct TwoFactorLoginRequest {
+    pub challenge_token: String,
+    /// 身份验证器中的6位验证码或恢复码
+    #[validate(length(min = 6, max = 32))]
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TwoFactorStatusResponse {
+    pub enabled: bool,
+    pub enabled_at: Option<i32>,
+    /// 剩余可用的恢复码数量
+    pub recovery_codes_remaining: i64,
+    /// 当前账户是否被要求启用双重验证
+    pub required: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TwoFactorSetupResponse {
+    /// Base32 编码的密钥，用于手动输入
+    pub secret: String,
+    /// `otpauth://` 链接，可生成二维码供身份验证器扫描
+    pub otpauth_url: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct TwoFactorCodeRequest {
+    /// 身份验证器中的6位验证码
+    #[validate(length(min = 6, max = 32))]
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct DisableTwoFactorRequest {
+    pub password: String,
+    /// 身份验证器中的6位验证码或恢复码
+    #[validate(length(min = 6, max = 32))]
+    pub code: String,
+}
+
+/// 恢复码只在生成时返回一次
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
@@ -8,6 +8,8 @@ pub struct User {
     pub invite_user_id: Option<i32>,
     pub telegram_id: Option<i64>,
     pub email: String,
+    /// 用户名，不区分大小写，从 V2Board 导入的用户为空
+    pub username: Option<String>,
     pub password: String,
     pub password_algo: Option<String>,
     pub password_salt: Option<String>,
@@ -28,6 +30,8 @@ pub struct User {
     pub uuid: String,
     pub group_id: Option<i32>,
     pub plan_id: Option<i32>,
+    /// 当前套餐的开通时间，开通前的重置日不重置流量
+    pub plan_activated_at: Option<i32>,
     pub speed_limit: Option<i32>,
     pub remind_expire: Option<bool>,
     pub remind_traffic: Option<bool>,
@@ -38,9 +42,45 @@ pub struct User {
     pub updated_at: i32,
 }
 
+/// 用户角色，权限从低到高排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// 普通用户
+    User,
+    /// 客服
+    Staff,
+    /// 管理员
+    Admin,
+}
+
+impl Role {
+    /// 是否可以修改、删除或封禁指定角色的账户
+    ///
+    /// 管理员可以管理所有账户，其他角色只能管理角色低于自己的账户，
+    /// 避免客服修改管理员的邮箱、密码后接管管理员账户
+    pub fn can_manage(&self, target: Role) -> bool {
+        *self == Role::Admin || target < *self
+    }
+}
+
+impl User {
+    /// 用户角色，同时标记为管理员和客服时按管理员处理
+    pub fn role(&self) -> Role {
+        if self.is_admin.unwrap_or(false) {
+            Role::Admin
+        } else if self.is_staff.unwrap_or(false) {
+            Role::Staff
+        } else {
+            Role::User
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateUser {
     pub email: String,
+    pub username: Option<String>,
     pub password: String,
     pub invite_user_id: Option<i32>,
     pub uuid: String,
@@ -52,6 +92,7 @@ pub struct UpdateUser {
     pub invite_user_id: Option<i32>,
     pub telegram_id: Option<i64>,
     pub email: Option<String>,
+    pub username: Option<String>,
     pub password: Option<String>,
     pub password_algo: Option<String>,
     pub password_salt: Option<String>,
@@ -84,6 +125,7 @@ pub struct UserResponse {
     pub invite_user_id: Option<i32>,
     pub telegram_id: Option<i64>,
     pub email: String,
+    pub username: Option<String>,
     pub balance: i32,
     pub discount: Option<i32>,
     pub commission_type: Option<bool>,
@@ -109,3 +151,26 @@ pub struct UserResponse {
     pub created_at: i32,
     pub updated_at: i32,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_order() {
+        assert!(Role::User < Role::Staff);
+        assert!(Role::Staff < Role::Admin);
+    }
+
+    #[test]
+    fn test_can_manage() {
+        assert!(Role::Admin.can_manage(Role::Admin));
+        assert!(Role::Admin.can_manage(Role::Staff));
+        assert!(Role::Admin.can_manage(Role::User));
+        assert!(Role::Staff.can_manage(Role::User));
+        // 客服不能管理其他客服和管理员
+        assert!(!Role::Staff.can_manage(Role::Staff));
+        assert!(!Role::Staff.can_manage(Role::Admin));
+        assert!(!Role::User.can_manage(Role::User));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::models::api_key::ApiKey;
+use anyhow::Result;
+use sqlx::PgPool;
+
+/// 最近使用时间的更新间隔（秒），避免每个请求都写数据库
+const TOUCH_INTERVAL: i32 = 60;
+
+#[derive(Clone)]
+pub struct ApiKeyRepository {
+    pool: PgPool,
+}
+
+impl ApiKeyRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(
+        &self,
+        user_id: i32,
+        name: &str,
+        prefix: &str,
+        secret_hash: &str,
+        scopes: &[String],
+        expired_at: Option<i32>,
+    ) -> Result<ApiKey> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let api_key = sqlx::query_as!(
+            ApiKey,
+            r#"
+            INSERT INTO purple_user_api_key (
+                user_id, name, prefix, secret_hash, scopes, expired_at, created_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+            user_id,
+            name,
+            prefix,
+            secret_hash,
+            scopes,
+            expired_at,
+            now
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(api_key)
+    }
+
+    pub async fn find_by_prefix(&self, prefix: &str) -> Result<Option<ApiKey>> {
+        let api_key = sqlx::query_as!(
+            ApiKey,
+            r#"
+            SELECT * FROM purple_user_api_key WHERE prefix = $1
+            "#,
+            prefix
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(api_key)
+    }
+
+    /// 获取用户未撤销的密钥，包含已过期的密钥
+    pub async fn find_by_user(&self, user_id: i32) -> Result<Vec<ApiKey>> {
+        let api_keys = sqlx::query_as!(
+            ApiKey,
+            r#"
+            SELECT * FROM purple_user_api_key
+            WHERE user_id = $1 AND revoked_at IS NULL
+            ORDER BY id DESC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(api_keys)
+    }
+
+    pub async fn count_by_user(&self, user_id: i32) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!" FROM purple_user_api_key
+            WHERE user_id = $1 AND revoked_at IS NULL
+            "#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// 撤销用户的密钥，返回是否撤销成功
+    pub async fn revoke(&self, user_id: i32, id: i32) -> Result<bool> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE purple_user_api_key
+            SET revoked_at = $3
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+            "#,
+            id,
+            user_id,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// 记录密钥的最近使用时间和IP，距上次记录不足一分钟时跳过
+    pub async fn touch(&self, id: i32, ip: Option<&str>) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        sqlx::query!(
+            r#"
+            UPDATE purple_user_api_key
+            SET last_used_at = $2, last_used_ip = $3
+            WHERE id = $1 AND (last_used_at IS NULL OR last_used_at <= $4)
+            "#,
+            id,
+            now,
+            ip,
+            now - TOUCH_INTERVAL
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
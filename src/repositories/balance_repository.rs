@@ -0,0 +1,201 @@
+use crate::models::balance::{BalanceLog, ChargeOutcome, MeteredBilling, MeteredCharge};
+use anyhow::Result;
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct BalanceRepository {
+    pool: PgPool,
+}
+
+impl BalanceRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// 从用户余额中扣费并写入余额变动记录
+    ///
+    /// 余额不足时只扣除剩余余额，余额耗尽的用户会被停用；
+    /// 带日期的扣费同一用户同一天只会执行一次，已停用的用户不再扣费。
+    /// 按流量扣费时已计费流量与 `expected_billed_transfer` 不一致说明已被其他任务计费，
+    /// 以上情况均跳过本次扣费并返回 None
+    pub async fn charge(&self, charge: &MeteredCharge) -> Result<Option<ChargeOutcome>> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let mut tx = self.pool.begin().await?;
+
+        // 先锁定用户，同一用户的扣费依次执行，之后读取的计费状态不会被并发修改
+        let user = sqlx::query!(
+            r#"
+            SELECT balance FROM purple_user WHERE id = $1 FOR UPDATE
+            "#,
+            charge.user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let user = match user {
+            Some(user) => user,
+            None => return Ok(None),
+        };
+
+        let billing = sqlx::query!(
+            r#"
+            SELECT billed_transfer, suspended FROM purple_metered_billing
+            WHERE user_id = $1 FOR UPDATE
+            "#,
+            charge.user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let (billed_transfer, suspended) = billing
+            .map(|billing| (billing.billed_transfer, billing.suspended))
+            .unwrap_or((0, false));
+        if suspended {
+            return Ok(None);
+        }
+        if charge
+            .expected_billed_transfer
+            .is_some_and(|expected| expected != billed_transfer)
+        {
+            return Ok(None);
+        }
+
+        let amount = charge.amount.min(user.balance.max(0));
+        let balance = user.balance - amount;
+
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO purple_balance_log (
+                user_id, type, amount, balance, period, transfer, remark, created_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (user_id, type, period) WHERE period IS NOT NULL DO NOTHING
+            "#,
+            charge.user_id,
+            charge.r#type.as_i16(),
+            -amount,
+            balance,
+            charge.period,
+            charge.transfer,
+            charge.remark,
+            now
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+        if !inserted {
+            return Ok(None);
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE purple_user
+            SET balance = $1, updated_at = $2
+            WHERE id = $3
+            "#,
+            balance,
+            now,
+            charge.user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let suspended = balance <= 0;
+        if suspended || charge.billed_transfer.is_some() {
+            sqlx::query!(
+                r#"
+                INSERT INTO purple_metered_billing (user_id, billed_transfer, suspended, updated_at)
+                VALUES ($1, COALESCE($2::bigint, 0), $3, $4)
+                ON CONFLICT (user_id) DO UPDATE
+                SET billed_transfer = COALESCE($2, purple_metered_billing.billed_transfer),
+                    suspended = $3,
+                    updated_at = $4
+                "#,
+                charge.user_id,
+                charge.billed_transfer,
+                suspended,
+                now
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(Some(ChargeOutcome { amount, suspended }))
+    }
+
+    /// 获取所有按量计费状态
+    pub async fn find_all_billing(&self) -> Result<Vec<MeteredBilling>> {
+        let billing = sqlx::query_as!(
+            MeteredBilling,
+            r#"
+            SELECT * FROM purple_metered_billing
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(billing)
+    }
+
+    /// 恢复因余额耗尽被停用、现已有余额的用户，返回恢复的用户数
+    pub async fn resume_suspended(&self) -> Result<u64> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE purple_metered_billing b
+            SET suspended = false, updated_at = $1
+            FROM purple_user u
+            WHERE u.id = b.user_id AND b.suspended AND u.balance > 0
+            "#,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn find_logs(
+        &self,
+        user_id: Option<i32>,
+        page: i32,
+        page_size: i32,
+    ) -> Result<(Vec<BalanceLog>, i64)> {
+        let offset = (page - 1) * page_size;
+
+        let logs = sqlx::query_as!(
+            BalanceLog,
+            r#"
+            SELECT * FROM purple_balance_log
+            WHERE ($1::integer IS NULL OR user_id = $1)
+            ORDER BY id DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            user_id,
+            page_size as i64,
+            offset as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let total = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as count FROM purple_balance_log
+            WHERE ($1::integer IS NULL OR user_id = $1)
+            "#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .count
+        .unwrap_or(0);
+
+        Ok((logs, total))
+    }
+}
@@ -0,0 +1,166 @@
+use crate::models::email_code::{EmailCode, EmailCodePurpose};
+use anyhow::Result;
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct EmailCodeRepository {
+    pool: PgPool,
+}
+
+impl EmailCodeRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(
+        &self,
+        email: &str,
+        purpose: EmailCodePurpose,
+        code_hash: &str,
+        ip: Option<&str>,
+        expired_at: i32,
+    ) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO purple_email_code (email, purpose, code_hash, ip, created_at, expired_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            email,
+            purpose.as_i16(),
+            code_hash,
+            ip,
+            now,
+            expired_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 获取邮箱最近一次发送的验证码，不区分用途
+    pub async fn find_last_by_email(&self, email: &str) -> Result<Option<EmailCode>> {
+        let code = sqlx::query_as!(
+            EmailCode,
+            r#"
+            SELECT id, code_hash, attempts, created_at FROM purple_email_code
+            WHERE email = $1
+            ORDER BY id DESC
+            LIMIT 1
+            "#,
+            email
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(code)
+    }
+
+    /// 获取邮箱指定用途最新的验证码，未使用且未过期时才返回
+    ///
+    /// 重新发送后旧验证码随即失效
+    pub async fn find_latest_valid(
+        &self,
+        email: &str,
+        purpose: EmailCodePurpose,
+    ) -> Result<Option<EmailCode>> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let code = sqlx::query_as!(
+            EmailCode,
+            r#"
+            SELECT id, code_hash, attempts, created_at FROM (
+                SELECT * FROM purple_email_code
+                WHERE email = $1 AND purpose = $2
+                ORDER BY id DESC
+                LIMIT 1
+            ) latest
+            WHERE used_at IS NULL AND expired_at > $3
+            "#,
+            email,
+            purpose.as_i16(),
+            now
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(code)
+    }
+
+    /// 统计邮箱在 `since` 之后发送的验证码数量
+    pub async fn count_by_email_since(&self, email: &str, since: i32) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!" FROM purple_email_code
+            WHERE email = $1 AND created_at >= $2
+            "#,
+            email,
+            since
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// 统计IP在 `since` 之后请求发送的验证码数量
+    pub async fn count_by_ip_since(&self, ip: &str, since: i32) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!" FROM purple_email_code
+            WHERE ip = $1 AND created_at >= $2
+            "#,
+            ip,
+            since
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// 记录一次验证失败
+    pub async fn increment_attempts(&self, id: i32) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE purple_email_code SET attempts = attempts + 1 WHERE id = $1
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 标记验证码已使用，返回是否标记成功
+    ///
+    /// 并发验证时只有一个请求能成功；失败次数已达 `max_attempts` 时不再接受，
+    /// 避免并发猜测绕过失败次数限制
+    pub async fn mark_used(&self, id: i32, max_attempts: i32) -> Result<bool> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE purple_email_code
+            SET used_at = $2
+            WHERE id = $1 AND used_at IS NULL AND attempts < $3
+            "#,
+            id,
+            now,
+            max_attempts
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
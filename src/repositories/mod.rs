@@ -1,7 +1,31 @@
+pub mod api_key_repository;
+pub mod balance_repository;
 mod coupon_repository;
+pub mod email_code_repository;
+pub mod order_repository;
+pub mod permission_repository;
 pub mod plan_repository;
+pub mod route_repository;
+pub mod server_repository;
+pub mod session_repository;
+pub mod subscribe_log_repository;
+pub mod subscribe_template_repository;
+pub mod traffic_reset_repository;
+pub mod two_factor_repository;
 pub mod user_repository;
 
+pub use api_key_repository::ApiKeyRepository;
+pub use balance_repository::BalanceRepository;
 pub use coupon_repository::CouponRepository;
+pub use email_code_repository::EmailCodeRepository;
+pub use order_repository::OrderRepository;
+pub use permission_repository::PermissionRepository;
 pub use plan_repository::PlanRepository;
+pub use route_repository::RouteRepository;
+pub use server_repository::ServerRepository;
+pub use session_repository::SessionRepository;
+pub use subscribe_log_repository::SubscribeLogRepository;
+pub use subscribe_template_repository::SubscribeTemplateRepository;
+pub use traffic_reset_repository::TrafficResetRepository;
+pub use two_factor_repository::TwoFactorRepository;
 pub use user_repository::UserRepository;
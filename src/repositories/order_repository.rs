@@ -0,0 +1,260 @@
+use crate::models::{
+    balance::BalanceLogType,
+    order::{BalancePayment, NewOrder, Order, OrderStatus},
+};
+use anyhow::Result;
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct OrderRepository {
+    pool: PgPool,
+}
+
+impl OrderRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, order: &NewOrder) -> Result<Order> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        Self::insert(&self.pool, order, now).await
+    }
+
+    /// 在套餐名额内创建订单，名额已满时返回 None
+    ///
+    /// 占用名额的用户包括持有该套餐且未过期的用户，以及在 `reserved_since`
+    /// 之后下单且仍待支付的用户；统计与创建在同一事务中按套餐加锁完成，
+    /// 并发下单不会超卖
+    pub async fn create_within_capacity(
+        &self,
+        order: &NewOrder,
+        capacity_limit: i32,
+        reserved_since: i32,
+    ) -> Result<Option<Order>> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            SELECT pg_advisory_xact_lock($1::bigint)
+            "#,
+            order.plan_id as i64
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let holders = sqlx::query!(
+            r#"
+            SELECT COUNT(*) AS "count!" FROM (
+                SELECT id AS user_id FROM purple_user
+                WHERE plan_id = $1
+                AND (expired_at IS NULL OR expired_at = 0 OR expired_at > $2)
+                UNION
+                SELECT user_id FROM purple_order
+                WHERE plan_id = $1 AND status = $3 AND created_at > $4
+            ) holders
+            WHERE user_id <> $5
+            "#,
+            order.plan_id,
+            now as i64,
+            OrderStatus::Pending.as_i16(),
+            reserved_since,
+            order.user_id
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .count;
+        if holders >= capacity_limit as i64 {
+            return Ok(None);
+        }
+
+        let order = Self::insert(&mut *tx, order, now).await?;
+
+        tx.commit().await?;
+        Ok(Some(order))
+    }
+
+    async fn insert<'e, E>(executor: E, order: &NewOrder, now: i32) -> Result<Order>
+    where
+        E: sqlx::PgExecutor<'e>,
+    {
+        let order = sqlx::query_as!(
+            Order,
+            r#"
+            INSERT INTO purple_order (
+                user_id, plan_id, type, period, trade_no, total_amount, transfer_amount,
+                status, created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $9)
+            RETURNING *
+            "#,
+            order.user_id,
+            order.plan_id,
+            order.r#type.as_i32(),
+            order.period,
+            order.trade_no,
+            order.total_amount,
+            order.transfer_amount,
+            OrderStatus::Pending.as_i16(),
+            now
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(order)
+    }
+
+    pub async fn find_by_trade_no(&self, user_id: i32, trade_no: &str) -> Result<Option<Order>> {
+        let order = sqlx::query_as!(
+            Order,
+            r#"
+            SELECT * FROM purple_order WHERE user_id = $1 AND trade_no = $2
+            "#,
+            user_id,
+            trade_no
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(order)
+    }
+
+    pub async fn find_by_user(
+        &self,
+        user_id: i32,
+        page: i32,
+        page_size: i32,
+    ) -> Result<(Vec<Order>, i64)> {
+        let offset = (page - 1) * page_size;
+
+        let orders = sqlx::query_as!(
+            Order,
+            r#"
+            SELECT * FROM purple_order
+            WHERE user_id = $1
+            ORDER BY id DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            user_id,
+            page_size as i64,
+            offset as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let total = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as count FROM purple_order WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .count
+        .unwrap_or(0);
+
+        Ok((orders, total))
+    }
+
+    /// 取消待支付的订单，订单已不是待支付状态时返回 false
+    pub async fn cancel(&self, id: i32) -> Result<bool> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE purple_order
+            SET status = $1, updated_at = $2
+            WHERE id = $3 AND status = $4
+            "#,
+            OrderStatus::Cancelled.as_i16(),
+            now,
+            id,
+            OrderStatus::Pending.as_i16()
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 使用余额支付流量包订单并增加用户可用流量
+    ///
+    /// 增加的流量由流量重置、套餐到期或重新开通套餐时恢复套餐流量收回
+    ///
+    /// 扣款、订单完成、增加流量和写入余额变动记录在同一事务中完成
+    pub async fn pay_traffic_addon_with_balance(
+        &self,
+        order: &Order,
+        transfer_bytes: i64,
+    ) -> Result<BalancePayment> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let mut tx = self.pool.begin().await?;
+
+        let completed = sqlx::query!(
+            r#"
+            UPDATE purple_order
+            SET status = $1, balance_amount = total_amount, paid_at = $2, updated_at = $2
+            WHERE id = $3 AND status = $4
+            "#,
+            OrderStatus::Completed.as_i16(),
+            now,
+            order.id,
+            OrderStatus::Pending.as_i16()
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+        if !completed {
+            return Ok(BalancePayment::NotPending);
+        }
+
+        let balance = sqlx::query!(
+            r#"
+            UPDATE purple_user
+            SET balance = balance - $1, transfer_enable = transfer_enable + $2, updated_at = $3
+            WHERE id = $4 AND balance >= $1
+            RETURNING balance
+            "#,
+            order.total_amount,
+            transfer_bytes,
+            now,
+            order.user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let balance = match balance {
+            Some(row) => row.balance,
+            None => return Ok(BalancePayment::InsufficientBalance),
+        };
+
+        sqlx::query!(
+            r#"
+            INSERT INTO purple_balance_log (user_id, type, amount, balance, order_id, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            order.user_id,
+            BalanceLogType::OrderPayment.as_i16(),
+            -order.total_amount,
+            balance,
+            order.id,
+            now
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(BalancePayment::Paid)
+    }
+}
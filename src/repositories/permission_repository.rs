@@ -0,0 +1,77 @@
+use crate::models::permission::Permission;
+use anyhow::Result;
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct PermissionRepository {
+    pool: PgPool,
+}
+
+impl PermissionRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// 获取用户被分配的权限，忽略已不存在的权限名称
+    pub async fn find_by_user(&self, user_id: i32) -> Result<Vec<Permission>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT permission FROM purple_staff_permission
+            WHERE user_id = $1
+            ORDER BY permission ASC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| match row.permission.parse() {
+                Ok(permission) => Some(permission),
+                Err(e) => {
+                    tracing::warn!("忽略用户 {} 的无效权限: {}", user_id, e);
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// 替换用户的全部权限
+    pub async fn replace(&self, user_id: i32, permissions: &[Permission]) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+        let names: Vec<String> = permissions
+            .iter()
+            .map(|permission| permission.as_str().to_string())
+            .collect();
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM purple_staff_permission WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO purple_staff_permission (user_id, permission, created_at)
+            SELECT $1, permission, $3 FROM UNNEST($2::varchar[]) AS permission
+            ON CONFLICT DO NOTHING
+            "#,
+            user_id,
+            &names,
+            now
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
@@ -1,6 +1,10 @@
-use crate::models::plan::{CreatePlanRequest, Plan, UpdatePlanRequest};
+use crate::models::{
+    order::OrderStatus,
+    plan::{CreatePlanRequest, Plan, UpdatePlanRequest},
+};
 use anyhow::Result;
 use sqlx::PgPool;
+use std::collections::HashMap;
 
 #[derive(Clone)]
 pub struct PlanRepository {
@@ -223,4 +227,42 @@ impl PlanRepository {
 
         Ok(plans)
     }
+
+    /// 统计各套餐占用名额的用户数
+    ///
+    /// 包括持有套餐且未过期的用户，以及在 `reserved_since` 之后下单且仍待支付的用户
+    pub async fn count_holders(
+        &self,
+        plan_ids: &[i32],
+        reserved_since: i32,
+    ) -> Result<HashMap<i32, i64>> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT plan_id AS "plan_id!", COUNT(*) AS "count!" FROM (
+                SELECT plan_id, id AS user_id FROM purple_user
+                WHERE plan_id = ANY($1)
+                AND (expired_at IS NULL OR expired_at = 0 OR expired_at > $2)
+                UNION
+                SELECT plan_id, user_id FROM purple_order
+                WHERE plan_id = ANY($1) AND status = $3 AND created_at > $4
+            ) holders
+            GROUP BY plan_id
+            "#,
+            plan_ids as &[i32],
+            now,
+            OrderStatus::Pending.as_i16(),
+            reserved_since
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.plan_id, row.count))
+            .collect())
+    }
 }
@@ -0,0 +1,178 @@
+use crate::models::route::{CreateRouteRequest, ServerRoute, UpdateRouteRequest};
+use anyhow::Result;
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct RouteRepository {
+    pool: PgPool,
+}
+
+impl RouteRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, route: &CreateRouteRequest) -> Result<ServerRoute> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+        let match_rules = serde_json::to_string(&route.r#match)?;
+
+        let route = sqlx::query_as!(
+            ServerRoute,
+            r#"
+            INSERT INTO purple_server_route (
+                remarks, "match", action, action_value, created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $5)
+            RETURNING *
+            "#,
+            route.remarks,
+            match_rules,
+            route.action,
+            route.action_value,
+            now
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(route)
+    }
+
+    pub async fn find_all(&self) -> Result<Vec<ServerRoute>> {
+        let routes = sqlx::query_as!(
+            ServerRoute,
+            r#"
+            SELECT * FROM purple_server_route
+            ORDER BY id ASC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(routes)
+    }
+
+    pub async fn find_by_id(&self, id: i32) -> Result<Option<ServerRoute>> {
+        let route = sqlx::query_as!(
+            ServerRoute,
+            r#"
+            SELECT * FROM purple_server_route WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(route)
+    }
+
+    pub async fn find_by_ids(&self, ids: &[i32]) -> Result<Vec<ServerRoute>> {
+        let routes = sqlx::query_as!(
+            ServerRoute,
+            r#"
+            SELECT * FROM purple_server_route
+            WHERE id = ANY($1)
+            ORDER BY id ASC
+            "#,
+            ids as &[i32]
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(routes)
+    }
+
+    pub async fn update(&self, id: i32, route: &UpdateRouteRequest) -> Result<ServerRoute> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+        let match_rules = route
+            .r#match
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
+
+        let route = sqlx::query_as!(
+            ServerRoute,
+            r#"
+            UPDATE purple_server_route
+            SET
+                remarks = COALESCE($1, remarks),
+                "match" = COALESCE($2, "match"),
+                action = COALESCE($3, action),
+                action_value = CASE WHEN $5 THEN $4 ELSE action_value END,
+                updated_at = $6
+            WHERE id = $7
+            RETURNING *
+            "#,
+            route.remarks,
+            match_rules,
+            route.action,
+            route.action_value.clone().flatten(),
+            route.action_value.is_some(),
+            now,
+            id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(route)
+    }
+
+    pub async fn delete(&self, id: i32) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM purple_server_route
+            WHERE id = $1
+            "#,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    #[sqlx::test(migrations = false)]
+    async fn test_update_clears_action_value(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let repo = RouteRepository::new(pool);
+
+        let route = repo
+            .create(&CreateRouteRequest {
+                remarks: "走代理".to_string(),
+                r#match: vec!["geosite:netflix".to_string()],
+                action: "proxy".to_string(),
+                action_value: Some("hk-out".to_string()),
+            })
+            .await
+            .unwrap();
+
+        let update = |json: &str| serde_json::from_str::<UpdateRouteRequest>(json).unwrap();
+
+        // 未传 action_value 时保持原值
+        let updated = repo
+            .update(route.id, &update(r#"{"remarks": "奈飞"}"#))
+            .await
+            .unwrap();
+        assert_eq!(updated.remarks, "奈飞");
+        assert_eq!(updated.action_value.as_deref(), Some("hk-out"));
+
+        let updated = repo
+            .update(
+                route.id,
+                &update(r#"{"action": "direct", "action_value": null}"#),
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated.action, "direct");
+        assert_eq!(updated.action_value, None);
+    }
+}
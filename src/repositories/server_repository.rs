@@ -0,0 +1,574 @@
+use crate::models::{
+    rate_schedule::ServerRateSchedule,
+    server::{
+        NodeStatusPush, Server, ServerHysteria, ServerShadowsocks, ServerStatus, ServerTrojan,
+        ServerType, ServerVless, ServerVmess,
+    },
+};
+use anyhow::Result;
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct ServerRepository {
+    pool: PgPool,
+}
+
+impl ServerRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find_all_vmess(&self) -> Result<Vec<ServerVmess>> {
+        let servers = sqlx::query_as!(
+            ServerVmess,
+            r#"
+            SELECT * FROM purple_server_vmess
+            ORDER BY sort ASC NULLS LAST, id ASC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(servers)
+    }
+
+    pub async fn find_all_vless(&self) -> Result<Vec<ServerVless>> {
+        let servers = sqlx::query_as!(
+            ServerVless,
+            r#"
+            SELECT * FROM purple_server_vless
+            ORDER BY sort ASC NULLS LAST, id ASC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(servers)
+    }
+
+    pub async fn find_all_trojan(&self) -> Result<Vec<ServerTrojan>> {
+        let servers = sqlx::query_as!(
+            ServerTrojan,
+            r#"
+            SELECT * FROM purple_server_trojan
+            ORDER BY sort ASC NULLS LAST, id ASC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(servers)
+    }
+
+    pub async fn find_all_shadowsocks(&self) -> Result<Vec<ServerShadowsocks>> {
+        let servers = sqlx::query_as!(
+            ServerShadowsocks,
+            r#"
+            SELECT * FROM purple_server_shadowsocks
+            ORDER BY sort ASC NULLS LAST, id ASC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(servers)
+    }
+
+    pub async fn find_all_hysteria(&self) -> Result<Vec<ServerHysteria>> {
+        let servers = sqlx::query_as!(
+            ServerHysteria,
+            r#"
+            SELECT * FROM purple_server_hysteria
+            ORDER BY sort ASC NULLS LAST, id ASC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(servers)
+    }
+
+    /// 获取所有协议的节点
+    pub async fn find_all(&self) -> Result<Vec<Server>> {
+        let mut servers = Vec::new();
+        servers.extend(self.find_all_vmess().await?.into_iter().map(Server::Vmess));
+        servers.extend(self.find_all_vless().await?.into_iter().map(Server::Vless));
+        servers.extend(
+            self.find_all_trojan()
+                .await?
+                .into_iter()
+                .map(Server::Trojan),
+        );
+        servers.extend(
+            self.find_all_shadowsocks()
+                .await?
+                .into_iter()
+                .map(Server::Shadowsocks),
+        );
+        servers.extend(
+            self.find_all_hysteria()
+                .await?
+                .into_iter()
+                .map(Server::Hysteria),
+        );
+
+        Ok(servers)
+    }
+
+    /// 获取指定类型的所有节点
+    pub async fn find_by_type(&self, server_type: ServerType) -> Result<Vec<Server>> {
+        let servers = match server_type {
+            ServerType::Vmess => self
+                .find_all_vmess()
+                .await?
+                .into_iter()
+                .map(Server::Vmess)
+                .collect(),
+            ServerType::Vless => self
+                .find_all_vless()
+                .await?
+                .into_iter()
+                .map(Server::Vless)
+                .collect(),
+            ServerType::Trojan => self
+                .find_all_trojan()
+                .await?
+                .into_iter()
+                .map(Server::Trojan)
+                .collect(),
+            ServerType::Shadowsocks => self
+                .find_all_shadowsocks()
+                .await?
+                .into_iter()
+                .map(Server::Shadowsocks)
+                .collect(),
+            ServerType::Hysteria => self
+                .find_all_hysteria()
+                .await?
+                .into_iter()
+                .map(Server::Hysteria)
+                .collect(),
+        };
+
+        Ok(servers)
+    }
+
+    pub async fn find_by_id(&self, server_type: ServerType, id: i32) -> Result<Option<Server>> {
+        let server = match server_type {
+            ServerType::Vmess => sqlx::query_as!(
+                ServerVmess,
+                r#"SELECT * FROM purple_server_vmess WHERE id = $1"#,
+                id
+            )
+            .fetch_optional(&self.pool)
+            .await?
+            .map(Server::Vmess),
+            ServerType::Vless => sqlx::query_as!(
+                ServerVless,
+                r#"SELECT * FROM purple_server_vless WHERE id = $1"#,
+                id
+            )
+            .fetch_optional(&self.pool)
+            .await?
+            .map(Server::Vless),
+            ServerType::Trojan => sqlx::query_as!(
+                ServerTrojan,
+                r#"SELECT * FROM purple_server_trojan WHERE id = $1"#,
+                id
+            )
+            .fetch_optional(&self.pool)
+            .await?
+            .map(Server::Trojan),
+            ServerType::Shadowsocks => sqlx::query_as!(
+                ServerShadowsocks,
+                r#"SELECT * FROM purple_server_shadowsocks WHERE id = $1"#,
+                id
+            )
+            .fetch_optional(&self.pool)
+            .await?
+            .map(Server::Shadowsocks),
+            ServerType::Hysteria => sqlx::query_as!(
+                ServerHysteria,
+                r#"SELECT * FROM purple_server_hysteria WHERE id = $1"#,
+                id
+            )
+            .fetch_optional(&self.pool)
+            .await?
+            .map(Server::Hysteria),
+        };
+
+        Ok(server)
+    }
+
+    pub async fn find_all_status(&self) -> Result<Vec<ServerStatus>> {
+        let statuses = sqlx::query_as!(
+            ServerStatus,
+            r#"
+            SELECT * FROM purple_server_status
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(statuses)
+    }
+
+    /// 记录节点最后一次拉取用户（心跳）的时间
+    pub async fn touch_check_at(&self, server_type: ServerType, server_id: i32) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO purple_server_status (server_id, server_type, last_check_at, updated_at)
+            VALUES ($1, $2, $3, $3)
+            ON CONFLICT (server_type, server_id)
+            DO UPDATE SET last_check_at = $3, updated_at = $3
+            "#,
+            server_id,
+            server_type.as_str(),
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 记录节点最后一次上报流量的时间
+    pub async fn touch_push_at(&self, server_type: ServerType, server_id: i32) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO purple_server_status (server_id, server_type, last_push_at, updated_at)
+            VALUES ($1, $2, $3, $3)
+            ON CONFLICT (server_type, server_id)
+            DO UPDATE SET last_push_at = $3, updated_at = $3
+            "#,
+            server_id,
+            server_type.as_str(),
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_online_user(
+        &self,
+        server_type: ServerType,
+        server_id: i32,
+        online_user: i32,
+    ) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO purple_server_status (server_id, server_type, online_user, updated_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (server_type, server_id)
+            DO UPDATE SET online_user = $3, updated_at = $4
+            "#,
+            server_id,
+            server_type.as_str(),
+            online_user,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_load(
+        &self,
+        server_type: ServerType,
+        server_id: i32,
+        load: &NodeStatusPush,
+    ) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO purple_server_status (
+                server_id, server_type, cpu, mem, disk, uptime, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (server_type, server_id)
+            DO UPDATE SET cpu = $3, mem = $4, disk = $5, uptime = $6, updated_at = $7
+            "#,
+            server_id,
+            server_type.as_str(),
+            load.cpu,
+            load.mem,
+            load.disk,
+            load.uptime,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 更新节点关联的路由规则
+    pub async fn update_route_id(
+        &self,
+        server_type: ServerType,
+        id: i32,
+        route_id: Option<&str>,
+    ) -> Result<bool> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let result = match server_type {
+            ServerType::Vmess => {
+                sqlx::query!(
+                    r#"UPDATE purple_server_vmess SET route_id = $1, updated_at = $2 WHERE id = $3"#,
+                    route_id,
+                    now,
+                    id
+                )
+                .execute(&self.pool)
+                .await?
+            }
+            ServerType::Vless => {
+                sqlx::query!(
+                    r#"UPDATE purple_server_vless SET route_id = $1, updated_at = $2 WHERE id = $3"#,
+                    route_id,
+                    now,
+                    id
+                )
+                .execute(&self.pool)
+                .await?
+            }
+            ServerType::Trojan => {
+                sqlx::query!(
+                    r#"UPDATE purple_server_trojan SET route_id = $1, updated_at = $2 WHERE id = $3"#,
+                    route_id,
+                    now,
+                    id
+                )
+                .execute(&self.pool)
+                .await?
+            }
+            ServerType::Shadowsocks => {
+                sqlx::query!(
+                    r#"UPDATE purple_server_shadowsocks SET route_id = $1, updated_at = $2 WHERE id = $3"#,
+                    route_id,
+                    now,
+                    id
+                )
+                .execute(&self.pool)
+                .await?
+            }
+            ServerType::Hysteria => {
+                sqlx::query!(
+                    r#"UPDATE purple_server_hysteria SET route_id = $1, updated_at = $2 WHERE id = $3"#,
+                    route_id,
+                    now,
+                    id
+                )
+                .execute(&self.pool)
+                .await?
+            }
+        };
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 更新节点的父节点
+    pub async fn update_parent_id(
+        &self,
+        server_type: ServerType,
+        id: i32,
+        parent_id: Option<i32>,
+    ) -> Result<bool> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let result = match server_type {
+            ServerType::Vmess => {
+                sqlx::query!(
+                    r#"UPDATE purple_server_vmess SET parent_id = $1, updated_at = $2 WHERE id = $3"#,
+                    parent_id,
+                    now,
+                    id
+                )
+                .execute(&self.pool)
+                .await?
+            }
+            ServerType::Vless => {
+                sqlx::query!(
+                    r#"UPDATE purple_server_vless SET parent_id = $1, updated_at = $2 WHERE id = $3"#,
+                    parent_id,
+                    now,
+                    id
+                )
+                .execute(&self.pool)
+                .await?
+            }
+            ServerType::Trojan => {
+                sqlx::query!(
+                    r#"UPDATE purple_server_trojan SET parent_id = $1, updated_at = $2 WHERE id = $3"#,
+                    parent_id,
+                    now,
+                    id
+                )
+                .execute(&self.pool)
+                .await?
+            }
+            ServerType::Shadowsocks => {
+                sqlx::query!(
+                    r#"UPDATE purple_server_shadowsocks SET parent_id = $1, updated_at = $2 WHERE id = $3"#,
+                    parent_id,
+                    now,
+                    id
+                )
+                .execute(&self.pool)
+                .await?
+            }
+            ServerType::Hysteria => {
+                sqlx::query!(
+                    r#"UPDATE purple_server_hysteria SET parent_id = $1, updated_at = $2 WHERE id = $3"#,
+                    parent_id,
+                    now,
+                    id
+                )
+                .execute(&self.pool)
+                .await?
+            }
+        };
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// 累加节点当日流量统计
+    pub async fn record_traffic(
+        &self,
+        server_type: ServerType,
+        server_id: i32,
+        u: i64,
+        d: i64,
+        record_at: i32,
+    ) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO purple_stat_server (
+                server_id, server_type, u, d, record_type, record_at, created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, 'd', $5, $6, $6)
+            ON CONFLICT (server_id, server_type, record_at)
+            DO UPDATE SET
+                u = purple_stat_server.u + $3,
+                d = purple_stat_server.d + $4,
+                updated_at = $6
+            "#,
+            server_id,
+            server_type.as_str(),
+            u,
+            d,
+            record_at,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn find_all_rate_schedules(&self) -> Result<Vec<ServerRateSchedule>> {
+        let schedules = sqlx::query_as!(
+            ServerRateSchedule,
+            r#"
+            SELECT * FROM purple_server_rate_schedule
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(schedules)
+    }
+
+    pub async fn find_rate_schedule(
+        &self,
+        server_type: ServerType,
+        server_id: i32,
+    ) -> Result<Option<ServerRateSchedule>> {
+        let schedule = sqlx::query_as!(
+            ServerRateSchedule,
+            r#"
+            SELECT * FROM purple_server_rate_schedule
+            WHERE server_type = $1 AND server_id = $2
+            "#,
+            server_type.as_str(),
+            server_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(schedule)
+    }
+
+    pub async fn upsert_rate_schedule(
+        &self,
+        server_type: ServerType,
+        server_id: i32,
+        windows: &str,
+    ) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO purple_server_rate_schedule (server_type, server_id, windows, updated_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (server_type, server_id)
+            DO UPDATE SET windows = $3, updated_at = $4
+            "#,
+            server_type.as_str(),
+            server_id,
+            windows,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_rate_schedule(
+        &self,
+        server_type: ServerType,
+        server_id: i32,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM purple_server_rate_schedule
+            WHERE server_type = $1 AND server_id = $2
+            "#,
+            server_type.as_str(),
+            server_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
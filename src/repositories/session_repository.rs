@@ -0,0 +1,254 @@
+use crate::models::session::{ClientInfo, Session};
+use anyhow::Result;
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct SessionRepository {
+    pool: PgPool,
+}
+
+impl SessionRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(
+        &self,
+        user_id: i32,
+        refresh_token_hash: &str,
+        client: &ClientInfo,
+        expired_at: i32,
+    ) -> Result<Session> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let session = sqlx::query_as!(
+            Session,
+            r#"
+            INSERT INTO purple_user_session (
+                user_id, refresh_token_hash, device, ip, created_at, last_used_at, expired_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $5, $6)
+            RETURNING id, user_id, refresh_token_hash, device, ip, created_at, last_used_at, expired_at, revoked_at
+            "#,
+            user_id,
+            refresh_token_hash,
+            client.device.as_deref(),
+            client.ip.as_deref(),
+            now,
+            expired_at
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(session)
+    }
+
+    pub async fn find_by_id(&self, id: i32) -> Result<Option<Session>> {
+        let session = sqlx::query_as!(
+            Session,
+            r#"
+            SELECT id, user_id, refresh_token_hash, device, ip, created_at, last_used_at, expired_at, revoked_at
+            FROM purple_user_session WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(session)
+    }
+
+    /// 按刷新令牌哈希查找会话，同时匹配当前和上一个刷新令牌
+    pub async fn find_by_token_hash(&self, token_hash: &str) -> Result<Option<Session>> {
+        let session = sqlx::query_as!(
+            Session,
+            r#"
+            SELECT id, user_id, refresh_token_hash, device, ip, created_at, last_used_at, expired_at, revoked_at
+            FROM purple_user_session
+            WHERE refresh_token_hash = $1 OR previous_token_hash = $1
+            ORDER BY id DESC
+            LIMIT 1
+            "#,
+            token_hash
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(session)
+    }
+
+    /// 获取用户未撤销且未过期的会话
+    pub async fn find_active_by_user(&self, user_id: i32) -> Result<Vec<Session>> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let sessions = sqlx::query_as!(
+            Session,
+            r#"
+            SELECT id, user_id, refresh_token_hash, device, ip, created_at, last_used_at, expired_at, revoked_at
+            FROM purple_user_session
+            WHERE user_id = $1 AND revoked_at IS NULL AND expired_at > $2
+            ORDER BY last_used_at DESC
+            "#,
+            user_id,
+            now
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(sessions)
+    }
+
+    /// 轮换刷新令牌
+    ///
+    /// 仅当会话有效且当前令牌仍为 `old_hash` 时更新，
+    /// 并发刷新时只有一个请求能成功，返回是否轮换成功
+    pub async fn rotate(
+        &self,
+        id: i32,
+        old_hash: &str,
+        new_hash: &str,
+        ip: Option<&str>,
+        expired_at: i32,
+    ) -> Result<bool> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE purple_user_session
+            SET refresh_token_hash = $3,
+                previous_token_hash = refresh_token_hash,
+                ip = COALESCE($4, ip),
+                last_used_at = $5,
+                expired_at = $6
+            WHERE id = $1
+              AND refresh_token_hash = $2
+              AND revoked_at IS NULL
+              AND expired_at > $5
+            "#,
+            id,
+            old_hash,
+            new_hash,
+            ip,
+            now,
+            expired_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// 撤销用户的单个会话，返回是否撤销成功
+    pub async fn revoke(&self, user_id: i32, id: i32) -> Result<bool> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE purple_user_session
+            SET revoked_at = $3
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+            "#,
+            id,
+            user_id,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// 撤销用户的全部会话，返回撤销的数量
+    pub async fn revoke_all(&self, user_id: i32) -> Result<u64> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE purple_user_session
+            SET revoked_at = $2
+            WHERE user_id = $1 AND revoked_at IS NULL
+            "#,
+            user_id,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    #[sqlx::test(migrations = false)]
+    async fn test_rotate_keeps_previous_hash_for_reuse_detection(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let user_id = test_support::insert_user(&pool, "session@example.com", None).await;
+        let repo = SessionRepository::new(pool);
+        let expired_at = test_support::now() + 3600;
+
+        let session = repo
+            .create(user_id, "hash-1", &ClientInfo::default(), expired_at)
+            .await
+            .unwrap();
+
+        assert!(repo
+            .rotate(session.id, "hash-1", "hash-2", Some("1.2.3.4"), expired_at)
+            .await
+            .unwrap());
+        // 旧令牌已轮换，再次轮换失败
+        assert!(!repo
+            .rotate(session.id, "hash-1", "hash-3", None, expired_at)
+            .await
+            .unwrap());
+
+        // 上一个令牌仍能找到会话，调用方据此识别重放
+        let found = repo.find_by_token_hash("hash-1").await.unwrap().unwrap();
+        assert_eq!(found.id, session.id);
+        assert_eq!(found.refresh_token_hash, "hash-2");
+        assert_eq!(found.ip.as_deref(), Some("1.2.3.4"));
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_revoke_all_hides_sessions(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let user_id = test_support::insert_user(&pool, "revoke@example.com", None).await;
+        let other_id = test_support::insert_user(&pool, "other@example.com", None).await;
+        let repo = SessionRepository::new(pool);
+        let expired_at = test_support::now() + 3600;
+        let client = ClientInfo::default();
+
+        let session = repo
+            .create(user_id, "a", &client, expired_at)
+            .await
+            .unwrap();
+        repo.create(user_id, "b", &client, expired_at)
+            .await
+            .unwrap();
+
+        // 不能撤销其他用户的会话
+        assert!(!repo.revoke(other_id, session.id).await.unwrap());
+        assert!(repo.revoke(user_id, session.id).await.unwrap());
+        assert_eq!(repo.find_active_by_user(user_id).await.unwrap().len(), 1);
+
+        assert_eq!(repo.revoke_all(user_id).await.unwrap(), 1);
+        assert!(repo.find_active_by_user(user_id).await.unwrap().is_empty());
+        assert!(!repo
+            .rotate(session.id, "a", "c", None, expired_at)
+            .await
+            .unwrap());
+    }
+}
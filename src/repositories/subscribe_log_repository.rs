@@ -0,0 +1,97 @@
+use crate::models::subscribe_log::SubscribeLog;
+use anyhow::Result;
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct SubscribeLogRepository {
+    pool: PgPool,
+}
+
+impl SubscribeLogRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(
+        &self,
+        user_id: i32,
+        ip: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<SubscribeLog> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let log = sqlx::query_as!(
+            SubscribeLog,
+            r#"
+            INSERT INTO purple_subscribe_log (user_id, ip, user_agent, created_at)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+            user_id,
+            ip,
+            user_agent,
+            now
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(log)
+    }
+
+    pub async fn find_by_user(
+        &self,
+        user_id: i32,
+        page: i32,
+        page_size: i32,
+    ) -> Result<(Vec<SubscribeLog>, i64)> {
+        let offset = (page - 1) * page_size;
+
+        let logs = sqlx::query_as!(
+            SubscribeLog,
+            r#"
+            SELECT * FROM purple_subscribe_log
+            WHERE user_id = $1
+            ORDER BY id DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            user_id,
+            page_size as i64,
+            offset as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let total = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as count FROM purple_subscribe_log WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .count
+        .unwrap_or(0);
+
+        Ok((logs, total))
+    }
+
+    /// 统计用户在指定时间之后的拉取次数
+    pub async fn count_since(&self, user_id: i32, since: i32) -> Result<i64> {
+        let count = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as count FROM purple_subscribe_log
+            WHERE user_id = $1 AND created_at >= $2
+            "#,
+            user_id,
+            since
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .count
+        .unwrap_or(0);
+
+        Ok(count)
+    }
+}
@@ -0,0 +1,66 @@
+use crate::models::subscribe_template::SubscribeTemplate;
+use anyhow::Result;
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct SubscribeTemplateRepository {
+    pool: PgPool,
+}
+
+impl SubscribeTemplateRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find_by_name(&self, name: &str) -> Result<Option<SubscribeTemplate>> {
+        let template = sqlx::query_as!(
+            SubscribeTemplate,
+            r#"
+            SELECT * FROM purple_subscribe_template WHERE name = $1
+            "#,
+            name
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(template)
+    }
+
+    pub async fn upsert(&self, name: &str, content: &str) -> Result<SubscribeTemplate> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let template = sqlx::query_as!(
+            SubscribeTemplate,
+            r#"
+            INSERT INTO purple_subscribe_template (name, content, created_at, updated_at)
+            VALUES ($1, $2, $3, $3)
+            ON CONFLICT (name) DO UPDATE
+            SET content = EXCLUDED.content, updated_at = EXCLUDED.updated_at
+            RETURNING *
+            "#,
+            name,
+            content,
+            now
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(template)
+    }
+
+    pub async fn delete(&self, name: &str) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM purple_subscribe_template
+            WHERE name = $1
+            "#,
+            name
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,152 @@
+use crate::models::traffic_reset::TrafficResetLog;
+use anyhow::Result;
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct TrafficResetRepository {
+    pool: PgPool,
+}
+
+impl TrafficResetRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// 重置用户已用流量并写入重置记录
+    ///
+    /// 可用流量恢复为套餐流量，已购买的流量包随之失效；
+    /// 记录与清零在同一事务中完成，同一用户同一周期只会重置一次，
+    /// 已重置过时返回 false，因此任务中断后可安全重跑
+    pub async fn reset_user(
+        &self,
+        user_id: i32,
+        plan_id: Option<i32>,
+        method: i16,
+        period: &str,
+        transfer_enable: i64,
+    ) -> Result<bool> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let mut tx = self.pool.begin().await?;
+
+        let traffic = sqlx::query!(
+            r#"
+            SELECT u, d FROM purple_user WHERE id = $1 FOR UPDATE
+            "#,
+            user_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let traffic = match traffic {
+            Some(traffic) => traffic,
+            None => return Ok(false),
+        };
+
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO purple_traffic_reset_log (
+                user_id, plan_id, reset_traffic_method, period, u, d, created_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (user_id, period) DO NOTHING
+            "#,
+            user_id,
+            plan_id,
+            method,
+            period,
+            traffic.u,
+            traffic.d,
+            now
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+        if !inserted {
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE purple_user
+            SET u = 0, d = 0, transfer_enable = $1, updated_at = $2
+            WHERE id = $3
+            "#,
+            transfer_enable,
+            now,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// 收回套餐已到期用户的流量包，可用流量恢复为套餐流量，返回收回的用户数
+    ///
+    /// 不重置流量的套餐没有重置日，流量包在套餐到期时才失效；按量计费套餐不出售流量包
+    pub async fn expire_addons(&self, gb: i64) -> Result<u64> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE purple_user
+            SET transfer_enable = purple_plan.transfer_enable::bigint * $2, updated_at = $1::integer
+            FROM purple_plan
+            WHERE purple_user.plan_id = purple_plan.id
+            AND purple_user.expired_at > 0 AND purple_user.expired_at <= $1
+            AND (purple_plan.daily_unit_price IS NULL OR purple_plan.daily_unit_price <= 0)
+            AND purple_user.transfer_enable > purple_plan.transfer_enable::bigint * $2
+            "#,
+            now,
+            gb
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn find_all(
+        &self,
+        user_id: Option<i32>,
+        page: i32,
+        page_size: i32,
+    ) -> Result<(Vec<TrafficResetLog>, i64)> {
+        let offset = (page - 1) * page_size;
+
+        let logs = sqlx::query_as!(
+            TrafficResetLog,
+            r#"
+            SELECT * FROM purple_traffic_reset_log
+            WHERE ($1::integer IS NULL OR user_id = $1)
+            ORDER BY id DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            user_id,
+            page_size as i64,
+            offset as i64
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let total = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as count FROM purple_traffic_reset_log
+            WHERE ($1::integer IS NULL OR user_id = $1)
+            "#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .count
+        .unwrap_or(0);
+
+        Ok((logs, total))
+    }
+}
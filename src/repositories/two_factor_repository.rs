@@ -0,0 +1,219 @@
+use crate::models::two_factor::TwoFactor;
+use anyhow::Result;
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct TwoFactorRepository {
+    pool: PgPool,
+}
+
+impl TwoFactorRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find_by_user(&self, user_id: i32) -> Result<Option<TwoFactor>> {
+        let two_factor = sqlx::query_as!(
+            TwoFactor,
+            r#"
+            SELECT secret, enabled_at, last_used_step FROM purple_user_two_factor
+            WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(two_factor)
+    }
+
+    /// 保存待确认的密钥，已启用时不覆盖，返回是否保存成功
+    pub async fn save_pending(&self, user_id: i32, secret: &str) -> Result<bool> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO purple_user_two_factor (user_id, secret, created_at, updated_at)
+            VALUES ($1, $2, $3, $3)
+            ON CONFLICT (user_id) DO UPDATE
+            SET secret = EXCLUDED.secret,
+                last_used_step = NULL,
+                created_at = EXCLUDED.created_at,
+                updated_at = EXCLUDED.updated_at
+            WHERE purple_user_two_factor.enabled_at IS NULL
+            "#,
+            user_id,
+            secret,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// 启用双重验证并替换恢复码
+    pub async fn enable(
+        &self,
+        user_id: i32,
+        step: i64,
+        recovery_code_hashes: &[String],
+    ) -> Result<bool> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE purple_user_two_factor
+            SET enabled_at = $2, last_used_step = $3, updated_at = $2
+            WHERE user_id = $1 AND enabled_at IS NULL
+            "#,
+            user_id,
+            now,
+            step
+        )
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() != 1 {
+            return Ok(false);
+        }
+
+        Self::replace_recovery_codes_in(&mut tx, user_id, recovery_code_hashes, now).await?;
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    /// 关闭双重验证，同时删除恢复码
+    pub async fn delete(&self, user_id: i32) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM purple_user_two_factor WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM purple_user_recovery_code WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// 记录验证通过的时间步，只接受比上次更新的时间步，返回是否记录成功
+    pub async fn use_step(&self, user_id: i32, step: i64) -> Result<bool> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE purple_user_two_factor
+            SET last_used_step = $2, updated_at = $3
+            WHERE user_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)
+            "#,
+            user_id,
+            step,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// 替换用户的全部恢复码
+    pub async fn replace_recovery_codes(&self, user_id: i32, code_hashes: &[String]) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let mut tx = self.pool.begin().await?;
+        Self::replace_recovery_codes_in(&mut tx, user_id, code_hashes, now).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn replace_recovery_codes_in(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: i32,
+        code_hashes: &[String],
+        now: i32,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM purple_user_recovery_code WHERE user_id = $1
+            "#,
+            user_id
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO purple_user_recovery_code (user_id, code_hash, created_at)
+            SELECT $1, code_hash, $3 FROM UNNEST($2::varchar[]) AS code_hash
+            "#,
+            user_id,
+            code_hashes,
+            now
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 使用恢复码，并发使用时只有一个请求能成功，返回是否使用成功
+    pub async fn use_recovery_code(&self, user_id: i32, code_hash: &str) -> Result<bool> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE purple_user_recovery_code
+            SET used_at = $3
+            WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+            "#,
+            user_id,
+            code_hash,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// 统计剩余可用的恢复码数量
+    pub async fn count_unused_recovery_codes(&self, user_id: i32) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!" FROM purple_user_recovery_code
+            WHERE user_id = $1 AND used_at IS NULL
+            "#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+}
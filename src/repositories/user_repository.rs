@@ -1,4 +1,7 @@
-use crate::models::user::{CreateUser, User};
+use crate::models::{
+    server::NodeUser,
+    user::{CreateUser, User},
+};
 use anyhow::Result;
 use sqlx::PgPool;
 
@@ -21,13 +24,14 @@ impl UserRepository {
             User,
             r#"
             INSERT INTO purple_user (
-                email, password, invite_user_id, uuid, token,
+                email, username, password, invite_user_id, uuid, token,
                 created_at, updated_at
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $6)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $7)
             RETURNING *
             "#,
             user.email,
+            user.username,
             user.password,
             user.invite_user_id,
             user.uuid,
@@ -68,6 +72,35 @@ impl UserRepository {
         Ok(user)
     }
 
+    /// 按用户名查找，不区分大小写
+    pub async fn find_by_username(&self, username: &str) -> Result<Option<User>> {
+        let user = sqlx::query_as!(
+            User,
+            r#"
+            SELECT * FROM purple_user WHERE lower(username) = lower($1)
+            "#,
+            username
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(user)
+    }
+
+    pub async fn find_by_token(&self, token: &str) -> Result<Option<User>> {
+        let user = sqlx::query_as!(
+            User,
+            r#"
+            SELECT * FROM purple_user WHERE token = $1
+            "#,
+            token
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(user)
+    }
+
     pub async fn update(&self, user: &User) -> Result<User> {
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)?
@@ -79,39 +112,42 @@ impl UserRepository {
             UPDATE purple_user
             SET 
                 email = $1,
-                password = $2,
-                password_algo = $3,
-                password_salt = $4,
-                telegram_id = $5,
-                invite_user_id = $6,
-                balance = $7,
-                discount = $8,
-                commission_type = $9,
-                commission_rate = $10,
-                commission_balance = $11,
-                t = $12,
-                u = $13,
-                d = $14,
-                transfer_enable = $15,
-                banned = $16,
-                is_admin = $17,
-                is_staff = $18,
-                last_login_at = $19,
-                last_login_ip = $20,
-                uuid = $21,
-                group_id = $22,
-                plan_id = $23,
-                speed_limit = $24,
-                token = $25,
-                remind_expire = $26,
-                remind_traffic = $27,
-                expired_at = $28,
-                remarks = $29,
-                updated_at = $30
-            WHERE id = $31
+                username = $2,
+                password = $3,
+                password_algo = $4,
+                password_salt = $5,
+                telegram_id = $6,
+                invite_user_id = $7,
+                balance = $8,
+                discount = $9,
+                commission_type = $10,
+                commission_rate = $11,
+                commission_balance = $12,
+                t = $13,
+                u = $14,
+                d = $15,
+                transfer_enable = $16,
+                banned = $17,
+                is_admin = $18,
+                is_staff = $19,
+                last_login_at = $20,
+                last_login_ip = $21,
+                uuid = $22,
+                group_id = $23,
+                plan_id = $24,
+                plan_activated_at = $25,
+                speed_limit = $26,
+                token = $27,
+                remind_expire = $28,
+                remind_traffic = $29,
+                expired_at = $30,
+                remarks = $31,
+                updated_at = $32
+            WHERE id = $33
             RETURNING *
             "#,
             user.email,
+            user.username,
             user.password,
             user.password_algo,
             user.password_salt,
@@ -134,6 +170,7 @@ impl UserRepository {
             user.uuid,
             user.group_id,
             user.plan_id,
+            user.plan_activated_at,
             user.speed_limit,
             user.token,
             user.remind_expire,
@@ -149,6 +186,57 @@ impl UserRepository {
         Ok(updated_user)
     }
 
+    /// 重置订阅令牌，传入 uuid 时一并重置，返回新的令牌和 UUID
+    pub async fn reset_token(
+        &self,
+        id: i32,
+        token: &str,
+        uuid: Option<&str>,
+    ) -> Result<Option<(String, String)>> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        let row = sqlx::query!(
+            r#"
+            UPDATE purple_user
+            SET token = $2, uuid = COALESCE($3, uuid), updated_at = $4
+            WHERE id = $1
+            RETURNING token, uuid
+            "#,
+            id,
+            token,
+            uuid,
+            now
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| (row.token, row.uuid)))
+    }
+
+    /// 更新密码哈希，同时清除旧系统的加密方式和盐
+    pub async fn update_password(&self, id: i32, password_hash: &str) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        sqlx::query!(
+            r#"
+            UPDATE purple_user
+            SET password = $2, password_algo = NULL, password_salt = NULL, updated_at = $3
+            WHERE id = $1
+            "#,
+            id,
+            password_hash,
+            now
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn delete(&self, id: i32) -> Result<bool> {
         let result = sqlx::query!(
             r#"
@@ -191,4 +279,117 @@ impl UserRepository {
 
         Ok((users, total))
     }
+
+    /// 获取指定权限组中可用的用户（未封禁、未因余额耗尽停用、未过期、流量未用尽）
+    pub async fn find_available_by_groups(&self, group_ids: &[i32]) -> Result<Vec<NodeUser>> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+
+        let users = sqlx::query_as!(
+            NodeUser,
+            r#"
+            SELECT id, uuid, speed_limit FROM purple_user
+            WHERE group_id = ANY($1)
+            AND banned = false
+            AND NOT EXISTS (
+                SELECT 1 FROM purple_metered_billing b
+                WHERE b.user_id = purple_user.id AND b.suspended
+            )
+            AND (expired_at IS NULL OR expired_at = 0 OR expired_at > $2)
+            AND u + d < transfer_enable
+            ORDER BY id ASC
+            "#,
+            group_ids as &[i32],
+            now
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(users)
+    }
+
+    /// 获取已订阅套餐且未过期的用户
+    pub async fn find_with_plan(&self) -> Result<Vec<User>> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i64;
+
+        let users = sqlx::query_as!(
+            User,
+            r#"
+            SELECT * FROM purple_user
+            WHERE plan_id IS NOT NULL
+            AND (expired_at IS NULL OR expired_at = 0 OR expired_at > $1)
+            ORDER BY id ASC
+            "#,
+            now
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(users)
+    }
+
+    /// 累加用户已用流量
+    pub async fn add_traffic(&self, id: i32, u: i64, d: i64) -> Result<()> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs() as i32;
+
+        sqlx::query!(
+            r#"
+            UPDATE purple_user
+            SET u = u + $1, d = d + $2, t = $3, updated_at = $3
+            WHERE id = $4
+            "#,
+            u,
+            d,
+            now,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn new_user(email: &str, username: &str) -> CreateUser {
+        let uuid = uuid::Uuid::new_v4();
+        CreateUser {
+            email: email.to_string(),
+            username: Some(username.to_string()),
+            password: String::new(),
+            invite_user_id: None,
+            uuid: uuid.to_string(),
+            token: uuid.simple().to_string(),
+        }
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_username_is_case_insensitive(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let repo = UserRepository::new(pool);
+
+        let user = repo
+            .create(new_user("alice@example.com", "Alice"))
+            .await
+            .unwrap();
+        let found = repo.find_by_username("ALICE").await.unwrap().unwrap();
+        assert_eq!(found.id, user.id);
+        assert_eq!(found.username.as_deref(), Some("Alice"));
+        assert!(repo.find_by_username("bob").await.unwrap().is_none());
+
+        // 仅大小写不同的用户名视为重复
+        assert!(repo
+            .create(new_user("other@example.com", "alice"))
+            .await
+            .is_err());
+    }
 }
@@ -2,7 +2,12 @@ use actix_web::web;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
-use crate::{api, api::openapi::ApiDoc};
+use crate::{
+    api,
+    api::openapi::ApiDoc,
+    middleware::{Auth, RequireRole, RequireTwoFactor},
+    models::user::Role,
+};
 
 /// 配置应用路由
 ///
@@ -20,55 +25,222 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
         .service(api::health_check)
         // 认证相关路由
         .configure(configure_auth_routes)
-        // 用户管理路由
-        .configure(configure_user_routes)
-        // 套餐管理路由
-        .configure(configure_plan_routes)
-        // 优惠券管理路由
-        .configure(configure_coupon_routes);
+        // 节点后端通讯路由
+        .configure(configure_agent_routes)
+        // 客户端订阅路由
+        .configure(configure_client_routes)
+        // 公开套餐路由
+        .configure(configure_public_plan_routes)
+        // 管理后台路由
+        .configure(configure_admin_routes)
+        // 用户自助路由
+        .configure(configure_self_service_routes);
 }
 
 /// 配置认证相关路由
 fn configure_auth_routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(api::register).service(api::login);
+    cfg.service(api::register)
+        .service(api::login)
+        .service(api::refresh_token)
+        .service(api::logout)
+        .service(api::login_two_factor)
+        .service(api::send_email_code)
+        .service(api::forgot_password)
+        .service(api::reset_password);
+}
+
+/// 配置管理后台路由
+///
+/// 需要员工或管理员登录，各接口再按细粒度权限校验，
+/// 权限不足时返回 `PermissionDenied`；要求启用双重验证时，未启用的账户返回 `TwoFactorRequired`
+fn configure_admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api/admin")
+            .wrap(RequireTwoFactor)
+            .wrap(RequireRole(Role::Staff))
+            .wrap(Auth::new())
+            // 权限管理路由
+            .configure(configure_permission_routes)
+            // 用户管理路由
+            .configure(configure_user_routes)
+            // 套餐管理路由
+            .configure(configure_plan_routes)
+            // 优惠券管理路由
+            .configure(configure_coupon_routes)
+            // 节点管理路由
+            .configure(configure_server_routes)
+            // 节点路由规则管理路由
+            .configure(configure_route_routes)
+            // 订阅模板路由
+            .configure(configure_subscribe_template_routes)
+            // 流量重置路由
+            .configure(configure_traffic_reset_routes)
+            // 按量计费路由
+            .configure(configure_billing_routes),
+    );
+}
+
+/// 配置权限管理路由
+fn configure_permission_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/permissions").service(api::list_permissions));
 }
 
 /// 配置用户管理路由
 fn configure_user_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
-        web::scope("/api/users")
+        web::scope("/users")
             .service(api::create_user)
             .service(api::get_users)
             .service(api::get_user)
             .service(api::update_user)
             .service(api::delete_user)
-            .service(api::update_user_status),
+            .service(api::update_user_status)
+            .service(api::unlock_user)
+            .service(api::get_user_permissions)
+            .service(api::update_user_permissions)
+            .service(api::list_user_api_keys)
+            .service(api::revoke_user_api_key),
     );
 }
 
 /// 配置套餐管理路由
 fn configure_plan_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
-        web::scope("/api/plans")
+        web::scope("/plans")
             .service(api::create_plan)
             .service(api::list_plans)
             .service(api::get_plan)
             .service(api::update_plan)
-            .service(api::delete_plan)
-            .service(api::get_enabled_plans),
+            .service(api::delete_plan),
     );
 }
 
 /// 配置优惠券管理路由
 fn configure_coupon_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
-        web::scope("/api/coupons")
+        web::scope("/coupons")
             .service(api::create_coupon)
             .service(api::list_coupons)
             .service(api::get_coupon)
             .service(api::update_coupon)
-            .service(api::delete_coupon)
-            .service(api::verify_coupon),
+            .service(api::delete_coupon),
+    );
+}
+
+/// 配置节点管理路由
+fn configure_server_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/servers")
+            .service(api::get_servers_health)
+            .service(api::list_servers)
+            .service(api::update_server_routes)
+            .service(api::update_server_parent)
+            .service(api::get_server_rate_schedule)
+            .service(api::update_server_rate_schedule),
+    );
+}
+
+/// 配置节点路由规则管理路由
+fn configure_route_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/server-routes")
+            .service(api::create_route)
+            .service(api::list_routes)
+            .service(api::get_route)
+            .service(api::update_route)
+            .service(api::delete_route),
+    );
+}
+
+/// 配置订阅模板路由
+fn configure_subscribe_template_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/subscribe-templates")
+            .service(api::get_subscribe_template)
+            .service(api::update_subscribe_template)
+            .service(api::reset_subscribe_template),
+    );
+}
+
+/// 配置流量重置路由
+fn configure_traffic_reset_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/traffic-resets")
+            .service(api::list_traffic_resets)
+            .service(api::run_traffic_reset),
+    );
+}
+
+/// 配置按量计费路由
+fn configure_billing_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/billing")
+            .service(api::list_balance_logs)
+            .service(api::run_billing),
+    );
+}
+
+/// 配置节点后端通讯路由
+///
+/// 节点后端通过 token 参数认证，不经过用户认证中间件
+fn configure_agent_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api/agent")
+            .service(api::agent_config)
+            .service(api::agent_users)
+            .service(api::agent_push)
+            .service(api::agent_alive)
+            .service(api::agent_status),
+    );
+}
+
+/// 配置客户端订阅路由
+///
+/// 订阅链接通过 token 参数鉴权，供代理客户端直接拉取
+fn configure_client_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/api/client").service(api::subscribe));
+}
+
+/// 配置公开套餐路由
+///
+/// 无需登录即可查看，已登录用户会附带个人的购买信息
+fn configure_public_plan_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api/plans")
+            .wrap(Auth::optional())
+            .service(api::get_enabled_plans),
+    );
+}
+
+/// 配置用户自助路由
+///
+/// 需要登录，操作对象均为当前用户；也可使用 API 密钥访问，
+/// 但修改密码、管理会话、双重验证和密钥等账户安全相关的接口，
+/// 以及余额支付和重置订阅令牌只接受登录会话
+fn configure_self_service_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/api/user")
+            .wrap(Auth::new())
+            .service(api::verify_coupon)
+            .service(api::reset_subscribe_token)
+            .service(api::list_subscribe_logs)
+            .service(api::create_plan_order)
+            .service(api::create_traffic_addon_order)
+            .service(api::pay_order)
+            .service(api::cancel_order)
+            .service(api::list_orders)
+            .service(api::list_my_balance_logs)
+            .service(api::change_password)
+            .service(api::list_sessions)
+            .service(api::revoke_session)
+            .service(api::get_two_factor_status)
+            .service(api::setup_two_factor)
+            .service(api::enable_two_factor)
+            .service(api::disable_two_factor)
+            .service(api::regenerate_recovery_codes)
+            .service(api::list_api_keys)
+            .service(api::create_api_key)
+            .service(api::revoke_api_key),
     );
 }
 
@@ -78,3 +250,70 @@ async fn serve_openapi_spec() -> actix_web::HttpResponse {
         .content_type("application/json")
         .json(ApiDoc::openapi())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::ErrorCode,
+        models::{
+            api_key::CreateApiKeyRequest,
+            permission::{Permission, PermissionSet},
+        },
+        repositories::{ApiKeyRepository, PermissionRepository, SessionRepository, UserRepository},
+        services::ApiKeyService,
+        test_support,
+    };
+    use actix_web::{test, App};
+    use sqlx::PgPool;
+
+    #[sqlx::test(migrations = false)]
+    async fn test_scoped_api_key_rejected_on_session_only_routes(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let user_id = test_support::insert_user(&pool, "admin@example.com", None).await;
+        sqlx::query("UPDATE purple_user SET is_admin = TRUE WHERE id = $1")
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let api_key_service = ApiKeyService::new(ApiKeyRepository::new(pool.clone()));
+        let key = api_key_service
+            .create(
+                user_id,
+                &PermissionSet::all(),
+                CreateApiKeyRequest {
+                    name: "ci".to_string(),
+                    scopes: vec![Permission::UsersRead],
+                    expired_at: None,
+                },
+            )
+            .await
+            .unwrap()
+            .key;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_support::auth_service(&pool)))
+                .app_data(web::Data::new(api_key_service))
+                .app_data(web::Data::new(UserRepository::new(pool.clone())))
+                .app_data(web::Data::new(SessionRepository::new(pool.clone())))
+                .app_data(web::Data::new(PermissionRepository::new(pool.clone())))
+                .configure(configure_self_service_routes),
+        )
+        .await;
+
+        for uri in [
+            "/api/user/orders/T0001/pay",
+            "/api/user/subscribe/reset-token",
+        ] {
+            let req = test::TestRequest::post()
+                .uri(uri)
+                .insert_header(("Authorization", format!("Bearer {}", key)))
+                .set_json(serde_json::json!({}))
+                .to_request();
+            let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(body["code"], ErrorCode::PermissionDenied as i32, "{}", uri);
+        }
+    }
+}
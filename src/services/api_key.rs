@@ -0,0 +1,273 @@
+use anyhow::Result;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+use crate::{
+    common::{ApiError, ErrorCode},
+    models::{
+        api_key::{ApiKey, ApiKeyResponse, CreateApiKeyRequest, CreateApiKeyResponse},
+        permission::{Permission, PermissionSet},
+    },
+    repositories::ApiKeyRepository,
+};
+
+/// 每个用户最多持有的有效密钥数量
+const MAX_API_KEYS_PER_USER: i64 = 20;
+
+/// 个人 API 密钥
+///
+/// 密钥只保存哈希，授予的权限在每次请求时与所属用户当前的权限取交集
+#[derive(Clone)]
+pub struct ApiKeyService {
+    repo: ApiKeyRepository,
+}
+
+impl ApiKeyService {
+    pub fn new(repo: ApiKeyRepository) -> Self {
+        Self { repo }
+    }
+
+    /// 创建密钥，授予的权限不能超过当前账户拥有的权限
+    pub async fn create(
+        &self,
+        user_id: i32,
+        permissions: &PermissionSet,
+        req: CreateApiKeyRequest,
+    ) -> Result<CreateApiKeyResponse> {
+        if let Some(scope) = req
+            .scopes
+            .iter()
+            .find(|scope| !permissions.contains(**scope))
+        {
+            return Err(ApiError::with_message(
+                ErrorCode::PermissionDenied,
+                format!("不能授予当前账户没有的权限: {}", scope.as_str()),
+            )
+            .into());
+        }
+        if req
+            .expired_at
+            .is_some_and(|expired_at| i64::from(expired_at) <= chrono::Utc::now().timestamp())
+        {
+            return Err(ApiError::with_message(
+                ErrorCode::InvalidParams,
+                "过期时间必须晚于当前时间".to_string(),
+            )
+            .into());
+        }
+        if self.repo.count_by_user(user_id).await? >= MAX_API_KEYS_PER_USER {
+            return Err(ApiError::with_message(
+                ErrorCode::InvalidParams,
+                format!("最多只能创建 {} 个 API 密钥", MAX_API_KEYS_PER_USER),
+            )
+            .into());
+        }
+
+        let mut scopes: Vec<String> = Vec::new();
+        for scope in req.scopes.iter().map(Permission::as_str) {
+            if !scopes.iter().any(|s| s == scope) {
+                scopes.push(scope.to_string());
+            }
+        }
+
+        let prefix = Self::generate_prefix();
+        let secret = Self::generate_secret();
+        let api_key = self
+            .repo
+            .create(
+                user_id,
+                req.name.trim(),
+                &prefix,
+                &Self::hash_secret(&secret),
+                &scopes,
+                req.expired_at,
+            )
+            .await?;
+        tracing::info!("用户 {} 创建了 API 密钥 {}", user_id, api_key.prefix);
+
+        Ok(CreateApiKeyResponse {
+            key: format!("{}{}_{}", ApiKey::TOKEN_PREFIX, prefix, secret),
+            api_key: api_key.into(),
+        })
+    }
+
+    /// 获取用户未撤销的密钥，不包含密钥本身
+    pub async fn list(&self, user_id: i32) -> Result<Vec<ApiKeyResponse>> {
+        Ok(self
+            .repo
+            .find_by_user(user_id)
+            .await?
+            .into_iter()
+            .map(ApiKeyResponse::from)
+            .collect())
+    }
+
+    /// 撤销密钥，撤销后立即失效
+    pub async fn revoke(&self, user_id: i32, id: i32) -> Result<()> {
+        if !self.repo.revoke(user_id, id).await? {
+            return Err(ApiError::with_message(
+                ErrorCode::InvalidParams,
+                "API 密钥不存在或已撤销".to_string(),
+            )
+            .into());
+        }
+        tracing::info!("已撤销用户 {} 的 API 密钥 {}", user_id, id);
+        Ok(())
+    }
+
+    /// 校验完整密钥，密钥有效时返回并记录使用时间
+    pub async fn authenticate(&self, token: &str, ip: Option<&str>) -> Result<Option<ApiKey>> {
+        let Some((prefix, secret)) = token
+            .strip_prefix(ApiKey::TOKEN_PREFIX)
+            .and_then(|token| token.split_once('_'))
+        else {
+            return Ok(None);
+        };
+
+        let api_key = match self.repo.find_by_prefix(prefix).await? {
+            Some(api_key)
+                if Self::secret_matches(&api_key, secret)
+                    && api_key.is_active(chrono::Utc::now().timestamp()) =>
+            {
+                api_key
+            }
+            _ => return Ok(None),
+        };
+
+        if let Err(e) = self.repo.touch(api_key.id, ip).await {
+            tracing::warn!("记录 API 密钥 {} 的使用时间失败: {}", api_key.id, e);
+        }
+        Ok(Some(api_key))
+    }
+
+    /// 密钥前缀，12 位十六进制字符
+    fn generate_prefix() -> String {
+        let mut bytes = [0u8; 6];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn generate_secret() -> String {
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    /// 以常量时间比较密钥哈希
+    fn secret_matches(api_key: &ApiKey, secret: &str) -> bool {
+        bool::from(
+            api_key
+                .secret_hash
+                .as_bytes()
+                .ct_eq(Self::hash_secret(secret).as_bytes()),
+        )
+    }
+
+    fn hash_secret(secret: &str) -> String {
+        format!("{:x}", Sha256::digest(secret.as_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::test_support;
+
+    fn request(scopes: Vec<Permission>, expired_at: Option<i32>) -> CreateApiKeyRequest {
+        CreateApiKeyRequest {
+            name: " ops ".to_string(),
+            scopes,
+            expired_at,
+        }
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_authenticate_and_revoke(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let user_id = test_support::insert_user(&pool, "ops@example.com", None).await;
+        let service = ApiKeyService::new(ApiKeyRepository::new(pool));
+        let permissions: PermissionSet = [Permission::UsersRead].into_iter().collect();
+
+        let created = service
+            .create(
+                user_id,
+                &permissions,
+                request(vec![Permission::UsersRead, Permission::UsersRead], None),
+            )
+            .await
+            .unwrap();
+        assert!(created.key.starts_with(ApiKey::TOKEN_PREFIX));
+
+        let api_key = service
+            .authenticate(&created.key, Some("1.2.3.4"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(api_key.user_id, user_id);
+        assert_eq!(api_key.name, "ops");
+        assert_eq!(api_key.permissions(), vec![Permission::UsersRead]);
+
+        // 前缀正确但密钥错误、格式错误的密钥都不能通过
+        let (prefix, _) = created.key.rsplit_once('_').unwrap();
+        for invalid in [
+            format!("{}_wrong", prefix),
+            "pk_".to_string(),
+            "token".to_string(),
+        ] {
+            assert!(service
+                .authenticate(&invalid, None)
+                .await
+                .unwrap()
+                .is_none());
+        }
+
+        let listed = service.list(user_id).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert!(listed[0].last_used_at.is_some());
+
+        service.revoke(user_id, api_key.id).await.unwrap();
+        assert!(service
+            .authenticate(&created.key, None)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(service.list(user_id).await.unwrap().is_empty());
+        assert!(service.revoke(user_id, api_key.id).await.is_err());
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_create_rejects_invalid_requests(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let user_id = test_support::insert_user(&pool, "user@example.com", None).await;
+        let service = ApiKeyService::new(ApiKeyRepository::new(pool));
+        let permissions = PermissionSet::default();
+
+        // 不能授予账户没有的权限
+        let err = service
+            .create(
+                user_id,
+                &permissions,
+                request(vec![Permission::UsersRead], None),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            test_support::error_code(&err),
+            Some(ErrorCode::PermissionDenied)
+        );
+
+        let expired_at = test_support::now() - 1;
+        let err = service
+            .create(user_id, &permissions, request(vec![], Some(expired_at)))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            test_support::error_code(&err),
+            Some(ErrorCode::InvalidParams)
+        );
+    }
+}
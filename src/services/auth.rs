@@ -1,74 +1,159 @@
+use std::sync::Arc;
+
 use anyhow::Result;
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Argon2,
 };
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::errors::Error as JwtError;
+use md5::Md5;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
 use uuid::Uuid;
 
 use crate::{
+    common::{ApiError, ErrorCode},
+    config::AuthConfig,
     models::{
         auth::{Claims, LoginRequest, RegisterRequest, TokenResponse},
+        email_code::{EmailCodePurpose, ResetPasswordRequest},
+        session::{ClientInfo, Session, SessionResponse},
+        two_factor::{LoginResponse, TwoFactorChallenge, TwoFactorClaims, TwoFactorLoginRequest},
         user::{CreateUser, User},
     },
-    repositories::UserRepository,
+    repositories::{SessionRepository, UserRepository},
+    services::{
+        EmailCodeService, KeyManager, LoginGuard, MemoryLoginAttemptStore, TwoFactorService,
+    },
 };
 
 #[derive(Clone)]
 pub struct AuthService {
     user_repo: UserRepository,
-    jwt_secret: String,
+    session_repo: SessionRepository,
+    keys: KeyManager,
+    guard: LoginGuard,
+    email_codes: EmailCodeService,
+    two_factor: TwoFactorService,
+    config: AuthConfig,
 }
 
 impl AuthService {
-    pub fn new(user_repo: UserRepository, jwt_secret: String) -> Self {
-        Self {
+    pub fn new(
+        user_repo: UserRepository,
+        session_repo: SessionRepository,
+        email_codes: EmailCodeService,
+        two_factor: TwoFactorService,
+        config: AuthConfig,
+    ) -> Result<Self> {
+        Ok(Self {
             user_repo,
-            jwt_secret,
-        }
+            session_repo,
+            keys: KeyManager::from_config(&config)?,
+            guard: LoginGuard::new(
+                Arc::new(MemoryLoginAttemptStore::new()),
+                config.login_guard.clone(),
+            ),
+            email_codes,
+            two_factor,
+            config,
+        })
+    }
+
+    /// 验证访问令牌的签名和有效期
+    pub fn decode_access_token(&self, token: &str) -> Result<Claims, JwtError> {
+        self.keys.decode(token)
     }
 
     pub async fn register(&self, req: RegisterRequest) -> Result<User> {
-        // 检查邮箱是否已存在
-        if let Some(_) = self.user_repo.find_by_email(&req.email).await? {
-            anyhow::bail!("邮箱已存在");
+        // 检查邮箱和用户名是否已存在
+        if self.user_repo.find_by_email(&req.email).await?.is_some() {
+            return Err(ApiError::with_message(
+                ErrorCode::UserAlreadyExists,
+                "邮箱已存在".to_string(),
+            )
+            .into());
+        }
+        if self
+            .user_repo
+            .find_by_username(&req.username)
+            .await?
+            .is_some()
+        {
+            return Err(ApiError::with_message(
+                ErrorCode::UserAlreadyExists,
+                "用户名已被使用".to_string(),
+            )
+            .into());
         }
 
-        // 生成密码哈希
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let password_hash = argon2
-            .hash_password(req.password.as_bytes(), &salt)
-            .map_err(|e| anyhow::anyhow!("密码加密失败: {}", e))?
-            .to_string();
+        // 校验邮箱验证码
+        if self.config.register_email_verify {
+            let code = req.email_code.as_deref().ok_or_else(|| {
+                ApiError::with_message(
+                    ErrorCode::InvalidVerificationCode,
+                    "请输入邮箱验证码".to_string(),
+                )
+            })?;
+            self.email_codes
+                .verify(&req.email, EmailCodePurpose::Register, code)
+                .await?;
+        }
 
         // 创建用户
         let user = CreateUser {
             email: req.email,
-            password: password_hash,
+            username: Some(req.username),
+            password: Self::hash_password(&req.password)?,
             invite_user_id: None, // TODO: 处理邀请码
             uuid: Uuid::new_v4().to_string(),
-            token: Uuid::new_v4().to_string(),
+            token: Uuid::new_v4().simple().to_string(),
         };
 
         let user = self.user_repo.create(user).await?;
         Ok(user)
     }
 
-    pub async fn login(&self, req: LoginRequest) -> Result<TokenResponse> {
-        // 查找用户（支持用户名或邮箱登录）
-        let user = match self.user_repo.find_by_email(&req.username).await? {
-            Some(user) => user,
-            None => anyhow::bail!("用户名或密码错误"),
+    pub async fn login(&self, req: LoginRequest, client: ClientInfo) -> Result<LoginResponse> {
+        // 查找用户（支持用户名或邮箱登录），用户名不能包含 @
+        let login = req.username.trim();
+        let user = if login.contains('@') {
+            self.user_repo.find_by_email(login).await?
+        } else {
+            self.user_repo.find_by_username(login).await?
         };
 
+        // 检查是否因多次失败被限制
+        let account_key = LoginGuard::account_key(user.as_ref().map(|user| user.id), &req.username);
+        let ip = client.ip.as_deref();
+        self.guard.check(&account_key, ip).await?;
+
         // 验证密码
-        let parsed_hash = PasswordHash::new(&user.password)
-            .map_err(|e| anyhow::anyhow!("密码哈希解析失败: {}", e))?;
-        if !Argon2::default()
-            .verify_password(req.password.as_bytes(), &parsed_hash)
-            .is_ok()
-        {
-            anyhow::bail!("用户名或密码错误");
+        let user = match user {
+            Some(user) if Self::verify_password(&user, &req.password)? => user,
+            _ => {
+                self.guard.record_failure(&account_key, ip).await?;
+                anyhow::bail!("用户名或密码错误");
+            }
+        };
+        self.guard.record_success(&account_key).await?;
+
+        // 旧系统导入的密码哈希升级为 argon2，失败不影响本次登录
+        if Self::needs_rehash(&user) {
+            let upgraded = match Self::hash_password(&req.password) {
+                Ok(password_hash) => {
+                    self.user_repo
+                        .update_password(user.id, &password_hash)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            match upgraded {
+                Ok(()) => tracing::info!("用户 {} 的密码哈希已升级为 argon2", user.id),
+                Err(e) => tracing::warn!("升级用户 {} 的密码哈希失败: {}", user.id, e),
+            }
         }
 
         // 检查账号状态
@@ -76,14 +161,541 @@ impl AuthService {
             anyhow::bail!("账号已被禁用");
         }
 
-        // 生成JWT
-        let claims = Claims::new(user.id);
-        let token = claims.encode()?;
+        // 启用双重验证时先返回挑战，验证码通过后再创建会话
+        if self.two_factor.is_enabled(user.id).await? {
+            let claims = TwoFactorClaims::new(user.id, self.config.two_factor.challenge_ttl);
+            return Ok(LoginResponse::TwoFactor(TwoFactorChallenge {
+                two_factor_required: true,
+                challenge_token: self.keys.encode(&claims)?,
+                expires_in: self.config.two_factor.challenge_ttl,
+            }));
+        }
+
+        Ok(LoginResponse::Token(
+            self.create_session(user.id, &client).await?,
+        ))
+    }
+
+    /// 双重验证登录第二步，使用挑战令牌和验证码或恢复码换取令牌
+    ///
+    /// 验证码错误计入账户的登录失败次数
+    pub async fn login_two_factor(
+        &self,
+        req: TwoFactorLoginRequest,
+        client: ClientInfo,
+    ) -> Result<TokenResponse> {
+        let claims = self
+            .keys
+            .decode::<TwoFactorClaims>(&req.challenge_token)
+            .ok()
+            .filter(|claims| claims.typ == TwoFactorClaims::TYPE)
+            .ok_or_else(|| {
+                ApiError::with_message(
+                    ErrorCode::InvalidToken,
+                    "登录验证已过期，请重新登录".to_string(),
+                )
+            })?;
+
+        let user = self
+            .user_repo
+            .find_by_id(claims.sub)
+            .await?
+            .ok_or_else(|| ApiError::new(ErrorCode::UserNotFound))?;
+        if user.banned.unwrap_or(false) {
+            return Err(ApiError::with_message(
+                ErrorCode::UserDisabled,
+                "账户已被禁用".to_string(),
+            )
+            .into());
+        }
+
+        let account_key = LoginGuard::account_key(Some(user.id), "");
+        let ip = client.ip.as_deref();
+        self.guard.check(&account_key, ip).await?;
+        if !self.two_factor.verify(&user, &req.code).await? {
+            self.guard.record_failure(&account_key, ip).await?;
+            return Err(ApiError::new(ErrorCode::InvalidVerificationCode).into());
+        }
+        self.guard.record_success(&account_key).await?;
+
+        self.create_session(user.id, &client).await
+    }
+
+    /// 使用刷新令牌换取新的访问令牌，刷新令牌同时轮换
+    ///
+    /// 已轮换掉的旧刷新令牌再次出现时视为泄露，撤销整个会话
+    pub async fn refresh(&self, refresh_token: &str, client: ClientInfo) -> Result<TokenResponse> {
+        let token_hash = Self::hash_refresh_token(refresh_token);
+        let session = self
+            .session_repo
+            .find_by_token_hash(&token_hash)
+            .await?
+            .ok_or_else(|| {
+                ApiError::with_message(ErrorCode::InvalidToken, "刷新令牌无效".to_string())
+            })?;
+
+        if session.refresh_token_hash != token_hash {
+            if session.revoked_at.is_none() {
+                tracing::warn!(
+                    "Refresh token reuse detected for session {} of user {}, revoking",
+                    session.id,
+                    session.user_id
+                );
+                self.session_repo
+                    .revoke(session.user_id, session.id)
+                    .await?;
+            }
+            return Err(ApiError::with_message(
+                ErrorCode::InvalidToken,
+                "刷新令牌已失效".to_string(),
+            )
+            .into());
+        }
+
+        if !session.is_active(chrono::Utc::now().timestamp()) {
+            return Err(ApiError::with_message(
+                ErrorCode::TokenExpired,
+                "会话已过期，请重新登录".to_string(),
+            )
+            .into());
+        }
+
+        let user = self
+            .user_repo
+            .find_by_id(session.user_id)
+            .await?
+            .ok_or_else(|| ApiError::new(ErrorCode::UserNotFound))?;
+        if user.banned.unwrap_or(false) {
+            return Err(ApiError::with_message(
+                ErrorCode::UserDisabled,
+                "账户已被禁用".to_string(),
+            )
+            .into());
+        }
+
+        let new_token = Self::generate_refresh_token();
+        let expired_at = self.refresh_expired_at();
+        let rotated = self
+            .session_repo
+            .rotate(
+                session.id,
+                &token_hash,
+                &Self::hash_refresh_token(&new_token),
+                client.ip.as_deref(),
+                expired_at,
+            )
+            .await?;
+        if !rotated {
+            // 并发刷新时另一个请求已完成轮换
+            return Err(ApiError::with_message(
+                ErrorCode::InvalidToken,
+                "刷新令牌已失效".to_string(),
+            )
+            .into());
+        }
+
+        let session = Session {
+            expired_at,
+            ..session
+        };
+        self.issue_tokens(&session, new_token)
+    }
+
+    /// 获取用户的有效会话
+    pub async fn sessions(&self, user_id: i32, current_id: i32) -> Result<Vec<SessionResponse>> {
+        let sessions = self.session_repo.find_active_by_user(user_id).await?;
+        Ok(sessions
+            .into_iter()
+            .map(|session| SessionResponse::from_session(session, current_id))
+            .collect())
+    }
+
+    /// 撤销用户的单个会话，会话不存在或已撤销时返回错误
+    pub async fn revoke_session(&self, user_id: i32, session_id: i32) -> Result<()> {
+        if !self.session_repo.revoke(user_id, session_id).await? {
+            return Err(ApiError::with_message(
+                ErrorCode::InvalidParams,
+                "会话不存在或已失效".to_string(),
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// 撤销用户的全部会话，用于修改密码、封禁账户等场景
+    pub async fn revoke_all_sessions(&self, user_id: i32) -> Result<u64> {
+        let revoked = self.session_repo.revoke_all(user_id).await?;
+        if revoked > 0 {
+            tracing::info!("已撤销用户 {} 的 {} 个会话", user_id, revoked);
+        }
+        Ok(revoked)
+    }
+
+    /// 解除账户的登录锁定
+    pub async fn unlock(&self, user_id: i32) -> Result<()> {
+        self.guard.unlock(user_id).await
+    }
+
+    /// 发送注册验证码，邮箱已注册时返回错误
+    pub async fn send_register_code(&self, email: &str, ip: Option<&str>) -> Result<()> {
+        if self.user_repo.find_by_email(email).await?.is_some() {
+            return Err(ApiError::with_message(
+                ErrorCode::UserAlreadyExists,
+                "邮箱已注册".to_string(),
+            )
+            .into());
+        }
+
+        self.email_codes
+            .send(email, EmailCodePurpose::Register, ip)
+            .await
+    }
+
+    /// 发送重置密码验证码
+    ///
+    /// 无论邮箱是否注册都先按邮箱和IP限制发送频率并计入次数，
+    /// 邮箱未注册或账户已禁用时不发送邮件，邮件发送失败只记录日志，
+    /// 各种情况的响应一致，避免探测邮箱是否注册
+    pub async fn forgot_password(&self, email: &str, ip: Option<&str>) -> Result<()> {
+        let purpose = EmailCodePurpose::ResetPassword;
+        let code = self.email_codes.issue(email, purpose, ip).await?;
+
+        match self.user_repo.find_by_email(email).await? {
+            Some(user) if !user.banned.unwrap_or(false) => {
+                // 发送失败已记录日志
+                let _ = self.email_codes.deliver(email, purpose, &code).await;
+            }
+            _ => tracing::info!("忽略未注册或已禁用邮箱的重置密码请求: {}", email),
+        }
+
+        Ok(())
+    }
+
+    /// 通过邮箱验证码重置密码，成功后撤销全部会话并解除登录锁定
+    pub async fn reset_password(&self, req: ResetPasswordRequest) -> Result<()> {
+        let user = self
+            .user_repo
+            .find_by_email(&req.email)
+            .await?
+            .ok_or_else(|| ApiError::new(ErrorCode::InvalidVerificationCode))?;
+
+        self.email_codes
+            .verify(&req.email, EmailCodePurpose::ResetPassword, &req.email_code)
+            .await?;
+
+        self.user_repo
+            .update_password(user.id, &Self::hash_password(&req.password)?)
+            .await?;
+        self.revoke_all_sessions(user.id).await?;
+        self.guard.unlock(user.id).await?;
+
+        Ok(())
+    }
+
+    /// 修改当前用户的密码，成功后撤销全部会话
+    pub async fn change_password(
+        &self,
+        user: &User,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<()> {
+        if !Self::verify_password(user, old_password)? {
+            return Err(ApiError::with_message(
+                ErrorCode::InvalidCredentials,
+                "原密码错误".to_string(),
+            )
+            .into());
+        }
+
+        self.user_repo
+            .update_password(user.id, &Self::hash_password(new_password)?)
+            .await?;
+        self.revoke_all_sessions(user.id).await?;
+
+        Ok(())
+    }
+
+    /// 关闭当前用户的双重验证，需要同时提供密码和验证码或恢复码
+    pub async fn disable_two_factor(&self, user: &User, password: &str, code: &str) -> Result<()> {
+        if !Self::verify_password(user, password)? {
+            return Err(ApiError::with_message(
+                ErrorCode::InvalidCredentials,
+                "密码错误".to_string(),
+            )
+            .into());
+        }
+        if !self.two_factor.verify(user, code).await? {
+            return Err(ApiError::new(ErrorCode::InvalidVerificationCode).into());
+        }
+
+        self.two_factor.disable(user.id).await
+    }
+
+    /// 生成密码哈希
+    pub fn hash_password(password: &str) -> Result<String> {
+        let salt = SaltString::generate(&mut OsRng);
+        let password_hash = Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|e| anyhow::anyhow!("密码加密失败: {}", e))?
+            .to_string();
+        Ok(password_hash)
+    }
+
+    /// 验证密码，兼容从 V2Board 导入的旧密码哈希
+    ///
+    /// `password_algo` 为 md5、sha256、md5salt 或 bcrypt 时按对应算法验证，
+    /// 为空时按哈希前缀区分 argon2 和 V2Board 默认的 bcrypt
+    fn verify_password(user: &User, password: &str) -> Result<bool> {
+        let hash = user.password.as_str();
+        match Self::legacy_algo(user) {
+            Some("md5") => Ok(Self::digest_matches(hash, Md5::digest(password))),
+            Some("sha256") => Ok(Self::digest_matches(hash, Sha256::digest(password))),
+            Some("md5salt") => {
+                let salt = user.password_salt.as_deref().unwrap_or_default().trim_end();
+                Ok(Self::digest_matches(
+                    hash,
+                    Md5::digest(format!("{}{}", password, salt)),
+                ))
+            }
+            Some("bcrypt") => Self::verify_bcrypt(hash, password),
+            Some(algo) => anyhow::bail!("不支持的密码加密方式: {}", algo),
+            None if hash.starts_with("$2") => Self::verify_bcrypt(hash, password),
+            None => {
+                let parsed_hash = PasswordHash::new(hash)
+                    .map_err(|e| anyhow::anyhow!("密码哈希解析失败: {}", e))?;
+                Ok(Argon2::default()
+                    .verify_password(password.as_bytes(), &parsed_hash)
+                    .is_ok())
+            }
+        }
+    }
+
+    /// 以常量时间比较旧系统保存的十六进制摘要，忽略大小写
+    fn digest_matches(hash: &str, digest: impl std::fmt::LowerHex) -> bool {
+        let expected = format!("{:x}", digest);
+        bool::from(
+            hash.to_ascii_lowercase()
+                .as_bytes()
+                .ct_eq(expected.as_bytes()),
+        )
+    }
+
+    fn verify_bcrypt(hash: &str, password: &str) -> Result<bool> {
+        bcrypt::verify(password, hash).map_err(|e| anyhow::anyhow!("密码哈希解析失败: {}", e))
+    }
+
+    /// 旧系统的密码加密方式，`password_algo` 为定长字段，需去掉填充的空格
+    fn legacy_algo(user: &User) -> Option<&str> {
+        user.password_algo
+            .as_deref()
+            .map(str::trim)
+            .filter(|algo| !algo.is_empty())
+    }
+
+    /// 密码哈希是否需要升级为 argon2
+    fn needs_rehash(user: &User) -> bool {
+        Self::legacy_algo(user).is_some() || !user.password.starts_with("$argon2")
+    }
+
+    /// 创建会话并签发令牌
+    async fn create_session(&self, user_id: i32, client: &ClientInfo) -> Result<TokenResponse> {
+        let refresh_token = Self::generate_refresh_token();
+        let session = self
+            .session_repo
+            .create(
+                user_id,
+                &Self::hash_refresh_token(&refresh_token),
+                client,
+                self.refresh_expired_at(),
+            )
+            .await?;
+
+        self.issue_tokens(&session, refresh_token)
+    }
+
+    fn issue_tokens(&self, session: &Session, refresh_token: String) -> Result<TokenResponse> {
+        let now = chrono::Utc::now().timestamp();
+        let claims = Claims::new(session.user_id, session.id, self.config.access_token_ttl);
+        let token = self.keys.encode(&claims)?;
 
         Ok(TokenResponse {
             access_token: token,
             token_type: "Bearer".to_string(),
-            expires_in: claims.exp - chrono::Utc::now().timestamp(),
+            expires_in: claims.exp - now,
+            refresh_token,
+            refresh_expires_in: i64::from(session.expired_at) - now,
         })
     }
+
+    fn refresh_expired_at(&self) -> i32 {
+        (chrono::Utc::now().timestamp() + self.config.refresh_token_ttl) as i32
+    }
+
+    fn generate_refresh_token() -> String {
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    fn hash_refresh_token(token: &str) -> String {
+        format!("{:x}", Sha256::digest(token.as_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::future::BoxFuture;
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{
+        repositories::{EmailCodeRepository, TwoFactorRepository},
+        services::{EmailCodeService, MailSender, TwoFactorService},
+        test_support,
+    };
+
+    /// 插入一个使用指定密码哈希的测试用户
+    async fn user_with_password(
+        pool: &PgPool,
+        password: &str,
+        algo: Option<&str>,
+        salt: Option<&str>,
+    ) -> User {
+        let user_id = test_support::insert_user(pool, "legacy@example.com", None).await;
+        sqlx::query(
+            "UPDATE purple_user SET password = $2, password_algo = $3, password_salt = $4 WHERE id = $1",
+        )
+        .bind(user_id)
+        .bind(password)
+        .bind(algo)
+        .bind(salt)
+        .execute(pool)
+        .await
+        .unwrap();
+
+        UserRepository::new(pool.clone())
+            .find_by_id(user_id)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_verify_legacy_password(pool: PgPool) {
+        test_support::migrate(&pool).await;
+
+        let cases = [
+            // md5("secret")
+            ("5EBE2294ECD0E0F08EAB7690D2A6EE69", Some("md5"), None),
+            // sha256("secret")
+            (
+                "2bb80d537b1da3e38bd30361aa855686bde0eacd7162fef6a25fe97bf527a25b",
+                Some("sha256"),
+                None,
+            ),
+            // md5("secret" + "salt")，定长字段读出时带填充空格
+            (
+                "99cd2e5a95d555ee7be3b038a4a84625",
+                Some("md5salt"),
+                Some("salt"),
+            ),
+        ];
+        for (hash, algo, salt) in cases {
+            let user = user_with_password(&pool, hash, algo, salt).await;
+            assert!(
+                AuthService::verify_password(&user, "secret").unwrap(),
+                "{algo:?}"
+            );
+            assert!(
+                !AuthService::verify_password(&user, "Secret").unwrap(),
+                "{algo:?}"
+            );
+            assert!(AuthService::needs_rehash(&user));
+            sqlx::query("DELETE FROM purple_user")
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_verify_bcrypt_and_argon2_password(pool: PgPool) {
+        test_support::migrate(&pool).await;
+
+        // V2Board 默认使用 bcrypt 且不设置 password_algo
+        let hash = bcrypt::hash("secret", 4).unwrap();
+        let user = user_with_password(&pool, &hash, None, None).await;
+        assert!(AuthService::verify_password(&user, "secret").unwrap());
+        assert!(!AuthService::verify_password(&user, "other").unwrap());
+        assert!(AuthService::needs_rehash(&user));
+
+        // 升级为 argon2 后清空旧字段，不再需要升级
+        UserRepository::new(pool.clone())
+            .update_password(user.id, &AuthService::hash_password("secret").unwrap())
+            .await
+            .unwrap();
+        let user = UserRepository::new(pool.clone())
+            .find_by_id(user.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(user.password_algo, None);
+        assert_eq!(user.password_salt, None);
+        assert!(AuthService::verify_password(&user, "secret").unwrap());
+        assert!(!AuthService::verify_password(&user, "other").unwrap());
+        assert!(!AuthService::needs_rehash(&user));
+    }
+
+    /// 总是发送失败的邮件发送器
+    struct FailingMailSender;
+
+    impl MailSender for FailingMailSender {
+        fn send<'a>(
+            &'a self,
+            _to: &'a str,
+            _subject: &'a str,
+            _body: &'a str,
+        ) -> BoxFuture<'a, Result<()>> {
+            Box::pin(async { Err(anyhow::anyhow!("SMTP 不可用")) })
+        }
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_forgot_password_does_not_reveal_registration(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        test_support::insert_user(&pool, "user@example.com", None).await;
+
+        let mut config = test_support::auth_config();
+        config.email_code.resend_interval = 60;
+        let service = AuthService::new(
+            UserRepository::new(pool.clone()),
+            SessionRepository::new(pool.clone()),
+            EmailCodeService::new(
+                EmailCodeRepository::new(pool.clone()),
+                Arc::new(FailingMailSender),
+                config.email_code.clone(),
+                "Purple".to_string(),
+            ),
+            TwoFactorService::new(
+                TwoFactorRepository::new(pool.clone()),
+                config.two_factor.clone(),
+            ),
+            config,
+        )
+        .unwrap();
+
+        let mut sequences = Vec::new();
+        for email in ["user@example.com", "nobody@example.com"] {
+            let mut codes = Vec::new();
+            for _ in 0..2 {
+                let result = service.forgot_password(email, Some("203.0.113.7")).await;
+                codes.push(result.err().and_then(|e| test_support::error_code(&e)));
+            }
+            sequences.push(codes);
+        }
+
+        // 邮件发送失败不影响响应，两个邮箱都在第二次请求时被限制发送频率
+        assert_eq!(sequences[0], vec![None, Some(ErrorCode::TooManyRequests)]);
+        assert_eq!(sequences[0], sequences[1]);
+    }
 }
@@ -0,0 +1,393 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use std::collections::HashMap;
+
+use crate::{
+    config::BillingConfig,
+    models::{
+        balance::{
+            BalanceLog, BalanceLogType, BillingSummary, ChargeOutcome, MeteredBilling,
+            MeteredCharge,
+        },
+        plan::Plan,
+        user::User,
+    },
+    repositories::{BalanceRepository, PlanRepository, UserRepository},
+    utils::GB,
+};
+
+#[derive(Clone)]
+pub struct BillingService {
+    user_repo: UserRepository,
+    plan_repo: PlanRepository,
+    balance_repo: BalanceRepository,
+    config: BillingConfig,
+}
+
+impl BillingService {
+    pub fn new(
+        user_repo: UserRepository,
+        plan_repo: PlanRepository,
+        balance_repo: BalanceRepository,
+        config: BillingConfig,
+    ) -> Self {
+        Self {
+            user_repo,
+            plan_repo,
+            balance_repo,
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &BillingConfig {
+        &self.config
+    }
+
+    /// 配置时区下的当前日期
+    pub fn today(&self) -> NaiveDate {
+        chrono::Utc::now()
+            .with_timezone(&self.config.timezone)
+            .date_naive()
+    }
+
+    /// 对按量计费套餐的用户扣费
+    ///
+    /// 未停用的用户每天按 `daily_unit_price` 扣费一次，以日期去重；
+    /// 已用流量每满 1GB 按 `transfer_unit_price` 扣费，不足 1GB 的部分留待下次。
+    /// 余额耗尽的用户会被停用（节点不再下发，但仍可登录充值），充值后在下次执行时自动恢复；
+    /// 停用与封禁相互独立，恢复时不会解除管理员的封禁
+    pub async fn run(&self, date: NaiveDate) -> Result<BillingSummary> {
+        let mut summary = BillingSummary {
+            date: date.to_string(),
+            resumed: self.balance_repo.resume_suspended().await?,
+            ..Default::default()
+        };
+
+        let users = self.user_repo.find_with_plan().await?;
+
+        let mut plan_ids: Vec<i32> = users.iter().filter_map(|user| user.plan_id).collect();
+        plan_ids.sort_unstable();
+        plan_ids.dedup();
+        let plans: HashMap<i32, Plan> = self
+            .plan_repo
+            .find_by_ids(&plan_ids)
+            .await?
+            .into_iter()
+            .filter(|plan| plan.is_metered())
+            .map(|plan| (plan.id, plan))
+            .collect();
+        let billing: HashMap<i32, MeteredBilling> = self
+            .balance_repo
+            .find_all_billing()
+            .await?
+            .into_iter()
+            .map(|billing| (billing.user_id, billing))
+            .collect();
+
+        for user in &users {
+            let plan = match user.plan_id.and_then(|plan_id| plans.get(&plan_id)) {
+                Some(plan) => plan,
+                None => continue,
+            };
+            let billing = billing.get(&user.id);
+            if user.banned.unwrap_or(false) || billing.is_some_and(|billing| billing.suspended) {
+                continue;
+            }
+            summary.checked += 1;
+
+            let billed_transfer = billing.map_or(0, |billing| billing.billed_transfer);
+            match self.charge_user(user, plan, date, billed_transfer).await {
+                Ok(outcome) => {
+                    if outcome.daily {
+                        summary.daily_charged += 1;
+                    }
+                    if outcome.transfer {
+                        summary.transfer_charged += 1;
+                    }
+                    if outcome.suspended {
+                        summary.suspended += 1;
+                    }
+                    summary.amount += outcome.amount;
+                }
+                Err(e) => {
+                    summary.failed += 1;
+                    tracing::error!("用户 {} 按量扣费失败: {:#}", user.id, e);
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// 分页获取余额变动记录
+    pub async fn logs(
+        &self,
+        user_id: Option<i32>,
+        page: u64,
+        page_size: u64,
+    ) -> Result<(Vec<BalanceLog>, i64)> {
+        self.balance_repo
+            .find_logs(user_id, page as i32, page_size as i32)
+            .await
+    }
+
+    async fn charge_user(
+        &self,
+        user: &User,
+        plan: &Plan,
+        date: NaiveDate,
+        billed_transfer: i64,
+    ) -> Result<UserCharge> {
+        let mut result = UserCharge::default();
+
+        let daily_price = plan.daily_unit_price.unwrap_or_default();
+        if let Some(outcome) = self
+            .balance_repo
+            .charge(&MeteredCharge {
+                user_id: user.id,
+                r#type: BalanceLogType::DailyCharge,
+                amount: daily_price,
+                period: Some(date.to_string()),
+                transfer: None,
+                expected_billed_transfer: None,
+                billed_transfer: None,
+                remark: Some(format!("{} 按日扣费", date)),
+            })
+            .await?
+        {
+            result.record(&outcome);
+            result.daily = true;
+            if outcome.suspended {
+                return Ok(result);
+            }
+        }
+
+        let transfer_price = plan.transfer_unit_price.unwrap_or_default();
+        if transfer_price <= 0 {
+            return Ok(result);
+        }
+
+        // 已用流量小于已计费流量说明流量被手动清零过，从零开始计费
+        let used = user.u + user.d;
+        let base = if used >= billed_transfer {
+            billed_transfer
+        } else {
+            0
+        };
+        let gb = (used - base) / GB;
+        if gb <= 0 {
+            return Ok(result);
+        }
+
+        let amount = (gb * transfer_price as i64).min(i32::MAX as i64) as i32;
+        if let Some(outcome) = self
+            .balance_repo
+            .charge(&MeteredCharge {
+                user_id: user.id,
+                r#type: BalanceLogType::TransferCharge,
+                amount,
+                period: None,
+                transfer: Some(gb * GB),
+                expected_billed_transfer: Some(billed_transfer),
+                billed_transfer: Some(base + gb * GB),
+                remark: Some(format!("{} GB 流量扣费", gb)),
+            })
+            .await?
+        {
+            result.record(&outcome);
+            result.transfer = true;
+        }
+
+        Ok(result)
+    }
+}
+
+/// 单个用户本次扣费的情况
+#[derive(Debug, Default)]
+struct UserCharge {
+    daily: bool,
+    transfer: bool,
+    suspended: bool,
+    amount: i64,
+}
+
+impl UserCharge {
+    fn record(&mut self, outcome: &ChargeOutcome) {
+        self.suspended |= outcome.suspended;
+        self.amount += outcome.amount as i64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use chrono::FixedOffset;
+    use sqlx::PgPool;
+
+    fn service(pool: &PgPool) -> BillingService {
+        BillingService::new(
+            UserRepository::new(pool.clone()),
+            PlanRepository::new(pool.clone()),
+            BalanceRepository::new(pool.clone()),
+            BillingConfig {
+                check_interval: 3600,
+                timezone: FixedOffset::east_opt(8 * 3600).unwrap(),
+            },
+        )
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap()
+    }
+
+    /// 插入持有按量计费套餐（每日 1 元、每 GB 0.5 元）的用户，返回用户ID
+    async fn metered_user(pool: &PgPool, balance: i32, used: i64) -> i32 {
+        let plan_id = test_support::insert_plan(pool, 1).await;
+        sqlx::query(
+            "UPDATE purple_plan SET daily_unit_price = 100, transfer_unit_price = 50 WHERE id = $1",
+        )
+        .bind(plan_id)
+        .execute(pool)
+        .await
+        .unwrap();
+        let user_id = test_support::insert_user(pool, "metered@example.com", Some(1)).await;
+        sqlx::query("UPDATE purple_user SET plan_id = $1, balance = $2, d = $3 WHERE id = $4")
+            .bind(plan_id)
+            .bind(balance)
+            .bind(used)
+            .bind(user_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        user_id
+    }
+
+    async fn balance(pool: &PgPool, user_id: i32) -> i32 {
+        sqlx::query_scalar("SELECT balance FROM purple_user WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn billing(pool: &PgPool, user_id: i32) -> MeteredBilling {
+        sqlx::query_as("SELECT * FROM purple_metered_billing WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn node_user_ids(pool: &PgPool) -> Vec<i32> {
+        UserRepository::new(pool.clone())
+            .find_available_by_groups(&[1])
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|user| user.id)
+            .collect()
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_run_charges_daily_and_transfer(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let user_id = metered_user(&pool, 1000, 5 * GB / 2).await;
+        let service = service(&pool);
+
+        let summary = service.run(date(1)).await.unwrap();
+        assert_eq!(summary.checked, 1);
+        assert_eq!((summary.daily_charged, summary.transfer_charged), (1, 1));
+        // 每日 100 + 2GB * 50，不足 1GB 的部分留待下次
+        assert_eq!(summary.amount, 200);
+        assert_eq!(balance(&pool, user_id).await, 800);
+        assert_eq!(billing(&pool, user_id).await.billed_transfer, 2 * GB);
+
+        // 同一天重复执行不会重复扣费
+        let summary = service.run(date(1)).await.unwrap();
+        assert_eq!((summary.daily_charged, summary.transfer_charged), (0, 0));
+        assert_eq!(balance(&pool, user_id).await, 800);
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_charge_skips_stale_billed_transfer(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let user_id = metered_user(&pool, 1000, 3 * GB).await;
+        let service = service(&pool);
+        service.run(date(1)).await.unwrap();
+        assert_eq!(balance(&pool, user_id).await, 750);
+
+        // 另一个任务在本次计费前读取了旧的已计费流量，不能再按同一段流量扣费
+        let user = UserRepository::new(pool.clone())
+            .find_by_id(user_id)
+            .await
+            .unwrap()
+            .unwrap();
+        let plan = PlanRepository::new(pool.clone())
+            .find_by_id(user.plan_id.unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        let charge = service.charge_user(&user, &plan, date(1), 0).await.unwrap();
+        assert!(!charge.daily && !charge.transfer);
+        assert_eq!(balance(&pool, user_id).await, 750);
+        assert_eq!(billing(&pool, user_id).await.billed_transfer, 3 * GB);
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_suspend_and_resume(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let user_id = metered_user(&pool, 50, 0).await;
+        let service = service(&pool);
+
+        // 余额不足时扣除剩余余额并停用，不影响封禁状态
+        let summary = service.run(date(1)).await.unwrap();
+        assert_eq!((summary.amount, summary.suspended), (50, 1));
+        assert_eq!(balance(&pool, user_id).await, 0);
+        assert!(billing(&pool, user_id).await.suspended);
+        assert!(node_user_ids(&pool).await.is_empty());
+
+        // 停用期间不再扣费
+        let summary = service.run(date(2)).await.unwrap();
+        assert_eq!((summary.checked, summary.daily_charged), (0, 0));
+
+        // 充值后恢复并继续扣费
+        sqlx::query("UPDATE purple_user SET balance = 500 WHERE id = $1")
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let summary = service.run(date(3)).await.unwrap();
+        assert_eq!((summary.resumed, summary.daily_charged), (1, 1));
+        assert_eq!(balance(&pool, user_id).await, 400);
+        assert!(!billing(&pool, user_id).await.suspended);
+        assert_eq!(node_user_ids(&pool).await, vec![user_id]);
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_resume_keeps_admin_ban(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let user_id = metered_user(&pool, 50, 0).await;
+        let service = service(&pool);
+        service.run(date(1)).await.unwrap();
+
+        // 停用期间被管理员封禁，充值后只解除停用
+        sqlx::query("UPDATE purple_user SET banned = true, balance = 500 WHERE id = $1")
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let summary = service.run(date(2)).await.unwrap();
+        assert_eq!((summary.resumed, summary.checked), (1, 0));
+        assert!(!billing(&pool, user_id).await.suspended);
+
+        let banned: bool = sqlx::query_scalar("SELECT banned FROM purple_user WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert!(banned);
+        assert_eq!(balance(&pool, user_id).await, 500);
+        assert!(node_user_ids(&pool).await.is_empty());
+    }
+}
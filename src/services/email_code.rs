@@ -0,0 +1,299 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    common::{ApiError, ErrorCode},
+    config::EmailCodeConfig,
+    models::email_code::EmailCodePurpose,
+    repositories::EmailCodeRepository,
+    services::MailSender,
+};
+
+/// 邮箱验证码
+///
+/// 验证码只保存哈希，按邮箱和IP限制发送频率，
+/// 同一用途只有最新发送的验证码有效，验证失败次数过多后失效
+#[derive(Clone)]
+pub struct EmailCodeService {
+    repo: EmailCodeRepository,
+    mailer: Arc<dyn MailSender>,
+    config: EmailCodeConfig,
+    app_name: String,
+}
+
+impl EmailCodeService {
+    pub fn new(
+        repo: EmailCodeRepository,
+        mailer: Arc<dyn MailSender>,
+        config: EmailCodeConfig,
+        app_name: String,
+    ) -> Self {
+        Self {
+            repo,
+            mailer,
+            config,
+            app_name,
+        }
+    }
+
+    /// 生成并发送验证码
+    pub async fn send(
+        &self,
+        email: &str,
+        purpose: EmailCodePurpose,
+        ip: Option<&str>,
+    ) -> Result<()> {
+        let code = self.issue(email, purpose, ip).await?;
+        self.deliver(email, purpose, &code).await
+    }
+
+    /// 按邮箱和IP检查发送频率后生成并保存验证码，返回明文验证码，不发送邮件
+    ///
+    /// 保存的记录计入之后的发送频率限制
+    pub async fn issue(
+        &self,
+        email: &str,
+        purpose: EmailCodePurpose,
+        ip: Option<&str>,
+    ) -> Result<String> {
+        let email = Self::normalize_email(email);
+        let now = chrono::Utc::now().timestamp();
+        self.check_rate_limit(&email, ip, now).await?;
+
+        let code = format!("{:06}", rand::thread_rng().gen_range(0..1_000_000));
+        self.repo
+            .create(
+                &email,
+                purpose,
+                &Self::hash_code(&code),
+                ip,
+                (now + self.config.ttl) as i32,
+            )
+            .await?;
+
+        Ok(code)
+    }
+
+    /// 将 [`issue`](Self::issue) 生成的验证码发送到邮箱，发送失败时记录日志并返回 `NetworkError`
+    pub async fn deliver(&self, email: &str, purpose: EmailCodePurpose, code: &str) -> Result<()> {
+        let email = Self::normalize_email(email);
+        let subject = format!("{} {}", self.app_name, purpose.subject());
+        let body = format!(
+            "您的{}为：{}\n\n验证码 {} 分钟内有效，请勿泄露给他人。如非本人操作，请忽略此邮件。",
+            purpose.subject(),
+            code,
+            (self.config.ttl / 60).max(1)
+        );
+        if let Err(e) = self.mailer.send(&email, &subject, &body).await {
+            tracing::error!("发送验证码邮件到 {} 失败: {:#}", email, e);
+            return Err(ApiError::with_message(
+                ErrorCode::NetworkError,
+                "验证码发送失败，请稍后重试".to_string(),
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// 验证并使用验证码，验证码错误、过期或已使用时返回 `InvalidVerificationCode`
+    pub async fn verify(&self, email: &str, purpose: EmailCodePurpose, code: &str) -> Result<()> {
+        let email = Self::normalize_email(email);
+        let record = match self.repo.find_latest_valid(&email, purpose).await? {
+            Some(record) if record.attempts < self.config.max_attempts => record,
+            _ => return Err(Self::invalid_code().into()),
+        };
+
+        if record.code_hash != Self::hash_code(code.trim()) {
+            self.repo.increment_attempts(record.id).await?;
+            return Err(Self::invalid_code().into());
+        }
+        if !self
+            .repo
+            .mark_used(record.id, self.config.max_attempts)
+            .await?
+        {
+            return Err(Self::invalid_code().into());
+        }
+
+        Ok(())
+    }
+
+    async fn check_rate_limit(&self, email: &str, ip: Option<&str>, now: i64) -> Result<()> {
+        if let Some(last) = self.repo.find_last_by_email(email).await? {
+            let wait = i64::from(last.created_at) + self.config.resend_interval - now;
+            if wait > 0 {
+                return Err(Self::too_many_requests(format!(
+                    "验证码发送过于频繁，请在 {} 秒后重试",
+                    wait
+                ))
+                .into());
+            }
+        }
+
+        let since = (now - 3600) as i32;
+        if self.repo.count_by_email_since(email, since).await? >= self.config.email_hourly_limit {
+            return Err(Self::too_many_requests(
+                "该邮箱验证码发送次数过多，请稍后再试".to_string(),
+            )
+            .into());
+        }
+        if let Some(ip) = ip {
+            if self.repo.count_by_ip_since(ip, since).await? >= self.config.ip_hourly_limit {
+                return Err(
+                    Self::too_many_requests("验证码请求过于频繁，请稍后再试".to_string()).into(),
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn normalize_email(email: &str) -> String {
+        email.trim().to_lowercase()
+    }
+
+    fn hash_code(code: &str) -> String {
+        format!("{:x}", Sha256::digest(code.as_bytes()))
+    }
+
+    fn invalid_code() -> ApiError {
+        ApiError::new(ErrorCode::InvalidVerificationCode)
+    }
+
+    fn too_many_requests(message: String) -> ApiError {
+        ApiError::with_message(ErrorCode::TooManyRequests, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use futures_util::future::BoxFuture;
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::test_support;
+
+    /// 记录发出的邮件正文
+    #[derive(Default)]
+    struct CaptureMailSender {
+        bodies: Mutex<Vec<String>>,
+    }
+
+    impl CaptureMailSender {
+        /// 最近一封邮件中的验证码
+        fn last_code(&self) -> String {
+            let bodies = self.bodies.lock().unwrap();
+            let body = bodies.last().expect("没有发出邮件");
+            let start = body.find('：').unwrap() + '：'.len_utf8();
+            body[start..start + 6].to_string()
+        }
+    }
+
+    impl MailSender for CaptureMailSender {
+        fn send<'a>(
+            &'a self,
+            _to: &'a str,
+            _subject: &'a str,
+            body: &'a str,
+        ) -> BoxFuture<'a, Result<()>> {
+            self.bodies.lock().unwrap().push(body.to_string());
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    fn service(pool: PgPool, mailer: Arc<CaptureMailSender>) -> EmailCodeService {
+        EmailCodeService::new(
+            EmailCodeRepository::new(pool),
+            mailer,
+            EmailCodeConfig {
+                ttl: 600,
+                resend_interval: 0,
+                email_hourly_limit: 3,
+                ip_hourly_limit: 10,
+                max_attempts: 2,
+            },
+            "Purple".to_string(),
+        )
+    }
+
+    fn assert_invalid(result: Result<()>) {
+        let err = result.unwrap_err();
+        assert_eq!(
+            test_support::error_code(&err),
+            Some(ErrorCode::InvalidVerificationCode)
+        );
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_code_is_single_use_and_bound_to_purpose(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let mailer = Arc::new(CaptureMailSender::default());
+        let service = service(pool, mailer.clone());
+
+        service
+            .send("User@Example.com", EmailCodePurpose::Register, None)
+            .await
+            .unwrap();
+        let code = mailer.last_code();
+
+        assert_invalid(
+            service
+                .verify("user@example.com", EmailCodePurpose::ResetPassword, &code)
+                .await,
+        );
+        service
+            .verify(" user@example.com", EmailCodePurpose::Register, &code)
+            .await
+            .unwrap();
+        assert_invalid(
+            service
+                .verify("user@example.com", EmailCodePurpose::Register, &code)
+                .await,
+        );
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_resend_and_failed_attempts_invalidate_code(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let mailer = Arc::new(CaptureMailSender::default());
+        let service = service(pool, mailer.clone());
+        let purpose = EmailCodePurpose::ResetPassword;
+
+        service.send("a@example.com", purpose, None).await.unwrap();
+        let first = mailer.last_code();
+        service.send("a@example.com", purpose, None).await.unwrap();
+        let second = mailer.last_code();
+
+        // 重新发送后只有最新的验证码有效
+        if first != second {
+            assert_invalid(service.verify("a@example.com", purpose, &first).await);
+        }
+
+        // 失败次数达到上限后正确的验证码也不再接受
+        let wrong = if second == "000000" {
+            "000001"
+        } else {
+            "000000"
+        };
+        assert_invalid(service.verify("a@example.com", purpose, wrong).await);
+        assert_invalid(service.verify("a@example.com", purpose, wrong).await);
+        assert_invalid(service.verify("a@example.com", purpose, &second).await);
+
+        // 每小时发送次数限制
+        service.send("a@example.com", purpose, None).await.unwrap();
+        let err = service
+            .send("a@example.com", purpose, None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            test_support::error_code(&err),
+            Some(ErrorCode::TooManyRequests)
+        );
+    }
+}
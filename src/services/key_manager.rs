@@ -0,0 +1,266 @@
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::{Context, Result};
+use jsonwebtoken::{
+    decode, decode_header, encode,
+    errors::{Error as JwtError, ErrorKind},
+    Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::config::{AuthConfig, JwtKeyConfig};
+
+/// `JWT_SECRET` 对应的 kid，不带 kid 的旧令牌也使用该密钥验证
+const DEFAULT_KID: &str = "default";
+
+struct JwtKey {
+    algorithm: Algorithm,
+    encoding: Option<EncodingKey>,
+    decoding: DecodingKey,
+}
+
+/// JWT 密钥管理
+///
+/// 按 kid 保存多把密钥，新令牌使用当前签名密钥并在头部写入 kid，
+/// 验证时按令牌的 kid 选择密钥，轮换后旧密钥签发的令牌在过期前仍然有效
+#[derive(Clone)]
+pub struct KeyManager {
+    keys: Arc<HashMap<String, JwtKey>>,
+    active_kid: String,
+}
+
+impl KeyManager {
+    /// 从配置加载密钥，没有可用于签名的密钥时返回错误
+    pub fn from_config(config: &AuthConfig) -> Result<Self> {
+        Self::new(
+            config.jwt_secret.as_deref(),
+            &config.jwt_keys,
+            config.jwt_active_kid.as_deref(),
+        )
+    }
+
+    fn new(
+        jwt_secret: Option<&str>,
+        jwt_keys: &[JwtKeyConfig],
+        active_kid: Option<&str>,
+    ) -> Result<Self> {
+        let mut keys = HashMap::new();
+        let mut last_signing_kid = None;
+
+        for key_config in jwt_keys {
+            let key = Self::load_key(key_config)
+                .with_context(|| format!("加载 JWT 密钥 {} 失败", key_config.kid))?;
+            if key.encoding.is_some() {
+                last_signing_kid = Some(key_config.kid.clone());
+            }
+            if keys.insert(key_config.kid.clone(), key).is_some() {
+                anyhow::bail!("JWT 密钥 kid 重复: {}", key_config.kid);
+            }
+        }
+
+        if let Some(secret) = jwt_secret {
+            if keys.contains_key(DEFAULT_KID) {
+                anyhow::bail!("kid `{}` 保留给 JWT_SECRET 使用", DEFAULT_KID);
+            }
+            keys.insert(
+                DEFAULT_KID.to_string(),
+                JwtKey {
+                    algorithm: Algorithm::HS256,
+                    encoding: Some(EncodingKey::from_secret(secret.as_bytes())),
+                    decoding: DecodingKey::from_secret(secret.as_bytes()),
+                },
+            );
+        }
+
+        let active_kid = active_kid
+            .map(str::to_string)
+            .or(last_signing_kid)
+            .or_else(|| jwt_secret.map(|_| DEFAULT_KID.to_string()))
+            .ok_or_else(|| anyhow::anyhow!("未配置 JWT 签名密钥，请设置 JWT_SECRET 或 JWT_KEYS"))?;
+        match keys.get(&active_kid) {
+            Some(key) if key.encoding.is_some() => {}
+            Some(_) => anyhow::bail!("JWT 签名密钥 {} 缺少私钥", active_kid),
+            None => anyhow::bail!("JWT 签名密钥 {} 不存在", active_kid),
+        }
+
+        tracing::info!(
+            "已加载 {} 个 JWT 密钥，当前签名密钥: {}",
+            keys.len(),
+            active_kid
+        );
+
+        Ok(Self {
+            keys: Arc::new(keys),
+            active_kid,
+        })
+    }
+
+    /// 使用当前签名密钥签发令牌
+    pub fn encode<T: Serialize>(&self, claims: &T) -> Result<String, JwtError> {
+        let key = self
+            .keys
+            .get(&self.active_kid)
+            .ok_or_else(|| JwtError::from(ErrorKind::InvalidKeyFormat))?;
+        let encoding = key
+            .encoding
+            .as_ref()
+            .ok_or_else(|| JwtError::from(ErrorKind::InvalidKeyFormat))?;
+
+        let mut header = Header::new(key.algorithm);
+        header.kid = Some(self.active_kid.clone());
+        encode(&header, claims, encoding)
+    }
+
+    /// 按令牌头部的 kid 选择密钥验证令牌，同时校验过期时间
+    pub fn decode<T: DeserializeOwned>(&self, token: &str) -> Result<T, JwtError> {
+        let header = decode_header(token)?;
+        let kid = header.kid.as_deref().unwrap_or(DEFAULT_KID);
+        let key = self
+            .keys
+            .get(kid)
+            .ok_or_else(|| JwtError::from(ErrorKind::InvalidToken))?;
+
+        let validation = Validation::new(key.algorithm);
+        let token_data = decode::<T>(token, &key.decoding, &validation)?;
+        Ok(token_data.claims)
+    }
+
+    fn load_key(config: &JwtKeyConfig) -> Result<JwtKey> {
+        let public_pem = std::fs::read(&config.public_key_path)
+            .with_context(|| format!("读取公钥 {} 失败", config.public_key_path))?;
+        let private_pem = match &config.private_key_path {
+            Some(path) => {
+                Some(std::fs::read(path).with_context(|| format!("读取私钥 {} 失败", path))?)
+            }
+            None => None,
+        };
+
+        let (decoding, encoding) = match config.algorithm {
+            Algorithm::RS256
+            | Algorithm::RS384
+            | Algorithm::RS512
+            | Algorithm::PS256
+            | Algorithm::PS384
+            | Algorithm::PS512 => (
+                DecodingKey::from_rsa_pem(&public_pem)?,
+                private_pem
+                    .map(|pem| EncodingKey::from_rsa_pem(&pem))
+                    .transpose()?,
+            ),
+            Algorithm::ES256 | Algorithm::ES384 => (
+                DecodingKey::from_ec_pem(&public_pem)?,
+                private_pem
+                    .map(|pem| EncodingKey::from_ec_pem(&pem))
+                    .transpose()?,
+            ),
+            Algorithm::EdDSA => (
+                DecodingKey::from_ed_pem(&public_pem)?,
+                private_pem
+                    .map(|pem| EncodingKey::from_ed_pem(&pem))
+                    .transpose()?,
+            ),
+            algorithm => anyhow::bail!(
+                "JWT_KEYS 仅支持非对称算法，HS256 请使用 JWT_SECRET: {:?}",
+                algorithm
+            ),
+        };
+
+        Ok(JwtKey {
+            algorithm: config.algorithm,
+            encoding,
+            decoding,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::auth::Claims;
+
+    fn key(kid: &str, name: &str, with_private: bool) -> JwtKeyConfig {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src/services/testdata");
+        JwtKeyConfig {
+            kid: kid.to_string(),
+            algorithm: Algorithm::EdDSA,
+            public_key_path: format!("{dir}/{name}.pub.pem"),
+            private_key_path: with_private.then(|| format!("{dir}/{name}.pem")),
+        }
+    }
+
+    #[test]
+    fn test_rotation_keeps_old_tokens_valid() {
+        let old = KeyManager::new(None, &[key("old", "jwt-old", true)], None).unwrap();
+        let old_token = old.encode(&Claims::new(1, 1, 3600)).unwrap();
+        assert_eq!(
+            decode_header(&old_token).unwrap().kid.as_deref(),
+            Some("old")
+        );
+
+        // 轮换后旧密钥只保留公钥，新令牌使用新密钥签发
+        let rotated = KeyManager::new(
+            Some("secret"),
+            &[key("old", "jwt-old", false), key("new", "jwt-new", true)],
+            None,
+        )
+        .unwrap();
+        let new_token = rotated.encode(&Claims::new(2, 2, 3600)).unwrap();
+        assert_eq!(
+            decode_header(&new_token).unwrap().kid.as_deref(),
+            Some("new")
+        );
+
+        assert_eq!(rotated.decode::<Claims>(&old_token).unwrap().sub, 1);
+        assert_eq!(rotated.decode::<Claims>(&new_token).unwrap().sub, 2);
+        // 旧实例不认识新 kid
+        assert!(old.decode::<Claims>(&new_token).is_err());
+    }
+
+    #[test]
+    fn test_secret_validates_tokens_without_kid() {
+        let keys = KeyManager::new(Some("secret"), &[], None).unwrap();
+        let legacy = encode(
+            &Header::default(),
+            &Claims::new(1, 1, 3600),
+            &EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap();
+        assert_eq!(keys.decode::<Claims>(&legacy).unwrap().sub, 1);
+
+        let forged = encode(
+            &Header::default(),
+            &Claims::new(1, 1, 3600),
+            &EncodingKey::from_secret(b"other"),
+        )
+        .unwrap();
+        assert!(keys.decode::<Claims>(&forged).is_err());
+
+        let expired = keys.encode(&Claims::new(1, 1, -3600)).unwrap();
+        assert!(matches!(
+            keys.decode::<Claims>(&expired).unwrap_err().kind(),
+            ErrorKind::ExpiredSignature
+        ));
+    }
+
+    #[test]
+    fn test_rejects_invalid_key_sets() {
+        // 没有任何签名密钥
+        assert!(KeyManager::new(None, &[], None).is_err());
+        assert!(KeyManager::new(None, &[key("old", "jwt-old", false)], None).is_err());
+        // 指定的签名密钥不存在或缺少私钥
+        assert!(KeyManager::new(Some("secret"), &[], Some("new")).is_err());
+        assert!(
+            KeyManager::new(Some("secret"), &[key("old", "jwt-old", false)], Some("old")).is_err()
+        );
+        // kid 重复或占用 JWT_SECRET 的 kid
+        assert!(KeyManager::new(
+            None,
+            &[key("new", "jwt-old", true), key("new", "jwt-new", true)],
+            None
+        )
+        .is_err());
+        assert!(
+            KeyManager::new(Some("secret"), &[key(DEFAULT_KID, "jwt-new", true)], None).is_err()
+        );
+    }
+}
@@ -0,0 +1,345 @@
+use std::{
+    collections::HashMap,
+    future::ready,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+
+use crate::{
+    common::{ApiError, ErrorCode},
+    config::LoginGuardConfig,
+};
+
+/// 登录失败记录
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoginAttempts {
+    /// 统计窗口内连续失败的次数
+    pub failures: u32,
+    pub last_failed_at: i64,
+}
+
+/// 登录失败记录存储
+///
+/// 默认使用进程内存保存，多实例部署时可替换为共享存储
+pub trait LoginAttemptStore: Send + Sync {
+    /// 获取登录失败记录，最后一次失败早于 `since` 的记录视为不存在
+    fn get<'a>(&'a self, key: &'a str, since: i64) -> BoxFuture<'a, Result<Option<LoginAttempts>>>;
+
+    /// 记录一次登录失败并返回更新后的记录，最后一次失败早于 `since` 时重新计数
+    fn record_failure<'a>(
+        &'a self,
+        key: &'a str,
+        now: i64,
+        since: i64,
+    ) -> BoxFuture<'a, Result<LoginAttempts>>;
+
+    /// 清除登录失败记录
+    fn clear<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<()>>;
+}
+
+/// 进程内存中的登录失败记录
+#[derive(Default)]
+pub struct MemoryLoginAttemptStore {
+    entries: Mutex<HashMap<String, LoginAttempts>>,
+}
+
+impl MemoryLoginAttemptStore {
+    /// 记录数超过该值时清理过期记录
+    const PRUNE_THRESHOLD: usize = 10_000;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl LoginAttemptStore for MemoryLoginAttemptStore {
+    fn get<'a>(&'a self, key: &'a str, since: i64) -> BoxFuture<'a, Result<Option<LoginAttempts>>> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let attempts = entries
+            .get(key)
+            .filter(|attempts| attempts.last_failed_at >= since)
+            .copied();
+        Box::pin(ready(Ok(attempts)))
+    }
+
+    fn record_failure<'a>(
+        &'a self,
+        key: &'a str,
+        now: i64,
+        since: i64,
+    ) -> BoxFuture<'a, Result<LoginAttempts>> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= Self::PRUNE_THRESHOLD {
+            entries.retain(|_, attempts| attempts.last_failed_at >= since);
+        }
+
+        let attempts = entries.entry(key.to_string()).or_insert(LoginAttempts {
+            failures: 0,
+            last_failed_at: now,
+        });
+        if attempts.last_failed_at < since {
+            attempts.failures = 0;
+        }
+        attempts.failures += 1;
+        attempts.last_failed_at = now;
+
+        Box::pin(ready(Ok(*attempts)))
+    }
+
+    fn clear<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<()>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(key);
+        Box::pin(ready(Ok(())))
+    }
+}
+
+/// 登录防暴力破解
+///
+/// 按账户和IP分别统计连续失败次数。账户失败后按指数退避限制重试间隔，
+/// 达到上限后临时锁定；IP 不退避，避免影响共用出口的用户，达到上限后临时拒绝该IP的登录请求
+#[derive(Clone)]
+pub struct LoginGuard {
+    store: Arc<dyn LoginAttemptStore>,
+    config: LoginGuardConfig,
+}
+
+impl LoginGuard {
+    pub fn new(store: Arc<dyn LoginAttemptStore>, config: LoginGuardConfig) -> Self {
+        Self { store, config }
+    }
+
+    /// 账户的统计键，账户不存在时按登录名统计，避免通过锁定行为判断账户是否存在
+    pub fn account_key(user_id: Option<i32>, login: &str) -> String {
+        match user_id {
+            Some(user_id) => format!("account:{}", user_id),
+            None => format!("login:{}", login.trim().to_lowercase()),
+        }
+    }
+
+    fn ip_key(ip: &str) -> String {
+        format!("ip:{}", ip)
+    }
+
+    /// 登录前检查账户和IP是否允许尝试
+    pub async fn check(&self, account_key: &str, ip: Option<&str>) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let since = now - self.config.failure_window;
+
+        if let Some(attempts) = self.store.get(account_key, since).await? {
+            if let Some(retry_at) = self.retry_at(attempts) {
+                if retry_at > now {
+                    return Err(if attempts.failures >= self.config.max_failures {
+                        Self::account_locked(retry_at - now)
+                    } else {
+                        Self::too_many_requests(retry_at - now)
+                    }
+                    .into());
+                }
+            }
+        }
+
+        if let Some(ip) = ip {
+            if let Some(attempts) = self.store.get(&Self::ip_key(ip), since).await? {
+                let retry_at = attempts.last_failed_at + self.config.lockout_duration;
+                if attempts.failures >= self.config.ip_max_failures && retry_at > now {
+                    return Err(Self::too_many_requests(retry_at - now).into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 记录一次登录失败，达到上限导致账户锁定时返回 `AccountLocked`
+    pub async fn record_failure(&self, account_key: &str, ip: Option<&str>) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let since = now - self.config.failure_window;
+
+        if let Some(ip) = ip {
+            self.store
+                .record_failure(&Self::ip_key(ip), now, since)
+                .await?;
+        }
+
+        let attempts = self.store.record_failure(account_key, now, since).await?;
+        if attempts.failures >= self.config.max_failures {
+            tracing::warn!(
+                "Login locked for {} after {} failed attempts",
+                account_key,
+                attempts.failures
+            );
+            return Err(Self::account_locked(self.config.lockout_duration).into());
+        }
+
+        Ok(())
+    }
+
+    /// 登录成功后清除账户的失败记录，IP 的记录保留到统计窗口结束
+    pub async fn record_success(&self, account_key: &str) -> Result<()> {
+        self.store.clear(account_key).await
+    }
+
+    /// 解除账户锁定
+    pub async fn unlock(&self, user_id: i32) -> Result<()> {
+        self.store
+            .clear(&Self::account_key(Some(user_id), ""))
+            .await
+    }
+
+    /// 账户下次允许尝试的时间
+    ///
+    /// 达到上限后锁定 `lockout_duration` 秒，此前从第二次失败开始按
+    /// `backoff_base * 2^(失败次数-2)` 秒退避，最长不超过锁定时长
+    fn retry_at(&self, attempts: LoginAttempts) -> Option<i64> {
+        if attempts.failures >= self.config.max_failures {
+            return Some(attempts.last_failed_at + self.config.lockout_duration);
+        }
+        if attempts.failures < 2 || self.config.backoff_base <= 0 {
+            return None;
+        }
+
+        let exponent = (attempts.failures - 2).min(30);
+        let delay = self
+            .config
+            .backoff_base
+            .saturating_mul(1 << exponent)
+            .min(self.config.lockout_duration);
+        Some(attempts.last_failed_at + delay)
+    }
+
+    fn account_locked(seconds: i64) -> ApiError {
+        ApiError::with_message(
+            ErrorCode::AccountLocked,
+            format!(
+                "登录失败次数过多，账户已临时锁定，请在 {} 秒后重试",
+                seconds
+            ),
+        )
+    }
+
+    fn too_many_requests(seconds: i64) -> ApiError {
+        ApiError::with_message(
+            ErrorCode::TooManyRequests,
+            format!("登录尝试过于频繁，请在 {} 秒后重试", seconds),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn guard(backoff_base: i64) -> LoginGuard {
+        LoginGuard::new(
+            Arc::new(MemoryLoginAttemptStore::new()),
+            LoginGuardConfig {
+                max_failures: 3,
+                ip_max_failures: 5,
+                lockout_duration: 900,
+                backoff_base,
+                failure_window: 3600,
+            },
+        )
+    }
+
+    fn attempts(failures: u32) -> LoginAttempts {
+        LoginAttempts {
+            failures,
+            last_failed_at: 1000,
+        }
+    }
+
+    #[test]
+    fn test_retry_at_backoff() {
+        let guard = guard(10);
+        assert_eq!(guard.retry_at(attempts(1)), None);
+        assert_eq!(guard.retry_at(attempts(2)), Some(1010));
+        // 达到上限后锁定完整的锁定时长
+        assert_eq!(guard.retry_at(attempts(3)), Some(1900));
+
+        let guard = LoginGuard {
+            config: LoginGuardConfig {
+                max_failures: 100,
+                ..guard.config
+            },
+            ..guard
+        };
+        assert_eq!(guard.retry_at(attempts(4)), Some(1040));
+        // 退避时长不超过锁定时长，且次数很大时不溢出
+        assert_eq!(guard.retry_at(attempts(10)), Some(1900));
+        assert_eq!(guard.retry_at(attempts(99)), Some(1900));
+    }
+
+    #[tokio::test]
+    async fn test_lockout_and_unlock() {
+        let guard = guard(0);
+        let key = LoginGuard::account_key(Some(1), "user@example.com");
+
+        for _ in 0..2 {
+            guard.check(&key, None).await.unwrap();
+            guard.record_failure(&key, None).await.unwrap();
+        }
+        let err = guard.record_failure(&key, None).await.unwrap_err();
+        assert_eq!(
+            test_support::error_code(&err),
+            Some(ErrorCode::AccountLocked)
+        );
+        let err = guard.check(&key, None).await.unwrap_err();
+        assert_eq!(
+            test_support::error_code(&err),
+            Some(ErrorCode::AccountLocked)
+        );
+
+        // 其他账户不受影响
+        let other = LoginGuard::account_key(Some(2), "");
+        guard.check(&other, None).await.unwrap();
+
+        guard.unlock(1).await.unwrap();
+        guard.check(&key, None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ip_limit_spans_accounts() {
+        let guard = guard(0);
+        let ip = Some("1.2.3.4");
+
+        for login in ["a", "b", "c", "d", "e"] {
+            let key = LoginGuard::account_key(None, login);
+            guard.check(&key, ip).await.unwrap();
+            guard.record_failure(&key, ip).await.unwrap();
+        }
+
+        let key = LoginGuard::account_key(None, "f");
+        let err = guard.check(&key, ip).await.unwrap_err();
+        assert_eq!(
+            test_support::error_code(&err),
+            Some(ErrorCode::TooManyRequests)
+        );
+        guard.check(&key, Some("5.6.7.8")).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_store_resets_outside_window() {
+        let store = MemoryLoginAttemptStore::new();
+
+        assert_eq!(store.record_failure("k", 100, 0).await.unwrap().failures, 1);
+        assert_eq!(store.record_failure("k", 200, 0).await.unwrap().failures, 2);
+        assert!(store.get("k", 201).await.unwrap().is_none());
+        // 上次失败早于统计窗口时重新计数
+        assert_eq!(
+            store.record_failure("k", 5000, 1000).await.unwrap(),
+            LoginAttempts {
+                failures: 1,
+                last_failed_at: 5000
+            }
+        );
+
+        store.clear("k").await.unwrap();
+        assert!(store.get("k", 0).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use futures_util::future::BoxFuture;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+
+use crate::config::MailConfig;
+
+/// 邮件发送
+pub trait MailSender: Send + Sync {
+    /// 发送纯文本邮件
+    fn send<'a>(
+        &'a self,
+        to: &'a str,
+        subject: &'a str,
+        body: &'a str,
+    ) -> BoxFuture<'a, Result<()>>;
+}
+
+/// 按配置创建邮件发送器，未配置 SMTP 服务器时只写日志
+pub fn mail_sender_from_config(config: &MailConfig) -> Result<Arc<dyn MailSender>> {
+    match &config.host {
+        Some(_) => Ok(Arc::new(SmtpMailSender::from_config(config)?)),
+        None => {
+            tracing::warn!("未配置 MAIL_HOST，邮件内容只写入日志");
+            Ok(Arc::new(LogMailSender))
+        }
+    }
+}
+
+/// 通过 SMTP 发送邮件
+pub struct SmtpMailSender {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailSender {
+    pub fn from_config(config: &MailConfig) -> Result<Self> {
+        let host = config
+            .host
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("未配置 MAIL_HOST"))?;
+
+        let builder = match config.encryption.as_str() {
+            "ssl" => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+            "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+            encryption => anyhow::bail!("MAIL_ENCRYPTION 仅支持 ssl、tls 或 none: {}", encryption),
+        };
+        let builder = match (&config.username, &config.password) {
+            (Some(username), Some(password)) => {
+                builder.credentials(Credentials::new(username.clone(), password.clone()))
+            }
+            _ => builder,
+        };
+
+        Ok(Self {
+            transport: builder.port(config.port).build(),
+            from: config
+                .from_address
+                .parse()
+                .with_context(|| format!("MAIL_FROM_ADDRESS 格式无效: {}", config.from_address))?,
+        })
+    }
+}
+
+impl MailSender for SmtpMailSender {
+    fn send<'a>(
+        &'a self,
+        to: &'a str,
+        subject: &'a str,
+        body: &'a str,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let message = Message::builder()
+                .from(self.from.clone())
+                .to(to.parse()?)
+                .subject(subject)
+                .header(ContentType::TEXT_PLAIN)
+                .body(body.to_string())?;
+            self.transport.send(message).await?;
+            Ok(())
+        })
+    }
+}
+
+/// 只将邮件写入日志，用于开发环境
+pub struct LogMailSender;
+
+impl MailSender for LogMailSender {
+    fn send<'a>(
+        &'a self,
+        to: &'a str,
+        subject: &'a str,
+        body: &'a str,
+    ) -> BoxFuture<'a, Result<()>> {
+        tracing::info!(
+            "邮件未发送（未配置 SMTP）: to={}, subject={}\n{}",
+            to,
+            subject,
+            body
+        );
+        Box::pin(std::future::ready(Ok(())))
+    }
+}
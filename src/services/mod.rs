@@ -1,5 +1,27 @@
 // 服务实现将在这里添加
 
+mod api_key;
 mod auth;
+mod billing;
+mod email_code;
+mod key_manager;
+mod login_guard;
+mod mailer;
+mod order;
+mod server;
+mod subscription;
+mod traffic_reset;
+mod two_factor;
 
+pub use api_key::ApiKeyService;
 pub use auth::AuthService;
+pub use billing::BillingService;
+pub use email_code::EmailCodeService;
+pub use key_manager::KeyManager;
+pub use login_guard::{LoginGuard, MemoryLoginAttemptStore};
+pub use mailer::{mail_sender_from_config, MailSender};
+pub use order::OrderService;
+pub use server::ServerService;
+pub use subscription::{SubscriptionService, SubscriptionUserInfo};
+pub use traffic_reset::TrafficResetService;
+pub use two_factor::TwoFactorService;
@@ -0,0 +1,713 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{
+    common::{ApiError, ErrorCode},
+    config::OrderConfig,
+    models::{
+        order::{BalancePayment, NewOrder, Order, OrderStatus, OrderType},
+        plan::{Plan, PlanAvailability, PlanPeriod},
+        user::User,
+    },
+    repositories::{OrderRepository, PlanRepository},
+    utils::GB,
+};
+
+#[derive(Clone)]
+pub struct OrderService {
+    order_repo: OrderRepository,
+    plan_repo: PlanRepository,
+    config: OrderConfig,
+}
+
+impl OrderService {
+    pub fn new(
+        order_repo: OrderRepository,
+        plan_repo: PlanRepository,
+        config: OrderConfig,
+    ) -> Self {
+        Self {
+            order_repo,
+            plan_repo,
+            config,
+        }
+    }
+
+    /// 创建套餐订单
+    ///
+    /// 已持有该套餐时为续费，持有其他套餐时为升级，否则为新购；
+    /// 续费和重置流量不占用新的名额，其余情况在套餐名额已满时拒绝下单。
+    /// 隐藏的套餐只有当前订阅该套餐的用户可以购买，不允许续费的套餐不能续费
+    pub async fn checkout(&self, user: &User, plan_id: i32, period: PlanPeriod) -> Result<Order> {
+        let plan = self
+            .plan_repo
+            .find_by_id(plan_id)
+            .await?
+            .ok_or_else(|| ApiError::new(ErrorCode::PlanNotFound))?;
+
+        let owns_plan = user.plan_id == Some(plan.id);
+        if !plan.show && !owns_plan {
+            return Err(ApiError::with_message(
+                ErrorCode::PlanUnavailable,
+                "该套餐暂不可购买".to_string(),
+            )
+            .into());
+        }
+        if owns_plan && !plan.renew && period.is_subscription() {
+            return Err(ApiError::with_message(
+                ErrorCode::PlanUnavailable,
+                "该套餐不支持续费，请更换其他套餐".to_string(),
+            )
+            .into());
+        }
+        let total_amount = plan.price(period).ok_or_else(|| {
+            ApiError::with_message(
+                ErrorCode::PlanUnavailable,
+                format!("套餐不支持该购买周期: {}", period.as_str()),
+            )
+        })?;
+
+        let holds_plan = user.plan_id == Some(plan.id) && !Self::is_expired(user);
+        let r#type = match (holds_plan, period) {
+            (true, PlanPeriod::Reset) => OrderType::ResetTraffic,
+            (false, PlanPeriod::Reset) => {
+                return Err(ApiError::with_message(
+                    ErrorCode::PlanUnavailable,
+                    "只能重置当前订阅套餐的流量".to_string(),
+                )
+                .into())
+            }
+            (true, _) => OrderType::Renew,
+            (false, _) if user.plan_id.is_some() && !Self::is_expired(user) => OrderType::Upgrade,
+            (false, _) => OrderType::New,
+        };
+
+        let order = NewOrder {
+            user_id: user.id,
+            plan_id: plan.id,
+            r#type,
+            period: period.as_str().to_string(),
+            trade_no: Uuid::new_v4().simple().to_string(),
+            total_amount,
+            transfer_amount: None,
+        };
+
+        match plan.capacity_limit {
+            Some(limit) if !holds_plan => self
+                .order_repo
+                .create_within_capacity(&order, limit, self.reserved_since())
+                .await?
+                .ok_or_else(|| ApiError::new(ErrorCode::PlanQuotaExceeded).into()),
+            _ => self.order_repo.create(&order).await,
+        }
+    }
+
+    /// 套餐对指定用户的购买信息，规则与下单时的校验一致
+    pub fn availability(
+        user: &User,
+        plan: &Plan,
+        remaining_seats: Option<i32>,
+    ) -> PlanAvailability {
+        let owned = user.plan_id == Some(plan.id);
+        let active = !Self::is_expired(user);
+        let renewable = owned && plan.renew && plan.has_price();
+        // 续费不占用新的名额
+        let has_seat = (owned && active) || remaining_seats.is_none_or(|seats| seats > 0);
+        let purchasable = if owned {
+            renewable && has_seat
+        } else {
+            plan.show && plan.has_price() && has_seat
+        };
+
+        PlanAvailability {
+            owned,
+            purchasable,
+            renewable,
+            upgrade: !owned && active && user.plan_id.is_some(),
+        }
+    }
+
+    /// 各套餐的剩余可售名额，未限制名额的套餐不包含在结果中
+    pub async fn remaining_seats(&self, plans: &[Plan]) -> Result<HashMap<i32, i32>> {
+        let plan_ids: Vec<i32> = plans
+            .iter()
+            .filter(|plan| plan.capacity_limit.is_some())
+            .map(|plan| plan.id)
+            .collect();
+        if plan_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let holders = self
+            .plan_repo
+            .count_holders(&plan_ids, self.reserved_since())
+            .await?;
+
+        Ok(plans
+            .iter()
+            .filter_map(|plan| {
+                let holders = holders.get(&plan.id).copied().unwrap_or(0);
+                plan.remaining_seats(holders).map(|seats| (plan.id, seats))
+            })
+            .collect())
+    }
+
+    /// 创建流量包订单，按当前套餐的 `transfer_unit_price` 以 GB 计价
+    pub async fn create_traffic_addon(&self, user: &User, transfer_gb: i32) -> Result<Order> {
+        let plan = self.addon_plan(user).await?;
+        let unit_price = plan.transfer_unit_price.unwrap_or_default();
+        let total_amount = unit_price.checked_mul(transfer_gb).ok_or_else(|| {
+            ApiError::with_message(ErrorCode::InvalidParams, "购买的流量过多".to_string())
+        })?;
+
+        let order = self
+            .order_repo
+            .create(&NewOrder {
+                user_id: user.id,
+                plan_id: plan.id,
+                r#type: OrderType::TrafficAddon,
+                period: "transfer".to_string(),
+                trade_no: Uuid::new_v4().simple().to_string(),
+                total_amount,
+                transfer_amount: Some(transfer_gb as i64),
+            })
+            .await?;
+
+        Ok(order)
+    }
+
+    /// 使用余额支付订单，目前仅支持流量包订单
+    ///
+    /// 流量包增加的流量在下次流量重置、套餐到期或重新开通套餐时失效，
+    /// 不重置流量的套餐上购买的流量包在套餐到期前一直有效
+    pub async fn pay_with_balance(&self, user: &User, trade_no: &str) -> Result<Order> {
+        let order = self.find_pending(user.id, trade_no).await?;
+
+        let result = match order.r#type {
+            t if t == OrderType::TrafficAddon.as_i32() => {
+                // 下单后更换了套餐或套餐已过期时不再开通
+                let plan = self.addon_plan(user).await?;
+                if plan.id != order.plan_id {
+                    return Err(ApiError::with_message(
+                        ErrorCode::PlanUnavailable,
+                        "流量包仅适用于下单时的套餐".to_string(),
+                    )
+                    .into());
+                }
+
+                let transfer_bytes = order.transfer_amount.unwrap_or_default() * GB;
+                self.order_repo
+                    .pay_traffic_addon_with_balance(&order, transfer_bytes)
+                    .await?
+            }
+            _ => {
+                return Err(ApiError::with_message(
+                    ErrorCode::PaymentFailed,
+                    "该订单暂不支持余额支付".to_string(),
+                )
+                .into())
+            }
+        };
+
+        match result {
+            BalancePayment::Paid => self.find(user.id, trade_no).await,
+            BalancePayment::NotPending => Err(ApiError::new(ErrorCode::OrderAlreadyPaid).into()),
+            BalancePayment::InsufficientBalance => {
+                Err(ApiError::new(ErrorCode::InsufficientBalance).into())
+            }
+        }
+    }
+
+    /// 取消待支付的订单
+    pub async fn cancel(&self, user: &User, trade_no: &str) -> Result<Order> {
+        let order = self.find_pending(user.id, trade_no).await?;
+        if !self.order_repo.cancel(order.id).await? {
+            return Err(ApiError::new(ErrorCode::OrderAlreadyPaid).into());
+        }
+
+        self.find(user.id, trade_no).await
+    }
+
+    /// 分页获取用户的订单
+    pub async fn list(&self, user_id: i32, page: u64, page_size: u64) -> Result<(Vec<Order>, i64)> {
+        self.order_repo
+            .find_by_user(user_id, page as i32, page_size as i32)
+            .await
+    }
+
+    pub async fn find(&self, user_id: i32, trade_no: &str) -> Result<Order> {
+        self.order_repo
+            .find_by_trade_no(user_id, trade_no)
+            .await?
+            .ok_or_else(|| ApiError::new(ErrorCode::OrderNotFound).into())
+    }
+
+    async fn find_pending(&self, user_id: i32, trade_no: &str) -> Result<Order> {
+        let order = self.find(user_id, trade_no).await?;
+        match order.status {
+            // 超过保留时长的订单已释放名额，需要重新下单
+            s if s == OrderStatus::Pending.as_i16()
+                && order.created_at <= self.reserved_since() =>
+            {
+                Err(ApiError::new(ErrorCode::OrderExpired).into())
+            }
+            s if s == OrderStatus::Pending.as_i16() => Ok(order),
+            s if s == OrderStatus::Cancelled.as_i16() => Err(ApiError::with_message(
+                ErrorCode::OrderExpired,
+                "订单已取消".to_string(),
+            )
+            .into()),
+            _ => Err(ApiError::new(ErrorCode::OrderAlreadyPaid).into()),
+        }
+    }
+
+    /// 获取可购买流量包的当前套餐：用户订阅中且套餐设置了流量单价
+    async fn addon_plan(&self, user: &User) -> Result<Plan> {
+        let plan_id = user.plan_id.ok_or_else(|| {
+            ApiError::with_message(ErrorCode::PlanNotFound, "当前没有订阅套餐".to_string())
+        })?;
+
+        if Self::is_expired(user) {
+            return Err(ApiError::new(ErrorCode::SubscriptionExpired).into());
+        }
+
+        let plan = self
+            .plan_repo
+            .find_by_id(plan_id)
+            .await?
+            .ok_or_else(|| ApiError::new(ErrorCode::PlanNotFound))?;
+        if !plan.sells_traffic_addon() {
+            return Err(ApiError::with_message(
+                ErrorCode::PlanUnavailable,
+                "当前套餐不支持购买流量包".to_string(),
+            )
+            .into());
+        }
+
+        Ok(plan)
+    }
+
+    /// 保留期起点，在此之后创建的待支付订单仍占用名额
+    fn reserved_since(&self) -> i32 {
+        chrono::Utc::now().timestamp() as i32 - self.config.reservation_window
+    }
+
+    fn is_expired(user: &User) -> bool {
+        let now = chrono::Utc::now().timestamp();
+        user.expired_at.is_some_and(|at| at > 0 && at < now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        repositories::UserRepository,
+        test_support::{self, error_code},
+    };
+    use sqlx::PgPool;
+
+    fn service(pool: &PgPool) -> OrderService {
+        OrderService::new(
+            OrderRepository::new(pool.clone()),
+            PlanRepository::new(pool.clone()),
+            OrderConfig {
+                reservation_window: 900,
+            },
+        )
+    }
+
+    async fn user(pool: &PgPool, id: i32) -> User {
+        UserRepository::new(pool.clone())
+            .find_by_id(id)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    /// 插入持有按 GB 出售流量包（2 元/GB）套餐的用户，返回 (用户ID, 套餐ID)
+    async fn addon_user(pool: &PgPool, balance: i32) -> (i32, i32) {
+        let plan_id = test_support::insert_plan(pool, 1).await;
+        sqlx::query("UPDATE purple_plan SET transfer_unit_price = 200 WHERE id = $1")
+            .bind(plan_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        let user_id = test_support::insert_user(pool, "addon@example.com", Some(1)).await;
+        sqlx::query("UPDATE purple_user SET plan_id = $1, balance = $2 WHERE id = $3")
+            .bind(plan_id)
+            .bind(balance)
+            .bind(user_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        (user_id, plan_id)
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_create_traffic_addon(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let (user_id, plan_id) = addon_user(&pool, 0).await;
+
+        let order = service(&pool)
+            .create_traffic_addon(&user(&pool, user_id).await, 5)
+            .await
+            .unwrap();
+        assert_eq!(order.plan_id, plan_id);
+        assert_eq!(order.r#type, OrderType::TrafficAddon.as_i32());
+        assert_eq!(order.total_amount, 1000);
+        assert_eq!(order.transfer_amount, Some(5));
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_create_traffic_addon_rejected(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let service = service(&pool);
+        let (user_id, plan_id) = addon_user(&pool, 0).await;
+
+        // 价格溢出
+        let err = service
+            .create_traffic_addon(&user(&pool, user_id).await, i32::MAX)
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::InvalidParams));
+
+        // 订阅已过期
+        sqlx::query("UPDATE purple_user SET expired_at = 1 WHERE id = $1")
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let err = service
+            .create_traffic_addon(&user(&pool, user_id).await, 5)
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::SubscriptionExpired));
+
+        // 套餐未设置流量单价
+        sqlx::query("UPDATE purple_user SET expired_at = NULL WHERE id = $1")
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE purple_plan SET transfer_unit_price = NULL WHERE id = $1")
+            .bind(plan_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let err = service
+            .create_traffic_addon(&user(&pool, user_id).await, 5)
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::PlanUnavailable));
+
+        // 没有订阅套餐
+        let user_id = test_support::insert_user(&pool, "none@example.com", None).await;
+        let err = service
+            .create_traffic_addon(&user(&pool, user_id).await, 5)
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::PlanNotFound));
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_pay_traffic_addon(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let service = service(&pool);
+        let (user_id, _) = addon_user(&pool, 1500).await;
+        let before = user(&pool, user_id).await;
+
+        let order = service.create_traffic_addon(&before, 5).await.unwrap();
+        let paid = service
+            .pay_with_balance(&before, &order.trade_no)
+            .await
+            .unwrap();
+        assert_eq!(paid.status, OrderStatus::Completed.as_i16());
+        assert_eq!(paid.balance_amount, Some(1000));
+
+        let after = user(&pool, user_id).await;
+        assert_eq!(after.balance, 500);
+        assert_eq!(after.transfer_enable, before.transfer_enable + 5 * GB);
+
+        // 重复支付
+        let err = service
+            .pay_with_balance(&after, &order.trade_no)
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::OrderAlreadyPaid));
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_pay_traffic_addon_insufficient_balance(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let service = service(&pool);
+        let (user_id, _) = addon_user(&pool, 500).await;
+        let before = user(&pool, user_id).await;
+
+        let order = service.create_traffic_addon(&before, 5).await.unwrap();
+        let err = service
+            .pay_with_balance(&before, &order.trade_no)
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::InsufficientBalance));
+
+        // 支付失败时订单、余额和流量都保持不变
+        let order = service.find(user_id, &order.trade_no).await.unwrap();
+        assert_eq!(order.status, OrderStatus::Pending.as_i16());
+        let after = user(&pool, user_id).await;
+        assert_eq!(after.balance, 500);
+        assert_eq!(after.transfer_enable, before.transfer_enable);
+    }
+
+    async fn set_capacity(pool: &PgPool, plan_id: i32, capacity_limit: i32) {
+        sqlx::query("UPDATE purple_plan SET capacity_limit = $1 WHERE id = $2")
+            .bind(capacity_limit)
+            .bind(plan_id)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_checkout_within_capacity(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let service = service(&pool);
+        let plan_id = test_support::insert_plan(&pool, 1).await;
+        set_capacity(&pool, plan_id, 1).await;
+        let first = test_support::insert_user(&pool, "first@example.com", None).await;
+        let second = test_support::insert_user(&pool, "second@example.com", None).await;
+
+        // 待支付的订单占用名额
+        let order = service
+            .checkout(&user(&pool, first).await, plan_id, PlanPeriod::Month)
+            .await
+            .unwrap();
+        assert_eq!(order.r#type, OrderType::New.as_i32());
+        let err = service
+            .checkout(&user(&pool, second).await, plan_id, PlanPeriod::Month)
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::PlanQuotaExceeded));
+
+        // 同一用户再次下单不重复占用名额
+        service
+            .checkout(&user(&pool, first).await, plan_id, PlanPeriod::Month)
+            .await
+            .unwrap();
+
+        let plan = PlanRepository::new(pool.clone())
+            .find_by_id(plan_id)
+            .await
+            .unwrap()
+            .unwrap();
+        let seats = service.remaining_seats(&[plan]).await.unwrap();
+        assert_eq!(seats.get(&plan_id), Some(&0));
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_expired_reservation_releases_seat(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let service = service(&pool);
+        let plan_id = test_support::insert_plan(&pool, 1).await;
+        set_capacity(&pool, plan_id, 1).await;
+        let first = test_support::insert_user(&pool, "first@example.com", None).await;
+        let second = test_support::insert_user(&pool, "second@example.com", None).await;
+
+        let order = service
+            .checkout(&user(&pool, first).await, plan_id, PlanPeriod::Month)
+            .await
+            .unwrap();
+        // 超过保留时长的订单不再占用名额，也不能再支付
+        sqlx::query("UPDATE purple_order SET created_at = created_at - 901 WHERE id = $1")
+            .bind(order.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let err = service
+            .pay_with_balance(&user(&pool, first).await, &order.trade_no)
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::OrderExpired));
+
+        service
+            .checkout(&user(&pool, second).await, plan_id, PlanPeriod::Month)
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_holder_renews_when_full(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let service = service(&pool);
+        let plan_id = test_support::insert_plan(&pool, 1).await;
+        set_capacity(&pool, plan_id, 1).await;
+        let holder = test_support::insert_user(&pool, "holder@example.com", Some(1)).await;
+        sqlx::query("UPDATE purple_user SET plan_id = $1 WHERE id = $2")
+            .bind(plan_id)
+            .bind(holder)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let other = test_support::insert_user(&pool, "other@example.com", None).await;
+
+        let err = service
+            .checkout(&user(&pool, other).await, plan_id, PlanPeriod::Month)
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::PlanQuotaExceeded));
+
+        // 续费不占用新的名额
+        let order = service
+            .checkout(&user(&pool, holder).await, plan_id, PlanPeriod::Month)
+            .await
+            .unwrap();
+        assert_eq!(order.r#type, OrderType::Renew.as_i32());
+
+        // 已过期的用户不再占用名额
+        sqlx::query("UPDATE purple_user SET expired_at = 1 WHERE id = $1")
+            .bind(holder)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE purple_order SET status = $1")
+            .bind(OrderStatus::Cancelled.as_i16())
+            .execute(&pool)
+            .await
+            .unwrap();
+        service
+            .checkout(&user(&pool, other).await, plan_id, PlanPeriod::Month)
+            .await
+            .unwrap();
+    }
+
+    async fn plan(pool: &PgPool, plan_id: i32) -> Plan {
+        PlanRepository::new(pool.clone())
+            .find_by_id(plan_id)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    async fn subscribe(pool: &PgPool, user_id: i32, plan_id: i32) {
+        sqlx::query("UPDATE purple_user SET plan_id = $1 WHERE id = $2")
+            .bind(plan_id)
+            .bind(user_id)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_checkout_hidden_plan(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let service = service(&pool);
+        let plan_id = test_support::insert_plan(&pool, 1).await;
+        sqlx::query("UPDATE purple_plan SET show = false WHERE id = $1")
+            .bind(plan_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let owner = test_support::insert_user(&pool, "owner@example.com", Some(1)).await;
+        subscribe(&pool, owner, plan_id).await;
+        let other = test_support::insert_user(&pool, "other@example.com", None).await;
+
+        // 隐藏的套餐只有当前订阅的用户可以购买
+        let err = service
+            .checkout(&user(&pool, other).await, plan_id, PlanPeriod::Month)
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::PlanUnavailable));
+        let order = service
+            .checkout(&user(&pool, owner).await, plan_id, PlanPeriod::Month)
+            .await
+            .unwrap();
+        assert_eq!(order.r#type, OrderType::Renew.as_i32());
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_checkout_without_renew(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let service = service(&pool);
+        let plan_id = test_support::insert_plan(&pool, 1).await;
+        sqlx::query("UPDATE purple_plan SET renew = false, reset_price = 500 WHERE id = $1")
+            .bind(plan_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let owner = test_support::insert_user(&pool, "owner@example.com", Some(1)).await;
+        subscribe(&pool, owner, plan_id).await;
+        let other = test_support::insert_user(&pool, "other@example.com", None).await;
+
+        let err = service
+            .checkout(&user(&pool, owner).await, plan_id, PlanPeriod::Month)
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::PlanUnavailable));
+
+        // 不允许续费时仍可以重置流量，未持有套餐时不能重置
+        let order = service
+            .checkout(&user(&pool, owner).await, plan_id, PlanPeriod::Reset)
+            .await
+            .unwrap();
+        assert_eq!(order.r#type, OrderType::ResetTraffic.as_i32());
+        let err = service
+            .checkout(&user(&pool, other).await, plan_id, PlanPeriod::Reset)
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::PlanUnavailable));
+
+        // 未设置价格的周期不能购买
+        let err = service
+            .checkout(&user(&pool, other).await, plan_id, PlanPeriod::Year)
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::PlanUnavailable));
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_availability(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let current_id = test_support::insert_plan(&pool, 1).await;
+        let other_id = test_support::insert_plan(&pool, 1).await;
+        let user_id = test_support::insert_user(&pool, "user@example.com", Some(1)).await;
+        let guest = user(
+            &pool,
+            test_support::insert_user(&pool, "guest@example.com", None).await,
+        )
+        .await;
+        subscribe(&pool, user_id, current_id).await;
+        let current = plan(&pool, current_id).await;
+        let mut other = plan(&pool, other_id).await;
+
+        let availability = OrderService::availability(&guest, &other, None);
+        assert!(availability.purchasable && !availability.owned && !availability.upgrade);
+
+        let subscriber = user(&pool, user_id).await;
+        let availability = OrderService::availability(&subscriber, &current, Some(0));
+        assert!(availability.owned && availability.renewable);
+        // 续费不受名额限制
+        assert!(availability.purchasable);
+
+        let availability = OrderService::availability(&subscriber, &other, Some(1));
+        assert!(availability.purchasable && availability.upgrade);
+        let availability = OrderService::availability(&subscriber, &other, Some(0));
+        assert!(!availability.purchasable);
+
+        other.show = false;
+        assert!(!OrderService::availability(&guest, &other, None).purchasable);
+        other.show = true;
+
+        // 已过期的用户续费需要重新占用名额
+        sqlx::query("UPDATE purple_user SET expired_at = 1 WHERE id = $1")
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let expired = user(&pool, user_id).await;
+        let availability = OrderService::availability(&expired, &current, Some(0));
+        assert!(availability.owned && !availability.purchasable);
+        let availability = OrderService::availability(&expired, &other, None);
+        assert!(availability.purchasable && !availability.upgrade);
+    }
+}
@@ -0,0 +1,871 @@
+use anyhow::Result;
+use chrono::NaiveTime;
+use std::collections::HashMap;
+use subtle::ConstantTimeEq;
+
+use crate::{
+    common::{ApiError, ErrorCode},
+    config::NodeConfig,
+    models::{
+        rate_schedule::{RateScheduleResponse, RateWindow},
+        route::RouteConfig,
+        server::{
+            NodeAlivePush, NodeBaseConfig, NodeConfigResponse, NodeQuery, NodeStatusPush,
+            NodeTrafficPush, NodeUser, Server, ServerAvailableStatus, ServerHealthResponse,
+            ServerNodeResponse, ServerType,
+        },
+    },
+    repositories::{RouteRepository, ServerRepository, UserRepository},
+};
+
+#[derive(Clone)]
+pub struct ServerService {
+    server_repo: ServerRepository,
+    user_repo: UserRepository,
+    route_repo: RouteRepository,
+    config: NodeConfig,
+}
+
+impl ServerService {
+    pub fn new(
+        server_repo: ServerRepository,
+        user_repo: UserRepository,
+        route_repo: RouteRepository,
+        config: NodeConfig,
+    ) -> Self {
+        Self {
+            server_repo,
+            user_repo,
+            route_repo,
+            config,
+        }
+    }
+
+    /// 校验节点后端的通讯密钥并返回对应节点
+    pub async fn authenticate(&self, query: &NodeQuery) -> Result<Server> {
+        if !self.token_matches(&query.token) {
+            return Err(ApiError::with_message(
+                ErrorCode::InvalidToken,
+                "节点通讯密钥无效".to_string(),
+            )
+            .into());
+        }
+
+        let server_type = query
+            .node_type
+            .parse::<ServerType>()
+            .map_err(|e| ApiError::with_message(ErrorCode::InvalidParams, e.to_string()))?;
+
+        match self
+            .server_repo
+            .find_by_id(server_type, query.node_id)
+            .await?
+        {
+            // 子节点（中转）的流量由父节点上报，不对节点后端开放
+            Some(server) if server.parent_id().is_some() => Err(ApiError::with_message(
+                ErrorCode::InvalidParams,
+                format!(
+                    "子节点不能直接对接节点后端: {}#{}",
+                    server_type, query.node_id
+                ),
+            )
+            .into()),
+            Some(server) => Ok(server),
+            None => Err(ApiError::with_message(
+                ErrorCode::InvalidParams,
+                format!("节点不存在: {}#{}", server_type, query.node_id),
+            )
+            .into()),
+        }
+    }
+
+    /// 以常量时间比较通讯密钥，未配置密钥时拒绝所有节点
+    fn token_matches(&self, token: &str) -> bool {
+        !self.config.token.is_empty()
+            && bool::from(token.as_bytes().ct_eq(self.config.token.as_bytes()))
+    }
+
+    /// 生成下发给节点后端的配置，包含节点关联的路由规则
+    pub async fn node_config(&self, server: &Server) -> Result<NodeConfigResponse> {
+        let route_ids = server.route_ids();
+        let routes = if route_ids.is_empty() {
+            Vec::new()
+        } else {
+            self.route_repo
+                .find_by_ids(&route_ids)
+                .await?
+                .iter()
+                .filter_map(|route| match RouteConfig::try_from(route) {
+                    Ok(route) => Some(route),
+                    Err(e) => {
+                        tracing::warn!("忽略无效的路由规则 {}: {}", route.id, e);
+                        None
+                    }
+                })
+                .collect()
+        };
+
+        Ok(NodeConfigResponse {
+            protocol: server.server_type(),
+            server_port: server.server_port(),
+            settings: server.protocol_settings(),
+            routes,
+            base_config: NodeBaseConfig {
+                push_interval: self.config.push_interval,
+                pull_interval: self.config.pull_interval,
+            },
+        })
+    }
+
+    /// 为节点分配路由规则，所有路由规则必须存在
+    pub async fn update_routes(
+        &self,
+        server_type: ServerType,
+        server_id: i32,
+        route_ids: &[i32],
+    ) -> Result<()> {
+        let mut route_ids = route_ids.to_vec();
+        route_ids.sort_unstable();
+        route_ids.dedup();
+
+        let routes = self.route_repo.find_by_ids(&route_ids).await?;
+        if let Some(missing) = route_ids
+            .iter()
+            .find(|id| !routes.iter().any(|route| route.id == **id))
+        {
+            return Err(ApiError::with_message(
+                ErrorCode::InvalidParams,
+                format!("路由规则不存在: {}", missing),
+            )
+            .into());
+        }
+
+        let route_id = if route_ids.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(&route_ids)?)
+        };
+
+        let updated = self
+            .server_repo
+            .update_route_id(server_type, server_id, route_id.as_deref())
+            .await?;
+        if !updated {
+            return Err(ApiError::with_message(
+                ErrorCode::InvalidParams,
+                format!("节点不存在: {}#{}", server_type, server_id),
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// 获取节点可用用户，同时记录节点心跳
+    ///
+    /// 子节点的用户经由父节点转发，因此父节点需要同时接受子节点权限组的用户
+    pub async fn fetch_users(&self, server: &Server) -> Result<Vec<NodeUser>> {
+        self.server_repo
+            .touch_check_at(server.server_type(), server.id())
+            .await?;
+
+        // 多级中转时同样需要接受所有后代节点权限组的用户
+        let servers = self.server_repo.find_by_type(server.server_type()).await?;
+        let mut group_ids = server.group_ids();
+        for descendant in &servers {
+            if descendant.id() != server.id()
+                && resolve_root_id(&servers, descendant) == server.id()
+            {
+                group_ids.extend(descendant.group_ids());
+            }
+        }
+        group_ids.sort_unstable();
+        group_ids.dedup();
+
+        self.user_repo.find_available_by_groups(&group_ids).await
+    }
+
+    /// 处理节点上报的用户流量，按节点当前生效的倍率计入用户已用流量
+    pub async fn push_traffic(&self, server: &Server, traffic: NodeTrafficPush) -> Result<()> {
+        let rate = self.effective_rate(server).await?;
+        let (mut total_u, mut total_d) = (0i64, 0i64);
+
+        for (user_id, [u, d]) in traffic {
+            total_u += u;
+            total_d += d;
+            let u = (u as f64 * rate) as i64;
+            let d = (d as f64 * rate) as i64;
+            self.user_repo.add_traffic(user_id, u, d).await?;
+        }
+
+        // 子节点不对接节点后端，经由子节点产生的流量统一计入父节点
+        let record_at = chrono::Utc::now()
+            .date_naive()
+            .and_hms_opt(0, 0, 0)
+            .expect("valid timestamp")
+            .and_utc()
+            .timestamp() as i32;
+        self.server_repo
+            .record_traffic(
+                server.server_type(),
+                server.id(),
+                total_u,
+                total_d,
+                record_at,
+            )
+            .await?;
+
+        self.server_repo
+            .touch_push_at(server.server_type(), server.id())
+            .await
+    }
+
+    /// 计算时间段倍率使用的当前时间
+    fn rate_time(&self) -> NaiveTime {
+        chrono::Utc::now()
+            .with_timezone(&self.config.rate_timezone)
+            .time()
+    }
+
+    /// 节点当前生效的流量倍率
+    pub async fn effective_rate(&self, server: &Server) -> Result<f64> {
+        let windows = self
+            .server_repo
+            .find_rate_schedule(server.server_type(), server.id())
+            .await?
+            .map(|schedule| schedule.window_list())
+            .unwrap_or_default();
+
+        Ok(server.rate_schedule(windows).rate_at(self.rate_time()))
+    }
+
+    /// 批量计算节点当前生效的流量倍率
+    ///
+    /// 子节点的流量由父节点上报并按父节点倍率计入，因此子节点沿用父节点的倍率
+    pub async fn effective_rates(
+        &self,
+        servers: &[Server],
+    ) -> Result<HashMap<(ServerType, i32), f64>> {
+        let schedules = self
+            .server_repo
+            .find_all_rate_schedules()
+            .await?
+            .into_iter()
+            .map(|schedule| {
+                (
+                    (schedule.server_type.clone(), schedule.server_id),
+                    schedule.window_list(),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+
+        let time = self.rate_time();
+        let rates = servers
+            .iter()
+            .map(|server| {
+                let root = resolve_root(servers, server);
+                let key = (root.server_type().as_str().to_string(), root.id());
+                let windows = schedules.get(&key).cloned().unwrap_or_default();
+                (
+                    (server.server_type(), server.id()),
+                    root.rate_schedule(windows).rate_at(time),
+                )
+            })
+            .collect();
+
+        Ok(rates)
+    }
+
+    /// 获取节点的时间段倍率
+    pub async fn rate_schedule(
+        &self,
+        server_type: ServerType,
+        server_id: i32,
+    ) -> Result<RateScheduleResponse> {
+        let server = self.find_server(server_type, server_id).await?;
+        let windows = self
+            .server_repo
+            .find_rate_schedule(server_type, server_id)
+            .await?
+            .map(|schedule| schedule.window_list())
+            .unwrap_or_default();
+
+        Ok(self.rate_schedule_response(&server, windows))
+    }
+
+    /// 设置节点的时间段倍率，空列表表示恢复为固定倍率
+    pub async fn update_rate_schedule(
+        &self,
+        server_type: ServerType,
+        server_id: i32,
+        windows: Vec<RateWindow>,
+    ) -> Result<RateScheduleResponse> {
+        let server = self.find_server(server_type, server_id).await?;
+        if windows.is_empty() {
+            self.server_repo
+                .delete_rate_schedule(server_type, server_id)
+                .await?;
+        } else {
+            self.server_repo
+                .upsert_rate_schedule(server_type, server_id, &serde_json::to_string(&windows)?)
+                .await?;
+        }
+
+        Ok(self.rate_schedule_response(&server, windows))
+    }
+
+    fn rate_schedule_response(
+        &self,
+        server: &Server,
+        windows: Vec<RateWindow>,
+    ) -> RateScheduleResponse {
+        let current_rate = server
+            .rate_schedule(windows.clone())
+            .rate_at(self.rate_time());
+        RateScheduleResponse {
+            base_rate: server.rate().to_string(),
+            windows,
+            current_rate,
+        }
+    }
+
+    async fn find_server(&self, server_type: ServerType, server_id: i32) -> Result<Server> {
+        self.server_repo
+            .find_by_id(server_type, server_id)
+            .await?
+            .ok_or_else(|| {
+                ApiError::with_message(
+                    ErrorCode::InvalidParams,
+                    format!("节点不存在: {}#{}", server_type, server_id),
+                )
+                .into()
+            })
+    }
+
+    /// 处理节点上报的在线用户
+    pub async fn push_alive(&self, server: &Server, alive: NodeAlivePush) -> Result<()> {
+        let online_user = alive.values().filter(|ips| !ips.is_empty()).count() as i32;
+
+        self.server_repo
+            .update_online_user(server.server_type(), server.id(), online_user)
+            .await
+    }
+
+    /// 处理节点上报的负载信息
+    pub async fn push_status(&self, server: &Server, load: NodeStatusPush) -> Result<()> {
+        self.server_repo
+            .update_load(server.server_type(), server.id(), &load)
+            .await
+    }
+
+    /// 获取节点列表（包含运行状态）
+    pub async fn list_nodes(
+        &self,
+        server_type: Option<ServerType>,
+    ) -> Result<Vec<ServerNodeResponse>> {
+        let servers = match server_type {
+            Some(server_type) => self.server_repo.find_by_type(server_type).await?,
+            None => self.server_repo.find_all().await?,
+        };
+
+        let statuses = self
+            .server_repo
+            .find_all_status()
+            .await?
+            .into_iter()
+            .map(|status| ((status.server_type.clone(), status.server_id), status))
+            .collect::<HashMap<_, _>>();
+
+        let now = chrono::Utc::now().timestamp() as i32;
+        let nodes = servers
+            .iter()
+            .map(|server| {
+                // 子节点沿用父节点的运行状态
+                let root_id = resolve_root_id(&servers, server);
+                let key = (server.server_type().as_str().to_string(), root_id);
+                ServerNodeResponse::new(
+                    server,
+                    statuses.get(&key),
+                    now,
+                    self.config.offline_threshold,
+                )
+            })
+            .collect();
+
+        Ok(nodes)
+    }
+
+    /// 获取节点健康概况，列出超过阈值未上报的节点
+    pub async fn health(&self) -> Result<ServerHealthResponse> {
+        // 子节点的状态继承自父节点，只统计父节点
+        let nodes = self
+            .list_nodes(None)
+            .await?
+            .into_iter()
+            .filter(|node| node.parent_id.is_none())
+            .collect::<Vec<_>>();
+        let total = nodes.len() as i64;
+
+        let (silent, online): (Vec<_>, Vec<_>) = nodes
+            .into_iter()
+            .partition(|node| node.available_status != ServerAvailableStatus::Online);
+
+        for node in &silent {
+            tracing::warn!(
+                server_type = %node.r#type,
+                server_id = node.id,
+                last_check_at = ?node.last_check_at,
+                last_push_at = ?node.last_push_at,
+                "节点超过 {} 秒未上报",
+                self.config.offline_threshold
+            );
+        }
+
+        Ok(ServerHealthResponse {
+            total,
+            online: online.len() as i64,
+            threshold: self.config.offline_threshold,
+            silent,
+        })
+    }
+
+    /// 设置节点的父节点
+    ///
+    /// 父节点必须是同一协议下已存在的节点，且不能形成循环引用
+    pub async fn update_parent(
+        &self,
+        server_type: ServerType,
+        server_id: i32,
+        parent_id: Option<i32>,
+        parent_type: Option<ServerType>,
+    ) -> Result<()> {
+        let servers = self.server_repo.find_by_type(server_type).await?;
+        if !servers.iter().any(|server| server.id() == server_id) {
+            return Err(ApiError::with_message(
+                ErrorCode::InvalidParams,
+                format!("节点不存在: {}#{}", server_type, server_id),
+            )
+            .into());
+        }
+
+        if let Some(parent_id) = parent_id {
+            if let Some(parent_type) = parent_type.filter(|t| *t != server_type) {
+                return Err(ApiError::with_message(
+                    ErrorCode::InvalidParams,
+                    format!("父节点不能跨协议引用: {} -> {}", server_type, parent_type),
+                )
+                .into());
+            }
+
+            // 沿父节点链向上查找，遇到自身即为循环引用
+            let mut current = Some(parent_id);
+            let mut depth = 0;
+            while let Some(id) = current {
+                if id == server_id {
+                    return Err(ApiError::with_message(
+                        ErrorCode::InvalidParams,
+                        "父节点不能形成循环引用".to_string(),
+                    )
+                    .into());
+                }
+
+                let parent = servers.iter().find(|server| server.id() == id);
+                match parent {
+                    Some(parent) => current = parent.parent_id(),
+                    None if id == parent_id => {
+                        return Err(ApiError::with_message(
+                            ErrorCode::InvalidParams,
+                            format!("父节点不存在: {}#{}", server_type, parent_id),
+                        )
+                        .into())
+                    }
+                    None => break,
+                }
+
+                depth += 1;
+                if depth > servers.len() {
+                    return Err(ApiError::with_message(
+                        ErrorCode::InvalidParams,
+                        "父节点不能形成循环引用".to_string(),
+                    )
+                    .into());
+                }
+            }
+        }
+
+        self.server_repo
+            .update_parent_id(server_type, server_id, parent_id)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// 沿父节点链查找实际对接节点后端的根节点ID
+fn resolve_root_id(servers: &[Server], server: &Server) -> i32 {
+    resolve_root(servers, server).id()
+}
+
+/// 沿父节点链向上查找根节点，父节点缺失时返回当前节点
+fn resolve_root<'a>(servers: &'a [Server], server: &'a Server) -> &'a Server {
+    let mut current = server;
+    for _ in 0..servers.len() {
+        let parent = current.parent_id().and_then(|parent_id| {
+            servers
+                .iter()
+                .find(|s| s.server_type() == current.server_type() && s.id() == parent_id)
+        });
+        match parent {
+            Some(parent) => current = parent,
+            None => break,
+        }
+    }
+
+    current
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, error_code};
+    use chrono::FixedOffset;
+    use sqlx::PgPool;
+
+    const TOKEN: &str = "node-secret";
+
+    fn service(pool: &PgPool, token: &str) -> ServerService {
+        ServerService::new(
+            ServerRepository::new(pool.clone()),
+            UserRepository::new(pool.clone()),
+            RouteRepository::new(pool.clone()),
+            NodeConfig {
+                token: token.to_string(),
+                offline_threshold: 300,
+                push_interval: 60,
+                pull_interval: 60,
+                rate_timezone: FixedOffset::east_opt(8 * 3600).unwrap(),
+            },
+        )
+    }
+
+    fn query(token: &str, node_id: i32) -> NodeQuery {
+        NodeQuery {
+            token: token.to_string(),
+            node_id,
+            node_type: "trojan".to_string(),
+        }
+    }
+
+    async fn insert_trojan(pool: &PgPool, group_id: i32, parent_id: Option<i32>) -> i32 {
+        sqlx::query_scalar(
+            r#"
+            INSERT INTO purple_server_trojan (
+                group_id, parent_id, name, rate, host, port, server_port, created_at, updated_at
+            )
+            VALUES ($1, $2, '香港', '1', 'hk.example.com', '443', 443, 0, 0)
+            RETURNING id
+            "#,
+        )
+        .bind(format!("[{}]", group_id))
+        .bind(parent_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_authenticate(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let id = insert_trojan(&pool, 1, None).await;
+        let service = service(&pool, TOKEN);
+
+        let server = service.authenticate(&query(TOKEN, id)).await.unwrap();
+        assert_eq!(server.server_type(), ServerType::Trojan);
+        assert_eq!(server.id(), id);
+
+        for token in ["", "node-secreT", "node-secret-", "node"] {
+            let err = service.authenticate(&query(token, id)).await.unwrap_err();
+            assert_eq!(error_code(&err), Some(ErrorCode::InvalidToken), "{token}");
+        }
+
+        let err = service
+            .authenticate(&query(TOKEN, id + 1))
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::InvalidParams));
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_authenticate_without_configured_token(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let id = insert_trojan(&pool, 1, None).await;
+
+        let err = service(&pool, "")
+            .authenticate(&query("", id))
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::InvalidToken));
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_report_status(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let id = insert_trojan(&pool, 1, None).await;
+        let child_id = insert_trojan(&pool, 2, Some(id)).await;
+        let service = service(&pool, TOKEN);
+
+        let nodes = service.list_nodes(None).await.unwrap();
+        assert!(nodes
+            .iter()
+            .all(|node| node.available_status == ServerAvailableStatus::Unknown));
+
+        let server = service.authenticate(&query(TOKEN, id)).await.unwrap();
+        service.fetch_users(&server).await.unwrap();
+        service
+            .push_alive(
+                &server,
+                HashMap::from([
+                    (1, vec!["1.1.1.1".to_string()]),
+                    (2, vec!["2.2.2.2".to_string(), "3.3.3.3".to_string()]),
+                    (3, Vec::new()),
+                ]),
+            )
+            .await
+            .unwrap();
+        service
+            .push_status(
+                &server,
+                NodeStatusPush {
+                    cpu: 12.5,
+                    mem: 40.0,
+                    disk: 73.0,
+                    uptime: 3600,
+                },
+            )
+            .await
+            .unwrap();
+
+        let nodes = service.list_nodes(None).await.unwrap();
+        let node = nodes.iter().find(|node| node.id == id).unwrap();
+        assert_eq!(node.available_status, ServerAvailableStatus::Online);
+        assert_eq!(node.online_user, 2);
+        assert!(node.last_check_at.is_some());
+        let load = node.load.as_ref().unwrap();
+        assert_eq!(load.cpu, Some(12.5));
+        assert_eq!(load.uptime, Some(3600));
+
+        // 子节点沿用父节点的运行状态，且不计入健康概况
+        let child = nodes.iter().find(|node| node.id == child_id).unwrap();
+        assert_eq!(child.available_status, ServerAvailableStatus::Online);
+        assert_eq!(child.online_user, 2);
+
+        let health = service.health().await.unwrap();
+        assert_eq!(health.total, 1);
+        assert_eq!(health.online, 1);
+        assert!(health.silent.is_empty());
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_report_status_offline(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let id = insert_trojan(&pool, 1, None).await;
+        let service = service(&pool, TOKEN);
+
+        sqlx::query(
+            r#"
+            INSERT INTO purple_server_status (server_id, server_type, last_check_at, updated_at)
+            VALUES ($1, 'trojan', $2, $2)
+            "#,
+        )
+        .bind(id)
+        .bind(test_support::now() - 301)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let health = service.health().await.unwrap();
+        assert_eq!(health.online, 0);
+        assert_eq!(health.silent.len(), 1);
+        assert_eq!(
+            health.silent[0].available_status,
+            ServerAvailableStatus::Offline
+        );
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_child_cannot_authenticate(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let id = insert_trojan(&pool, 1, None).await;
+        let child_id = insert_trojan(&pool, 2, Some(id)).await;
+
+        let err = service(&pool, TOKEN)
+            .authenticate(&query(TOKEN, child_id))
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::InvalidParams));
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_fetch_users_includes_descendant_groups(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let root = insert_trojan(&pool, 1, None).await;
+        let child = insert_trojan(&pool, 2, Some(root)).await;
+        insert_trojan(&pool, 3, Some(child)).await;
+        insert_trojan(&pool, 4, None).await;
+
+        let mut expected = Vec::new();
+        for group_id in 1..=4 {
+            let user_id = test_support::insert_user(
+                &pool,
+                &format!("{}@example.com", group_id),
+                Some(group_id),
+            )
+            .await;
+            if group_id <= 3 {
+                expected.push(user_id);
+            }
+        }
+
+        let service = service(&pool, TOKEN);
+        let server = service.authenticate(&query(TOKEN, root)).await.unwrap();
+        let users = service.fetch_users(&server).await.unwrap();
+        assert_eq!(
+            users.iter().map(|user| user.id).collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_update_parent(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let root = insert_trojan(&pool, 1, None).await;
+        let child = insert_trojan(&pool, 1, Some(root)).await;
+        let grandchild = insert_trojan(&pool, 1, Some(child)).await;
+        let service = service(&pool, TOKEN);
+
+        for (server_id, parent_id) in [(root, grandchild), (root, root), (child, root + 100)] {
+            let err = service
+                .update_parent(ServerType::Trojan, server_id, Some(parent_id), None)
+                .await
+                .unwrap_err();
+            assert_eq!(error_code(&err), Some(ErrorCode::InvalidParams));
+        }
+
+        let err = service
+            .update_parent(
+                ServerType::Trojan,
+                child,
+                Some(root),
+                Some(ServerType::Vmess),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::InvalidParams));
+
+        service
+            .update_parent(ServerType::Trojan, grandchild, Some(root), None)
+            .await
+            .unwrap();
+        service
+            .update_parent(ServerType::Trojan, child, None, None)
+            .await
+            .unwrap();
+        let servers = ServerRepository::new(pool)
+            .find_by_type(ServerType::Trojan)
+            .await
+            .unwrap();
+        let parent_of = |id: i32| {
+            servers
+                .iter()
+                .find(|server| server.id() == id)
+                .unwrap()
+                .parent_id()
+        };
+        assert_eq!(parent_of(grandchild), Some(root));
+        assert_eq!(parent_of(child), None);
+    }
+
+    /// 覆盖当前时间的时间段倍率
+    fn current_window(service: &ServerService, rate: f64) -> RateWindow {
+        let now = service.rate_time();
+        RateWindow {
+            start: (now - chrono::Duration::hours(1))
+                .format("%H:%M")
+                .to_string(),
+            end: (now + chrono::Duration::hours(1))
+                .format("%H:%M")
+                .to_string(),
+            rate,
+        }
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_push_traffic_applies_rate_schedule(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let id = insert_trojan(&pool, 1, None).await;
+        let child_id = insert_trojan(&pool, 1, Some(id)).await;
+        let user_id = test_support::insert_user(&pool, "user@example.com", Some(1)).await;
+        let service = service(&pool, TOKEN);
+
+        let schedule = service
+            .update_rate_schedule(ServerType::Trojan, id, vec![current_window(&service, 0.5)])
+            .await
+            .unwrap();
+        assert_eq!(schedule.base_rate, "1");
+        assert_eq!(schedule.current_rate, 0.5);
+
+        // 子节点沿用父节点的倍率
+        let servers = ServerRepository::new(pool.clone())
+            .find_by_type(ServerType::Trojan)
+            .await
+            .unwrap();
+        let rates = service.effective_rates(&servers).await.unwrap();
+        assert_eq!(rates[&(ServerType::Trojan, id)], 0.5);
+        assert_eq!(rates[&(ServerType::Trojan, child_id)], 0.5);
+
+        let server = service.authenticate(&query(TOKEN, id)).await.unwrap();
+        service
+            .push_traffic(&server, HashMap::from([(user_id, [1000, 3000])]))
+            .await
+            .unwrap();
+        let (u, d): (i64, i64) = sqlx::query_as("SELECT u, d FROM purple_user WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!((u, d), (500, 1500));
+
+        // 恢复固定倍率后按基础倍率计入
+        let schedule = service
+            .update_rate_schedule(ServerType::Trojan, id, Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(schedule.current_rate, 1.0);
+        assert!(service
+            .rate_schedule(ServerType::Trojan, id)
+            .await
+            .unwrap()
+            .windows
+            .is_empty());
+        service
+            .push_traffic(&server, HashMap::from([(user_id, [1000, 3000])]))
+            .await
+            .unwrap();
+        let (u, d): (i64, i64) = sqlx::query_as("SELECT u, d FROM purple_user WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!((u, d), (1500, 4500));
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_rate_schedule_of_missing_node(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let err = service(&pool, TOKEN)
+            .rate_schedule(ServerType::Trojan, 1)
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::InvalidParams));
+    }
+}
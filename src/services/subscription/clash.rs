@@ -0,0 +1,394 @@
+//! Clash / Clash.Meta 订阅生成
+
+use anyhow::{Context, Result};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashSet;
+
+use super::node::{first_port, is_port_range, Tls, Transport};
+use crate::models::server::{
+    parse_json_setting, Server, ServerHysteria, ServerShadowsocks, ServerTrojan, ServerVless,
+    ServerVmess,
+};
+
+/// 内置的默认模板
+pub const DEFAULT_TEMPLATE: &str = include_str!("templates/clash.yaml");
+
+/// 模板中的站点名称占位符
+const APP_NAME_PLACEHOLDER: &str = "$app_name";
+/// 策略组中的节点列表占位符，会被展开为全部节点名称
+const PROXIES_PLACEHOLDER: &str = "$proxies";
+
+/// 校验模板是否为合法的 Clash 配置
+pub fn validate_template(template: &str) -> Result<()> {
+    parse_template(template, "").map(|_| ())
+}
+
+/// 生成 Clash 配置
+///
+/// `meta` 为 false 时输出原版 Clash 配置，跳过其不支持的 VLESS 和 Hysteria2 节点
+pub fn render(
+    servers: &[Server],
+    uuid: &str,
+    app_name: &str,
+    template: &str,
+    meta: bool,
+) -> Result<String> {
+    let mut config = parse_template(template, app_name)?;
+
+    let mut names = HashSet::new();
+    let proxies: Vec<Value> = servers
+        .iter()
+        .filter_map(|server| build_proxy(server, uuid, meta).map(|proxy| (server, proxy)))
+        .map(|(server, mut proxy)| {
+            // Clash 要求节点名称唯一，重名时追加节点类型和ID
+            let name = proxy_name(&proxy);
+            if !names.insert(name.clone()) {
+                let name = format!("{} ({}-{})", name, server.server_type(), server.id());
+                names.insert(name.clone());
+                proxy.insert("name".into(), name.into());
+            }
+            Value::Mapping(proxy)
+        })
+        .collect();
+    let proxy_names: Vec<Value> = proxies.iter().map(|proxy| proxy["name"].clone()).collect();
+
+    let groups = match config.shift_remove("proxy-groups") {
+        Some(Value::Sequence(groups)) => groups,
+        _ => vec![default_group(app_name)],
+    };
+    let groups: Vec<Value> = groups
+        .into_iter()
+        .map(|group| expand_group(group, &proxy_names))
+        .collect();
+
+    let rules = match config.shift_remove("rules") {
+        Some(Value::Sequence(rules)) => rules,
+        _ => vec![format!("MATCH,{}", app_name).into()],
+    };
+
+    config.shift_remove("proxies");
+    config.insert("proxies".into(), Value::Sequence(proxies));
+    config.insert("proxy-groups".into(), Value::Sequence(groups));
+    config.insert("rules".into(), Value::Sequence(rules));
+
+    Ok(serde_yaml::to_string(&config)?)
+}
+
+fn parse_template(template: &str, app_name: &str) -> Result<Mapping> {
+    let value: Value = serde_yaml::from_str(template).context("Clash 模板不是合法的 YAML")?;
+    match replace_app_name(value, app_name) {
+        Value::Mapping(mapping) => Ok(mapping),
+        _ => anyhow::bail!("Clash 模板顶层必须为对象"),
+    }
+}
+
+/// 将模板中所有字符串里的站点名称占位符替换为实际名称
+fn replace_app_name(value: Value, app_name: &str) -> Value {
+    match value {
+        Value::String(s) => Value::String(s.replace(APP_NAME_PLACEHOLDER, app_name)),
+        Value::Sequence(items) => Value::Sequence(
+            items
+                .into_iter()
+                .map(|item| replace_app_name(item, app_name))
+                .collect(),
+        ),
+        Value::Mapping(mapping) => Value::Mapping(
+            mapping
+                .into_iter()
+                .map(|(key, value)| (key, replace_app_name(value, app_name)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+fn default_group(app_name: &str) -> Value {
+    let mut group = Mapping::new();
+    group.insert("name".into(), app_name.into());
+    group.insert("type".into(), "select".into());
+    group.insert(
+        "proxies".into(),
+        Value::Sequence(vec![PROXIES_PLACEHOLDER.into()]),
+    );
+    Value::Mapping(group)
+}
+
+/// 展开策略组中的节点占位符，无可用节点时回落到 DIRECT
+fn expand_group(group: Value, proxy_names: &[Value]) -> Value {
+    let mut group = match group {
+        Value::Mapping(group) => group,
+        other => return other,
+    };
+
+    if let Some(Value::Sequence(proxies)) = group.get_mut("proxies") {
+        let mut expanded = Vec::new();
+        for proxy in std::mem::take(proxies) {
+            if proxy.as_str() == Some(PROXIES_PLACEHOLDER) {
+                expanded.extend(proxy_names.iter().cloned());
+            } else {
+                expanded.push(proxy);
+            }
+        }
+        if expanded.is_empty() {
+            expanded.push("DIRECT".into());
+        }
+        *proxies = expanded;
+    }
+
+    Value::Mapping(group)
+}
+
+fn proxy_name(proxy: &Mapping) -> String {
+    proxy
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn build_proxy(server: &Server, uuid: &str, meta: bool) -> Option<Mapping> {
+    match server {
+        Server::Vmess(s) => Some(vmess_proxy(s, uuid)),
+        Server::Vless(s) if meta => Some(vless_proxy(s, uuid)),
+        Server::Trojan(s) => Some(trojan_proxy(s, uuid)),
+        Server::Shadowsocks(s) => Some(shadowsocks_proxy(s, uuid)),
+        Server::Hysteria(s) if meta => Some(hysteria_proxy(s, uuid)),
+        _ => None,
+    }
+}
+
+fn base_proxy(name: &str, proxy_type: &str, host: &str, port: &str) -> Mapping {
+    let mut proxy = Mapping::new();
+    proxy.insert("name".into(), name.into());
+    proxy.insert("type".into(), proxy_type.into());
+    proxy.insert("server".into(), host.into());
+    proxy.insert("port".into(), first_port(port).into());
+    proxy
+}
+
+fn vmess_proxy(server: &ServerVmess, uuid: &str) -> Mapping {
+    let mut proxy = base_proxy(&server.name, "vmess", &server.host, &server.port);
+    proxy.insert("uuid".into(), uuid.into());
+    proxy.insert("alterId".into(), 0.into());
+    proxy.insert("cipher".into(), "auto".into());
+    proxy.insert("udp".into(), true.into());
+
+    let tls = Tls::vmess(server);
+    if tls.enabled {
+        proxy.insert("tls".into(), true.into());
+        insert_tls(&mut proxy, &tls);
+    }
+    insert_transport(
+        &mut proxy,
+        &Transport::parse(&server.network, server.networksettings.as_deref()),
+    );
+    proxy
+}
+
+fn vless_proxy(server: &ServerVless, uuid: &str) -> Mapping {
+    let mut proxy = base_proxy(
+        &server.name,
+        "vless",
+        &server.host,
+        &server.port.to_string(),
+    );
+    proxy.insert("uuid".into(), uuid.into());
+    proxy.insert("udp".into(), true.into());
+    if let Some(flow) = server.flow.as_deref().filter(|flow| !flow.is_empty()) {
+        proxy.insert("flow".into(), flow.into());
+    }
+
+    let tls = Tls::vless(server);
+    if tls.enabled {
+        proxy.insert("tls".into(), true.into());
+        insert_tls(&mut proxy, &tls);
+        if let Some(reality) = &tls.reality {
+            let mut opts = Mapping::new();
+            opts.insert("public-key".into(), reality.public_key.as_str().into());
+            if !reality.short_id.is_empty() {
+                opts.insert("short-id".into(), reality.short_id.as_str().into());
+            }
+            proxy.insert("reality-opts".into(), Value::Mapping(opts));
+            proxy.insert("client-fingerprint".into(), "chrome".into());
+        }
+    }
+    insert_transport(
+        &mut proxy,
+        &Transport::parse(&server.network, server.network_settings.as_deref()),
+    );
+    proxy
+}
+
+fn trojan_proxy(server: &ServerTrojan, uuid: &str) -> Mapping {
+    let mut proxy = base_proxy(&server.name, "trojan", &server.host, &server.port);
+    proxy.insert("password".into(), uuid.into());
+    proxy.insert("udp".into(), true.into());
+    if let Some(server_name) = server.server_name.as_deref().filter(|s| !s.is_empty()) {
+        proxy.insert("sni".into(), server_name.into());
+    }
+    proxy.insert("skip-cert-verify".into(), server.allow_insecure.into());
+    proxy
+}
+
+fn shadowsocks_proxy(server: &ServerShadowsocks, uuid: &str) -> Mapping {
+    let mut proxy = base_proxy(&server.name, "ss", &server.host, &server.port);
+    proxy.insert("cipher".into(), server.cipher.as_str().into());
+    proxy.insert("password".into(), server.user_password(uuid).into());
+    proxy.insert("udp".into(), true.into());
+
+    if server.obfs.as_deref().map(str::trim) == Some("http") {
+        let settings = parse_json_setting(server.obfs_settings.as_deref());
+        let mut opts = Mapping::new();
+        opts.insert("mode".into(), "http".into());
+        if let Some(host) = settings.get("host").and_then(|host| host.as_str()) {
+            opts.insert("host".into(), host.into());
+        }
+        proxy.insert("plugin".into(), "obfs".into());
+        proxy.insert("plugin-opts".into(), Value::Mapping(opts));
+    }
+    proxy
+}
+
+fn hysteria_proxy(server: &ServerHysteria, uuid: &str) -> Mapping {
+    let mut proxy = base_proxy(&server.name, "hysteria2", &server.host, &server.port);
+    if is_port_range(&server.port) {
+        proxy.insert("ports".into(), server.port.as_str().into());
+    }
+    proxy.insert("password".into(), uuid.into());
+    // 节点的上行带宽即客户端的下行带宽
+    proxy.insert("up".into(), format!("{} Mbps", server.down_mbps).into());
+    proxy.insert("down".into(), format!("{} Mbps", server.up_mbps).into());
+    if let Some(obfs_password) = server.obfs_password() {
+        proxy.insert(
+            "obfs".into(),
+            server
+                .obfs_type
+                .as_deref()
+                .unwrap_or_default()
+                .trim()
+                .into(),
+        );
+        proxy.insert("obfs-password".into(), obfs_password.into());
+    }
+    if let Some(server_name) = server.server_name.as_deref().filter(|s| !s.is_empty()) {
+        proxy.insert("sni".into(), server_name.into());
+    }
+    proxy.insert("skip-cert-verify".into(), server.insecure.into());
+    proxy
+}
+
+fn insert_tls(proxy: &mut Mapping, tls: &Tls) {
+    if let Some(server_name) = &tls.server_name {
+        proxy.insert("servername".into(), server_name.as_str().into());
+    }
+    proxy.insert("skip-cert-verify".into(), tls.insecure.into());
+}
+
+fn insert_transport(proxy: &mut Mapping, transport: &Transport) {
+    match transport.network.as_str() {
+        "ws" => {
+            proxy.insert("network".into(), "ws".into());
+            let mut opts = Mapping::new();
+            if let Some(path) = &transport.path {
+                opts.insert("path".into(), path.as_str().into());
+            }
+            if let Some(host) = &transport.host {
+                let mut headers = Mapping::new();
+                headers.insert("Host".into(), host.as_str().into());
+                opts.insert("headers".into(), Value::Mapping(headers));
+            }
+            proxy.insert("ws-opts".into(), Value::Mapping(opts));
+        }
+        "grpc" => {
+            proxy.insert("network".into(), "grpc".into());
+            let mut opts = Mapping::new();
+            if let Some(service_name) = &transport.service_name {
+                opts.insert("grpc-service-name".into(), service_name.as_str().into());
+            }
+            proxy.insert("grpc-opts".into(), Value::Mapping(opts));
+        }
+        "h2" => {
+            proxy.insert("network".into(), "h2".into());
+            let mut opts = Mapping::new();
+            if let Some(host) = &transport.host {
+                opts.insert("host".into(), Value::Sequence(vec![host.as_str().into()]));
+            }
+            if let Some(path) = &transport.path {
+                opts.insert("path".into(), path.as_str().into());
+            }
+            proxy.insert("h2-opts".into(), Value::Mapping(opts));
+        }
+        "tcp" if transport.is_http_obfs() => {
+            proxy.insert("network".into(), "http".into());
+            let mut opts = Mapping::new();
+            opts.insert("method".into(), "GET".into());
+            opts.insert(
+                "path".into(),
+                Value::Sequence(vec![transport.path.as_deref().unwrap_or("/").into()]),
+            );
+            if let Some(host) = &transport.host {
+                let mut headers = Mapping::new();
+                headers.insert("Host".into(), Value::Sequence(vec![host.as_str().into()]));
+                opts.insert("headers".into(), Value::Mapping(headers));
+            }
+            proxy.insert("http-opts".into(), Value::Mapping(opts));
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::subscription::fixtures;
+
+    #[test]
+    fn test_render_clash_meta_golden() {
+        let output = render(
+            &fixtures::servers(),
+            fixtures::UUID,
+            "Purple",
+            DEFAULT_TEMPLATE,
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(output, include_str!("testdata/clash_meta.yaml"));
+    }
+
+    #[test]
+    fn test_render_clash_golden() {
+        let output = render(
+            &fixtures::servers(),
+            fixtures::UUID,
+            "Purple",
+            DEFAULT_TEMPLATE,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(output, include_str!("testdata/clash.yaml"));
+    }
+
+    #[test]
+    fn test_render_without_groups_and_rules() {
+        let output = render(
+            &fixtures::servers(),
+            fixtures::UUID,
+            "Purple",
+            "mode: rule",
+            true,
+        )
+        .unwrap();
+        let config: Value = serde_yaml::from_str(&output).unwrap();
+
+        assert_eq!(config["proxy-groups"][0]["name"], "Purple");
+        assert_eq!(config["rules"][0], "MATCH,Purple");
+    }
+
+    #[test]
+    fn test_invalid_template() {
+        assert!(validate_template("- not\n- a mapping").is_err());
+        assert!(validate_template(DEFAULT_TEMPLATE).is_ok());
+    }
+}
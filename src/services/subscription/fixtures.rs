@@ -0,0 +1,193 @@
+//! 订阅生成器测试使用的节点样例
+
+use crate::models::server::{
+    Server, ServerHysteria, ServerShadowsocks, ServerTrojan, ServerVless, ServerVmess,
+};
+
+pub const UUID: &str = "8e1b2c3d-4f5a-4b6c-9d7e-0f1a2b3c4d5e";
+
+const CREATED_AT: i32 = 1700000000;
+
+pub fn vmess_ws_tls() -> ServerVmess {
+    ServerVmess {
+        id: 1,
+        group_id: r#"["1"]"#.to_string(),
+        route_id: None,
+        name: "香港 VMess".to_string(),
+        parent_id: None,
+        host: "hk.example.com".to_string(),
+        port: "443".to_string(),
+        server_port: 10086,
+        tls: true,
+        tags: None,
+        rate: "1".to_string(),
+        network: "ws".to_string(),
+        rules: None,
+        networksettings: Some(
+            r#"{"path":"/vmess","headers":{"Host":"cdn.example.com"}}"#.to_string(),
+        ),
+        tlssettings: Some(r#"{"serverName":"cdn.example.com","allowInsecure":"0"}"#.to_string()),
+        rulesettings: None,
+        dnssettings: None,
+        show: true,
+        sort: Some(1),
+        created_at: CREATED_AT,
+        updated_at: CREATED_AT,
+    }
+}
+
+pub fn vmess_tcp_http() -> ServerVmess {
+    ServerVmess {
+        id: 2,
+        name: "日本 VMess".to_string(),
+        host: "jp.example.com".to_string(),
+        port: "8080".to_string(),
+        tls: false,
+        network: "tcp".to_string(),
+        networksettings: Some(
+            r#"{"header":{"type":"http","request":{"path":["/"],"headers":{"Host":["www.bing.com"]}}}}"#
+                .to_string(),
+        ),
+        tlssettings: None,
+        sort: Some(2),
+        ..vmess_ws_tls()
+    }
+}
+
+pub fn vless_reality() -> ServerVless {
+    ServerVless {
+        id: 3,
+        group_id: r#"["1"]"#.to_string(),
+        route_id: None,
+        name: "美国 REALITY".to_string(),
+        parent_id: None,
+        host: "us.example.com".to_string(),
+        port: 443,
+        server_port: 443,
+        tls: true,
+        tls_settings: Some(
+            r#"{"server_name":"www.microsoft.com","allow_insecure":false,"public_key":"Z84J2IelR9ch3k8VtlVhhs5ycBUlXA7wHBWcBrjqnAw","short_id":"6ba85179e30d4fc2"}"#
+                .to_string(),
+        ),
+        flow: Some("xtls-rprx-vision".to_string()),
+        network: "tcp".to_string(),
+        network_settings: None,
+        tags: None,
+        rate: "1.5".to_string(),
+        show: true,
+        sort: Some(3),
+        created_at: CREATED_AT,
+        updated_at: CREATED_AT,
+    }
+}
+
+pub fn vless_grpc_tls() -> ServerVless {
+    ServerVless {
+        id: 4,
+        name: "新加坡 VLESS".to_string(),
+        host: "sg.example.com".to_string(),
+        tls_settings: Some(r#"{"server_name":"sg.example.com"}"#.to_string()),
+        flow: None,
+        network: "grpc".to_string(),
+        network_settings: Some(r#"{"serviceName":"grpc-service"}"#.to_string()),
+        rate: "1".to_string(),
+        sort: Some(4),
+        ..vless_reality()
+    }
+}
+
+pub fn trojan() -> ServerTrojan {
+    ServerTrojan {
+        id: 5,
+        group_id: r#"["1"]"#.to_string(),
+        route_id: None,
+        parent_id: None,
+        tags: None,
+        name: "台湾 Trojan".to_string(),
+        rate: "1".to_string(),
+        host: "tw.example.com".to_string(),
+        port: "443".to_string(),
+        server_port: 443,
+        allow_insecure: true,
+        server_name: Some("tw.example.com".to_string()),
+        show: true,
+        sort: Some(5),
+        created_at: CREATED_AT,
+        updated_at: CREATED_AT,
+    }
+}
+
+pub fn shadowsocks_obfs() -> ServerShadowsocks {
+    ServerShadowsocks {
+        id: 6,
+        group_id: r#"["1"]"#.to_string(),
+        route_id: None,
+        parent_id: None,
+        tags: None,
+        name: "韩国 SS".to_string(),
+        rate: "0.5".to_string(),
+        host: "kr.example.com".to_string(),
+        port: "8388".to_string(),
+        server_port: 8388,
+        cipher: "aes-128-gcm".to_string(),
+        obfs: Some("http".to_string()),
+        obfs_settings: Some(r#"{"path":"/","host":"download.windowsupdate.com"}"#.to_string()),
+        show: true,
+        sort: Some(6),
+        created_at: CREATED_AT,
+        updated_at: CREATED_AT,
+    }
+}
+
+pub fn shadowsocks_2022() -> ServerShadowsocks {
+    ServerShadowsocks {
+        id: 7,
+        name: "德国 SS2022".to_string(),
+        host: "de.example.com".to_string(),
+        cipher: "2022-blake3-aes-128-gcm".to_string(),
+        obfs: None,
+        obfs_settings: None,
+        rate: "1".to_string(),
+        sort: Some(7),
+        ..shadowsocks_obfs()
+    }
+}
+
+pub fn hysteria() -> ServerHysteria {
+    ServerHysteria {
+        id: 8,
+        group_id: r#"["1"]"#.to_string(),
+        route_id: None,
+        name: "英国 Hysteria2".to_string(),
+        parent_id: None,
+        host: "uk.example.com".to_string(),
+        port: "20000-30000".to_string(),
+        server_port: 443,
+        tags: None,
+        rate: "1".to_string(),
+        show: true,
+        sort: Some(8),
+        up_mbps: 1000,
+        down_mbps: 200,
+        server_name: Some("uk.example.com".to_string()),
+        insecure: false,
+        ignore_client_bandwidth: false,
+        obfs_type: Some("salamander".to_string()),
+        created_at: CREATED_AT,
+        updated_at: CREATED_AT,
+    }
+}
+
+/// 覆盖全部协议及常见传输方式的节点列表
+pub fn servers() -> Vec<Server> {
+    vec![
+        Server::Vmess(vmess_ws_tls()),
+        Server::Vmess(vmess_tcp_http()),
+        Server::Vless(vless_reality()),
+        Server::Vless(vless_grpc_tls()),
+        Server::Trojan(trojan()),
+        Server::Shadowsocks(shadowsocks_obfs()),
+        Server::Shadowsocks(shadowsocks_2022()),
+        Server::Hysteria(hysteria()),
+    ]
+}
@@ -0,0 +1,712 @@
+use anyhow::Result;
+use uuid::Uuid;
+
+use crate::{
+    common::{ApiError, ErrorCode},
+    config::SubscribeConfig,
+    models::{
+        server::Server,
+        subscribe_log::{ResetSubscribeTokenResponse, SubscribeLog},
+        subscribe_template::{SubscribeTemplateKind, SubscribeTemplateResponse},
+        user::User,
+    },
+    repositories::{
+        ServerRepository, SubscribeLogRepository, SubscribeTemplateRepository, UserRepository,
+    },
+    services::ServerService,
+};
+
+mod clash;
+#[cfg(test)]
+mod fixtures;
+mod node;
+mod quantumultx;
+mod singbox;
+mod surge;
+mod uri;
+
+/// 订阅客户端类型，根据 User-Agent 识别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientType {
+    Clash,
+    ClashMeta,
+    SingBox,
+    Shadowrocket,
+    QuantumultX,
+    Surge,
+    V2rayN,
+    Stash,
+    Loon,
+}
+
+impl ClientType {
+    /// 根据 User-Agent 识别客户端，无法识别时返回 None
+    ///
+    /// 匹配顺序很重要：Clash.Meta 系客户端的 UA 中同样包含 clash，
+    /// 因此需要先于 Clash 判断
+    pub fn from_user_agent(user_agent: &str) -> Option<Self> {
+        let ua = user_agent.to_ascii_lowercase();
+        let contains_any = |keys: &[&str]| keys.iter().any(|key| ua.contains(key));
+
+        if contains_any(&["sing-box", "singbox", "sfa/", "sfi/", "sfm/"]) {
+            Some(ClientType::SingBox)
+        } else if contains_any(&[
+            "mihomo",
+            "clash.meta",
+            "clashmeta",
+            "clash-verge",
+            "flclash",
+            "nyanpasu",
+        ]) {
+            Some(ClientType::ClashMeta)
+        } else if ua.contains("stash") {
+            Some(ClientType::Stash)
+        } else if ua.contains("clash") {
+            Some(ClientType::Clash)
+        } else if ua.contains("shadowrocket") {
+            Some(ClientType::Shadowrocket)
+        } else if contains_any(&["quantumult x", "quantumult%20x", "quantumultx"]) {
+            Some(ClientType::QuantumultX)
+        } else if ua.contains("surge") {
+            Some(ClientType::Surge)
+        } else if ua.contains("loon") {
+            Some(ClientType::Loon)
+        } else if ua.contains("v2rayn") || ua.contains("v2rayng") {
+            Some(ClientType::V2rayN)
+        } else {
+            None
+        }
+    }
+
+    /// 解析 flag 参数，用于手动指定订阅格式
+    pub fn from_flag(flag: &str) -> Option<Self> {
+        match flag.to_ascii_lowercase().as_str() {
+            "clash" => Some(ClientType::Clash),
+            "meta" | "clashmeta" | "clash.meta" | "mihomo" => Some(ClientType::ClashMeta),
+            "sing-box" | "singbox" => Some(ClientType::SingBox),
+            "shadowrocket" => Some(ClientType::Shadowrocket),
+            "quantumultx" | "quantumult x" => Some(ClientType::QuantumultX),
+            "surge" => Some(ClientType::Surge),
+            "v2rayn" | "v2rayng" | "base64" => Some(ClientType::V2rayN),
+            "stash" => Some(ClientType::Stash),
+            "loon" => Some(ClientType::Loon),
+            _ => None,
+        }
+    }
+
+    /// 客户端对应的订阅格式
+    pub fn format(&self) -> SubscriptionFormat {
+        match self {
+            ClientType::Clash => SubscriptionFormat::Clash,
+            ClientType::ClashMeta | ClientType::Stash => SubscriptionFormat::ClashMeta,
+            ClientType::SingBox => SubscriptionFormat::SingBox,
+            ClientType::Shadowrocket => SubscriptionFormat::Shadowrocket,
+            ClientType::QuantumultX => SubscriptionFormat::QuantumultX,
+            ClientType::Surge => SubscriptionFormat::Surge,
+            ClientType::V2rayN | ClientType::Loon => SubscriptionFormat::Base64,
+        }
+    }
+}
+
+/// 订阅输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionFormat {
+    Clash,
+    ClashMeta,
+    SingBox,
+    Shadowrocket,
+    QuantumultX,
+    Surge,
+    Base64,
+}
+
+impl SubscriptionFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubscriptionFormat::Clash => "clash",
+            SubscriptionFormat::ClashMeta => "clash.meta",
+            SubscriptionFormat::SingBox => "sing-box",
+            SubscriptionFormat::Shadowrocket => "shadowrocket",
+            SubscriptionFormat::QuantumultX => "quantumult x",
+            SubscriptionFormat::Surge => "surge",
+            SubscriptionFormat::Base64 => "base64",
+        }
+    }
+}
+
+/// 渲染完成的订阅内容
+#[derive(Debug)]
+pub struct SubscriptionOutput {
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+/// 订阅流量信息，用于 subscription-userinfo 响应头
+#[derive(Debug)]
+pub struct SubscriptionUserInfo {
+    pub upload: i64,
+    pub download: i64,
+    pub total: i64,
+    pub expire: i64,
+}
+
+impl SubscriptionUserInfo {
+    pub fn from_user(user: &User) -> Self {
+        Self {
+            upload: user.u,
+            download: user.d,
+            total: user.transfer_enable,
+            expire: user.expired_at.unwrap_or(0),
+        }
+    }
+
+    pub fn header_value(&self) -> String {
+        format!(
+            "upload={}; download={}; total={}; expire={}",
+            self.upload, self.download, self.total, self.expire
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct SubscriptionService {
+    user_repo: UserRepository,
+    server_repo: ServerRepository,
+    template_repo: SubscribeTemplateRepository,
+    log_repo: SubscribeLogRepository,
+    server_service: ServerService,
+    config: SubscribeConfig,
+}
+
+impl SubscriptionService {
+    pub fn new(
+        user_repo: UserRepository,
+        server_repo: ServerRepository,
+        template_repo: SubscribeTemplateRepository,
+        log_repo: SubscribeLogRepository,
+        server_service: ServerService,
+        config: SubscribeConfig,
+    ) -> Self {
+        Self {
+            user_repo,
+            server_repo,
+            template_repo,
+            log_repo,
+            server_service,
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &SubscribeConfig {
+        &self.config
+    }
+
+    /// 通过订阅令牌获取用户，并校验封禁、过期和流量状态
+    pub async fn authenticate(&self, token: &str) -> Result<User> {
+        let user = self.user_repo.find_by_token(token).await?.ok_or_else(|| {
+            ApiError::with_message(ErrorCode::InvalidToken, "订阅令牌无效".to_string())
+        })?;
+
+        if user.banned.unwrap_or(false) {
+            return Err(ApiError::new(ErrorCode::UserDisabled).into());
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        if user.expired_at.is_some_and(|at| at > 0 && at < now) {
+            return Err(ApiError::new(ErrorCode::SubscriptionExpired).into());
+        }
+
+        if user.u + user.d >= user.transfer_enable {
+            return Err(ApiError::new(ErrorCode::TrafficExhausted).into());
+        }
+
+        Ok(user)
+    }
+
+    /// 校验订阅令牌最近一小时的拉取次数是否超过限制
+    ///
+    /// 每个用户同一时间只有一个有效令牌，按用户统计即为按令牌统计；
+    /// 重置令牌不会清空计数，避免通过频繁重置绕过限制
+    pub async fn check_rate_limit(&self, user: &User) -> Result<()> {
+        if self.config.rate_limit <= 0 {
+            return Ok(());
+        }
+
+        let since = chrono::Utc::now().timestamp() as i32 - 3600;
+        let count = self.log_repo.count_since(user.id, since).await?;
+        if count >= self.config.rate_limit as i64 {
+            return Err(ApiError::with_message(
+                ErrorCode::TooManyRequests,
+                "订阅拉取过于频繁，请稍后再试".to_string(),
+            )
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// 记录一次订阅拉取
+    pub async fn record_access(
+        &self,
+        user: &User,
+        ip: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<()> {
+        // 与数据库字段长度保持一致，超长的 User-Agent 截断保存
+        let user_agent = user_agent.map(|ua| match ua.char_indices().nth(255) {
+            Some((index, _)) => &ua[..index],
+            None => ua,
+        });
+        self.log_repo.create(user.id, ip, user_agent).await?;
+        Ok(())
+    }
+
+    /// 分页获取用户的订阅拉取记录
+    pub async fn access_logs(
+        &self,
+        user_id: i32,
+        page: u64,
+        page_size: u64,
+    ) -> Result<(Vec<SubscribeLog>, i64)> {
+        self.log_repo
+            .find_by_user(user_id, page as i32, page_size as i32)
+            .await
+    }
+
+    /// 重置订阅令牌，旧的订阅链接立即失效
+    ///
+    /// 同时重置 UUID 时，节点侧的用户凭据也会随下一次用户拉取更新，
+    /// 已导入旧配置的客户端将无法继续连接
+    pub async fn reset_token(
+        &self,
+        user_id: i32,
+        reset_uuid: bool,
+    ) -> Result<ResetSubscribeTokenResponse> {
+        let token = Uuid::new_v4().simple().to_string();
+        let uuid = reset_uuid.then(|| Uuid::new_v4().to_string());
+        let (token, uuid) = self
+            .user_repo
+            .reset_token(user_id, &token, uuid.as_deref())
+            .await?
+            .ok_or_else(|| ApiError::new(ErrorCode::UserNotFound))?;
+
+        Ok(ResetSubscribeTokenResponse { token, uuid })
+    }
+
+    /// 获取用户可见的节点：已显示且所属权限组包含用户的权限组
+    ///
+    /// 当前生效的倍率不为 1 时附加在节点名称后，如 `香港 [0.5x]`
+    pub async fn available_servers(&self, user: &User) -> Result<Vec<Server>> {
+        let group_id = match user.group_id {
+            Some(group_id) => group_id,
+            None => return Ok(Vec::new()),
+        };
+
+        let all_servers = self.server_repo.find_all().await?;
+        let rates = self.server_service.effective_rates(&all_servers).await?;
+
+        let mut servers: Vec<Server> = all_servers
+            .into_iter()
+            .filter(|server| server.show() && server.group_ids().contains(&group_id))
+            .collect();
+        servers.sort_by_key(|server| (server.sort().unwrap_or(i32::MAX), server.id()));
+
+        for server in &mut servers {
+            let rate = rates
+                .get(&(server.server_type(), server.id()))
+                .copied()
+                .unwrap_or(1.0);
+            if rate != 1.0 {
+                let name = format!("{} [{}x]", server.name(), rate);
+                server.set_name(name);
+            }
+        }
+
+        Ok(servers)
+    }
+
+    /// 根据 flag 参数或 User-Agent 确定订阅格式，默认返回通用 Base64 格式
+    pub fn detect_format(&self, flag: Option<&str>, user_agent: &str) -> SubscriptionFormat {
+        flag.and_then(ClientType::from_flag)
+            .or_else(|| ClientType::from_user_agent(user_agent))
+            .map(|client| client.format())
+            .unwrap_or(SubscriptionFormat::Base64)
+    }
+
+    /// 按指定格式渲染订阅内容
+    pub async fn render(
+        &self,
+        user: &User,
+        servers: &[Server],
+        format: SubscriptionFormat,
+    ) -> Result<SubscriptionOutput> {
+        let app_name = &self.config.app_name;
+        tracing::debug!("用户 {} 获取 {} 格式订阅", user.id, format.as_str());
+
+        match format {
+            SubscriptionFormat::Clash | SubscriptionFormat::ClashMeta => {
+                let template = self.template(SubscribeTemplateKind::Clash).await?;
+                let body = clash::render(
+                    servers,
+                    &user.uuid,
+                    app_name,
+                    &template,
+                    format == SubscriptionFormat::ClashMeta,
+                )?;
+                Ok(SubscriptionOutput {
+                    content_type: "text/yaml; charset=utf-8",
+                    body,
+                })
+            }
+            SubscriptionFormat::SingBox => {
+                let template = self.template(SubscribeTemplateKind::SingBox).await?;
+                let body = singbox::render(servers, &user.uuid, app_name, &template)?;
+                Ok(SubscriptionOutput {
+                    content_type: "application/json; charset=utf-8",
+                    body,
+                })
+            }
+            SubscriptionFormat::Shadowrocket => Ok(SubscriptionOutput {
+                content_type: "text/plain; charset=utf-8",
+                body: uri::render_shadowrocket(
+                    servers,
+                    &user.uuid,
+                    &SubscriptionUserInfo::from_user(user),
+                ),
+            }),
+            SubscriptionFormat::QuantumultX => Ok(SubscriptionOutput {
+                content_type: "text/plain; charset=utf-8",
+                body: quantumultx::render(servers, &user.uuid),
+            }),
+            SubscriptionFormat::Surge => Ok(SubscriptionOutput {
+                content_type: "text/plain; charset=utf-8",
+                body: surge::render(servers, &user.uuid, app_name),
+            }),
+            SubscriptionFormat::Base64 => Ok(SubscriptionOutput {
+                content_type: "text/plain; charset=utf-8",
+                body: uri::render(servers, &user.uuid),
+            }),
+        }
+    }
+
+    /// 获取模板内容，未自定义时使用内置默认模板
+    async fn template(&self, kind: SubscribeTemplateKind) -> Result<String> {
+        Ok(self
+            .template_repo
+            .find_by_name(kind.as_str())
+            .await?
+            .map(|template| template.content)
+            .unwrap_or_else(|| default_template(kind).to_string()))
+    }
+
+    pub async fn get_template(
+        &self,
+        kind: SubscribeTemplateKind,
+    ) -> Result<SubscribeTemplateResponse> {
+        let response = match self.template_repo.find_by_name(kind.as_str()).await? {
+            Some(template) => SubscribeTemplateResponse {
+                name: template.name,
+                content: template.content,
+                is_default: false,
+                updated_at: Some(template.updated_at),
+            },
+            None => SubscribeTemplateResponse {
+                name: kind.as_str().to_string(),
+                content: default_template(kind).to_string(),
+                is_default: true,
+                updated_at: None,
+            },
+        };
+
+        Ok(response)
+    }
+
+    /// 保存自定义模板，保存前校验模板格式
+    pub async fn update_template(
+        &self,
+        kind: SubscribeTemplateKind,
+        content: &str,
+    ) -> Result<SubscribeTemplateResponse> {
+        let validation = match kind {
+            SubscribeTemplateKind::Clash => clash::validate_template(content),
+            SubscribeTemplateKind::SingBox => singbox::validate_template(content),
+        };
+        validation.map_err(|e| {
+            ApiError::with_message(ErrorCode::InvalidParams, format!("模板格式无效: {:#}", e))
+        })?;
+
+        let template = self.template_repo.upsert(kind.as_str(), content).await?;
+        Ok(SubscribeTemplateResponse {
+            name: template.name,
+            content: template.content,
+            is_default: false,
+            updated_at: Some(template.updated_at),
+        })
+    }
+
+    /// 删除自定义模板，恢复为内置默认模板
+    pub async fn reset_template(
+        &self,
+        kind: SubscribeTemplateKind,
+    ) -> Result<SubscribeTemplateResponse> {
+        self.template_repo.delete(kind.as_str()).await?;
+        self.get_template(kind).await
+    }
+}
+
+fn default_template(kind: SubscribeTemplateKind) -> &'static str {
+    match kind {
+        SubscribeTemplateKind::Clash => clash::DEFAULT_TEMPLATE,
+        SubscribeTemplateKind::SingBox => singbox::DEFAULT_TEMPLATE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, error_code};
+    use sqlx::PgPool;
+
+    fn service(pool: &PgPool) -> SubscriptionService {
+        let server_repo = ServerRepository::new(pool.clone());
+        SubscriptionService::new(
+            UserRepository::new(pool.clone()),
+            server_repo.clone(),
+            SubscribeTemplateRepository::new(pool.clone()),
+            SubscribeLogRepository::new(pool.clone()),
+            ServerService::new(
+                server_repo,
+                UserRepository::new(pool.clone()),
+                crate::repositories::RouteRepository::new(pool.clone()),
+                crate::config::NodeConfig {
+                    token: String::new(),
+                    offline_threshold: 300,
+                    push_interval: 60,
+                    pull_interval: 60,
+                    rate_timezone: chrono::FixedOffset::east_opt(0).unwrap(),
+                },
+            ),
+            SubscribeConfig {
+                app_name: "Purple".to_string(),
+                update_interval: 24,
+                rate_limit: 3,
+            },
+        )
+    }
+
+    #[test]
+    fn test_client_type_from_user_agent() {
+        let cases = [
+            (
+                "ClashMetaForAndroid/2.10.1.Meta",
+                Some(ClientType::ClashMeta),
+            ),
+            ("clash.meta", Some(ClientType::ClashMeta)),
+            ("mihomo/1.18.3", Some(ClientType::ClashMeta)),
+            ("clash-verge/v1.6.6", Some(ClientType::ClashMeta)),
+            ("ClashForWindows/0.20.39", Some(ClientType::Clash)),
+            ("Stash/2.4.7 Clash/1.9.0", Some(ClientType::Stash)),
+            ("SFA/1.9.0 (sing-box 1.9.0)", Some(ClientType::SingBox)),
+            (
+                "Shadowrocket/2070 CFNetwork/1490.0.4",
+                Some(ClientType::Shadowrocket),
+            ),
+            ("Quantumult%20X/1.4.1", Some(ClientType::QuantumultX)),
+            ("Surge iOS/2920", Some(ClientType::Surge)),
+            ("Loon/3.2.1", Some(ClientType::Loon)),
+            ("v2rayNG/1.8.19", Some(ClientType::V2rayN)),
+            ("Mozilla/5.0 (Windows NT 10.0; Win64; x64)", None),
+            ("", None),
+        ];
+        for (user_agent, expected) in cases {
+            assert_eq!(
+                ClientType::from_user_agent(user_agent),
+                expected,
+                "{user_agent}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_client_type_format() {
+        assert_eq!(
+            ClientType::from_flag("meta").map(|c| c.format()),
+            Some(SubscriptionFormat::ClashMeta)
+        );
+        assert_eq!(
+            ClientType::from_flag("Stash").map(|c| c.format()),
+            Some(SubscriptionFormat::ClashMeta)
+        );
+        assert_eq!(
+            ClientType::from_flag("loon").map(|c| c.format()),
+            Some(SubscriptionFormat::Base64)
+        );
+        assert_eq!(ClientType::from_flag("unknown"), None);
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_detect_format_prefers_flag(pool: PgPool) {
+        let service = service(&pool);
+        assert_eq!(
+            service.detect_format(Some("sing-box"), "ClashForWindows/0.20.39"),
+            SubscriptionFormat::SingBox
+        );
+        assert_eq!(
+            service.detect_format(Some("unknown"), "ClashForWindows/0.20.39"),
+            SubscriptionFormat::Clash
+        );
+        assert_eq!(
+            service.detect_format(None, "curl/8.0"),
+            SubscriptionFormat::Base64
+        );
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_authenticate(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let service = service(&pool);
+        let user_id = test_support::insert_user(&pool, "user@example.com", Some(1)).await;
+        let token: String = sqlx::query_scalar("SELECT token FROM purple_user WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        assert_eq!(service.authenticate(&token).await.unwrap().id, user_id);
+
+        let err = service.authenticate("invalid").await.unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::InvalidToken));
+
+        let cases = [
+            (
+                "UPDATE purple_user SET u = transfer_enable WHERE id = $1",
+                ErrorCode::TrafficExhausted,
+            ),
+            (
+                "UPDATE purple_user SET u = 0, expired_at = 1 WHERE id = $1",
+                ErrorCode::SubscriptionExpired,
+            ),
+            (
+                "UPDATE purple_user SET expired_at = NULL, banned = true WHERE id = $1",
+                ErrorCode::UserDisabled,
+            ),
+        ];
+        for (sql, code) in cases {
+            sqlx::query(sql).bind(user_id).execute(&pool).await.unwrap();
+            let err = service.authenticate(&token).await.unwrap_err();
+            assert_eq!(error_code(&err), Some(code), "{sql}");
+        }
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_template_override_and_reset(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let service = service(&pool);
+
+        let template = service
+            .get_template(SubscribeTemplateKind::Clash)
+            .await
+            .unwrap();
+        assert!(template.is_default);
+        assert_eq!(template.content, clash::DEFAULT_TEMPLATE);
+
+        let err = service
+            .update_template(SubscribeTemplateKind::Clash, "proxies: [")
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::InvalidParams));
+
+        let content = "mixed-port: 7890\nrules:\n  - MATCH,DIRECT\n";
+        let template = service
+            .update_template(SubscribeTemplateKind::Clash, content)
+            .await
+            .unwrap();
+        assert!(!template.is_default);
+        assert_eq!(
+            service
+                .template(SubscribeTemplateKind::Clash)
+                .await
+                .unwrap(),
+            content
+        );
+
+        let template = service
+            .reset_template(SubscribeTemplateKind::Clash)
+            .await
+            .unwrap();
+        assert!(template.is_default);
+        assert_eq!(template.content, clash::DEFAULT_TEMPLATE);
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_reset_token(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let service = service(&pool);
+        let user_id = test_support::insert_user(&pool, "user@example.com", Some(1)).await;
+        let user = UserRepository::new(pool.clone())
+            .find_by_id(user_id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // 重置期间节点上报的流量不会被覆盖
+        sqlx::query("UPDATE purple_user SET u = 1024 WHERE id = $1")
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let reset = service.reset_token(user_id, false).await.unwrap();
+        assert_ne!(reset.token, user.token);
+        assert_eq!(reset.token.len(), 32);
+        assert_eq!(reset.uuid, user.uuid);
+
+        let err = service.authenticate(&user.token).await.unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::InvalidToken));
+        let updated = service.authenticate(&reset.token).await.unwrap();
+        assert_eq!(updated.u, 1024);
+
+        let reset_uuid = service.reset_token(user_id, true).await.unwrap();
+        assert_ne!(reset_uuid.token, reset.token);
+        assert_ne!(reset_uuid.uuid, user.uuid);
+
+        let err = service.reset_token(user_id + 1, false).await.unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::UserNotFound));
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_rate_limit(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let service = service(&pool);
+        let user_id = test_support::insert_user(&pool, "user@example.com", Some(1)).await;
+        let user = UserRepository::new(pool.clone())
+            .find_by_id(user_id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let user_agent = "a".repeat(300);
+        for _ in 0..3 {
+            service.check_rate_limit(&user).await.unwrap();
+            service
+                .record_access(&user, Some("203.0.113.7"), Some(&user_agent))
+                .await
+                .unwrap();
+        }
+        let err = service.check_rate_limit(&user).await.unwrap_err();
+        assert_eq!(error_code(&err), Some(ErrorCode::TooManyRequests));
+
+        // 超长的 User-Agent 截断保存
+        let (logs, total) = service.access_logs(user_id, 1, 10).await.unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(logs[0].user_agent.as_deref().map(str::len), Some(255));
+        assert_eq!(logs[0].ip.as_deref(), Some("203.0.113.7"));
+
+        // 一小时前的记录不计入限制
+        sqlx::query("UPDATE purple_subscribe_log SET created_at = created_at - 3601")
+            .execute(&pool)
+            .await
+            .unwrap();
+        service.check_rate_limit(&user).await.unwrap();
+    }
+}
@@ -0,0 +1,162 @@
+//! 各订阅格式共用的节点参数解析
+
+use serde_json::Value;
+
+use crate::models::server::{parse_json_setting, ServerVless, ServerVmess};
+
+/// 传输层配置
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Transport {
+    /// 传输方式：tcp/ws/grpc/h2/httpupgrade
+    pub network: String,
+    pub path: Option<String>,
+    pub host: Option<String>,
+    /// gRPC 服务名
+    pub service_name: Option<String>,
+    /// tcp 伪装类型（如 http）
+    pub header_type: Option<String>,
+}
+
+impl Transport {
+    /// 解析节点的 network 与 network_settings
+    pub fn parse(network: &str, settings: Option<&str>) -> Self {
+        let settings = parse_json_setting(settings);
+        let network = network.trim().to_ascii_lowercase();
+        let mut transport = Transport {
+            network: if network.is_empty() {
+                "tcp".to_string()
+            } else {
+                network
+            },
+            ..Default::default()
+        };
+
+        match transport.network.as_str() {
+            "ws" | "httpupgrade" => {
+                transport.path = string_field(&settings, &["path"]);
+                transport.host = settings
+                    .get("headers")
+                    .and_then(|headers| string_field(headers, &["Host", "host"]))
+                    .or_else(|| string_field(&settings, &["host"]));
+            }
+            "grpc" => {
+                transport.service_name = string_field(&settings, &["serviceName", "service_name"]);
+            }
+            "h2" | "http" => {
+                transport.network = "h2".to_string();
+                transport.path = string_field(&settings, &["path"]);
+                transport.host = first_string(settings.get("host"));
+            }
+            "tcp" => {
+                if let Some(header) = settings.get("header") {
+                    transport.header_type =
+                        string_field(header, &["type"]).filter(|header_type| header_type != "none");
+                    if let Some(request) = header.get("request") {
+                        transport.path = first_string(request.get("path"));
+                        transport.host = request
+                            .get("headers")
+                            .and_then(|headers| first_string(headers.get("Host")));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        transport
+    }
+
+    /// 是否为 tcp + http 伪装
+    pub fn is_http_obfs(&self) -> bool {
+        self.network == "tcp" && self.header_type.as_deref() == Some("http")
+    }
+}
+
+/// REALITY 参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reality {
+    pub public_key: String,
+    pub short_id: String,
+}
+
+/// TLS 配置
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tls {
+    pub enabled: bool,
+    pub server_name: Option<String>,
+    pub insecure: bool,
+    pub reality: Option<Reality>,
+}
+
+impl Tls {
+    pub fn vmess(server: &ServerVmess) -> Self {
+        Self::parse(server.tls, server.tlssettings.as_deref())
+    }
+
+    /// tls_settings 中设置了 public_key 时视为 REALITY
+    pub fn vless(server: &ServerVless) -> Self {
+        Self::parse(server.tls, server.tls_settings.as_deref())
+    }
+
+    fn parse(enabled: bool, settings: Option<&str>) -> Self {
+        if !enabled {
+            return Tls::default();
+        }
+
+        let settings = parse_json_setting(settings);
+        let reality =
+            string_field(&settings, &["public_key", "publicKey"]).map(|public_key| Reality {
+                public_key,
+                short_id: string_field(&settings, &["short_id", "shortId"]).unwrap_or_default(),
+            });
+
+        Tls {
+            enabled,
+            server_name: string_field(&settings, &["server_name", "serverName"]),
+            insecure: bool_field(&settings, &["allow_insecure", "allowInsecure"]),
+            reality,
+        }
+    }
+}
+
+/// 取端口范围中的首个端口，用于不支持端口跳跃的客户端
+pub fn first_port(port: &str) -> u16 {
+    port.split(['-', ','])
+        .next()
+        .and_then(|port| port.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// 端口是否为范围（端口跳跃）
+pub fn is_port_range(port: &str) -> bool {
+    port.contains('-') || port.contains(',')
+}
+
+fn string_field(value: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .find_map(|key| value.get(*key).and_then(Value::as_str))
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+fn bool_field(value: &Value, keys: &[&str]) -> bool {
+    keys.iter()
+        .find_map(|key| value.get(*key))
+        .map(|value| match value {
+            Value::Bool(b) => *b,
+            Value::Number(n) => n.as_i64() == Some(1),
+            Value::String(s) => s == "1" || s.eq_ignore_ascii_case("true"),
+            _ => false,
+        })
+        .unwrap_or(false)
+}
+
+/// 兼容字符串和字符串数组两种写法，取第一个值
+fn first_string(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(s) => Some(s.clone()),
+        Value::Array(items) => items.iter().find_map(Value::as_str).map(str::to_string),
+        _ => None,
+    }
+    .filter(|s| !s.trim().is_empty())
+}
@@ -0,0 +1,188 @@
+//! Quantumult X 节点列表
+
+use serde_json::Value;
+
+use super::node::{first_port, Tls, Transport};
+use crate::models::server::{
+    parse_json_setting, Server, ServerShadowsocks, ServerTrojan, ServerVless, ServerVmess,
+};
+
+/// 生成 Quantumult X 节点列表，每行一个节点
+///
+/// Quantumult X 不支持 gRPC 传输、REALITY 和 Hysteria2，对应节点会被跳过
+pub fn render(servers: &[Server], uuid: &str) -> String {
+    servers
+        .iter()
+        .filter_map(|server| encode(server, uuid))
+        .map(|line| line + "\r\n")
+        .collect()
+}
+
+pub fn encode(server: &Server, uuid: &str) -> Option<String> {
+    match server {
+        Server::Vmess(s) => vmess(s, uuid),
+        Server::Vless(s) => vless(s, uuid),
+        Server::Trojan(s) => Some(trojan(s, uuid)),
+        Server::Shadowsocks(s) => Some(shadowsocks(s, uuid)),
+        Server::Hysteria(_) => None,
+    }
+}
+
+pub fn vmess(server: &ServerVmess, uuid: &str) -> Option<String> {
+    let tls = Tls::vmess(server);
+    let transport = Transport::parse(&server.network, server.networksettings.as_deref());
+
+    let mut fields = vec![
+        format!("vmess={}:{}", server.host, first_port(&server.port)),
+        "method=chacha20-poly1305".to_string(),
+        format!("password={}", uuid),
+    ];
+    fields.extend(obfs_fields(&tls, &transport)?);
+    fields.extend(common_fields(&server.name));
+
+    Some(fields.join(", "))
+}
+
+pub fn vless(server: &ServerVless, uuid: &str) -> Option<String> {
+    let tls = Tls::vless(server);
+    if tls.reality.is_some() {
+        return None;
+    }
+    let transport = Transport::parse(&server.network, server.network_settings.as_deref());
+
+    let mut fields = vec![
+        format!("vless={}:{}", server.host, server.port),
+        "method=none".to_string(),
+        format!("password={}", uuid),
+    ];
+    fields.extend(obfs_fields(&tls, &transport)?);
+    fields.extend(common_fields(&server.name));
+
+    Some(fields.join(", "))
+}
+
+pub fn trojan(server: &ServerTrojan, uuid: &str) -> String {
+    let mut fields = vec![
+        format!("trojan={}:{}", server.host, first_port(&server.port)),
+        format!("password={}", uuid),
+        "over-tls=true".to_string(),
+    ];
+    if let Some(server_name) = server.server_name.as_deref().filter(|s| !s.is_empty()) {
+        fields.push(format!("tls-host={}", server_name));
+    }
+    fields.push(format!("tls-verification={}", !server.allow_insecure));
+    fields.extend(common_fields(&server.name));
+
+    fields.join(", ")
+}
+
+pub fn shadowsocks(server: &ServerShadowsocks, uuid: &str) -> String {
+    let mut fields = vec![
+        format!("shadowsocks={}:{}", server.host, first_port(&server.port)),
+        format!("method={}", server.cipher),
+        format!("password={}", server.user_password(uuid)),
+    ];
+    if server.obfs.as_deref().map(str::trim) == Some("http") {
+        let settings = parse_json_setting(server.obfs_settings.as_deref());
+        fields.push("obfs=http".to_string());
+        if let Some(host) = settings.get("host").and_then(Value::as_str) {
+            fields.push(format!("obfs-host={}", host));
+        }
+        if let Some(path) = settings.get("path").and_then(Value::as_str) {
+            fields.push(format!("obfs-uri={}", path));
+        }
+    }
+    fields.extend(common_fields(&server.name));
+
+    fields.join(", ")
+}
+
+/// 将传输方式和 TLS 映射为 obfs 参数，不支持的传输方式返回 None
+fn obfs_fields(tls: &Tls, transport: &Transport) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    match transport.network.as_str() {
+        "ws" => {
+            fields.push(format!("obfs={}", if tls.enabled { "wss" } else { "ws" }));
+            if let Some(host) = transport.host.as_ref().or(tls.server_name.as_ref()) {
+                fields.push(format!("obfs-host={}", host));
+            }
+            if let Some(path) = &transport.path {
+                fields.push(format!("obfs-uri={}", path));
+            }
+        }
+        "tcp" if transport.is_http_obfs() && !tls.enabled => {
+            fields.push("obfs=http".to_string());
+            if let Some(host) = &transport.host {
+                fields.push(format!("obfs-host={}", host));
+            }
+            if let Some(path) = &transport.path {
+                fields.push(format!("obfs-uri={}", path));
+            }
+        }
+        "tcp" if tls.enabled => {
+            fields.push("obfs=over-tls".to_string());
+            if let Some(server_name) = &tls.server_name {
+                fields.push(format!("obfs-host={}", server_name));
+            }
+        }
+        "tcp" => {}
+        _ => return None,
+    }
+    if tls.enabled {
+        fields.push(format!("tls-verification={}", !tls.insecure));
+    }
+    Some(fields)
+}
+
+fn common_fields(name: &str) -> Vec<String> {
+    vec![
+        "fast-open=false".to_string(),
+        "udp-relay=true".to_string(),
+        format!("tag={}", name),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::subscription::fixtures;
+
+    #[test]
+    fn test_vmess_line() {
+        assert_eq!(
+            vmess(&fixtures::vmess_ws_tls(), fixtures::UUID).unwrap(),
+            "vmess=hk.example.com:443, method=chacha20-poly1305, \
+             password=8e1b2c3d-4f5a-4b6c-9d7e-0f1a2b3c4d5e, obfs=wss, \
+             obfs-host=cdn.example.com, obfs-uri=/vmess, tls-verification=true, \
+             fast-open=false, udp-relay=true, tag=香港 VMess"
+        );
+    }
+
+    #[test]
+    fn test_unsupported_nodes_are_skipped() {
+        assert!(vless(&fixtures::vless_reality(), fixtures::UUID).is_none());
+        assert!(vless(&fixtures::vless_grpc_tls(), fixtures::UUID).is_none());
+        assert!(encode(&Server::Hysteria(fixtures::hysteria()), fixtures::UUID).is_none());
+    }
+
+    #[test]
+    fn test_trojan_line() {
+        assert_eq!(
+            trojan(&fixtures::trojan(), fixtures::UUID),
+            "trojan=tw.example.com:443, password=8e1b2c3d-4f5a-4b6c-9d7e-0f1a2b3c4d5e, \
+             over-tls=true, tls-host=tw.example.com, tls-verification=false, \
+             fast-open=false, udp-relay=true, tag=台湾 Trojan"
+        );
+    }
+
+    #[test]
+    fn test_shadowsocks_line() {
+        assert_eq!(
+            shadowsocks(&fixtures::shadowsocks_obfs(), fixtures::UUID),
+            "shadowsocks=kr.example.com:8388, method=aes-128-gcm, \
+             password=8e1b2c3d-4f5a-4b6c-9d7e-0f1a2b3c4d5e, obfs=http, \
+             obfs-host=download.windowsupdate.com, obfs-uri=/, \
+             fast-open=false, udp-relay=true, tag=韩国 SS"
+        );
+    }
+}
@@ -0,0 +1,394 @@
+//! sing-box 订阅生成
+
+use anyhow::{Context, Result};
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+
+use super::node::{first_port, is_port_range, Tls, Transport};
+use crate::models::server::{
+    parse_json_setting, Server, ServerHysteria, ServerShadowsocks, ServerTrojan, ServerVless,
+    ServerVmess,
+};
+
+/// 内置的默认模板
+pub const DEFAULT_TEMPLATE: &str = include_str!("templates/sing-box.json");
+
+/// 模板中的站点名称占位符
+const APP_NAME_PLACEHOLDER: &str = "$app_name";
+/// 出站组中的节点列表占位符，会被展开为全部节点标签
+const PROXIES_PLACEHOLDER: &str = "$proxies";
+
+/// 校验模板是否为合法的 sing-box 配置
+pub fn validate_template(template: &str) -> Result<()> {
+    let config = parse_template(template, "")?;
+    if !matches!(config.get("outbounds"), None | Some(Value::Array(_))) {
+        anyhow::bail!("outbounds 必须为数组");
+    }
+    Ok(())
+}
+
+/// 生成 sing-box 配置
+///
+/// 模板中除节点外的部分（dns、inbounds、route 等）原样保留，
+/// 节点出站追加在模板出站之后
+pub fn render(servers: &[Server], uuid: &str, app_name: &str, template: &str) -> Result<String> {
+    let mut config = parse_template(template, app_name)?;
+
+    let mut tags = HashSet::new();
+    let nodes: Vec<Value> = servers
+        .iter()
+        .map(|server| {
+            let mut outbound = build_outbound(server, uuid);
+            // sing-box 要求出站标签唯一，重名时追加节点类型和ID
+            if !tags.insert(server.name().to_string()) {
+                let tag = format!(
+                    "{} ({}-{})",
+                    server.name(),
+                    server.server_type(),
+                    server.id()
+                );
+                tags.insert(tag.clone());
+                outbound.insert("tag".to_string(), tag.into());
+            }
+            Value::Object(outbound)
+        })
+        .collect();
+    let node_tags: Vec<Value> = nodes.iter().map(|node| node["tag"].clone()).collect();
+
+    let outbounds = match config.get_mut("outbounds") {
+        Some(Value::Array(outbounds)) => std::mem::take(outbounds),
+        _ => default_outbounds(app_name),
+    };
+    let mut outbounds: Vec<Value> = outbounds
+        .into_iter()
+        .map(|outbound| expand_group(outbound, &node_tags))
+        .collect();
+    outbounds.extend(nodes);
+
+    // 已存在的 outbounds 保持在模板中的原有位置
+    config.insert("outbounds".to_string(), Value::Array(outbounds));
+
+    Ok(serde_json::to_string_pretty(&config)?)
+}
+
+fn parse_template(template: &str, app_name: &str) -> Result<Map<String, Value>> {
+    let value: Value = serde_json::from_str(template).context("sing-box 模板不是合法的 JSON")?;
+    match replace_app_name(value, app_name) {
+        Value::Object(config) => Ok(config),
+        _ => anyhow::bail!("sing-box 模板顶层必须为对象"),
+    }
+}
+
+/// 将模板中所有字符串里的站点名称占位符替换为实际名称
+fn replace_app_name(value: Value, app_name: &str) -> Value {
+    match value {
+        Value::String(s) => Value::String(s.replace(APP_NAME_PLACEHOLDER, app_name)),
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|item| replace_app_name(item, app_name))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, replace_app_name(value, app_name)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+fn default_outbounds(app_name: &str) -> Vec<Value> {
+    vec![
+        json!({
+            "type": "selector",
+            "tag": app_name,
+            "outbounds": [PROXIES_PLACEHOLDER],
+        }),
+        json!({
+            "type": "direct",
+            "tag": "direct",
+        }),
+    ]
+}
+
+/// 展开出站组中的节点占位符，无可用节点时回落到 direct
+fn expand_group(outbound: Value, node_tags: &[Value]) -> Value {
+    let mut outbound = match outbound {
+        Value::Object(outbound) => outbound,
+        other => return other,
+    };
+
+    if let Some(Value::Array(members)) = outbound.get_mut("outbounds") {
+        let mut expanded = Vec::new();
+        for member in std::mem::take(members) {
+            if member.as_str() == Some(PROXIES_PLACEHOLDER) {
+                expanded.extend(node_tags.iter().cloned());
+            } else {
+                expanded.push(member);
+            }
+        }
+        if expanded.is_empty() {
+            expanded.push("direct".into());
+        }
+        *members = expanded;
+    }
+
+    Value::Object(outbound)
+}
+
+fn build_outbound(server: &Server, uuid: &str) -> Map<String, Value> {
+    let outbound = match server {
+        Server::Vmess(s) => vmess_outbound(s, uuid),
+        Server::Vless(s) => vless_outbound(s, uuid),
+        Server::Trojan(s) => trojan_outbound(s, uuid),
+        Server::Shadowsocks(s) => shadowsocks_outbound(s, uuid),
+        Server::Hysteria(s) => hysteria_outbound(s, uuid),
+    };
+
+    match outbound {
+        Value::Object(outbound) => outbound,
+        _ => unreachable!("出站配置必须为对象"),
+    }
+}
+
+fn vmess_outbound(server: &ServerVmess, uuid: &str) -> Value {
+    let mut outbound = json!({
+        "type": "vmess",
+        "tag": server.name,
+        "server": server.host,
+        "server_port": first_port(&server.port),
+        "uuid": uuid,
+        "security": "auto",
+        "alter_id": 0,
+    });
+
+    let tls = Tls::vmess(server);
+    if tls.enabled {
+        outbound["tls"] = tls_options(&tls);
+    }
+    insert_transport(
+        &mut outbound,
+        &Transport::parse(&server.network, server.networksettings.as_deref()),
+    );
+    outbound
+}
+
+fn vless_outbound(server: &ServerVless, uuid: &str) -> Value {
+    let mut outbound = json!({
+        "type": "vless",
+        "tag": server.name,
+        "server": server.host,
+        "server_port": server.port,
+        "uuid": uuid,
+    });
+    if let Some(flow) = server.flow.as_deref().filter(|flow| !flow.is_empty()) {
+        outbound["flow"] = flow.into();
+    }
+
+    let tls = Tls::vless(server);
+    if tls.enabled {
+        outbound["tls"] = tls_options(&tls);
+    }
+    insert_transport(
+        &mut outbound,
+        &Transport::parse(&server.network, server.network_settings.as_deref()),
+    );
+    outbound
+}
+
+fn trojan_outbound(server: &ServerTrojan, uuid: &str) -> Value {
+    let mut tls = json!({
+        "enabled": true,
+        "insecure": server.allow_insecure,
+    });
+    if let Some(server_name) = server.server_name.as_deref().filter(|s| !s.is_empty()) {
+        tls["server_name"] = server_name.into();
+    }
+
+    json!({
+        "type": "trojan",
+        "tag": server.name,
+        "server": server.host,
+        "server_port": first_port(&server.port),
+        "password": uuid,
+        "tls": tls,
+    })
+}
+
+fn shadowsocks_outbound(server: &ServerShadowsocks, uuid: &str) -> Value {
+    let mut outbound = json!({
+        "type": "shadowsocks",
+        "tag": server.name,
+        "server": server.host,
+        "server_port": first_port(&server.port),
+        "method": server.cipher,
+        "password": server.user_password(uuid),
+    });
+
+    if server.obfs.as_deref().map(str::trim) == Some("http") {
+        let settings = parse_json_setting(server.obfs_settings.as_deref());
+        let mut opts = "obfs=http".to_string();
+        if let Some(host) = settings.get("host").and_then(Value::as_str) {
+            opts.push_str(&format!(";obfs-host={}", host));
+        }
+        outbound["plugin"] = "obfs-local".into();
+        outbound["plugin_opts"] = opts.into();
+    }
+    outbound
+}
+
+fn hysteria_outbound(server: &ServerHysteria, uuid: &str) -> Value {
+    let mut tls = json!({
+        "enabled": true,
+        "insecure": server.insecure,
+    });
+    if let Some(server_name) = server.server_name.as_deref().filter(|s| !s.is_empty()) {
+        tls["server_name"] = server_name.into();
+    }
+
+    // 节点的上行带宽即客户端的下行带宽
+    let mut outbound = json!({
+        "type": "hysteria2",
+        "tag": server.name,
+        "server": server.host,
+        "server_port": first_port(&server.port),
+        "up_mbps": server.down_mbps,
+        "down_mbps": server.up_mbps,
+        "password": uuid,
+        "tls": tls,
+    });
+    if is_port_range(&server.port) {
+        // sing-box 端口范围使用冒号分隔
+        let ports: Vec<String> = server
+            .port
+            .split(',')
+            .map(|range| range.trim().replace('-', ":"))
+            .collect();
+        outbound["server_ports"] = json!(ports);
+    }
+    if let Some(obfs_password) = server.obfs_password() {
+        outbound["obfs"] = json!({
+            "type": server.obfs_type.as_deref().unwrap_or_default().trim(),
+            "password": obfs_password,
+        });
+    }
+    outbound
+}
+
+fn tls_options(tls: &Tls) -> Value {
+    let mut options = json!({
+        "enabled": true,
+        "insecure": tls.insecure,
+    });
+    if let Some(server_name) = &tls.server_name {
+        options["server_name"] = server_name.as_str().into();
+    }
+    if let Some(reality) = &tls.reality {
+        options["utls"] = json!({
+            "enabled": true,
+            "fingerprint": "chrome",
+        });
+        options["reality"] = json!({
+            "enabled": true,
+            "public_key": reality.public_key,
+            "short_id": reality.short_id,
+        });
+    }
+    options
+}
+
+fn insert_transport(outbound: &mut Value, transport: &Transport) {
+    let options = match transport.network.as_str() {
+        "ws" | "httpupgrade" => {
+            let mut options = json!({ "type": transport.network });
+            if let Some(path) = &transport.path {
+                options["path"] = path.as_str().into();
+            }
+            if let Some(host) = &transport.host {
+                if transport.network == "ws" {
+                    options["headers"] = json!({ "Host": host });
+                } else {
+                    options["host"] = host.as_str().into();
+                }
+            }
+            options
+        }
+        "grpc" => {
+            let mut options = json!({ "type": "grpc" });
+            if let Some(service_name) = &transport.service_name {
+                options["service_name"] = service_name.as_str().into();
+            }
+            options
+        }
+        "h2" => {
+            let mut options = json!({ "type": "http" });
+            if let Some(host) = &transport.host {
+                options["host"] = json!([host]);
+            }
+            if let Some(path) = &transport.path {
+                options["path"] = path.as_str().into();
+            }
+            options
+        }
+        // 未启用 TLS 的 http 传输即为 HTTP/1.1 明文，对应 tcp 的 http 伪装
+        "tcp" if transport.is_http_obfs() => {
+            let mut options = json!({
+                "type": "http",
+                "method": "GET",
+                "path": transport.path.as_deref().unwrap_or("/"),
+            });
+            if let Some(host) = &transport.host {
+                options["host"] = json!([host]);
+            }
+            options
+        }
+        _ => return,
+    };
+
+    outbound["transport"] = options;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::subscription::fixtures;
+
+    #[test]
+    fn test_render_sing_box_golden() {
+        let output = render(
+            &fixtures::servers(),
+            fixtures::UUID,
+            "Purple",
+            DEFAULT_TEMPLATE,
+        )
+        .unwrap();
+
+        assert_eq!(output, include_str!("testdata/sing-box.json"));
+    }
+
+    #[test]
+    fn test_render_with_custom_template() {
+        let template = r#"{"log":{"level":"warn"},"route":{"final":"$app_name"}}"#;
+        let output = render(&fixtures::servers(), fixtures::UUID, "Purple", template).unwrap();
+        let config: Value = serde_json::from_str(&output).unwrap();
+
+        assert_eq!(config["log"]["level"], "warn");
+        assert_eq!(config["route"]["final"], "Purple");
+        assert_eq!(config["outbounds"][0]["tag"], "Purple");
+        assert_eq!(
+            config["outbounds"][0]["outbounds"]
+                .as_array()
+                .unwrap()
+                .len(),
+            fixtures::servers().len()
+        );
+    }
+
+    #[test]
+    fn test_invalid_template() {
+        assert!(validate_template("[]").is_err());
+        assert!(validate_template(r#"{"outbounds":{}}"#).is_err());
+        assert!(validate_template(DEFAULT_TEMPLATE).is_ok());
+    }
+}
@@ -0,0 +1,245 @@
+//! Surge 配置
+
+use serde_json::Value;
+use std::collections::HashSet;
+
+use super::node::{first_port, Tls, Transport};
+use crate::models::server::{
+    parse_json_setting, Server, ServerHysteria, ServerShadowsocks, ServerTrojan, ServerVmess,
+};
+
+/// 生成 Surge 配置
+///
+/// Surge 不支持 VLESS 以及 gRPC 传输，对应节点会被跳过
+pub fn render(servers: &[Server], uuid: &str, app_name: &str) -> String {
+    let mut names = HashSet::new();
+    let mut proxies: Vec<(String, String)> = Vec::new();
+    for server in servers {
+        let Some(line) = encode(server, uuid) else {
+            continue;
+        };
+        // 策略组按名称引用节点，重名时追加节点类型和ID
+        let name = proxy_name(server.name());
+        if names.insert(name.clone()) {
+            proxies.push((name, line));
+            continue;
+        }
+        let name = format!("{} ({}-{})", name, server.server_type(), server.id());
+        names.insert(name.clone());
+        let mut server = server.clone();
+        server.set_name(name.clone());
+        proxies.extend(encode(&server, uuid).map(|line| (name, line)));
+    }
+    let names = if proxies.is_empty() {
+        "DIRECT".to_string()
+    } else {
+        proxies
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let app_name = proxy_name(app_name);
+
+    let mut profile = String::new();
+    profile.push_str("[General]\n");
+    profile.push_str("loglevel = notify\n");
+    profile.push_str("dns-server = system, 223.5.5.5, 119.29.29.29\n");
+    profile.push_str(
+        "skip-proxy = 127.0.0.1, 192.168.0.0/16, 10.0.0.0/8, 172.16.0.0/12, 100.64.0.0/10, localhost, *.local\n",
+    );
+    profile.push_str("\n[Proxy]\n");
+    for (_, line) in &proxies {
+        profile.push_str(line);
+        profile.push('\n');
+    }
+    profile.push_str("\n[Proxy Group]\n");
+    profile.push_str(&format!("{} = select, 自动选择, {}\n", app_name, names));
+    profile.push_str(&format!(
+        "自动选择 = url-test, {}, url=http://www.gstatic.com/generate_204, interval=300\n",
+        names
+    ));
+    profile.push_str("\n[Rule]\n");
+    profile.push_str("DOMAIN-SUFFIX,local,DIRECT\n");
+    profile.push_str("IP-CIDR,127.0.0.0/8,DIRECT,no-resolve\n");
+    profile.push_str("IP-CIDR,10.0.0.0/8,DIRECT,no-resolve\n");
+    profile.push_str("IP-CIDR,172.16.0.0/12,DIRECT,no-resolve\n");
+    profile.push_str("IP-CIDR,192.168.0.0/16,DIRECT,no-resolve\n");
+    profile.push_str("GEOIP,CN,DIRECT\n");
+    profile.push_str(&format!("FINAL,{},dns-failed\n", app_name));
+
+    profile
+}
+
+/// 生成单个节点的 Surge 代理行
+pub fn encode(server: &Server, uuid: &str) -> Option<String> {
+    match server {
+        Server::Vmess(s) => vmess(s, uuid),
+        Server::Vless(_) => None,
+        Server::Trojan(s) => Some(trojan(s, uuid)),
+        Server::Shadowsocks(s) => Some(shadowsocks(s, uuid)),
+        Server::Hysteria(s) => Some(hysteria2(s, uuid)),
+    }
+}
+
+pub fn vmess(server: &ServerVmess, uuid: &str) -> Option<String> {
+    let tls = Tls::vmess(server);
+    let transport = Transport::parse(&server.network, server.networksettings.as_deref());
+
+    let mut fields = vec![
+        format!("{} = vmess", proxy_name(&server.name)),
+        server.host.clone(),
+        first_port(&server.port).to_string(),
+        format!("username={}", uuid),
+    ];
+    if tls.enabled {
+        fields.push("tls=true".to_string());
+        if let Some(server_name) = &tls.server_name {
+            fields.push(format!("sni={}", server_name));
+        }
+        fields.push(format!("skip-cert-verify={}", tls.insecure));
+    }
+    match transport.network.as_str() {
+        "ws" => {
+            fields.push("ws=true".to_string());
+            if let Some(path) = &transport.path {
+                fields.push(format!("ws-path={}", path));
+            }
+            if let Some(host) = &transport.host {
+                fields.push(format!("ws-headers=Host:\"{}\"", host));
+            }
+        }
+        "tcp" => {}
+        _ => return None,
+    }
+    fields.push("vmess-aead=true".to_string());
+    fields.push("tfo=true".to_string());
+    fields.push("udp-relay=true".to_string());
+
+    Some(fields.join(", "))
+}
+
+pub fn trojan(server: &ServerTrojan, uuid: &str) -> String {
+    let mut fields = vec![
+        format!("{} = trojan", proxy_name(&server.name)),
+        server.host.clone(),
+        first_port(&server.port).to_string(),
+        format!("password={}", uuid),
+    ];
+    if let Some(server_name) = server.server_name.as_deref().filter(|s| !s.is_empty()) {
+        fields.push(format!("sni={}", server_name));
+    }
+    fields.push(format!("skip-cert-verify={}", server.allow_insecure));
+    fields.push("tfo=true".to_string());
+    fields.push("udp-relay=true".to_string());
+
+    fields.join(", ")
+}
+
+pub fn shadowsocks(server: &ServerShadowsocks, uuid: &str) -> String {
+    let mut fields = vec![
+        format!("{} = ss", proxy_name(&server.name)),
+        server.host.clone(),
+        first_port(&server.port).to_string(),
+        format!("encrypt-method={}", server.cipher),
+        format!("password={}", server.user_password(uuid)),
+    ];
+    if server.obfs.as_deref().map(str::trim) == Some("http") {
+        let settings = parse_json_setting(server.obfs_settings.as_deref());
+        fields.push("obfs=http".to_string());
+        if let Some(host) = settings.get("host").and_then(Value::as_str) {
+            fields.push(format!("obfs-host={}", host));
+        }
+        if let Some(path) = settings.get("path").and_then(Value::as_str) {
+            fields.push(format!("obfs-uri={}", path));
+        }
+    }
+    fields.push("tfo=true".to_string());
+    fields.push("udp-relay=true".to_string());
+
+    fields.join(", ")
+}
+
+pub fn hysteria2(server: &ServerHysteria, uuid: &str) -> String {
+    let mut fields = vec![
+        format!("{} = hysteria2", proxy_name(&server.name)),
+        server.host.clone(),
+        first_port(&server.port).to_string(),
+        format!("password={}", uuid),
+    ];
+    if let Some(server_name) = server.server_name.as_deref().filter(|s| !s.is_empty()) {
+        fields.push(format!("sni={}", server_name));
+    }
+    fields.push(format!("skip-cert-verify={}", server.insecure));
+    // 节点的上行带宽即客户端的下行带宽
+    fields.push(format!("download-bandwidth={}", server.up_mbps));
+
+    fields.join(", ")
+}
+
+/// Surge 以逗号和等号分隔字段，名称中不能包含这两个字符
+fn proxy_name(name: &str) -> String {
+    name.replace([',', '='], " ").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::subscription::fixtures;
+
+    #[test]
+    fn test_vmess_line() {
+        assert_eq!(
+            vmess(&fixtures::vmess_ws_tls(), fixtures::UUID).unwrap(),
+            "香港 VMess = vmess, hk.example.com, 443, \
+             username=8e1b2c3d-4f5a-4b6c-9d7e-0f1a2b3c4d5e, tls=true, sni=cdn.example.com, \
+             skip-cert-verify=false, ws=true, ws-path=/vmess, ws-headers=Host:\"cdn.example.com\", \
+             vmess-aead=true, tfo=true, udp-relay=true"
+        );
+    }
+
+    #[test]
+    fn test_trojan_line() {
+        assert_eq!(
+            trojan(&fixtures::trojan(), fixtures::UUID),
+            "台湾 Trojan = trojan, tw.example.com, 443, \
+             password=8e1b2c3d-4f5a-4b6c-9d7e-0f1a2b3c4d5e, sni=tw.example.com, \
+             skip-cert-verify=true, tfo=true, udp-relay=true"
+        );
+    }
+
+    #[test]
+    fn test_shadowsocks_line() {
+        assert_eq!(
+            shadowsocks(&fixtures::shadowsocks_obfs(), fixtures::UUID),
+            "韩国 SS = ss, kr.example.com, 8388, encrypt-method=aes-128-gcm, \
+             password=8e1b2c3d-4f5a-4b6c-9d7e-0f1a2b3c4d5e, obfs=http, \
+             obfs-host=download.windowsupdate.com, obfs-uri=/, tfo=true, udp-relay=true"
+        );
+    }
+
+    #[test]
+    fn test_hysteria2_line() {
+        assert_eq!(
+            hysteria2(&fixtures::hysteria(), fixtures::UUID),
+            "英国 Hysteria2 = hysteria2, uk.example.com, 20000, \
+             password=8e1b2c3d-4f5a-4b6c-9d7e-0f1a2b3c4d5e, sni=uk.example.com, \
+             skip-cert-verify=false, download-bandwidth=1000"
+        );
+    }
+
+    #[test]
+    fn test_render_surge_golden() {
+        // 重名节点追加节点类型和ID，VLESS 节点被跳过
+        let mut servers = fixtures::servers();
+        servers.push(Server::Trojan(ServerTrojan {
+            id: 9,
+            ..fixtures::trojan()
+        }));
+
+        assert_eq!(
+            render(&servers, fixtures::UUID, "Purple"),
+            include_str!("testdata/surge.conf")
+        );
+    }
+}
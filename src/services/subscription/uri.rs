@@ -0,0 +1,407 @@
+//! 通用分享链接（v2rayN / Shadowrocket 等客户端使用的 Base64 订阅）
+
+use base64::{
+    engine::general_purpose::{STANDARD as BASE64, URL_SAFE_NO_PAD as BASE64_URL},
+    Engine,
+};
+use serde_json::{json, Value};
+
+use super::{
+    node::{first_port, is_port_range, Tls, Transport},
+    SubscriptionUserInfo,
+};
+use crate::{
+    models::server::{
+        parse_json_setting, Server, ServerHysteria, ServerShadowsocks, ServerTrojan, ServerVless,
+        ServerVmess,
+    },
+    utils::percent_encode,
+};
+
+const GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// 生成 Base64 编码的分享链接列表
+pub fn render(servers: &[Server], uuid: &str) -> String {
+    BASE64.encode(links(servers, uuid))
+}
+
+/// 生成 Shadowrocket 订阅，首行为流量与到期时间的 STATUS 行
+pub fn render_shadowrocket(servers: &[Server], uuid: &str, info: &SubscriptionUserInfo) -> String {
+    let expire = if info.expire > 0 {
+        chrono::DateTime::from_timestamp(info.expire, 0)
+            .map(|at| at.format("%Y-%m-%d").to_string())
+            .unwrap_or_default()
+    } else {
+        "长期有效".to_string()
+    };
+    let status = format!(
+        "STATUS=🚀↑:{:.2}GB,↓:{:.2}GB,TOT:{:.2}GB💡Expires:{}\r\n",
+        info.upload as f64 / GB,
+        info.download as f64 / GB,
+        info.total as f64 / GB,
+        expire
+    );
+
+    BASE64.encode(status + &links(servers, uuid))
+}
+
+fn links(servers: &[Server], uuid: &str) -> String {
+    servers
+        .iter()
+        .map(|server| encode(server, uuid) + "\r\n")
+        .collect()
+}
+
+/// 生成单个节点的分享链接
+pub fn encode(server: &Server, uuid: &str) -> String {
+    match server {
+        Server::Vmess(s) => vmess(s, uuid),
+        Server::Vless(s) => vless(s, uuid),
+        Server::Trojan(s) => trojan(s, uuid),
+        Server::Shadowsocks(s) => shadowsocks(s, uuid),
+        Server::Hysteria(s) => hysteria2(s, uuid),
+    }
+}
+
+/// vmess://，v2rayN 格式的 Base64 JSON
+pub fn vmess(server: &ServerVmess, uuid: &str) -> String {
+    let tls = Tls::vmess(server);
+    let transport = Transport::parse(&server.network, server.networksettings.as_deref());
+
+    let mut config = json!({
+        "v": "2",
+        "ps": server.name,
+        "add": server.host,
+        "port": first_port(&server.port).to_string(),
+        "id": uuid,
+        "aid": "0",
+        "scy": "auto",
+        "net": transport.network,
+        "type": "none",
+        "host": transport.host.as_deref().unwrap_or_default(),
+        "path": transport.path.as_deref().unwrap_or_default(),
+        "tls": if tls.enabled { "tls" } else { "" },
+    });
+    if let Some(server_name) = &tls.server_name {
+        config["sni"] = server_name.as_str().into();
+    }
+    match transport.network.as_str() {
+        "grpc" => {
+            config["type"] = "gun".into();
+            config["path"] = transport.service_name.as_deref().unwrap_or_default().into();
+        }
+        "h2" => config["net"] = "h2".into(),
+        _ if transport.is_http_obfs() => config["type"] = "http".into(),
+        _ => {}
+    }
+
+    format!("vmess://{}", BASE64.encode(config.to_string()))
+}
+
+/// vless://uuid@host:port?参数#名称
+pub fn vless(server: &ServerVless, uuid: &str) -> String {
+    let tls = Tls::vless(server);
+    let transport = Transport::parse(&server.network, server.network_settings.as_deref());
+
+    let mut params = vec![("encryption", "none".to_string())];
+    params.extend(transport_params(&transport));
+    match &tls.reality {
+        Some(reality) => {
+            params.push(("security", "reality".to_string()));
+            params.push(("pbk", reality.public_key.clone()));
+            if !reality.short_id.is_empty() {
+                params.push(("sid", reality.short_id.clone()));
+            }
+            params.push(("fp", "chrome".to_string()));
+        }
+        None if tls.enabled => params.push(("security", "tls".to_string())),
+        None => params.push(("security", "none".to_string())),
+    }
+    if let Some(server_name) = &tls.server_name {
+        params.push(("sni", server_name.clone()));
+    }
+    if tls.insecure {
+        params.push(("allowInsecure", "1".to_string()));
+    }
+    if let Some(flow) = server.flow.as_deref().filter(|flow| !flow.is_empty()) {
+        params.push(("flow", flow.to_string()));
+    }
+
+    format!(
+        "vless://{}@{}:{}?{}#{}",
+        uuid,
+        format_host(&server.host),
+        server.port,
+        query_string(&params),
+        percent_encode(&server.name)
+    )
+}
+
+/// trojan://password@host:port?参数#名称
+pub fn trojan(server: &ServerTrojan, uuid: &str) -> String {
+    let mut params = vec![(
+        "allowInsecure",
+        if server.allow_insecure { "1" } else { "0" }.to_string(),
+    )];
+    if let Some(server_name) = server.server_name.as_deref().filter(|s| !s.is_empty()) {
+        params.push(("peer", server_name.to_string()));
+        params.push(("sni", server_name.to_string()));
+    }
+
+    format!(
+        "trojan://{}@{}:{}?{}#{}",
+        percent_encode(uuid),
+        format_host(&server.host),
+        first_port(&server.port),
+        query_string(&params),
+        percent_encode(&server.name)
+    )
+}
+
+/// ss://，SIP002 格式
+///
+/// Shadowsocks 2022 的用户信息按 SIP002 要求使用百分号编码，其余加密方式使用 Base64URL
+pub fn shadowsocks(server: &ServerShadowsocks, uuid: &str) -> String {
+    let password = server.user_password(uuid);
+    let user_info = if server.is_2022_cipher() {
+        format!(
+            "{}:{}",
+            percent_encode(&server.cipher),
+            percent_encode(&password)
+        )
+    } else {
+        BASE64_URL.encode(format!("{}:{}", server.cipher, password))
+    };
+
+    let mut link = format!(
+        "ss://{}@{}:{}",
+        user_info,
+        format_host(&server.host),
+        first_port(&server.port)
+    );
+    if server.obfs.as_deref().map(str::trim) == Some("http") {
+        let settings = parse_json_setting(server.obfs_settings.as_deref());
+        let mut plugin = "obfs-local;obfs=http".to_string();
+        if let Some(host) = settings.get("host").and_then(Value::as_str) {
+            plugin.push_str(&format!(";obfs-host={}", host));
+        }
+        link.push_str(&format!("/?plugin={}", percent_encode(&plugin)));
+    }
+
+    format!("{}#{}", link, percent_encode(&server.name))
+}
+
+/// hysteria2://password@host:port/?参数#名称
+pub fn hysteria2(server: &ServerHysteria, uuid: &str) -> String {
+    let mut params = Vec::new();
+    if let Some(server_name) = server.server_name.as_deref().filter(|s| !s.is_empty()) {
+        params.push(("sni", server_name.to_string()));
+    }
+    params.push((
+        "insecure",
+        if server.insecure { "1" } else { "0" }.to_string(),
+    ));
+    if let Some(obfs_password) = server.obfs_password() {
+        params.push((
+            "obfs",
+            server
+                .obfs_type
+                .as_deref()
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+        ));
+        params.push(("obfs-password", obfs_password));
+    }
+    if is_port_range(&server.port) {
+        params.push(("mport", server.port.clone()));
+    }
+
+    format!(
+        "hysteria2://{}@{}:{}/?{}#{}",
+        percent_encode(uuid),
+        format_host(&server.host),
+        first_port(&server.port),
+        query_string(&params),
+        percent_encode(&server.name)
+    )
+}
+
+fn transport_params(transport: &Transport) -> Vec<(&'static str, String)> {
+    let mut params = Vec::new();
+    match transport.network.as_str() {
+        "grpc" => {
+            params.push(("type", "grpc".to_string()));
+            if let Some(service_name) = &transport.service_name {
+                params.push(("serviceName", service_name.clone()));
+            }
+        }
+        "h2" => params.push(("type", "http".to_string())),
+        network => {
+            params.push(("type", network.to_string()));
+            if transport.is_http_obfs() {
+                params.push(("headerType", "http".to_string()));
+            }
+        }
+    }
+    if let Some(host) = &transport.host {
+        params.push(("host", host.clone()));
+    }
+    if let Some(path) = &transport.path {
+        params.push(("path", path.clone()));
+    }
+    params
+}
+
+fn query_string(params: &[(&str, String)]) -> String {
+    params
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, percent_encode(value)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// IPv6 地址需要使用方括号包裹
+fn format_host(host: &str) -> String {
+    if host.parse::<std::net::Ipv6Addr>().is_ok() {
+        format!("[{}]", host)
+    } else {
+        host.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::subscription::fixtures;
+
+    #[test]
+    fn test_vmess_link() {
+        let link = vmess(&fixtures::vmess_ws_tls(), fixtures::UUID);
+        let config: Value = serde_json::from_slice(
+            &BASE64
+                .decode(link.strip_prefix("vmess://").unwrap())
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            config,
+            json!({
+                "v": "2",
+                "ps": "香港 VMess",
+                "add": "hk.example.com",
+                "port": "443",
+                "id": fixtures::UUID,
+                "aid": "0",
+                "scy": "auto",
+                "net": "ws",
+                "type": "none",
+                "host": "cdn.example.com",
+                "path": "/vmess",
+                "tls": "tls",
+                "sni": "cdn.example.com",
+            })
+        );
+    }
+
+    #[test]
+    fn test_vmess_tcp_http_link() {
+        let link = vmess(&fixtures::vmess_tcp_http(), fixtures::UUID);
+        let config: Value = serde_json::from_slice(
+            &BASE64
+                .decode(link.strip_prefix("vmess://").unwrap())
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(config["net"], "tcp");
+        assert_eq!(config["type"], "http");
+        assert_eq!(config["host"], "www.bing.com");
+        assert_eq!(config["tls"], "");
+    }
+
+    #[test]
+    fn test_vless_reality_link() {
+        assert_eq!(
+            vless(&fixtures::vless_reality(), fixtures::UUID),
+            "vless://8e1b2c3d-4f5a-4b6c-9d7e-0f1a2b3c4d5e@us.example.com:443\
+             ?encryption=none&type=tcp&security=reality\
+             &pbk=Z84J2IelR9ch3k8VtlVhhs5ycBUlXA7wHBWcBrjqnAw&sid=6ba85179e30d4fc2&fp=chrome\
+             &sni=www.microsoft.com&flow=xtls-rprx-vision\
+             #%E7%BE%8E%E5%9B%BD%20REALITY"
+        );
+    }
+
+    #[test]
+    fn test_vless_grpc_link() {
+        assert_eq!(
+            vless(&fixtures::vless_grpc_tls(), fixtures::UUID),
+            "vless://8e1b2c3d-4f5a-4b6c-9d7e-0f1a2b3c4d5e@sg.example.com:443\
+             ?encryption=none&type=grpc&serviceName=grpc-service&security=tls\
+             &sni=sg.example.com\
+             #%E6%96%B0%E5%8A%A0%E5%9D%A1%20VLESS"
+        );
+    }
+
+    #[test]
+    fn test_trojan_link() {
+        assert_eq!(
+            trojan(&fixtures::trojan(), fixtures::UUID),
+            "trojan://8e1b2c3d-4f5a-4b6c-9d7e-0f1a2b3c4d5e@tw.example.com:443\
+             ?allowInsecure=1&peer=tw.example.com&sni=tw.example.com\
+             #%E5%8F%B0%E6%B9%BE%20Trojan"
+        );
+    }
+
+    #[test]
+    fn test_shadowsocks_link() {
+        assert_eq!(
+            shadowsocks(&fixtures::shadowsocks_obfs(), fixtures::UUID),
+            "ss://YWVzLTEyOC1nY206OGUxYjJjM2QtNGY1YS00YjZjLTlkN2UtMGYxYTJiM2M0ZDVl\
+             @kr.example.com:8388\
+             /?plugin=obfs-local%3Bobfs%3Dhttp%3Bobfs-host%3Ddownload.windowsupdate.com\
+             #%E9%9F%A9%E5%9B%BD%20SS"
+        );
+    }
+
+    #[test]
+    fn test_shadowsocks_2022_link() {
+        assert_eq!(
+            shadowsocks(&fixtures::shadowsocks_2022(), fixtures::UUID),
+            "ss://2022-blake3-aes-128-gcm:MjQ5MjBkZWNmODNmNjk2MA%3D%3D%3AOGUxYjJjM2QtNGY1YS00Yg%3D%3D\
+             @de.example.com:8388\
+             #%E5%BE%B7%E5%9B%BD%20SS2022"
+        );
+    }
+
+    #[test]
+    fn test_hysteria2_link() {
+        assert_eq!(
+            hysteria2(&fixtures::hysteria(), fixtures::UUID),
+            "hysteria2://8e1b2c3d-4f5a-4b6c-9d7e-0f1a2b3c4d5e@uk.example.com:20000\
+             /?sni=uk.example.com&insecure=0&obfs=salamander\
+             &obfs-password=MjQ5MjBkZWNmODNmNjk2MA%3D%3D&mport=20000-30000\
+             #%E8%8B%B1%E5%9B%BD%20Hysteria2"
+        );
+    }
+
+    #[test]
+    fn test_shadowrocket_status_line() {
+        let info = SubscriptionUserInfo {
+            upload: 1024 * 1024 * 1024,
+            download: 512 * 1024 * 1024,
+            total: 100 * 1024 * 1024 * 1024,
+            expire: 1735689600,
+        };
+        let output = render_shadowrocket(&fixtures::servers(), fixtures::UUID, &info);
+        let decoded = String::from_utf8(BASE64.decode(output).unwrap()).unwrap();
+
+        let mut lines = decoded.lines();
+        assert_eq!(
+            lines.next(),
+            Some("STATUS=🚀↑:1.00GB,↓:0.50GB,TOT:100.00GB💡Expires:2025-01-01")
+        );
+        assert_eq!(lines.count(), fixtures::servers().len());
+    }
+}
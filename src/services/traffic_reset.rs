@@ -0,0 +1,364 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate};
+use std::collections::HashMap;
+
+use crate::{
+    config::TrafficResetConfig,
+    models::{
+        plan::{Plan, ResetTrafficMethod},
+        traffic_reset::{TrafficResetLog, TrafficResetSummary},
+        user::User,
+    },
+    repositories::{PlanRepository, TrafficResetRepository, UserRepository},
+    utils::GB,
+};
+
+#[derive(Clone)]
+pub struct TrafficResetService {
+    user_repo: UserRepository,
+    plan_repo: PlanRepository,
+    reset_repo: TrafficResetRepository,
+    config: TrafficResetConfig,
+}
+
+impl TrafficResetService {
+    pub fn new(
+        user_repo: UserRepository,
+        plan_repo: PlanRepository,
+        reset_repo: TrafficResetRepository,
+        config: TrafficResetConfig,
+    ) -> Self {
+        Self {
+            user_repo,
+            plan_repo,
+            reset_repo,
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &TrafficResetConfig {
+        &self.config
+    }
+
+    /// 配置时区下的当前日期
+    pub fn today(&self) -> NaiveDate {
+        chrono::Utc::now()
+            .with_timezone(&self.config.timezone)
+            .date_naive()
+    }
+
+    /// 重置截至指定日期尚未在当前周期重置过的用户
+    ///
+    /// 每个用户的重置与记录在独立事务中完成，并以重置周期去重，
+    /// 错过重置日后的下一次执行会补做重置，重复执行不会重复重置；
+    /// 在最近一次重置日当天或之后才开通套餐的用户本周期不重置
+    pub async fn run(&self, date: NaiveDate) -> Result<TrafficResetSummary> {
+        let users = self.user_repo.find_with_plan().await?;
+
+        let mut plan_ids: Vec<i32> = users.iter().filter_map(|user| user.plan_id).collect();
+        plan_ids.sort_unstable();
+        plan_ids.dedup();
+        let plans: HashMap<i32, Plan> = self
+            .plan_repo
+            .find_by_ids(&plan_ids)
+            .await?
+            .into_iter()
+            .map(|plan| (plan.id, plan))
+            .collect();
+
+        let mut summary = TrafficResetSummary {
+            date: date.to_string(),
+            ..Default::default()
+        };
+        // 套餐到期后流量包随之失效，到期用户不再参与下面的重置
+        match self.reset_repo.expire_addons(GB).await {
+            Ok(count) => summary.expired_addons = count,
+            Err(e) => tracing::error!("收回已到期用户的流量包失败: {:#}", e),
+        }
+        for user in &users {
+            // 套餐已被删除的用户不做处理
+            let plan = match user.plan_id.and_then(|plan_id| plans.get(&plan_id)) {
+                Some(plan) => plan,
+                None => continue,
+            };
+            // 按量计费套餐按实际用量扣费，不参与流量重置
+            if plan.is_metered() {
+                continue;
+            }
+            let method = self.resolve_method(plan.reset_traffic_method);
+            summary.checked += 1;
+
+            let anchor = self.anchor(user);
+            let reset_date = match method.last_reset_date(date, anchor) {
+                Some(reset_date) => reset_date,
+                None => continue,
+            };
+            // 重置日之后才开通套餐的用户，本周期的流量属于新套餐，到下个重置日再重置
+            if self
+                .activated_on(user)
+                .is_some_and(|activated_on| activated_on >= reset_date)
+            {
+                summary.skipped += 1;
+                continue;
+            }
+            let period = match method.reset_period(date, anchor) {
+                Some(period) => period,
+                None => continue,
+            };
+
+            match self
+                .reset_repo
+                .reset_user(
+                    user.id,
+                    user.plan_id,
+                    method.as_i16(),
+                    &period,
+                    plan.transfer_enable_bytes(),
+                )
+                .await
+            {
+                Ok(true) => summary.reset += 1,
+                Ok(false) => summary.skipped += 1,
+                Err(e) => {
+                    summary.failed += 1;
+                    tracing::error!("重置用户 {} 流量失败: {:#}", user.id, e);
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// 分页获取流量重置记录
+    pub async fn logs(
+        &self,
+        user_id: Option<i32>,
+        page: u64,
+        page_size: u64,
+    ) -> Result<(Vec<TrafficResetLog>, i64)> {
+        self.reset_repo
+            .find_all(user_id, page as i32, page_size as i32)
+            .await
+    }
+
+    /// 套餐未设置或设置了无效的重置方式时使用全局默认值
+    fn resolve_method(&self, method: Option<i16>) -> ResetTrafficMethod {
+        method
+            .and_then(ResetTrafficMethod::from_i16)
+            .unwrap_or(self.config.default_method)
+    }
+
+    /// 按购买日重置时以到期时间的日期为准，长期有效的用户没有购买日
+    fn anchor(&self, user: &User) -> Option<NaiveDate> {
+        user.expired_at
+            .filter(|expired_at| *expired_at > 0)
+            .and_then(|expired_at| DateTime::from_timestamp(expired_at, 0))
+            .map(|at| at.with_timezone(&self.config.timezone).date_naive())
+    }
+
+    /// 当前套餐开通的日期，未记录时按早已开通处理
+    fn activated_on(&self, user: &User) -> Option<NaiveDate> {
+        user.plan_activated_at
+            .and_then(|activated_at| DateTime::from_timestamp(activated_at.into(), 0))
+            .map(|at| at.with_timezone(&self.config.timezone).date_naive())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+    use chrono::FixedOffset;
+    use sqlx::PgPool;
+
+    fn service(pool: &PgPool) -> TrafficResetService {
+        TrafficResetService::new(
+            UserRepository::new(pool.clone()),
+            PlanRepository::new(pool.clone()),
+            TrafficResetRepository::new(pool.clone()),
+            TrafficResetConfig {
+                default_method: ResetTrafficMethod::MonthFirstDay,
+                check_interval: 3600,
+                timezone: FixedOffset::east_opt(8 * 3600).unwrap(),
+            },
+        )
+    }
+
+    async fn used_traffic(pool: &PgPool, user_id: i32) -> i64 {
+        sqlx::query_scalar("SELECT u + d FROM purple_user WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_run_catches_up_missed_reset_day(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let plan_id = test_support::insert_plan(&pool, 1).await;
+        let user_id = test_support::insert_user(&pool, "reset@example.com", Some(1)).await;
+        sqlx::query("UPDATE purple_user SET plan_id = $1, u = 100, d = 200 WHERE id = $2")
+            .bind(plan_id)
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let service = service(&pool);
+        // 1 日未执行，3 日补做本月的重置
+        let date = NaiveDate::from_ymd_opt(2025, 3, 3).unwrap();
+        let summary = service.run(date).await.unwrap();
+        assert_eq!((summary.checked, summary.reset, summary.skipped), (1, 1, 0));
+        assert_eq!(used_traffic(&pool, user_id).await, 0);
+
+        // 同一周期内再次执行不会重复重置
+        sqlx::query("UPDATE purple_user SET d = 50 WHERE id = $1")
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let summary = service.run(date.succ_opt().unwrap()).await.unwrap();
+        assert_eq!((summary.reset, summary.skipped), (0, 1));
+        assert_eq!(used_traffic(&pool, user_id).await, 50);
+
+        let (logs, total) = service.logs(Some(user_id), 1, 10).await.unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(logs[0].period, "2025-03");
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_run_skips_user_activated_after_reset_day(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let plan_id = test_support::insert_plan(&pool, 1).await;
+        let user_id = test_support::insert_user(&pool, "new@example.com", Some(1)).await;
+        // 3 月 20 日开通套餐，随后购买了 5GB 流量包
+        let activated_at = NaiveDate::from_ymd_opt(2025, 3, 20)
+            .unwrap()
+            .and_hms_opt(4, 0, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp() as i32;
+        let transfer_enable: i64 = 105 * crate::utils::GB;
+        sqlx::query(
+            r#"
+            UPDATE purple_user
+            SET plan_id = $1, plan_activated_at = $2, transfer_enable = $3, u = 100, d = 200
+            WHERE id = $4
+            "#,
+        )
+        .bind(plan_id)
+        .bind(activated_at)
+        .bind(transfer_enable)
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let service = service(&pool);
+        let summary = service
+            .run(NaiveDate::from_ymd_opt(2025, 3, 25).unwrap())
+            .await
+            .unwrap();
+        assert_eq!((summary.reset, summary.skipped), (0, 1));
+        assert_eq!(used_traffic(&pool, user_id).await, 300);
+        let current: i64 =
+            sqlx::query_scalar("SELECT transfer_enable FROM purple_user WHERE id = $1")
+                .bind(user_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(current, transfer_enable);
+
+        // 下个重置日照常重置
+        let summary = service
+            .run(NaiveDate::from_ymd_opt(2025, 4, 1).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(summary.reset, 1);
+        assert_eq!(used_traffic(&pool, user_id).await, 0);
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_addon_on_never_plan_lasts_until_expiry(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        let plan_id = test_support::insert_plan(&pool, 1).await;
+        sqlx::query("UPDATE purple_plan SET reset_traffic_method = $1 WHERE id = $2")
+            .bind(ResetTrafficMethod::Never.as_i16())
+            .bind(plan_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let user_id = test_support::insert_user(&pool, "addon@example.com", Some(1)).await;
+        sqlx::query("UPDATE purple_user SET plan_id = $1, transfer_enable = $2 WHERE id = $3")
+            .bind(plan_id)
+            .bind(105 * GB)
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let transfer_enable = || async {
+            sqlx::query_scalar::<_, i64>("SELECT transfer_enable FROM purple_user WHERE id = $1")
+                .bind(user_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        };
+
+        let service = service(&pool);
+        let date = NaiveDate::from_ymd_opt(2025, 3, 3).unwrap();
+        let summary = service.run(date).await.unwrap();
+        assert_eq!((summary.reset, summary.expired_addons), (0, 0));
+        assert_eq!(transfer_enable().await, 105 * GB);
+
+        // 套餐到期后流量包失效
+        sqlx::query("UPDATE purple_user SET expired_at = 1 WHERE id = $1")
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let summary = service.run(date).await.unwrap();
+        assert_eq!(summary.expired_addons, 1);
+        assert_eq!(transfer_enable().await, 100 * GB);
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_migrate_boolean_reset_method_keeps_null(pool: PgPool) {
+        test_support::migrate(&pool).await;
+        // 模拟早期版本 boolean 类型的 reset_traffic_method
+        sqlx::query(
+            "ALTER TABLE purple_plan ALTER COLUMN reset_traffic_method TYPE boolean USING (reset_traffic_method = 1)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let mut ids = Vec::new();
+        for method in [None, Some(true), Some(false)] {
+            let id: i32 = sqlx::query_scalar(
+                r#"
+                INSERT INTO purple_plan (group_id, transfer_enable, name, show, reset_traffic_method, created_at, updated_at)
+                VALUES (1, 100, 'legacy', true, $1, 0, 0)
+                RETURNING id
+                "#,
+            )
+            .bind(method)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            ids.push(id);
+        }
+
+        test_support::migrate(&pool).await;
+
+        let mut methods = Vec::new();
+        for id in ids {
+            let method: Option<i16> =
+                sqlx::query_scalar("SELECT reset_traffic_method FROM purple_plan WHERE id = $1")
+                    .bind(id)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap();
+            methods.push(method);
+        }
+        assert_eq!(methods, vec![None, Some(1), Some(0)]);
+    }
+}
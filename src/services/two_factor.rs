@@ -0,0 +1,345 @@
+use anyhow::Result;
+use rand::{Rng, RngCore};
+use sha2::{Digest, Sha256};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::{
+    common::{ApiError, ErrorCode},
+    config::TwoFactorConfig,
+    models::{
+        two_factor::{
+            RecoveryCodesResponse, TwoFactor, TwoFactorSetupResponse, TwoFactorStatusResponse,
+        },
+        user::{Role, User},
+    },
+    repositories::TwoFactorRepository,
+};
+
+/// 生成的恢复码数量
+const RECOVERY_CODE_COUNT: usize = 10;
+/// 恢复码字符集，去掉了容易混淆的字符
+const RECOVERY_CODE_CHARSET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+/// TOTP 时间步长（秒）
+const TOTP_STEP: i64 = 30;
+
+/// TOTP 双重验证
+///
+/// 密钥生成后需用第一个验证码确认才会启用，同一时间步的验证码只能使用一次，
+/// 恢复码只保存哈希，每个只能使用一次
+#[derive(Clone)]
+pub struct TwoFactorService {
+    repo: TwoFactorRepository,
+    config: TwoFactorConfig,
+}
+
+impl TwoFactorService {
+    pub fn new(repo: TwoFactorRepository, config: TwoFactorConfig) -> Self {
+        Self { repo, config }
+    }
+
+    /// 账户是否被要求启用双重验证
+    pub fn is_required(&self, user: &User) -> bool {
+        self.config.required_for_staff && user.role() >= Role::Staff
+    }
+
+    pub async fn is_enabled(&self, user_id: i32) -> Result<bool> {
+        Ok(self
+            .repo
+            .find_by_user(user_id)
+            .await?
+            .is_some_and(|two_factor| two_factor.is_enabled()))
+    }
+
+    pub async fn status(&self, user: &User) -> Result<TwoFactorStatusResponse> {
+        let enabled_at = self
+            .repo
+            .find_by_user(user.id)
+            .await?
+            .and_then(|two_factor| two_factor.enabled_at);
+        let recovery_codes_remaining = match enabled_at {
+            Some(_) => self.repo.count_unused_recovery_codes(user.id).await?,
+            None => 0,
+        };
+
+        Ok(TwoFactorStatusResponse {
+            enabled: enabled_at.is_some(),
+            enabled_at,
+            recovery_codes_remaining,
+            required: self.is_required(user),
+        })
+    }
+
+    /// 生成新的密钥，确认前不生效，重复调用会替换未确认的密钥
+    pub async fn setup(&self, user: &User) -> Result<TwoFactorSetupResponse> {
+        let mut bytes = [0u8; 20];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let secret = Secret::Raw(bytes.to_vec()).to_encoded().to_string();
+
+        if !self.repo.save_pending(user.id, &secret).await? {
+            return Err(ApiError::with_message(
+                ErrorCode::InvalidParams,
+                "双重验证已启用，请先关闭后再重新设置".to_string(),
+            )
+            .into());
+        }
+
+        Ok(TwoFactorSetupResponse {
+            otpauth_url: self.totp(&secret, &user.email)?.get_url(),
+            secret,
+        })
+    }
+
+    /// 使用身份验证器中的第一个验证码确认并启用，返回恢复码
+    pub async fn enable(&self, user: &User, code: &str) -> Result<RecoveryCodesResponse> {
+        let two_factor = match self.repo.find_by_user(user.id).await? {
+            Some(two_factor) if !two_factor.is_enabled() => two_factor,
+            Some(_) => {
+                return Err(ApiError::with_message(
+                    ErrorCode::InvalidParams,
+                    "双重验证已启用".to_string(),
+                )
+                .into())
+            }
+            None => {
+                return Err(ApiError::with_message(
+                    ErrorCode::InvalidParams,
+                    "请先生成双重验证密钥".to_string(),
+                )
+                .into())
+            }
+        };
+
+        let step = self
+            .match_step(&two_factor, &user.email, code)?
+            .ok_or_else(|| ApiError::new(ErrorCode::InvalidVerificationCode))?;
+
+        let recovery_codes = Self::generate_recovery_codes();
+        let hashes: Vec<String> = recovery_codes
+            .iter()
+            .map(|code| Self::hash_recovery_code(code))
+            .collect();
+        if !self.repo.enable(user.id, step, &hashes).await? {
+            return Err(ApiError::with_message(
+                ErrorCode::InvalidParams,
+                "双重验证已启用".to_string(),
+            )
+            .into());
+        }
+        tracing::info!("用户 {} 已启用双重验证", user.id);
+
+        Ok(RecoveryCodesResponse { recovery_codes })
+    }
+
+    /// 关闭双重验证，调用方需先确认用户身份
+    pub async fn disable(&self, user_id: i32) -> Result<()> {
+        self.repo.delete(user_id).await?;
+        tracing::info!("用户 {} 已关闭双重验证", user_id);
+        Ok(())
+    }
+
+    /// 使用当前验证码重新生成恢复码，旧恢复码全部失效
+    pub async fn regenerate_recovery_codes(
+        &self,
+        user: &User,
+        code: &str,
+    ) -> Result<RecoveryCodesResponse> {
+        if !self.verify_totp(user, code).await? {
+            return Err(ApiError::new(ErrorCode::InvalidVerificationCode).into());
+        }
+
+        let recovery_codes = Self::generate_recovery_codes();
+        let hashes: Vec<String> = recovery_codes
+            .iter()
+            .map(|code| Self::hash_recovery_code(code))
+            .collect();
+        self.repo.replace_recovery_codes(user.id, &hashes).await?;
+
+        Ok(RecoveryCodesResponse { recovery_codes })
+    }
+
+    /// 验证6位验证码或恢复码，未启用双重验证时返回 false
+    pub async fn verify(&self, user: &User, code: &str) -> Result<bool> {
+        let code = code.trim();
+        if Self::is_totp_code(code) {
+            return self.verify_totp(user, code).await;
+        }
+
+        if !self.is_enabled(user.id).await? {
+            return Ok(false);
+        }
+        let used = self
+            .repo
+            .use_recovery_code(user.id, &Self::hash_recovery_code(code))
+            .await?;
+        if used {
+            tracing::info!("用户 {} 使用恢复码完成双重验证", user.id);
+        }
+        Ok(used)
+    }
+
+    /// 验证6位验证码，验证通过后该时间步及之前的验证码失效
+    async fn verify_totp(&self, user: &User, code: &str) -> Result<bool> {
+        let two_factor = match self.repo.find_by_user(user.id).await? {
+            Some(two_factor) if two_factor.is_enabled() => two_factor,
+            _ => return Ok(false),
+        };
+
+        match self.match_step(&two_factor, &user.email, code.trim())? {
+            Some(step) => self.repo.use_step(user.id, step).await,
+            None => Ok(false),
+        }
+    }
+
+    /// 查找验证码对应的时间步，允许前后各偏差一个时间步，已使用过的时间步不再匹配
+    fn match_step(&self, two_factor: &TwoFactor, account: &str, code: &str) -> Result<Option<i64>> {
+        if !Self::is_totp_code(code) {
+            return Ok(None);
+        }
+
+        let totp = self.totp(&two_factor.secret, account)?;
+        let current = chrono::Utc::now().timestamp() / TOTP_STEP;
+        Ok((current - 1..=current + 1).find(|step| {
+            two_factor.last_used_step.is_none_or(|last| *step > last)
+                && totp.generate((*step * TOTP_STEP) as u64) == code
+        }))
+    }
+
+    fn totp(&self, secret: &str, account: &str) -> Result<TOTP> {
+        let secret = Secret::Encoded(secret.to_string())
+            .to_bytes()
+            .map_err(|e| anyhow::anyhow!("TOTP 密钥解析失败: {:?}", e))?;
+        TOTP::new(
+            Algorithm::SHA1,
+            6,
+            1,
+            TOTP_STEP as u64,
+            secret,
+            Some(self.config.issuer.replace(':', "")),
+            account.replace(':', ""),
+        )
+        .map_err(|e| anyhow::anyhow!("创建 TOTP 失败: {:?}", e))
+    }
+
+    fn is_totp_code(code: &str) -> bool {
+        code.len() == 6 && code.bytes().all(|b| b.is_ascii_digit())
+    }
+
+    /// 生成恢复码，格式为 `XXXXX-XXXXX`
+    fn generate_recovery_codes() -> Vec<String> {
+        let mut rng = rand::thread_rng();
+        (0..RECOVERY_CODE_COUNT)
+            .map(|_| {
+                let chars: String = (0..10)
+                    .map(|_| {
+                        RECOVERY_CODE_CHARSET[rng.gen_range(0..RECOVERY_CODE_CHARSET.len())] as char
+                    })
+                    .collect();
+                format!("{}-{}", &chars[..5], &chars[5..])
+            })
+            .collect()
+    }
+
+    /// 恢复码忽略大小写和分隔符
+    fn hash_recovery_code(code: &str) -> String {
+        let normalized: String = code
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        format!("{:x}", Sha256::digest(normalized.as_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::{repositories::UserRepository, test_support};
+
+    async fn setup(pool: &PgPool) -> (TwoFactorService, User) {
+        test_support::migrate(pool).await;
+        let user_id = test_support::insert_user(pool, "totp@example.com", None).await;
+        let user = UserRepository::new(pool.clone())
+            .find_by_id(user_id)
+            .await
+            .unwrap()
+            .unwrap();
+        let service = TwoFactorService::new(
+            TwoFactorRepository::new(pool.clone()),
+            TwoFactorConfig {
+                required_for_staff: true,
+                challenge_ttl: 300,
+                issuer: "Purple".to_string(),
+            },
+        );
+        (service, user)
+    }
+
+    /// 按当前时间偏移 `offset` 个时间步生成验证码
+    fn code(service: &TwoFactorService, secret: &str, user: &User, offset: i64) -> String {
+        let step = chrono::Utc::now().timestamp() / TOTP_STEP + offset;
+        service
+            .totp(secret, &user.email)
+            .unwrap()
+            .generate((step * TOTP_STEP) as u64)
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_totp_step_is_single_use(pool: PgPool) {
+        let (service, user) = setup(&pool).await;
+        let setup = service.setup(&user).await.unwrap();
+        assert!(setup.otpauth_url.starts_with("otpauth://totp/Purple:"));
+        assert!(!service
+            .verify(&user, &code(&service, &setup.secret, &user, 0))
+            .await
+            .unwrap());
+
+        service
+            .enable(&user, &code(&service, &setup.secret, &user, -1))
+            .await
+            .unwrap();
+        assert!(service.is_enabled(user.id).await.unwrap());
+        // 启用后不能重新生成密钥
+        assert!(service.setup(&user).await.is_err());
+
+        // 确认时使用的时间步及之前的验证码不能再次使用
+        let previous = code(&service, &setup.secret, &user, -1);
+        assert!(!service.verify(&user, &previous).await.unwrap());
+        let current = code(&service, &setup.secret, &user, 0);
+        assert!(service.verify(&user, &current).await.unwrap());
+        assert!(!service.verify(&user, &current).await.unwrap());
+        // 超出允许偏差的验证码无效
+        let future = code(&service, &setup.secret, &user, 3);
+        assert!(!service.verify(&user, &future).await.unwrap());
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_recovery_codes(pool: PgPool) {
+        let (service, user) = setup(&pool).await;
+        let setup = service.setup(&user).await.unwrap();
+        let codes = service
+            .enable(&user, &code(&service, &setup.secret, &user, 0))
+            .await
+            .unwrap()
+            .recovery_codes;
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+
+        // 恢复码忽略大小写和分隔符，且只能使用一次
+        let first = codes[0].to_lowercase().replace('-', "");
+        assert!(service.verify(&user, &first).await.unwrap());
+        assert!(!service.verify(&user, &codes[0]).await.unwrap());
+        assert_eq!(
+            service
+                .status(&user)
+                .await
+                .unwrap()
+                .recovery_codes_remaining,
+            RECOVERY_CODE_COUNT as i64 - 1
+        );
+
+        service.disable(user.id).await.unwrap();
+        assert!(!service.verify(&user, &codes[1]).await.unwrap());
+        assert!(!service.status(&user).await.unwrap().enabled);
+    }
+}
@@ -5,6 +5,7 @@ use tracing::info;
 use crate::{
     app_state::AppState,
     config::{Config, DatabaseConfig},
+    jobs,
     logging::{init_logging, LogGuard},
     routes::configure_routes,
 };
@@ -38,7 +39,10 @@ impl Application {
         let log_guard = init_logging(&config.log)?;
 
         // 创建应用状态
-        let app_state = AppState::new(&database_config).await?;
+        let app_state = AppState::new(&config, &database_config).await?;
+
+        // 启动后台任务
+        jobs::spawn_all(&app_state);
 
         // 记录启动信息
         log_startup_info(&config);
@@ -63,9 +67,11 @@ impl Application {
 /// 创建HTTP服务器
 fn create_server(app_state: AppState, config: &Config) -> Result<actix_web::dev::Server> {
     let app_state_for_factory = app_state.clone();
+    let proxy_config = web::Data::new(config.proxy.clone());
 
     let server = HttpServer::new(move || {
         App::new()
+            .app_data(proxy_config.clone())
             .app_data(web::Data::new(
                 app_state_for_factory.user_repository.clone(),
             ))
@@ -75,7 +81,36 @@ fn create_server(app_state: AppState, config: &Config) -> Result<actix_web::dev:
             .app_data(web::Data::new(
                 app_state_for_factory.coupon_repository.clone(),
             ))
+            .app_data(web::Data::new(
+                app_state_for_factory.server_repository.clone(),
+            ))
+            .app_data(web::Data::new(
+                app_state_for_factory.route_repository.clone(),
+            ))
+            .app_data(web::Data::new(
+                app_state_for_factory.permission_repository.clone(),
+            ))
+            .app_data(web::Data::new(
+                app_state_for_factory.session_repository.clone(),
+            ))
             .app_data(web::Data::new(app_state_for_factory.auth_service.clone()))
+            .app_data(web::Data::new(
+                app_state_for_factory.two_factor_service.clone(),
+            ))
+            .app_data(web::Data::new(
+                app_state_for_factory.api_key_service.clone(),
+            ))
+            .app_data(web::Data::new(app_state_for_factory.server_service.clone()))
+            .app_data(web::Data::new(
+                app_state_for_factory.subscription_service.clone(),
+            ))
+            .app_data(web::Data::new(
+                app_state_for_factory.traffic_reset_service.clone(),
+            ))
+            .app_data(web::Data::new(app_state_for_factory.order_service.clone()))
+            .app_data(web::Data::new(
+                app_state_for_factory.billing_service.clone(),
+            ))
             .configure(configure_routes)
     })
     .bind((config.server_addr.as_str(), config.server_port))?
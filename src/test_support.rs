@@ -0,0 +1,131 @@
+//! 单元测试辅助工具
+//!
+//! 依赖数据库的测试使用 `#[sqlx::test(migrations = false)]`，由 sqlx 根据
+//! DATABASE_URL 为每个测试创建独立的空数据库，再通过 [`migrate`] 执行建表脚本
+
+use sqlx::{Executor, PgPool};
+
+use crate::{
+    common::{ApiError, ErrorCode},
+    config::{AuthConfig, EmailCodeConfig, LoginGuardConfig, MailConfig, TwoFactorConfig},
+    repositories::{EmailCodeRepository, SessionRepository, TwoFactorRepository, UserRepository},
+    services::{mail_sender_from_config, AuthService, EmailCodeService, TwoFactorService},
+};
+
+/// 在测试数据库上执行 migrations/init.sql
+pub async fn migrate(pool: &PgPool) {
+    pool.execute(include_str!("../migrations/init.sql"))
+        .await
+        .expect("执行 migrations/init.sql 失败");
+}
+
+/// 取出业务错误的错误码，非业务错误返回 None
+pub fn error_code(err: &anyhow::Error) -> Option<ErrorCode> {
+    err.downcast_ref::<ApiError>().map(|e| e.error_code)
+}
+
+/// 当前时间戳（秒）
+pub fn now() -> i32 {
+    chrono::Utc::now().timestamp() as i32
+}
+
+/// 插入一个可用的测试用户（10GB 流量、永不过期），返回用户ID
+pub async fn insert_user(pool: &PgPool, email: &str, group_id: Option<i32>) -> i32 {
+    let uuid = uuid::Uuid::new_v4();
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO purple_user (
+            email, password, transfer_enable, uuid, group_id, token, created_at, updated_at
+        )
+        VALUES ($1, '', 10737418240, $2, $3, $4, 0, 0)
+        RETURNING id
+        "#,
+    )
+    .bind(email)
+    .bind(uuid.to_string())
+    .bind(group_id)
+    .bind(uuid.simple().to_string())
+    .fetch_one(pool)
+    .await
+    .expect("插入测试用户失败")
+}
+
+/// 插入一个 100GB 流量、月付 10 元的测试套餐，返回套餐ID
+pub async fn insert_plan(pool: &PgPool, group_id: i32) -> i32 {
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO purple_plan (
+            group_id, transfer_enable, name, show, month_price, created_at, updated_at
+        )
+        VALUES ($1, 100, 'test', true, 1000, 0, 0)
+        RETURNING id
+        "#,
+    )
+    .bind(group_id)
+    .fetch_one(pool)
+    .await
+    .expect("插入测试套餐失败")
+}
+
+/// 使用 HS256 测试密钥的认证配置
+pub fn auth_config() -> AuthConfig {
+    AuthConfig {
+        access_token_ttl: 900,
+        refresh_token_ttl: 3600,
+        jwt_secret: Some("test-secret".to_string()),
+        jwt_keys: Vec::new(),
+        jwt_active_kid: None,
+        login_guard: LoginGuardConfig {
+            max_failures: 5,
+            ip_max_failures: 20,
+            lockout_duration: 900,
+            backoff_base: 0,
+            failure_window: 3600,
+        },
+        register_email_verify: false,
+        email_code: EmailCodeConfig {
+            ttl: 600,
+            resend_interval: 0,
+            email_hourly_limit: 5,
+            ip_hourly_limit: 20,
+            max_attempts: 5,
+        },
+        two_factor: TwoFactorConfig {
+            required_for_staff: false,
+            challenge_ttl: 300,
+            issuer: "Purple".to_string(),
+        },
+    }
+}
+
+/// 使用 [`auth_config`] 创建认证服务，邮件只写日志
+pub fn auth_service(pool: &PgPool) -> AuthService {
+    let config = auth_config();
+    let mailer = mail_sender_from_config(&MailConfig {
+        host: None,
+        port: 465,
+        encryption: "ssl".to_string(),
+        username: None,
+        password: None,
+        from_address: "noreply@localhost".to_string(),
+        app_name: "Purple".to_string(),
+    })
+    .expect("创建邮件发送器失败");
+
+    AuthService::new(
+        UserRepository::new(pool.clone()),
+        SessionRepository::new(pool.clone()),
+        EmailCodeService::new(
+            EmailCodeRepository::new(pool.clone()),
+            mailer,
+            config.email_code.clone(),
+            "Purple".to_string(),
+        ),
+        TwoFactorService::new(
+            TwoFactorRepository::new(pool.clone()),
+            config.two_factor.clone(),
+        ),
+        config,
+    )
+    .expect("创建认证服务失败")
+}
@@ -1 +1,123 @@
 // 工具函数将在这里添加
+
+use actix_web::{web, HttpRequest};
+use serde::{Deserialize, Deserializer};
+use std::net::IpAddr;
+
+use crate::config::ProxyConfig;
+
+/// 1 GB 对应的字节数
+pub const GB: i64 = 1024 * 1024 * 1024;
+
+/// 百分号编码，仅保留 RFC 3986 中的非保留字符
+pub fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// 反序列化可置空的更新字段：未传时为 None，显式传 null 时为 Some(None)
+///
+/// 需要配合 `#[serde(default, deserialize_with = "double_option")]` 使用
+pub fn double_option<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// 获取客户端真实IP
+///
+/// 只有直连地址属于可信反向代理时才采信 X-Forwarded-For 和 X-Real-IP，
+/// X-Forwarded-For 从右向左跳过可信代理，第一个不可信的地址即为客户端地址
+pub fn client_ip(req: &HttpRequest) -> Option<String> {
+    let peer = req.peer_addr()?.ip();
+    let proxy = match req.app_data::<web::Data<ProxyConfig>>() {
+        Some(proxy) if proxy.is_trusted(&peer) => proxy,
+        _ => return Some(peer.to_string()),
+    };
+
+    let forwarded: Vec<IpAddr> = req
+        .headers()
+        .get_all("x-forwarded-for")
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|ip| ip.trim().parse().ok())
+        .collect();
+    let ip = forwarded
+        .iter()
+        .rev()
+        .find(|ip| !proxy.is_trusted(ip))
+        .or(forwarded.first())
+        .copied()
+        .or_else(|| {
+            req.headers()
+                .get("x-real-ip")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|ip| ip.trim().parse().ok())
+        })
+        .unwrap_or(peer);
+
+    Some(ip.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn proxy_config(proxies: &[&str]) -> web::Data<ProxyConfig> {
+        web::Data::new(ProxyConfig {
+            trusted_proxies: proxies.iter().map(|proxy| proxy.parse().unwrap()).collect(),
+        })
+    }
+
+    #[test]
+    fn test_client_ip_ignores_headers_from_untrusted_peer() {
+        let req = TestRequest::default()
+            .peer_addr("203.0.113.7:50000".parse().unwrap())
+            .insert_header(("x-forwarded-for", "1.2.3.4"))
+            .insert_header(("x-real-ip", "1.2.3.4"))
+            .app_data(proxy_config(&["127.0.0.1"]))
+            .to_http_request();
+        assert_eq!(client_ip(&req).as_deref(), Some("203.0.113.7"));
+
+        // 未配置可信代理时同样只使用直连地址
+        let req = TestRequest::default()
+            .peer_addr("203.0.113.7:50000".parse().unwrap())
+            .insert_header(("x-forwarded-for", "1.2.3.4"))
+            .to_http_request();
+        assert_eq!(client_ip(&req).as_deref(), Some("203.0.113.7"));
+    }
+
+    #[test]
+    fn test_client_ip_behind_trusted_proxy() {
+        // 客户端伪造的最左侧地址不被采信
+        let req = TestRequest::default()
+            .peer_addr("127.0.0.1:50000".parse().unwrap())
+            .insert_header(("x-forwarded-for", "1.2.3.4, 198.51.100.20, 172.18.0.3"))
+            .app_data(proxy_config(&["127.0.0.1", "172.16.0.0/12"]))
+            .to_http_request();
+        assert_eq!(client_ip(&req).as_deref(), Some("198.51.100.20"));
+
+        let req = TestRequest::default()
+            .peer_addr("[::1]:50000".parse().unwrap())
+            .insert_header(("x-real-ip", "2001:db8::1"))
+            .app_data(proxy_config(&["::1"]))
+            .to_http_request();
+        assert_eq!(client_ip(&req).as_deref(), Some("2001:db8::1"));
+
+        let req = TestRequest::default()
+            .peer_addr("127.0.0.1:50000".parse().unwrap())
+            .app_data(proxy_config(&["127.0.0.1"]))
+            .to_http_request();
+        assert_eq!(client_ip(&req).as_deref(), Some("127.0.0.1"));
+    }
+}